@@ -6,9 +6,14 @@ extern crate alloc;
 use stylus_sdk::{
     alloy_primitives::{Address, U256},
     alloy_sol_types::sol,
-    block, call, contract, msg,
+    call,
     prelude::*,
 };
+use stylus_reentrancy_guard::{
+    non_reentrant, safe_transfer_eth, GasStipend, ReentrancyError, ReentrancyGuard, ReentrancyGuardReentrantCall,
+    ReentrancyGuarded, SafeTransferError, SafeTransferEthRejected, SafeTransferEthReverted,
+    SafeTransferInsufficientBalance,
+};
 
 // ERC721 interface for NFT transfers
 sol_interface! {
@@ -48,6 +53,32 @@ pub enum DutchAuctionError {
     NotNFTOwner(NotNFTOwner),
     NotApproved(NotApproved),
     AuctionNotStarted(AuctionNotStarted),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    InsufficientBalance(SafeTransferInsufficientBalance),
+    EthRejected(SafeTransferEthRejected),
+    EthReverted(SafeTransferEthReverted),
+}
+
+impl From<ReentrancyError> for DutchAuctionError {
+    fn from(err: ReentrancyError) -> Self {
+        match err {
+            ReentrancyError::ReentrantCall(e) => DutchAuctionError::ReentrantCall(e),
+            ReentrancyError::ReadOnlyReentrantCall(_) => {
+                DutchAuctionError::ReentrantCall(ReentrancyGuardReentrantCall {})
+            }
+        }
+    }
+}
+
+impl From<SafeTransferError> for DutchAuctionError {
+    fn from(err: SafeTransferError) -> Self {
+        match err {
+            SafeTransferError::InsufficientBalance(e) => DutchAuctionError::InsufficientBalance(e),
+            SafeTransferError::EthRejected(e) => DutchAuctionError::EthRejected(e),
+            SafeTransferError::EthReverted(e) => DutchAuctionError::EthReverted(e),
+            SafeTransferError::Erc20FailedOperation(_) => DutchAuctionError::PaymentFailed(PaymentFailed {}),
+        }
+    }
 }
 
 sol_storage! {
@@ -63,6 +94,13 @@ sol_storage! {
         bool ended;
         address winner;
         uint256 final_price;
+        ReentrancyGuard guard;
+    }
+}
+
+impl ReentrancyGuarded for DutchAuction {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.guard
     }
 }
 
@@ -97,10 +135,11 @@ impl DutchAuction {
         self.starting_price.set(starting_price);
         self.ending_price.set(ending_price);
         self.duration.set(duration);
-        self.start_time.set(U256::from(block::timestamp()));
+        self.start_time.set(U256::from(self.vm().block_timestamp()));
         self.ended.set(false);
         self.winner.set(Address::ZERO);
         self.final_price.set(U256::ZERO);
+        self.guard.init();
 
         // Verify NFT ownership and approval
         self.verify_nft_authorization(seller)?;
@@ -114,7 +153,7 @@ impl DutchAuction {
             return Err(DutchAuctionError::AuctionAlreadyEnded(AuctionAlreadyEnded {}));
         }
 
-        let current_time = U256::from(block::timestamp());
+        let current_time = U256::from(self.vm().block_timestamp());
         let start_time = self.start_time.get();
         let duration = self.duration.get();
         let starting_price = self.starting_price.get();
@@ -138,14 +177,16 @@ impl DutchAuction {
     }
 
     /// Purchase the item at current price
+    #[payable]
+    #[non_reentrant]
     pub fn buy(&mut self) -> Result<(), DutchAuctionError> {
         if self.ended.get() {
             return Err(DutchAuctionError::AuctionAlreadyEnded(AuctionAlreadyEnded {}));
         }
 
         let current_price = self.get_current_price()?;
-        let payment = msg::value();
-        let buyer = msg::sender();
+        let payment = self.vm().msg_value();
+        let buyer = self.vm().msg_sender();
         let seller = self.seller.get();
 
         if payment < current_price {
@@ -187,7 +228,7 @@ impl DutchAuction {
         }
 
         // Check if this contract is approved to transfer the NFT
-        let contract_address = contract::address();
+        let contract_address = self.vm().contract_address();
         let approved_result = nft_contract.get_approved(call::Call::new_in(self), token_id);
         let approved_for_all_result = nft_contract.is_approved_for_all(call::Call::new_in(self), seller, contract_address);
 
@@ -234,10 +275,7 @@ impl DutchAuction {
         }
 
         // Transfer ETH to the seller
-        let result = call::transfer_eth(to, amount);
-        if result.is_err() {
-            return Err(DutchAuctionError::PaymentFailed(PaymentFailed {}));
-        }
+        safe_transfer_eth(self, to, amount, GasStipend::All)?;
 
         Ok(())
     }
@@ -253,17 +291,14 @@ impl DutchAuction {
         }
 
         // Refund excess ETH to the buyer
-        let result = call::transfer_eth(to, amount);
-        if result.is_err() {
-            return Err(DutchAuctionError::PaymentFailed(PaymentFailed {}));
-        }
+        safe_transfer_eth(self, to, amount, GasStipend::All)?;
 
         Ok(())
     }
 
     /// Stop the auction (only seller)
     pub fn stop_auction(&mut self) -> Result<(), DutchAuctionError> {
-        if msg::sender() != self.seller.get() {
+        if self.vm().msg_sender() != self.seller.get() {
             return Err(DutchAuctionError::OnlySeller(OnlySeller {}));
         }
 
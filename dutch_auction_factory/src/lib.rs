@@ -13,12 +13,11 @@ extern crate alloc;
 use alloc::vec::Vec;
 
 use stylus_sdk::{
-    alloy_primitives::{U256, Address, B256}, 
-    prelude::*, 
+    alloy_primitives::{keccak256, U256, Address},
+    prelude::*,
     deploy::RawDeploy,
-    msg,
-    crypto
 };
+use stylus_common::{Initializable, Ownable2Step};
 
 // Import the compiled dutch auction WASM bytecode at compile time
 static DUTCH_AUCTION_WASM: &[u8] = include_bytes!("../dutch_auction.wasm");
@@ -28,16 +27,20 @@ sol_storage! {
     pub struct DutchAuctionFactory {
         uint256 auction_count;
         mapping(uint256 => address) auctions;
-        address owner;
+        Ownable2Step ownership;
+        Initializable init;
     }
 }
 
 #[public]
 impl DutchAuctionFactory {
-    /// Initialize the factory
+    /// Initialize the factory, making the caller its owner
+    ///
+    /// Runs once, so it can't be replayed to take over the factory.
     pub fn new(&mut self) -> Result<(), Vec<u8>> {
+        self.init.initializer()?;
+        self.ownership.init(self.vm().msg_sender())?;
         self.auction_count.set(U256::from(0));
-        self.owner.set(msg::sender());
         Ok(())
     }
 
@@ -63,7 +66,7 @@ impl DutchAuctionFactory {
         }
 
         let auction_id = self.auction_count.get() + U256::from(1);
-        let sender = msg::sender();
+        let sender = self.vm().msg_sender();
         
         // Use embedded bytecode
         let bytecode = DUTCH_AUCTION_WASM;
@@ -75,13 +78,13 @@ impl DutchAuctionFactory {
         salt_data.extend_from_slice(nft_contract.as_slice());
         salt_data.extend_from_slice(&token_id.as_le_bytes());
         
-        let salt = B256::from_slice(&crypto::keccak(salt_data)[0..32]);
+        let salt = keccak256(salt_data);
 
         // Deploy the auction contract using RawDeploy with CREATE2
         let auction_address = unsafe {
             RawDeploy::new()
                 .salt(salt)
-                .deploy(&bytecode, U256::from(0))
+                .deploy(self.vm(), bytecode, U256::from(0))
                 .map_err(|e| {
                     let mut err = "Failed to deploy auction contract: ".as_bytes().to_vec();
                     err.extend_from_slice(&e);
@@ -110,7 +113,22 @@ impl DutchAuctionFactory {
 
     /// Get factory owner
     pub fn get_owner(&self) -> Address {
-        self.owner.get()
+        self.ownership.owner()
+    }
+
+    /// Get the owner nominated by `transfer_ownership` who hasn't accepted yet
+    pub fn get_pending_owner(&self) -> Address {
+        self.ownership.pending_owner()
+    }
+
+    /// Nominate `new_owner`, who takes over by calling `accept_ownership` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        Ok(self.ownership.transfer_ownership(new_owner)?)
+    }
+
+    /// Take over the factory (nominated owner only)
+    pub fn accept_ownership(&mut self) -> Result<(), Vec<u8>> {
+        Ok(self.ownership.accept_ownership()?)
     }
 
     /// Get embedded auction bytecode length
@@ -0,0 +1,112 @@
+//! Calendar helpers for the payroll schedule
+//!
+//! Converts between Unix timestamps and proleptic Gregorian dates so pay days
+//! can be expressed as days of the month without relying on an oracle.
+
+/// Number of seconds in a day
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+/// Number of days since 1970-01-01 for the given date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = if year >= 0 { year } else { year - 399 } / 400;
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date (year, month, day) for the given number of days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = if days >= 0 { days } else { days - 146_096 } / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Timestamp of midnight (UTC) on the given date
+pub fn timestamp_of(year: i64, month: u32, day: u32) -> u64 {
+    (days_from_civil(year, month, day) as u64) * SECONDS_PER_DAY
+}
+
+/// Year and month of the given timestamp
+pub fn year_month_of(timestamp: u64) -> (i64, u32) {
+    let (year, month, _) = civil_from_days((timestamp / SECONDS_PER_DAY) as i64);
+    (year, month)
+}
+
+/// The month before (year, month)
+pub fn previous_month(year: i64, month: u32) -> (i64, u32) {
+    if month == 1 { (year - 1, 12) } else { (year, month - 1) }
+}
+
+/// The month after (year, month)
+pub fn next_month(year: i64, month: u32) -> (i64, u32) {
+    if month == 12 { (year + 1, 1) } else { (year, month + 1) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Date of the day after (year, month, day)
+    fn day_after(year: i64, month: u32, day: u32) -> (i64, u32, u32) {
+        civil_from_days(days_from_civil(year, month, day) + 1)
+    }
+
+    #[test]
+    fn test_epoch() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(timestamp_of(2024, 2, 29), 1_709_164_800);
+    }
+
+    #[test]
+    fn test_leap_years() {
+        // Divisible by 4, except centuries not divisible by 400
+        assert_eq!(day_after(2024, 2, 28), (2024, 2, 29));
+        assert_eq!(day_after(2024, 2, 29), (2024, 3, 1));
+        assert_eq!(day_after(2023, 2, 28), (2023, 3, 1));
+        assert_eq!(day_after(1900, 2, 28), (1900, 3, 1));
+        assert_eq!(day_after(2000, 2, 28), (2000, 2, 29));
+        assert_eq!(days_from_civil(2025, 1, 1) - days_from_civil(2024, 1, 1), 366);
+        assert_eq!(days_from_civil(2101, 1, 1) - days_from_civil(2100, 1, 1), 365);
+    }
+
+    #[test]
+    fn test_month_ends() {
+        assert_eq!(day_after(2023, 1, 31), (2023, 2, 1));
+        assert_eq!(day_after(2023, 4, 30), (2023, 5, 1));
+        assert_eq!(day_after(2023, 7, 31), (2023, 8, 1));
+
+        // The last second of a month still belongs to it
+        assert_eq!(year_month_of(timestamp_of(2024, 3, 1) - 1), (2024, 2));
+        assert_eq!(year_month_of(timestamp_of(2024, 3, 1)), (2024, 3));
+    }
+
+    #[test]
+    fn test_year_rollover() {
+        assert_eq!(day_after(2023, 12, 31), (2024, 1, 1));
+        assert_eq!(year_month_of(timestamp_of(2024, 1, 1) - 1), (2023, 12));
+        assert_eq!(next_month(2023, 12), (2024, 1));
+        assert_eq!(previous_month(2024, 1), (2023, 12));
+        assert_eq!(next_month(2024, 1), (2024, 2));
+        assert_eq!(previous_month(2024, 3), (2024, 2));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for days in days_from_civil(1969, 1, 1)..days_from_civil(2101, 1, 1) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+}
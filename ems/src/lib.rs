@@ -2,20 +2,99 @@ extern crate alloc;
 
 use stylus_sdk::{
     prelude::*,
-    msg, block
+    alloy_sol_types::sol,
+    storage::Erase,
 };
+use stylus_common::{Initializable, Ownable2Step};
 
 use alloc::{vec::Vec, string::String};
-use alloy_primitives::{U256, Address};
+use alloy_primitives::{keccak256, U256, U8, Address, B256};
+
+mod calendar;
+use calendar::{next_month, previous_month, timestamp_of, year_month_of, SECONDS_PER_DAY};
+
+/// Length of one pay period in seconds (30 days)
+const PAY_PERIOD: u64 = 30 * 24 * 60 * 60;
+
+/// Leave days granted to a new employee
+const DEFAULT_LEAVE_DAYS: u64 = 20;
+
+/// Pay periods of salary owed as severance on termination
+const SEVERANCE_PERIODS: u64 = 1;
+
+/// Latest day of the month allowed as a pay day, so every month has it
+const MAX_PAY_DAY: u8 = 28;
+
+/// Longest window after a pay day during which payroll may still be executed
+const MAX_PAYROLL_WINDOW: u64 = 7 * SECONDS_PER_DAY;
+
+/// Maximum number of audit entries returned per page
+const MAX_AUDIT_PAGE_SIZE: u64 = 100;
+
+/// Maximum number of employees one `execute_scheduled_payroll` call works through
+const MAX_PAYROLL_BATCH: u64 = 100;
+
+/// Audit log action codes
+pub const ACTION_ADD_EMPLOYEE: u64 = 1;
+pub const ACTION_TERMINATE_EMPLOYEE: u64 = 2;
+pub const ACTION_SET_PAYROLL_SCHEDULE: u64 = 3;
+pub const ACTION_APPROVE_INVOICE: u64 = 4;
+pub const ACTION_REJECT_INVOICE: u64 = 5;
+pub const ACTION_REINITIALIZE: u64 = 6;
+pub const ACTION_TRANSFER_ADMIN: u64 = 7;
+pub const ACTION_ACCEPT_ADMIN: u64 = 8;
+
+/// Invoice statuses
+const INVOICE_PENDING: u8 = 0;
+const INVOICE_APPROVED: u8 = 1;
+const INVOICE_REJECTED: u8 = 2;
+
+/// How an employee is engaged, which decides how they are paid
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EmploymentType {
+    /// Fixed salary, accrues leave and severance
+    FullTime = 0,
+    /// Fixed salary, set at the reduced part-time rate; accrues leave and severance
+    PartTime = 1,
+    /// Paid per approved invoice; no salary, leave, or severance
+    Contractor = 2,
+}
+
+impl TryFrom<u8> for EmploymentType {
+    type Error = Vec<u8>;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(EmploymentType::FullTime),
+            1 => Ok(EmploymentType::PartTime),
+            2 => Ok(EmploymentType::Contractor),
+            _ => Err("Invalid employment type".as_bytes().to_vec()),
+        }
+    }
+}
 
 sol_storage! {
     #[entrypoint]
     pub struct EmployeeManagement {
-        address admin;
+        Initializable init;
+        Ownable2Step ownership;
         uint256 employee_count;
         mapping(address => Employee) employees;
         address[] employee_addresses;
         mapping(uint256 => address[]) department_employees;
+        uint256 audit_nonce;
+        bytes32 audit_head;
+        mapping(uint256 => AuditEntry) audit_log;
+        uint8[] pay_days;
+        uint256 payroll_window;
+        uint256 last_payroll_window;
+        mapping(uint8 => uint256) active_count_by_type;
+        uint256 salary_commitment;
+        uint256 invoice_count;
+        mapping(uint256 => Invoice) invoices;
+        uint256 pending_invoice_total;
+        uint256 contractor_spend;
+        uint256 payroll_cursor;
     }
 
     pub struct Employee {
@@ -25,6 +104,67 @@ sol_storage! {
         uint256 department;
         uint256 hire_date;
         bool is_active;
+        uint256 salary;
+        uint256 total_earned;
+        uint256 last_paid;
+        uint256 leave_balance;
+        uint8 employment_type;
+        uint256 severance_owed;
+    }
+
+    pub struct Invoice {
+        address contractor;
+        uint256 amount;
+        bytes description;
+        uint8 status;
+        uint256 submitted_at;
+    }
+
+    pub struct AuditEntry {
+        address actor;
+        uint256 action;
+        address target;
+        bytes32 old_values_hash;
+        bytes32 new_values_hash;
+        uint256 timestamp;
+        bytes32 chain_hash;
+    }
+}
+
+sol! {
+    struct EmployeeProfile {
+        uint256 id;
+        address employee_address;
+        string name;
+        uint256 department;
+        uint256 hire_date;
+        bool is_active;
+        uint256 salary;
+        uint256 accrued_pay;
+        uint256 next_pay_date;
+        uint256 leave_balance;
+        uint8 employment_type;
+        uint256 severance_owed;
+    }
+
+    struct WorkforceSummary {
+        uint256 full_time_count;
+        uint256 part_time_count;
+        uint256 contractor_count;
+        uint256 salary_commitment;
+        uint256 contractor_spend;
+        uint256 pending_invoice_total;
+    }
+
+    struct AuditRecord {
+        uint256 nonce;
+        address actor;
+        uint256 action;
+        address target;
+        bytes32 old_values_hash;
+        bytes32 new_values_hash;
+        uint256 timestamp;
+        bytes32 chain_hash;
     }
 }
 
@@ -32,32 +172,76 @@ sol_storage! {
 impl EmployeeManagement {
     /// Initialize employee management system
     pub fn new(&mut self) -> Result<(), Vec<u8>> {
-        self.admin.set(msg::sender());
+        self.init.initializer()?;
+        self.ownership.init(self.vm().msg_sender())?;
         self.employee_count.set(U256::from(0));
         
         Ok(())
     }
 
+    /// Bump the storage version after an upgrade (admin only)
+    ///
+    /// Each version can only be applied once and versions must strictly increase,
+    /// so migration steps cannot be replayed.
+    pub fn reinitialize(&mut self, version: u64) -> Result<(), Vec<u8>> {
+        self.only_admin()?;
+
+        if !self.init.is_initialized() {
+            return Err("Not initialized".as_bytes().to_vec());
+        }
+
+        let old_version = self.init.initialized_version();
+        self.init.reinitializer(version)?;
+
+        self.record_audit(
+            ACTION_REINITIALIZE,
+            Address::ZERO,
+            Self::hash_values(&[&old_version.to_be_bytes()]),
+            Self::hash_values(&[&version.to_be_bytes()]),
+        );
+
+        Ok(())
+    }
+
+    /// Get the initialized storage version (0 before `new`)
+    pub fn get_version(&self) -> u64 {
+        self.init.initialized_version()
+    }
+
     /// Add new employee (admin only)
+    ///
+    /// Adding a terminated employee rehires them under their old id, keeping
+    /// their total earnings and any severance still owed.
     pub fn add_employee(
         &mut self,
         employee_address: Address,
         name: String,
         department: U256,
+        salary: U256,
+        employment_type: u8,
     ) -> Result<U256, Vec<u8>> {
         self.only_admin()?;
         
         if employee_address == Address::ZERO {
             return Err("Invalid employee address".as_bytes().to_vec());
         }
+
+        let kind = EmploymentType::try_from(employment_type)?;
+        if kind == EmploymentType::Contractor && salary != U256::ZERO {
+            return Err("Contractors are paid per invoice".as_bytes().to_vec());
+        }
         
-        if self.employees.get(employee_address).is_active.get() {
+        let (is_active, previous_id, previous_department) = {
+            let employee = self.employees.get(employee_address);
+            (employee.is_active.get(), employee.id.get(), employee.department.get())
+        };
+        if is_active {
             return Err("Employee already exists".as_bytes().to_vec());
         }
         
-        
-        let employee_id = self.employee_count.get() + U256::from(1);
-        let hire_date = U256::from(block::timestamp());
+        let rehire = previous_id != U256::ZERO;
+        let employee_id = if rehire { previous_id } else { self.employee_count.get() + U256::from(1) };
+        let hire_date = U256::from(self.vm().block_timestamp());
         
         let mut employee = self.employees.setter(employee_address);
         employee.id.set(employee_id);
@@ -66,10 +250,35 @@ impl EmployeeManagement {
         employee.department.set(department);
         employee.hire_date.set(hire_date);
         employee.is_active.set(true);
+        employee.salary.set(salary);
+        employee.last_paid.set(hire_date);
+        employee.employment_type.set(U8::from(employment_type));
+        if kind == EmploymentType::Contractor {
+            employee.leave_balance.set(U256::ZERO);
+        } else {
+            employee.leave_balance.set(U256::from(DEFAULT_LEAVE_DAYS));
+        }
         
-        self.employee_addresses.push(employee_address);
-        self.department_employees.setter(department).push(employee_address);
-        self.employee_count.set(employee_id);
+        let mut active_count = self.active_count_by_type.setter(U8::from(employment_type));
+        active_count.set(active_count.get() + U256::from(1));
+        self.salary_commitment.set(self.salary_commitment.get() + salary);
+
+        if !rehire {
+            self.employee_addresses.push(employee_address);
+            self.department_employees.setter(department).push(employee_address);
+            self.employee_count.set(employee_id);
+        } else if department != previous_department {
+            self.department_employees.setter(department).push(employee_address);
+        }
+
+        let new_values = Self::hash_values(&[
+            &employee_id.to_be_bytes::<32>(),
+            name.as_bytes(),
+            &department.to_be_bytes::<32>(),
+            &salary.to_be_bytes::<32>(),
+            &[employment_type],
+        ]);
+        self.record_audit(ACTION_ADD_EMPLOYEE, employee_address, B256::ZERO, new_values);
         
         Ok(employee_id)
     }
@@ -79,37 +288,95 @@ impl EmployeeManagement {
         self.only_admin()?;
         
         // check if employee exists
-        let is_active = {
+        let (is_active, employment_type, salary) = {
             let employee = self.employees.get(employee_address);
-            employee.is_active.get()
+            (employee.is_active.get(), employee.employment_type.get(), employee.salary.get())
         };
         
         if !is_active {
             return Err("Employee not found or already terminated".as_bytes().to_vec());
         }
+
+        // Contractors are not owed severance
+        let severance = if EmploymentType::try_from(employment_type.to::<u8>())? == EmploymentType::Contractor {
+            U256::ZERO
+        } else {
+            salary * U256::from(SEVERANCE_PERIODS)
+        };
         
         // Update employee status
-        self.employees.setter(employee_address).is_active.set(false);
+        let mut employee = self.employees.setter(employee_address);
+        employee.is_active.set(false);
+        employee.severance_owed.set(severance);
+
+        let mut active_count = self.active_count_by_type.setter(employment_type);
+        active_count.set(active_count.get() - U256::from(1));
+        self.salary_commitment.set(self.salary_commitment.get() - salary);
+
+        self.record_audit(
+            ACTION_TERMINATE_EMPLOYEE,
+            employee_address,
+            Self::hash_values(&[&[1u8]]),
+            Self::hash_values(&[&[0u8]]),
+        );
         
         Ok(())
     }
 
     /// Get employee details
-    pub fn get_employee(&self, employee_address: Address) -> (U256, Address, Vec<u8>, U256, U256, bool) {
-        let employee = self.employees.get(employee_address);
-        (
-            employee.id.get(),
-            employee.employee_address.get(),
-            employee.name.get_bytes(),
-            employee.department.get(),
-            employee.hire_date.get(),
-            employee.is_active.get(),
-        )
+    pub fn get_employee(&self, employee_address: Address) -> EmployeeProfile {
+        self.build_profile(employee_address)
+    }
+
+    /// Get the caller's own employee profile
+    pub fn get_my_profile(&self) -> Result<EmployeeProfile, Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        if self.employees.get(caller).id.get() == U256::ZERO {
+            return Err("Employee not found".as_bytes().to_vec());
+        }
+
+        Ok(self.build_profile(caller))
     }
 
     /// Get admin address
     pub fn get_admin(&self) -> Address {
-        self.admin.get()
+        self.ownership.owner()
+    }
+
+    /// Get the admin nominated by `transfer_admin` who hasn't accepted yet
+    pub fn get_pending_admin(&self) -> Address {
+        self.ownership.pending_owner()
+    }
+
+    /// Nominate `new_admin`, who takes over by calling `accept_admin` (admin only)
+    pub fn transfer_admin(&mut self, new_admin: Address) -> Result<(), Vec<u8>> {
+        let old_pending = self.ownership.pending_owner();
+        self.ownership.transfer_ownership(new_admin)?;
+
+        self.record_audit(
+            ACTION_TRANSFER_ADMIN,
+            new_admin,
+            Self::hash_values(&[old_pending.as_slice()]),
+            Self::hash_values(&[new_admin.as_slice()]),
+        );
+
+        Ok(())
+    }
+
+    /// Take over as admin (nominated admin only)
+    pub fn accept_admin(&mut self) -> Result<(), Vec<u8>> {
+        let old_admin = self.ownership.owner();
+        self.ownership.accept_ownership()?;
+        let new_admin = self.ownership.owner();
+
+        self.record_audit(
+            ACTION_ACCEPT_ADMIN,
+            new_admin,
+            Self::hash_values(&[old_admin.as_slice()]),
+            Self::hash_values(&[new_admin.as_slice()]),
+        );
+
+        Ok(())
     }
 
     /// Check if employee exists and is active
@@ -117,13 +384,391 @@ impl EmployeeManagement {
         self.employees.get(employee_address).is_active.get()
     }
 
+    /// Submit an invoice for work performed (active contractors only)
+    pub fn submit_invoice(&mut self, amount: U256, description: String) -> Result<U256, Vec<u8>> {
+        let contractor = self.vm().msg_sender();
+        let (is_active, employment_type) = {
+            let employee = self.employees.get(contractor);
+            (employee.is_active.get(), employee.employment_type.get())
+        };
+
+        if !is_active || EmploymentType::try_from(employment_type.to::<u8>())? != EmploymentType::Contractor {
+            return Err("Only active contractors can submit invoices".as_bytes().to_vec());
+        }
+        if amount == U256::ZERO {
+            return Err("Invoice amount must be greater than zero".as_bytes().to_vec());
+        }
+
+        let invoice_id = self.invoice_count.get() + U256::from(1);
+        let submitted_at = U256::from(self.vm().block_timestamp());
+        let mut invoice = self.invoices.setter(invoice_id);
+        invoice.contractor.set(contractor);
+        invoice.amount.set(amount);
+        invoice.description.set_bytes(description.as_bytes());
+        invoice.status.set(U8::from(INVOICE_PENDING));
+        invoice.submitted_at.set(submitted_at);
+
+        self.invoice_count.set(invoice_id);
+        self.pending_invoice_total.set(self.pending_invoice_total.get() + amount);
+
+        Ok(invoice_id)
+    }
+
+    /// Approve a pending invoice, crediting the contractor (admin only)
+    pub fn approve_invoice(&mut self, invoice_id: U256) -> Result<(), Vec<u8>> {
+        self.only_admin()?;
+
+        let (contractor, amount) = self.pending_invoice(invoice_id)?;
+        self.invoices.setter(invoice_id).status.set(U8::from(INVOICE_APPROVED));
+
+        let mut employee = self.employees.setter(contractor);
+        employee.total_earned.set(employee.total_earned.get() + amount);
+
+        self.pending_invoice_total.set(self.pending_invoice_total.get() - amount);
+        self.contractor_spend.set(self.contractor_spend.get() + amount);
+
+        self.record_audit(
+            ACTION_APPROVE_INVOICE,
+            contractor,
+            Self::hash_values(&[&invoice_id.to_be_bytes::<32>(), &[INVOICE_PENDING]]),
+            Self::hash_values(&[&invoice_id.to_be_bytes::<32>(), &[INVOICE_APPROVED]]),
+        );
+
+        Ok(())
+    }
+
+    /// Reject a pending invoice (admin only)
+    pub fn reject_invoice(&mut self, invoice_id: U256) -> Result<(), Vec<u8>> {
+        self.only_admin()?;
+
+        let (contractor, amount) = self.pending_invoice(invoice_id)?;
+        self.invoices.setter(invoice_id).status.set(U8::from(INVOICE_REJECTED));
+        self.pending_invoice_total.set(self.pending_invoice_total.get() - amount);
+
+        self.record_audit(
+            ACTION_REJECT_INVOICE,
+            contractor,
+            Self::hash_values(&[&invoice_id.to_be_bytes::<32>(), &[INVOICE_PENDING]]),
+            Self::hash_values(&[&invoice_id.to_be_bytes::<32>(), &[INVOICE_REJECTED]]),
+        );
+
+        Ok(())
+    }
+
+    /// Get invoice details: contractor, amount, description, status, submission time
+    pub fn get_invoice(&self, invoice_id: U256) -> (Address, U256, String, u8, U256) {
+        let invoice = self.invoices.get(invoice_id);
+        (
+            invoice.contractor.get(),
+            invoice.amount.get(),
+            String::from_utf8_lossy(&invoice.description.get_bytes()).into_owned(),
+            invoice.status.get().to::<u8>(),
+            invoice.submitted_at.get(),
+        )
+    }
+
+    /// Get headcount per employment type, salaried commitments, and contractor spend
+    pub fn get_workforce_summary(&self) -> WorkforceSummary {
+        WorkforceSummary {
+            full_time_count: self.active_count_by_type.get(U8::from(EmploymentType::FullTime as u8)),
+            part_time_count: self.active_count_by_type.get(U8::from(EmploymentType::PartTime as u8)),
+            contractor_count: self.active_count_by_type.get(U8::from(EmploymentType::Contractor as u8)),
+            salary_commitment: self.salary_commitment.get(),
+            contractor_spend: self.contractor_spend.get(),
+            pending_invoice_total: self.pending_invoice_total.get(),
+        }
+    }
+
+    /// Set the days of the month on which payroll runs (admin only)
+    ///
+    /// Each scheduled run may be executed once, by anyone, within `window`
+    /// seconds of midnight (UTC) on the pay day.
+    pub fn set_payroll_schedule(&mut self, pay_days: Vec<u8>, window: U256) -> Result<(), Vec<u8>> {
+        self.only_admin()?;
+
+        if pay_days.is_empty() {
+            return Err("Pay days required".as_bytes().to_vec());
+        }
+        for day in &pay_days {
+            if *day == 0 || *day > MAX_PAY_DAY {
+                return Err("Invalid pay day".as_bytes().to_vec());
+            }
+        }
+        if window == U256::ZERO || window > U256::from(MAX_PAYROLL_WINDOW) {
+            return Err("Invalid payroll window".as_bytes().to_vec());
+        }
+
+        let old_values = self.schedule_hash();
+
+        self.pay_days.erase();
+        for day in &pay_days {
+            self.pay_days.push(U8::from(*day));
+        }
+        self.payroll_window.set(window);
+
+        let new_values = self.schedule_hash();
+        self.record_audit(ACTION_SET_PAYROLL_SCHEDULE, Address::ZERO, old_values, new_values);
+
+        Ok(())
+    }
+
+    /// Pay active employees for the time accrued since their last payment
+    ///
+    /// Callable by anyone (e.g. an automation keeper), but only inside a scheduled
+    /// payroll window. Each call works through the next `MAX_PAYROLL_BATCH`
+    /// employees from where the previous one stopped, so keepers call it until
+    /// `is_payroll_due` is false; the run is done once every employee has been
+    /// reached. Employees the window closes on are paid for the extra time by
+    /// the next run. Returns the amount paid by this call.
+    pub fn execute_scheduled_payroll(&mut self) -> Result<U256, Vec<u8>> {
+        let now = self.vm().block_timestamp();
+        let window_start = match self.current_payroll_window(now) {
+            Some(start) => start,
+            None => return Err("Outside payroll window".as_bytes().to_vec()),
+        };
+
+        let employee_total = self.employee_addresses.len();
+        if U256::from(window_start) != self.last_payroll_window.get() {
+            self.last_payroll_window.set(U256::from(window_start));
+            self.payroll_cursor.set(U256::ZERO);
+        } else if self.payroll_cursor.get() >= U256::from(employee_total) {
+            return Err("Payroll already executed for this window".as_bytes().to_vec());
+        }
+
+        let now = U256::from(now);
+        let pay_period = U256::from(PAY_PERIOD);
+        let mut total_paid = U256::ZERO;
+
+        let start = self.payroll_cursor.get().to::<usize>();
+        let end = employee_total.min(start + MAX_PAYROLL_BATCH as usize);
+        for i in start..end {
+            let employee_address = self.employee_addresses.get(i).unwrap();
+            let mut employee = self.employees.setter(employee_address);
+            if !employee.is_active.get() {
+                continue;
+            }
+
+            // Contractors are paid through approved invoices instead
+            if employee.employment_type.get() == U8::from(EmploymentType::Contractor as u8) {
+                continue;
+            }
+
+            let last_paid = employee.last_paid.get();
+            if now <= last_paid {
+                continue;
+            }
+
+            let amount = employee.salary.get() * (now - last_paid) / pay_period;
+            employee.total_earned.set(employee.total_earned.get() + amount);
+            employee.last_paid.set(now);
+            total_paid += amount;
+        }
+        self.payroll_cursor.set(U256::from(end));
+
+        Ok(total_paid)
+    }
+
+    /// Get the progress of the latest payroll run: employees reached so far, out of all employees
+    pub fn get_payroll_progress(&self) -> (U256, U256) {
+        (self.payroll_cursor.get(), U256::from(self.employee_addresses.len()))
+    }
+
+    /// Get the payroll schedule: pay days, window length, and start of the last executed window
+    pub fn get_payroll_schedule(&self) -> (Vec<u8>, U256, U256) {
+        (
+            self.scheduled_pay_days(),
+            self.payroll_window.get(),
+            self.last_payroll_window.get(),
+        )
+    }
+
+    /// Check whether `execute_scheduled_payroll` would currently succeed
+    pub fn is_payroll_due(&self) -> bool {
+        match self.current_payroll_window(self.vm().block_timestamp()) {
+            Some(start) => {
+                U256::from(start) != self.last_payroll_window.get()
+                    || self.payroll_cursor.get() < U256::from(self.employee_addresses.len())
+            }
+            None => false,
+        }
+    }
+
+    /// Get the nonce of the most recent audit entry
+    pub fn get_audit_nonce(&self) -> U256 {
+        self.audit_nonce.get()
+    }
+
+    /// Get the running hash over every audit entry recorded so far
+    pub fn get_audit_head(&self) -> B256 {
+        self.audit_head.get()
+    }
+
+    /// Get a page of audit entries, oldest first
+    pub fn get_audit_entries(&self, offset: U256, limit: U256) -> Vec<AuditRecord> {
+        let total = self.audit_nonce.get();
+        let limit = limit.min(U256::from(MAX_AUDIT_PAGE_SIZE));
+
+        let mut records = Vec::new();
+        if offset >= total {
+            return records;
+        }
+        let mut nonce = offset + U256::from(1);
+        while nonce <= total && U256::from(records.len()) < limit {
+            let entry = self.audit_log.get(nonce);
+            records.push(AuditRecord {
+                nonce,
+                actor: entry.actor.get(),
+                action: entry.action.get(),
+                target: entry.target.get(),
+                old_values_hash: entry.old_values_hash.get(),
+                new_values_hash: entry.new_values_hash.get(),
+                timestamp: entry.timestamp.get(),
+                chain_hash: entry.chain_hash.get(),
+            });
+            nonce += U256::from(1);
+        }
+
+        records
+    }
+
     // Internal functions
     fn only_admin(&self) -> Result<(), Vec<u8>> {
-        if msg::sender() != self.admin.get() {
-            return Err("Only admin can perform this action".as_bytes().to_vec());
+        Ok(self.ownership.only_owner()?)
+    }
+
+    /// Append an entry to the audit log, chaining it to the previous entry's hash
+    fn record_audit(&mut self, action: u64, target: Address, old_values_hash: B256, new_values_hash: B256) {
+        let nonce = self.audit_nonce.get() + U256::from(1);
+        let actor = self.vm().msg_sender();
+        let action = U256::from(action);
+        let timestamp = U256::from(self.vm().block_timestamp());
+
+        let chain_hash = Self::hash_values(&[
+            self.audit_head.get().as_slice(),
+            &nonce.to_be_bytes::<32>(),
+            actor.as_slice(),
+            &action.to_be_bytes::<32>(),
+            target.as_slice(),
+            old_values_hash.as_slice(),
+            new_values_hash.as_slice(),
+            &timestamp.to_be_bytes::<32>(),
+        ]);
+
+        let mut entry = self.audit_log.setter(nonce);
+        entry.actor.set(actor);
+        entry.action.set(action);
+        entry.target.set(target);
+        entry.old_values_hash.set(old_values_hash);
+        entry.new_values_hash.set(new_values_hash);
+        entry.timestamp.set(timestamp);
+        entry.chain_hash.set(chain_hash);
+
+        self.audit_nonce.set(nonce);
+        self.audit_head.set(chain_hash);
+    }
+
+    /// Contractor and amount of an invoice that is still awaiting a decision
+    fn pending_invoice(&self, invoice_id: U256) -> Result<(Address, U256), Vec<u8>> {
+        let invoice = self.invoices.get(invoice_id);
+        if invoice.contractor.get() == Address::ZERO {
+            return Err("Invoice not found".as_bytes().to_vec());
         }
-        Ok(())
+        if invoice.status.get() != U8::from(INVOICE_PENDING) {
+            return Err("Invoice already processed".as_bytes().to_vec());
+        }
+        Ok((invoice.contractor.get(), invoice.amount.get()))
+    }
+
+    fn scheduled_pay_days(&self) -> Vec<u8> {
+        let mut days = Vec::new();
+        for i in 0..self.pay_days.len() {
+            days.push(self.pay_days.get(i).unwrap().to::<u8>());
+        }
+        days
+    }
+
+    fn schedule_hash(&self) -> B256 {
+        Self::hash_values(&[
+            &self.scheduled_pay_days(),
+            &self.payroll_window.get().to_be_bytes::<32>(),
+        ])
     }
 
+    /// Start of the payroll window containing `now`, if any
+    fn current_payroll_window(&self, now: u64) -> Option<u64> {
+        let window = self.payroll_window.get().to::<u64>();
+        let (year, month) = year_month_of(now);
+        let (prev_year, prev_month) = previous_month(year, month);
+
+        let mut current = None;
+        for day in self.scheduled_pay_days() {
+            for (y, m) in [(prev_year, prev_month), (year, month)] {
+                let start = timestamp_of(y, m, day as u32);
+                if start <= now && now < start + window && current.map_or(true, |c| start > c) {
+                    current = Some(start);
+                }
+            }
+        }
+        current
+    }
+
+    /// Start of the first scheduled pay day after `now`, if a schedule is set
+    fn next_scheduled_payday(&self, now: u64) -> Option<u64> {
+        let (year, month) = year_month_of(now);
+        let (next_year, next_month) = next_month(year, month);
+
+        let mut next = None;
+        for day in self.scheduled_pay_days() {
+            for (y, m) in [(year, month), (next_year, next_month)] {
+                let start = timestamp_of(y, m, day as u32);
+                if start > now && next.map_or(true, |n| start < n) {
+                    next = Some(start);
+                }
+            }
+        }
+        next
+    }
+
+    fn hash_values(parts: &[&[u8]]) -> B256 {
+        let mut data = Vec::new();
+        for part in parts {
+            data.extend_from_slice(part);
+        }
+        keccak256(data)
+    }
+
+    fn build_profile(&self, employee_address: Address) -> EmployeeProfile {
+        let employee = self.employees.get(employee_address);
+        let is_active = employee.is_active.get();
+        let salary = employee.salary.get();
+        let last_paid = employee.last_paid.get();
+        let pay_period = U256::from(PAY_PERIOD);
+
+        // Pay accrues linearly over the current period and stops on termination
+        let timestamp = self.vm().block_timestamp();
+        let now = U256::from(timestamp);
+        let accrued_pay = if is_active && now > last_paid {
+            salary * (now - last_paid) / pay_period
+        } else {
+            U256::ZERO
+        };
+
+        EmployeeProfile {
+            id: employee.id.get(),
+            employee_address: employee.employee_address.get(),
+            name: String::from_utf8_lossy(&employee.name.get_bytes()).into_owned(),
+            department: employee.department.get(),
+            hire_date: employee.hire_date.get(),
+            is_active,
+            salary,
+            accrued_pay,
+            next_pay_date: self
+                .next_scheduled_payday(timestamp)
+                .map_or(last_paid + pay_period, U256::from),
+            leave_balance: employee.leave_balance.get(),
+            employment_type: employee.employment_type.get().to::<u8>(),
+            severance_owed: employee.severance_owed.get(),
+        }
+    }
 }
 
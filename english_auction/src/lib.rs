@@ -0,0 +1,791 @@
+//!
+//! English Auction Contract
+//!
+//! Sells one escrowed NFT to the highest bidder. The first bid must meet the
+//! reserve price and every later bid must beat the current one by the minimum
+//! increment. Outbid bidders and the seller are credited rather than paid, and
+//! pull their funds with `claim`. A bid close to the end pushes the end back
+//! by the extension window, so the auction can't be sniped. The seller can
+//! also restrict bidding to a merkle allowlist of addresses.
+//!
+//! Auctions are initialized with `initialize` rather than a constructor, so
+//! the factory can deploy them as clones of one implementation.
+
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+extern crate alloc;
+
+use stylus_common::{Initializable, InitializableError, InvalidInitialization};
+use stylus_reentrancy_guard::{
+    non_reentrant, safe_transfer_eth, GasStipend, ReentrancyError, ReentrancyGuard, ReentrancyGuardReentrantCall,
+    ReentrancyGuarded, SafeTransferError, SafeTransferEthRejected, SafeTransferEthReverted,
+    SafeTransferInsufficientBalance,
+};
+use stylus_sdk::{
+    alloy_primitives::{Address, B256, U256},
+    alloy_sol_types::{sol, SolValue},
+    call::Call,
+    prelude::*,
+};
+
+// ERC721 interface for NFT transfers
+sol_interface! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function ownerOf(uint256 tokenId) external view returns (address);
+    }
+}
+
+// Events and custom error types
+sol! {
+    event AuctionCreated(address indexed seller, address indexed nftContract, uint256 indexed tokenId, uint256 reservePrice, uint256 endTime);
+    event BidPlaced(address indexed bidder, uint256 amount, uint256 endTime);
+    event AuctionExtended(uint256 endTime);
+    event AuctionSettled(address indexed winner, uint256 amount);
+    event AuctionCancelled();
+    event AllowlistSet(bytes32 root);
+    event Claimed(address indexed account, uint256 amount);
+
+    error AuctionNotActive();
+    error AuctionNotEnded();
+    error AuctionAlreadySettled();
+    error OnlySeller();
+    error ZeroAddress();
+    error InvalidDuration();
+    error InvalidIncrement();
+    error BidTooLow(uint256 minimum);
+    error SellerCannotBid();
+    error AuctionHasBids();
+    error NothingToClaim();
+    error NFTTransferFailed();
+    error NotNFTOwner();
+    error NotAllowlisted();
+}
+
+#[derive(SolidityError)]
+pub enum EnglishAuctionError {
+    AuctionNotActive(AuctionNotActive),
+    AuctionNotEnded(AuctionNotEnded),
+    AuctionAlreadySettled(AuctionAlreadySettled),
+    OnlySeller(OnlySeller),
+    ZeroAddress(ZeroAddress),
+    InvalidDuration(InvalidDuration),
+    InvalidIncrement(InvalidIncrement),
+    BidTooLow(BidTooLow),
+    SellerCannotBid(SellerCannotBid),
+    AuctionHasBids(AuctionHasBids),
+    NothingToClaim(NothingToClaim),
+    NFTTransferFailed(NFTTransferFailed),
+    NotNFTOwner(NotNFTOwner),
+    NotAllowlisted(NotAllowlisted),
+    InvalidInitialization(InvalidInitialization),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    InsufficientBalance(SafeTransferInsufficientBalance),
+    EthRejected(SafeTransferEthRejected),
+    EthReverted(SafeTransferEthReverted),
+}
+
+impl From<InitializableError> for EnglishAuctionError {
+    fn from(err: InitializableError) -> Self {
+        match err {
+            InitializableError::InvalidInitialization(e) => EnglishAuctionError::InvalidInitialization(e),
+        }
+    }
+}
+
+impl From<ReentrancyError> for EnglishAuctionError {
+    fn from(err: ReentrancyError) -> Self {
+        match err {
+            ReentrancyError::ReentrantCall(e) => EnglishAuctionError::ReentrantCall(e),
+            ReentrancyError::ReadOnlyReentrantCall(_) => {
+                EnglishAuctionError::ReentrantCall(ReentrancyGuardReentrantCall {})
+            }
+        }
+    }
+}
+
+impl From<SafeTransferError> for EnglishAuctionError {
+    fn from(err: SafeTransferError) -> Self {
+        match err {
+            SafeTransferError::InsufficientBalance(e) => EnglishAuctionError::InsufficientBalance(e),
+            SafeTransferError::EthRejected(e) => EnglishAuctionError::EthRejected(e),
+            SafeTransferError::EthReverted(e) => EnglishAuctionError::EthReverted(e),
+            SafeTransferError::Erc20FailedOperation(_) => {
+                EnglishAuctionError::InsufficientBalance(SafeTransferInsufficientBalance {})
+            }
+        }
+    }
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct EnglishAuction {
+        address seller;
+        address nft_contract;
+        uint256 token_id;
+        uint256 reserve_price;
+        uint256 min_increment;
+        uint256 start_time;
+        uint256 end_time;
+        /// Bids this close to `end_time` push it back to this long after the bid
+        uint256 extension_window;
+        address highest_bidder;
+        uint256 highest_bid;
+        /// Outbid bids and the seller's proceeds, waiting to be claimed
+        mapping(address => uint256) pending_returns;
+        bool settled;
+        /// Merkle root of the addresses allowed to bid, or zero for anyone
+        bytes32 allowlist_root;
+        Initializable init;
+        ReentrancyGuard guard;
+    }
+}
+
+impl ReentrancyGuarded for EnglishAuction {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.guard
+    }
+}
+
+#[public]
+impl EnglishAuction {
+    /// Start the auction of `seller`'s NFT, running for `duration` seconds
+    ///
+    /// The NFT is escrowed from the caller, who must own it and approve this
+    /// contract first: the seller themselves, or a factory holding it for
+    /// them. Runs once.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize(
+        &mut self,
+        seller: Address,
+        nft_contract: Address,
+        token_id: U256,
+        reserve_price: U256,
+        min_increment: U256,
+        duration: U256,
+        extension_window: U256,
+    ) -> Result<(), EnglishAuctionError> {
+        self.init.initializer()?;
+
+        if seller == Address::ZERO || nft_contract == Address::ZERO {
+            return Err(EnglishAuctionError::ZeroAddress(ZeroAddress {}));
+        }
+
+        if duration == U256::ZERO {
+            return Err(EnglishAuctionError::InvalidDuration(InvalidDuration {}));
+        }
+
+        if min_increment == U256::ZERO {
+            return Err(EnglishAuctionError::InvalidIncrement(InvalidIncrement {}));
+        }
+
+        let now = U256::from(self.vm().block_timestamp());
+        self.seller.set(seller);
+        self.nft_contract.set(nft_contract);
+        self.token_id.set(token_id);
+        self.reserve_price.set(reserve_price);
+        self.min_increment.set(min_increment);
+        self.start_time.set(now);
+        self.end_time.set(now + duration);
+        self.extension_window.set(extension_window);
+        self.guard.init();
+
+        self.escrow_nft()?;
+
+        log(self.vm(), AuctionCreated {
+            seller,
+            nftContract: nft_contract,
+            tokenId: token_id,
+            reservePrice: reserve_price,
+            endTime: now + duration,
+        });
+
+        Ok(())
+    }
+
+    /// Bid the sent value, which must be at least `minimum_bid()`
+    ///
+    /// The previous highest bid is credited to its bidder, who can `claim` it.
+    /// Fails once an allowlist is set; allowlisted bidders use `bid_with_proof`.
+    #[payable]
+    pub fn bid(&mut self) -> Result<(), EnglishAuctionError> {
+        if self.allowlist_root.get() != B256::ZERO {
+            return Err(EnglishAuctionError::NotAllowlisted(NotAllowlisted {}));
+        }
+        self.place_bid()
+    }
+
+    /// Bid the sent value as an allowlisted bidder
+    ///
+    /// `proof` places the caller under `allowlist_root()`, in a tree built
+    /// with `StandardMerkleTree.of(bidders, ["address"])`. Without an
+    /// allowlist the proof is ignored and this is a plain `bid`.
+    #[payable]
+    pub fn bid_with_proof(&mut self, proof: Vec<B256>) -> Result<(), EnglishAuctionError> {
+        let root = self.allowlist_root.get();
+        if root != B256::ZERO {
+            let leaf = stylus_merkle::leaf_hash(&self.vm().msg_sender().abi_encode());
+            if !stylus_merkle::verify(&proof, root, leaf) {
+                return Err(EnglishAuctionError::NotAllowlisted(NotAllowlisted {}));
+            }
+        }
+        self.place_bid()
+    }
+
+    /// Restrict bidding to the addresses under `root`, or lift it with zero
+    ///
+    /// Seller only, and only before the first bid, so nobody already bidding
+    /// is locked out.
+    pub fn set_allowlist(&mut self, root: B256) -> Result<(), EnglishAuctionError> {
+        if self.vm().msg_sender() != self.seller.get() {
+            return Err(EnglishAuctionError::OnlySeller(OnlySeller {}));
+        }
+
+        if !self.is_active() {
+            return Err(EnglishAuctionError::AuctionNotActive(AuctionNotActive {}));
+        }
+
+        if self.highest_bidder.get() != Address::ZERO {
+            return Err(EnglishAuctionError::AuctionHasBids(AuctionHasBids {}));
+        }
+
+        self.allowlist_root.set(root);
+        log(self.vm(), AllowlistSet { root });
+        Ok(())
+    }
+
+    /// Close the auction once it has ended (anyone)
+    ///
+    /// The NFT goes to the highest bidder and their bid is credited to the
+    /// seller, or the NFT goes back to the seller if nobody bid.
+    #[non_reentrant]
+    pub fn settle(&mut self) -> Result<(), EnglishAuctionError> {
+        if !self.init.is_initialized() {
+            return Err(EnglishAuctionError::AuctionNotActive(AuctionNotActive {}));
+        }
+
+        if self.settled.get() {
+            return Err(EnglishAuctionError::AuctionAlreadySettled(AuctionAlreadySettled {}));
+        }
+
+        if U256::from(self.vm().block_timestamp()) < self.end_time.get() {
+            return Err(EnglishAuctionError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+
+        self.settled.set(true);
+        let seller = self.seller.get();
+        let winner = self.highest_bidder.get();
+        let amount = self.highest_bid.get();
+
+        if winner == Address::ZERO {
+            self.release_nft(seller)?;
+        } else {
+            self.credit(seller, amount);
+            self.release_nft(winner)?;
+        }
+
+        log(self.vm(), AuctionSettled { winner, amount });
+        Ok(())
+    }
+
+    /// Call off the auction and take the NFT back (seller only, before any bid)
+    #[non_reentrant]
+    pub fn cancel(&mut self) -> Result<(), EnglishAuctionError> {
+        let seller = self.seller.get();
+        if self.vm().msg_sender() != seller {
+            return Err(EnglishAuctionError::OnlySeller(OnlySeller {}));
+        }
+
+        if self.settled.get() {
+            return Err(EnglishAuctionError::AuctionAlreadySettled(AuctionAlreadySettled {}));
+        }
+
+        if self.highest_bidder.get() != Address::ZERO {
+            return Err(EnglishAuctionError::AuctionHasBids(AuctionHasBids {}));
+        }
+
+        self.settled.set(true);
+        self.release_nft(seller)?;
+
+        log(self.vm(), AuctionCancelled {});
+        Ok(())
+    }
+
+    /// Send the caller their outbid bids, or the seller their proceeds
+    #[non_reentrant]
+    pub fn claim(&mut self) -> Result<(), EnglishAuctionError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_returns.get(account);
+        if amount == U256::ZERO {
+            return Err(EnglishAuctionError::NothingToClaim(NothingToClaim {}));
+        }
+
+        // Clear the balance before paying, so a re-entering claim gets nothing
+        self.pending_returns.setter(account).set(U256::ZERO);
+        safe_transfer_eth(self, account, amount, GasStipend::All)?;
+
+        log(self.vm(), Claimed { account, amount });
+        Ok(())
+    }
+
+    /// Smallest bid `bid` currently accepts
+    pub fn minimum_bid(&self) -> U256 {
+        if self.highest_bidder.get() == Address::ZERO {
+            // Zero-value bids can't be told apart from no bid
+            self.reserve_price.get().max(U256::from(1))
+        } else {
+            self.highest_bid.get() + self.min_increment.get()
+        }
+    }
+
+    /// Check if the auction takes bids
+    pub fn is_active(&self) -> bool {
+        self.init.is_initialized()
+            && !self.settled.get()
+            && U256::from(self.vm().block_timestamp()) < self.end_time.get()
+    }
+
+    /// Get auction details
+    pub fn get_auction_details(&self) -> (Address, Address, U256, U256, U256, U256, U256, Address, U256, bool) {
+        (
+            self.seller.get(),
+            self.nft_contract.get(),
+            self.token_id.get(),
+            self.reserve_price.get(),
+            self.min_increment.get(),
+            self.start_time.get(),
+            self.end_time.get(),
+            self.highest_bidder.get(),
+            self.highest_bid.get(),
+            self.settled.get(),
+        )
+    }
+
+    // View functions
+    pub fn seller(&self) -> Address {
+        self.seller.get()
+    }
+
+    pub fn nft_contract(&self) -> Address {
+        self.nft_contract.get()
+    }
+
+    pub fn token_id(&self) -> U256 {
+        self.token_id.get()
+    }
+
+    pub fn reserve_price(&self) -> U256 {
+        self.reserve_price.get()
+    }
+
+    pub fn min_increment(&self) -> U256 {
+        self.min_increment.get()
+    }
+
+    pub fn start_time(&self) -> U256 {
+        self.start_time.get()
+    }
+
+    pub fn end_time(&self) -> U256 {
+        self.end_time.get()
+    }
+
+    pub fn extension_window(&self) -> U256 {
+        self.extension_window.get()
+    }
+
+    pub fn highest_bidder(&self) -> Address {
+        self.highest_bidder.get()
+    }
+
+    pub fn highest_bid(&self) -> U256 {
+        self.highest_bid.get()
+    }
+
+    pub fn pending_returns(&self, account: Address) -> U256 {
+        self.pending_returns.get(account)
+    }
+
+    pub fn settled(&self) -> bool {
+        self.settled.get()
+    }
+
+    pub fn allowlist_root(&self) -> B256 {
+        self.allowlist_root.get()
+    }
+}
+
+impl EnglishAuction {
+    /// Take the sent value as a bid from the caller
+    fn place_bid(&mut self) -> Result<(), EnglishAuctionError> {
+        if !self.is_active() {
+            return Err(EnglishAuctionError::AuctionNotActive(AuctionNotActive {}));
+        }
+
+        let bidder = self.vm().msg_sender();
+        if bidder == self.seller.get() {
+            return Err(EnglishAuctionError::SellerCannotBid(SellerCannotBid {}));
+        }
+
+        let amount = self.vm().msg_value();
+        let minimum = self.minimum_bid();
+        if amount < minimum {
+            return Err(EnglishAuctionError::BidTooLow(BidTooLow { minimum }));
+        }
+
+        // Escrow the outbid bid for its bidder to pull
+        let previous_bidder = self.highest_bidder.get();
+        if previous_bidder != Address::ZERO {
+            self.credit(previous_bidder, self.highest_bid.get());
+        }
+        self.highest_bidder.set(bidder);
+        self.highest_bid.set(amount);
+
+        // Anti-snipe: a late bid leaves everyone else a full window to answer
+        let now = U256::from(self.vm().block_timestamp());
+        let extended_end = now + self.extension_window.get();
+        if extended_end > self.end_time.get() {
+            self.end_time.set(extended_end);
+            log(self.vm(), AuctionExtended { endTime: extended_end });
+        }
+
+        log(self.vm(), BidPlaced {
+            bidder,
+            amount,
+            endTime: self.end_time.get(),
+        });
+
+        Ok(())
+    }
+
+    /// Add `amount` to what `account` can claim
+    fn credit(&mut self, account: Address, amount: U256) {
+        let mut pending = self.pending_returns.setter(account);
+        let balance = pending.get();
+        pending.set(balance + amount);
+    }
+
+    /// Take custody of the NFT, pulling it from the caller
+    ///
+    /// An NFT already held here is never accepted, since anyone could claim
+    /// to be the seller of it.
+    fn escrow_nft(&mut self) -> Result<(), EnglishAuctionError> {
+        let nft_contract = IERC721::new(self.nft_contract.get());
+        let token_id = self.token_id.get();
+        let auction = self.vm().contract_address();
+        let caller = self.vm().msg_sender();
+
+        let owner = nft_contract
+            .owner_of(Call::new_in(self), token_id)
+            .map_err(|_| EnglishAuctionError::NotNFTOwner(NotNFTOwner {}))?;
+        if owner != caller {
+            return Err(EnglishAuctionError::NotNFTOwner(NotNFTOwner {}));
+        }
+
+        nft_contract
+            .transfer_from(Call::new_in(self), caller, auction, token_id)
+            .map_err(|_| EnglishAuctionError::NFTTransferFailed(NFTTransferFailed {}))
+    }
+
+    /// Hand the escrowed NFT to `to`
+    fn release_nft(&mut self, to: Address) -> Result<(), EnglishAuctionError> {
+        let nft_contract = IERC721::new(self.nft_contract.get());
+        let token_id = self.token_id.get();
+        let auction = self.vm().contract_address();
+
+        nft_contract
+            .transfer_from(Call::new_in(self), auction, to, token_id)
+            .map_err(|_| EnglishAuctionError::NFTTransferFailed(NFTTransferFailed {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_sol_types::SolCall;
+    use stylus_sdk::testing::*;
+
+    sol! {
+        function transferFrom(address from, address to, uint256 tokenId);
+        function ownerOf(uint256 tokenId);
+    }
+
+    const TOKEN_ID: u64 = 7;
+    const RESERVE: u64 = 1_000;
+    const INCREMENT: u64 = 100;
+    const START: u64 = 10_000;
+    const DURATION: u64 = 3_600;
+    const WINDOW: u64 = 600;
+
+    fn seller() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn alice() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([3u8; 20])
+    }
+
+    fn nft() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn mock_owner(vm: &TestVM, owner: Address) {
+        let calldata = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        let mut output = vec![0u8; 12];
+        output.extend_from_slice(owner.as_slice());
+        vm.mock_static_call(nft(), calldata, Ok(output));
+    }
+
+    fn mock_transfer(vm: &TestVM, from: Address, to: Address) {
+        let calldata = transferFromCall { from, to, tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+    }
+
+    /// An auction of the seller's NFT started at `START`
+    fn deploy(vm: &TestVM) -> EnglishAuction {
+        let mut contract = EnglishAuction::from(vm);
+        let auction = contract.vm().contract_address();
+        mock_owner(vm, seller());
+        mock_transfer(vm, seller(), auction);
+
+        vm.set_block_timestamp(START);
+        vm.set_sender(seller());
+        assert!(contract
+            .initialize(
+                seller(),
+                nft(),
+                U256::from(TOKEN_ID),
+                U256::from(RESERVE),
+                U256::from(INCREMENT),
+                U256::from(DURATION),
+                U256::from(WINDOW),
+            )
+            .is_ok());
+        contract
+    }
+
+    fn bid(contract: &mut EnglishAuction, vm: &TestVM, bidder: Address, amount: u64) -> Result<(), EnglishAuctionError> {
+        vm.set_sender(bidder);
+        vm.set_value(U256::from(amount));
+        contract.bid()
+    }
+
+    #[test]
+    fn test_initialize_runs_once() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(contract.is_active());
+        assert_eq!(contract.end_time(), U256::from(START + DURATION));
+        assert_eq!(contract.minimum_bid(), U256::from(RESERVE));
+
+        let result = contract.initialize(
+            alice(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::ZERO,
+            U256::from(1),
+            U256::from(1),
+            U256::ZERO,
+        );
+        assert!(matches!(result, Err(EnglishAuctionError::InvalidInitialization(_))));
+        assert_eq!(contract.seller(), seller());
+    }
+
+    fn start(contract: &mut EnglishAuction, seller: Address) -> Result<(), EnglishAuctionError> {
+        contract.initialize(
+            seller,
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(RESERVE),
+            U256::from(INCREMENT),
+            U256::from(DURATION),
+            U256::from(WINDOW),
+        )
+    }
+
+    #[test]
+    fn test_initialize_escrows_from_caller() {
+        // Nobody else can have the seller's NFT pulled in
+        let vm = TestVM::default();
+        let mut contract = EnglishAuction::from(&vm);
+        mock_owner(&vm, seller());
+        vm.set_sender(alice());
+        assert!(matches!(start(&mut contract, seller()), Err(EnglishAuctionError::NotNFTOwner(_))));
+
+        // Nor start the auction of an NFT already sent here, even as its
+        // seller
+        let vm = TestVM::default();
+        let mut contract = EnglishAuction::from(&vm);
+        mock_owner(&vm, contract.vm().contract_address());
+        vm.set_sender(alice());
+        assert!(matches!(start(&mut contract, alice()), Err(EnglishAuctionError::NotNFTOwner(_))));
+        let vm = TestVM::default();
+        let mut contract = EnglishAuction::from(&vm);
+        mock_owner(&vm, contract.vm().contract_address());
+        vm.set_sender(seller());
+        assert!(matches!(start(&mut contract, seller()), Err(EnglishAuctionError::NotNFTOwner(_))));
+
+        // A factory holding the NFT escrows it for the seller
+        let vm = TestVM::default();
+        let mut contract = EnglishAuction::from(&vm);
+        let factory = Address::from([5u8; 20]);
+        mock_owner(&vm, factory);
+        mock_transfer(&vm, factory, contract.vm().contract_address());
+        vm.set_sender(factory);
+        assert!(start(&mut contract, seller()).is_ok());
+        assert_eq!(contract.seller(), seller());
+    }
+
+    #[test]
+    fn test_bids_must_meet_reserve_and_increment() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        assert!(matches!(bid(&mut contract, &vm, seller(), RESERVE), Err(EnglishAuctionError::SellerCannotBid(_))));
+        assert!(matches!(bid(&mut contract, &vm, alice(), RESERVE - 1), Err(EnglishAuctionError::BidTooLow(_))));
+        assert!(bid(&mut contract, &vm, alice(), RESERVE).is_ok());
+
+        assert_eq!(contract.minimum_bid(), U256::from(RESERVE + INCREMENT));
+        assert!(matches!(
+            bid(&mut contract, &vm, bob(), RESERVE + INCREMENT - 1),
+            Err(EnglishAuctionError::BidTooLow(_))
+        ));
+        assert!(bid(&mut contract, &vm, bob(), RESERVE + INCREMENT).is_ok());
+
+        // The outbid bid is escrowed for alice to pull
+        assert_eq!(contract.highest_bidder(), bob());
+        assert_eq!(contract.highest_bid(), U256::from(RESERVE + INCREMENT));
+        assert_eq!(contract.pending_returns(alice()), U256::from(RESERVE));
+        assert_eq!(contract.pending_returns(bob()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_late_bids_extend_the_auction() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        // Bids before the window leave the end alone
+        vm.set_block_timestamp(START + DURATION - WINDOW - 1);
+        assert!(bid(&mut contract, &vm, alice(), RESERVE).is_ok());
+        assert_eq!(contract.end_time(), U256::from(START + DURATION));
+
+        // A bid in the last minutes gives everyone a full window to answer
+        let late = START + DURATION - 10;
+        vm.set_block_timestamp(late);
+        assert!(bid(&mut contract, &vm, bob(), RESERVE + INCREMENT).is_ok());
+        assert_eq!(contract.end_time(), U256::from(late + WINDOW));
+
+        vm.set_block_timestamp(START + DURATION);
+        assert!(contract.is_active());
+        vm.set_block_timestamp(late + WINDOW);
+        assert!(!contract.is_active());
+        assert!(matches!(
+            bid(&mut contract, &vm, alice(), RESERVE * 2),
+            Err(EnglishAuctionError::AuctionNotActive(_))
+        ));
+    }
+
+    #[test]
+    fn test_settle_credits_seller_and_sends_nft_to_winner() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(bid(&mut contract, &vm, alice(), RESERVE).is_ok());
+        assert!(bid(&mut contract, &vm, bob(), RESERVE + INCREMENT).is_ok());
+
+        assert!(matches!(contract.settle(), Err(EnglishAuctionError::AuctionNotEnded(_))));
+
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, bob());
+        vm.set_block_timestamp(START + DURATION);
+        assert!(contract.settle().is_ok());
+        assert!(contract.settled());
+        assert_eq!(contract.pending_returns(seller()), U256::from(RESERVE + INCREMENT));
+        assert_eq!(contract.pending_returns(alice()), U256::from(RESERVE));
+
+        assert!(matches!(contract.settle(), Err(EnglishAuctionError::AuctionAlreadySettled(_))));
+        vm.set_sender(seller());
+        assert!(matches!(contract.cancel(), Err(EnglishAuctionError::AuctionAlreadySettled(_))));
+    }
+
+    #[test]
+    fn test_settle_without_bids_returns_nft() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, seller());
+
+        vm.set_block_timestamp(START + DURATION);
+        assert!(contract.settle().is_ok());
+        assert_eq!(contract.pending_returns(seller()), U256::ZERO);
+    }
+
+    fn allowlist_leaf(account: Address) -> B256 {
+        stylus_merkle::leaf_hash(&account.abi_encode())
+    }
+
+    #[test]
+    fn test_allowlist_gates_bids() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let carol = Address::from([4u8; 20]);
+        let root = stylus_merkle::hash_pair(allowlist_leaf(alice()), allowlist_leaf(bob()));
+
+        vm.set_sender(alice());
+        assert!(matches!(contract.set_allowlist(root), Err(EnglishAuctionError::OnlySeller(_))));
+        vm.set_sender(seller());
+        assert!(contract.set_allowlist(root).is_ok());
+        assert_eq!(contract.allowlist_root(), root);
+
+        // Plain bids and bids with someone else's proof are turned away
+        assert!(matches!(
+            bid(&mut contract, &vm, alice(), RESERVE),
+            Err(EnglishAuctionError::NotAllowlisted(_))
+        ));
+        vm.set_sender(carol);
+        assert!(matches!(
+            contract.bid_with_proof(vec![allowlist_leaf(bob())]),
+            Err(EnglishAuctionError::NotAllowlisted(_))
+        ));
+
+        vm.set_sender(alice());
+        assert!(contract.bid_with_proof(vec![allowlist_leaf(bob())]).is_ok());
+        assert_eq!(contract.highest_bidder(), alice());
+
+        // The list is fixed once bidding has started
+        vm.set_sender(seller());
+        assert!(matches!(contract.set_allowlist(B256::ZERO), Err(EnglishAuctionError::AuctionHasBids(_))));
+    }
+
+    #[test]
+    fn test_cancel_only_before_bids() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, seller());
+
+        vm.set_sender(alice());
+        assert!(matches!(contract.cancel(), Err(EnglishAuctionError::OnlySeller(_))));
+
+        vm.set_sender(seller());
+        assert!(contract.cancel().is_ok());
+        assert!(!contract.is_active());
+
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(bid(&mut contract, &vm, alice(), RESERVE).is_ok());
+        vm.set_sender(seller());
+        assert!(matches!(contract.cancel(), Err(EnglishAuctionError::AuctionHasBids(_))));
+    }
+
+    #[test]
+    fn test_claim_requires_balance() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(alice());
+        assert!(matches!(contract.claim(), Err(EnglishAuctionError::NothingToClaim(_))));
+    }
+}
@@ -0,0 +1,290 @@
+//!
+//! English Auction Factory Contract
+//!
+//! This contract creates and manages English auction instances.
+//! Each auction is an EIP-1167 minimal proxy delegating to one deployed
+//! `english_auction` implementation, so creating an auction costs a small
+//! clone deployment instead of the full program.
+
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    alloy_primitives::{keccak256, U256, Address},
+    alloy_sol_types::sol,
+    call::Call,
+    prelude::*,
+    deploy::RawDeploy,
+};
+use stylus_common::{Initializable, Ownable2Step};
+
+sol_interface! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function approve(address to, uint256 tokenId) external;
+    }
+
+    interface IEnglishAuction {
+        function initialize(address seller, address nftContract, uint256 tokenId, uint256 reservePrice, uint256 minIncrement, uint256 duration, uint256 extensionWindow) external;
+    }
+}
+
+sol! {
+    event AuctionCreated(uint256 indexed auctionId, address indexed auction, address indexed seller);
+}
+
+/// EIP-1167 creation code for a minimal proxy delegating to `implementation`
+pub fn clone_code(implementation: Address) -> Vec<u8> {
+    let mut code = Vec::with_capacity(55);
+    code.extend_from_slice(&[
+        0x3d, 0x60, 0x2d, 0x80, 0x60, 0x0a, 0x3d, 0x39, 0x81, 0xf3, 0x36, 0x3d, 0x3d, 0x37, 0x3d, 0x3d, 0x3d, 0x36,
+        0x3d, 0x73,
+    ]);
+    code.extend_from_slice(implementation.as_slice());
+    code.extend_from_slice(&[
+        0x5a, 0xf4, 0x3d, 0x82, 0x80, 0x3e, 0x90, 0x3d, 0x91, 0x60, 0x2b, 0x57, 0xfd, 0x5b, 0xf3,
+    ]);
+    code
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct EnglishAuctionFactory {
+        address implementation;
+        uint256 auction_count;
+        mapping(uint256 => address) auctions;
+        Ownable2Step ownership;
+        Initializable init;
+    }
+}
+
+#[public]
+impl EnglishAuctionFactory {
+    /// Initialize the factory with the deployed auction `implementation`,
+    /// making the caller its owner
+    ///
+    /// Runs once, so it can't be replayed to take over the factory.
+    pub fn new(&mut self, implementation: Address) -> Result<(), Vec<u8>> {
+        self.init.initializer()?;
+        if implementation == Address::ZERO {
+            return Err("Invalid implementation".as_bytes().to_vec());
+        }
+        self.ownership.init(self.vm().msg_sender())?;
+        self.implementation.set(implementation);
+        Ok(())
+    }
+
+    /// Create an English auction of the caller's NFT
+    ///
+    /// The factory takes the NFT and hands it to the new auction, which pulls
+    /// it as it starts, so the caller must approve the factory first.
+    pub fn create_auction(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        reserve_price: U256,
+        min_increment: U256,
+        duration: U256,
+        extension_window: U256,
+    ) -> Result<Address, Vec<u8>> {
+        if nft_contract == Address::ZERO {
+            return Err("Invalid NFT contract".as_bytes().to_vec());
+        }
+
+        if min_increment == U256::from(0) {
+            return Err("Minimum increment must be greater than zero".as_bytes().to_vec());
+        }
+
+        if duration == U256::from(0) {
+            return Err("Duration must be greater than zero".as_bytes().to_vec());
+        }
+
+        let auction_id = self.auction_count.get() + U256::from(1);
+        let seller = self.vm().msg_sender();
+
+        // Create salt from auction parameters for deterministic addresses
+        let mut salt_data = Vec::new();
+        salt_data.extend_from_slice(&auction_id.as_le_bytes());
+        salt_data.extend_from_slice(seller.as_slice());
+        salt_data.extend_from_slice(nft_contract.as_slice());
+        salt_data.extend_from_slice(&token_id.as_le_bytes());
+
+        let salt = keccak256(salt_data);
+        let bytecode = clone_code(self.implementation.get());
+
+        // Deploy the auction clone using RawDeploy with CREATE2
+        let auction = unsafe {
+            RawDeploy::new()
+                .salt(salt)
+                .deploy(self.vm(), &bytecode, U256::from(0))
+                .map_err(|e| {
+                    let mut err = "Failed to deploy auction contract: ".as_bytes().to_vec();
+                    err.extend_from_slice(&e);
+                    err
+                })?
+        };
+
+        // Take the NFT and let the auction pull it from us as it starts, in
+        // the same transaction so nobody can initialize the clone first
+        let factory = self.vm().contract_address();
+        let nft = IERC721::new(nft_contract);
+        nft.transfer_from(Call::new_in(self), seller, factory, token_id)
+            .map_err(|_| "NFT transfer failed".as_bytes().to_vec())?;
+        nft.approve(Call::new_in(self), auction, token_id)
+            .map_err(|_| "NFT approval failed".as_bytes().to_vec())?;
+        IEnglishAuction::new(auction)
+            .initialize(
+                Call::new_in(self),
+                seller,
+                nft_contract,
+                token_id,
+                reserve_price,
+                min_increment,
+                duration,
+                extension_window,
+            )
+            .map_err(|_| "Failed to initialize auction".as_bytes().to_vec())?;
+
+        // Store the deployed auction address
+        self.auction_count.set(auction_id);
+        self.auctions.setter(auction_id).set(auction);
+
+        log(self.vm(), AuctionCreated { auctionId: auction_id, auction, seller });
+
+        Ok(auction)
+    }
+
+    /// Get total number of auctions created
+    pub fn get_auction_count(&self) -> U256 {
+        self.auction_count.get()
+    }
+
+    /// Get auction address by ID
+    pub fn get_auction(&self, auction_id: U256) -> Address {
+        self.auctions.get(auction_id)
+    }
+
+    /// Get the auction implementation new auctions delegate to
+    pub fn get_implementation(&self) -> Address {
+        self.implementation.get()
+    }
+
+    /// Point new auctions at another implementation (owner only)
+    ///
+    /// Auctions already created keep delegating to the one they were cloned from.
+    pub fn set_implementation(&mut self, implementation: Address) -> Result<(), Vec<u8>> {
+        self.ownership.only_owner()?;
+        if implementation == Address::ZERO {
+            return Err("Invalid implementation".as_bytes().to_vec());
+        }
+        self.implementation.set(implementation);
+        Ok(())
+    }
+
+    /// Get factory owner
+    pub fn get_owner(&self) -> Address {
+        self.ownership.owner()
+    }
+
+    /// Get the owner nominated by `transfer_ownership` who hasn't accepted yet
+    pub fn get_pending_owner(&self) -> Address {
+        self.ownership.pending_owner()
+    }
+
+    /// Nominate `new_owner`, who takes over by calling `accept_ownership` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        Ok(self.ownership.transfer_ownership(new_owner)?)
+    }
+
+    /// Take over the factory (nominated owner only)
+    pub fn accept_ownership(&mut self) -> Result<(), Vec<u8>> {
+        Ok(self.ownership.accept_ownership()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    fn owner() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn implementation() -> Address {
+        Address::from([0xaa; 20])
+    }
+
+    fn err(message: &str) -> Result<(), Vec<u8>> {
+        Err(message.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn test_clone_code_embeds_implementation() {
+        let code = clone_code(implementation());
+        assert_eq!(code.len(), 55);
+        assert_eq!(&code[20..40], implementation().as_slice());
+        assert_eq!(code[..10], [0x3d, 0x60, 0x2d, 0x80, 0x60, 0x0a, 0x3d, 0x39, 0x81, 0xf3]);
+    }
+
+    #[test]
+    fn test_new_runs_once() {
+        let vm = TestVM::default();
+        let mut contract = EnglishAuctionFactory::from(&vm);
+        vm.set_sender(owner());
+        assert_eq!(contract.new(Address::ZERO), err("Invalid implementation"));
+
+        let vm = TestVM::default();
+        let mut contract = EnglishAuctionFactory::from(&vm);
+        vm.set_sender(owner());
+        assert!(contract.new(implementation()).is_ok());
+        assert_eq!(contract.get_owner(), owner());
+        assert_eq!(contract.get_implementation(), implementation());
+
+        vm.set_sender(Address::from([2u8; 20]));
+        assert!(contract.new(Address::from([2u8; 20])).is_err());
+        assert_eq!(contract.get_owner(), owner());
+    }
+
+    #[test]
+    fn test_set_implementation_is_owner_only() {
+        let vm = TestVM::default();
+        let mut contract = EnglishAuctionFactory::from(&vm);
+        vm.set_sender(owner());
+        assert!(contract.new(implementation()).is_ok());
+
+        let upgraded = Address::from([0xbb; 20]);
+        vm.set_sender(Address::from([2u8; 20]));
+        assert!(contract.set_implementation(upgraded).is_err());
+
+        vm.set_sender(owner());
+        assert!(contract.set_implementation(upgraded).is_ok());
+        assert_eq!(contract.get_implementation(), upgraded);
+    }
+
+    #[test]
+    fn test_create_auction_validates_parameters() {
+        let vm = TestVM::default();
+        let mut contract = EnglishAuctionFactory::from(&vm);
+        vm.set_sender(owner());
+        assert!(contract.new(implementation()).is_ok());
+
+        let nft = Address::from([9u8; 20]);
+        let (one, day) = (U256::from(1), U256::from(86_400));
+        let create = |contract: &mut EnglishAuctionFactory, nft, increment, duration| {
+            contract.create_auction(nft, one, one, increment, duration, U256::ZERO).map(|_| ())
+        };
+        assert_eq!(create(&mut contract, Address::ZERO, one, day), err("Invalid NFT contract"));
+        assert_eq!(
+            create(&mut contract, nft, U256::ZERO, day),
+            err("Minimum increment must be greater than zero")
+        );
+        assert_eq!(create(&mut contract, nft, one, U256::ZERO), err("Duration must be greater than zero"));
+        assert_eq!(contract.get_auction_count(), U256::ZERO);
+    }
+}
@@ -0,0 +1,787 @@
+//!
+//! Escrow Contract
+//!
+//! Holds ETH, an ERC20 amount or an NFT deposited by a payer for a payee,
+//! with a third-party arbiter. Funds reach the payee only once the payee has
+//! confirmed delivery (the payer then releases, in parts if they like) or on
+//! the arbiter's decision. Either party can raise a dispute, which freezes the
+//! deal until the arbiter splits it. After the deadline an undelivered deal
+//! can be refunded to the payer and an undisputed delivered one claimed by
+//! the payee.
+//!
+//! ETH payouts are credited and pulled with `withdraw`, so a party that can't
+//! take ETH never blocks the other. Tokens and NFTs are sent directly.
+
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+extern crate alloc;
+
+use stylus_reentrancy_guard::{
+    non_reentrant, safe_erc20_transfer, safe_erc20_transfer_from, safe_transfer_eth, GasStipend, ReentrancyError,
+    ReentrancyGuard, ReentrancyGuardReentrantCall, ReentrancyGuarded, SafeERC20FailedOperation, SafeTransferError,
+    SafeTransferEthRejected, SafeTransferEthReverted, SafeTransferInsufficientBalance,
+};
+use stylus_sdk::{
+    alloy_primitives::{aliases::U8, Address, U256},
+    alloy_sol_types::sol,
+    call::Call,
+    prelude::*,
+};
+
+/// Deposited asset kinds
+pub const ASSET_ETH: u8 = 0;
+pub const ASSET_ERC20: u8 = 1;
+pub const ASSET_NFT: u8 = 2;
+
+// ERC721 interface for NFT transfers
+sol_interface! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+    }
+}
+
+// Events and custom error types
+sol! {
+    event Deposited(uint256 indexed dealId, address indexed payer, address indexed payee, address arbiter, uint8 asset, address token, uint256 tokenId, uint256 amount, uint256 deadline);
+    event DeliveryConfirmed(uint256 indexed dealId);
+    event Released(uint256 indexed dealId, address indexed payee, uint256 amount);
+    event Refunded(uint256 indexed dealId, address indexed payer, uint256 amount);
+    event DisputeRaised(uint256 indexed dealId, address indexed raisedBy);
+    event DisputeResolved(uint256 indexed dealId, uint256 payeeAmount, uint256 payerAmount);
+    event Withdrawn(address indexed account, uint256 amount);
+
+    error DealNotFound();
+    error DealClosed();
+    error OnlyPayer();
+    error OnlyPayee();
+    error OnlyArbiter();
+    error OnlyParty();
+    error InvalidParties();
+    error InvalidAmount();
+    error InvalidDeadline();
+    error DeliveryNotConfirmed();
+    error DeliveryAlreadyConfirmed();
+    error DealDisputed();
+    error DealNotDisputed();
+    error DeadlineNotPassed();
+    error ExceedsRemaining(uint256 remaining, uint256 amount);
+    error NothingToWithdraw();
+    error NFTTransferFailed();
+}
+
+#[derive(SolidityError)]
+pub enum EscrowError {
+    DealNotFound(DealNotFound),
+    DealClosed(DealClosed),
+    OnlyPayer(OnlyPayer),
+    OnlyPayee(OnlyPayee),
+    OnlyArbiter(OnlyArbiter),
+    OnlyParty(OnlyParty),
+    InvalidParties(InvalidParties),
+    InvalidAmount(InvalidAmount),
+    InvalidDeadline(InvalidDeadline),
+    DeliveryNotConfirmed(DeliveryNotConfirmed),
+    DeliveryAlreadyConfirmed(DeliveryAlreadyConfirmed),
+    DealDisputed(DealDisputed),
+    DealNotDisputed(DealNotDisputed),
+    DeadlineNotPassed(DeadlineNotPassed),
+    ExceedsRemaining(ExceedsRemaining),
+    NothingToWithdraw(NothingToWithdraw),
+    NFTTransferFailed(NFTTransferFailed),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    InsufficientBalance(SafeTransferInsufficientBalance),
+    EthRejected(SafeTransferEthRejected),
+    EthReverted(SafeTransferEthReverted),
+    Erc20FailedOperation(SafeERC20FailedOperation),
+}
+
+impl From<ReentrancyError> for EscrowError {
+    fn from(err: ReentrancyError) -> Self {
+        match err {
+            ReentrancyError::ReentrantCall(e) => EscrowError::ReentrantCall(e),
+            ReentrancyError::ReadOnlyReentrantCall(_) => EscrowError::ReentrantCall(ReentrancyGuardReentrantCall {}),
+        }
+    }
+}
+
+impl From<SafeTransferError> for EscrowError {
+    fn from(err: SafeTransferError) -> Self {
+        match err {
+            SafeTransferError::InsufficientBalance(e) => EscrowError::InsufficientBalance(e),
+            SafeTransferError::EthRejected(e) => EscrowError::EthRejected(e),
+            SafeTransferError::EthReverted(e) => EscrowError::EthReverted(e),
+            SafeTransferError::Erc20FailedOperation(e) => EscrowError::Erc20FailedOperation(e),
+        }
+    }
+}
+
+sol_storage! {
+    pub struct Deal {
+        address payer;
+        address payee;
+        address arbiter;
+        uint8 asset;
+        /// ERC20 or NFT contract, zero for ETH
+        address token;
+        uint256 token_id;
+        /// Deposited amount; 1 for an NFT
+        uint256 amount;
+        /// Amount released to the payee or refunded to the payer so far
+        uint256 paid_out;
+        uint256 deadline;
+        bool delivered;
+        bool disputed;
+        bool closed;
+    }
+
+    #[entrypoint]
+    pub struct Escrow {
+        uint256 deal_count;
+        mapping(uint256 => Deal) deals;
+        /// ETH released or refunded to each account, waiting to be withdrawn
+        mapping(address => uint256) pending_withdrawals;
+        ReentrancyGuard guard;
+    }
+}
+
+impl ReentrancyGuarded for Escrow {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.guard
+    }
+}
+
+#[public]
+impl Escrow {
+    #[constructor]
+    pub fn constructor(&mut self) {
+        self.guard.init();
+    }
+
+    /// Escrow the sent ETH for `payee`, returning the deal id
+    #[payable]
+    pub fn deposit_eth(&mut self, payee: Address, arbiter: Address, deadline: U256) -> Result<U256, EscrowError> {
+        let amount = self.vm().msg_value();
+        self.open_deal(payee, arbiter, ASSET_ETH, Address::ZERO, U256::ZERO, amount, deadline)
+    }
+
+    /// Escrow `amount` of `token` for `payee`, returning the deal id
+    ///
+    /// The caller must approve the escrow for `amount` first. Fee-on-transfer
+    /// tokens aren't supported.
+    #[non_reentrant]
+    pub fn deposit_erc20(
+        &mut self,
+        payee: Address,
+        arbiter: Address,
+        token: Address,
+        amount: U256,
+        deadline: U256,
+    ) -> Result<U256, EscrowError> {
+        if token == Address::ZERO {
+            return Err(EscrowError::InvalidParties(InvalidParties {}));
+        }
+        let deal_id = self.open_deal(payee, arbiter, ASSET_ERC20, token, U256::ZERO, amount, deadline)?;
+        let payer = self.vm().msg_sender();
+        safe_erc20_transfer_from(token, payer, self.vm().contract_address(), amount)?;
+        Ok(deal_id)
+    }
+
+    /// Escrow the NFT `token_id` of `nft_contract` for `payee`, returning the deal id
+    ///
+    /// The caller must approve the escrow for the NFT first.
+    #[non_reentrant]
+    pub fn deposit_nft(
+        &mut self,
+        payee: Address,
+        arbiter: Address,
+        nft_contract: Address,
+        token_id: U256,
+        deadline: U256,
+    ) -> Result<U256, EscrowError> {
+        if nft_contract == Address::ZERO {
+            return Err(EscrowError::InvalidParties(InvalidParties {}));
+        }
+        let deal_id = self.open_deal(payee, arbiter, ASSET_NFT, nft_contract, token_id, U256::from(1), deadline)?;
+        let payer = self.vm().msg_sender();
+        let escrow = self.vm().contract_address();
+        IERC721::new(nft_contract)
+            .transfer_from(Call::new_in(self), payer, escrow, token_id)
+            .map_err(|_| EscrowError::NFTTransferFailed(NFTTransferFailed {}))?;
+        Ok(deal_id)
+    }
+
+    /// Confirm the goods or services were delivered (payee only)
+    ///
+    /// Lets the payer release funds, and the payee claim them after the
+    /// deadline unless the payer disputes first.
+    pub fn confirm_delivery(&mut self, deal_id: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        let deal = self.deals.get(deal_id);
+        if self.vm().msg_sender() != deal.payee.get() {
+            return Err(EscrowError::OnlyPayee(OnlyPayee {}));
+        }
+        if deal.delivered.get() {
+            return Err(EscrowError::DeliveryAlreadyConfirmed(DeliveryAlreadyConfirmed {}));
+        }
+
+        self.deals.setter(deal_id).delivered.set(true);
+        log(self.vm(), DeliveryConfirmed { dealId: deal_id });
+        Ok(())
+    }
+
+    /// Release `amount` to the payee once delivery is confirmed (payer only)
+    ///
+    /// Can be called several times to pay in parts; an NFT is released whole.
+    #[non_reentrant]
+    pub fn release(&mut self, deal_id: U256, amount: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        let deal = self.deals.get(deal_id);
+        if self.vm().msg_sender() != deal.payer.get() {
+            return Err(EscrowError::OnlyPayer(OnlyPayer {}));
+        }
+        if !deal.delivered.get() {
+            return Err(EscrowError::DeliveryNotConfirmed(DeliveryNotConfirmed {}));
+        }
+        if deal.disputed.get() {
+            return Err(EscrowError::DealDisputed(DealDisputed {}));
+        }
+
+        self.pay_payee(deal_id, amount)
+    }
+
+    /// Release `amount` to the payee on the arbiter's decision (arbiter only)
+    #[non_reentrant]
+    pub fn arbiter_release(&mut self, deal_id: U256, amount: U256) -> Result<(), EscrowError> {
+        self.require_arbiter(deal_id)?;
+        self.pay_payee(deal_id, amount)
+    }
+
+    /// Refund `amount` to the payer on the arbiter's decision (arbiter only)
+    #[non_reentrant]
+    pub fn arbiter_refund(&mut self, deal_id: U256, amount: U256) -> Result<(), EscrowError> {
+        self.require_arbiter(deal_id)?;
+        self.pay_payer(deal_id, amount)
+    }
+
+    /// Freeze the deal until the arbiter resolves it (payer or payee)
+    pub fn raise_dispute(&mut self, deal_id: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        let deal = self.deals.get(deal_id);
+        let sender = self.vm().msg_sender();
+        if sender != deal.payer.get() && sender != deal.payee.get() {
+            return Err(EscrowError::OnlyParty(OnlyParty {}));
+        }
+        if deal.disputed.get() {
+            return Err(EscrowError::DealDisputed(DealDisputed {}));
+        }
+
+        self.deals.setter(deal_id).disputed.set(true);
+        log(self.vm(), DisputeRaised { dealId: deal_id, raisedBy: sender });
+        Ok(())
+    }
+
+    /// Settle a disputed deal: `payee_amount` of the remainder to the payee
+    /// and the rest back to the payer (arbiter only)
+    ///
+    /// For an NFT, `payee_amount` is 1 to award it to the payee and 0 to return it.
+    #[non_reentrant]
+    pub fn resolve_dispute(&mut self, deal_id: U256, payee_amount: U256) -> Result<(), EscrowError> {
+        self.require_arbiter(deal_id)?;
+        if !self.deals.get(deal_id).disputed.get() {
+            return Err(EscrowError::DealNotDisputed(DealNotDisputed {}));
+        }
+
+        let remaining = self.remaining(deal_id);
+        if payee_amount > remaining {
+            return Err(EscrowError::ExceedsRemaining(ExceedsRemaining { remaining, amount: payee_amount }));
+        }
+        let payer_amount = remaining - payee_amount;
+        if payee_amount > U256::ZERO {
+            self.pay_payee(deal_id, payee_amount)?;
+        }
+        if payer_amount > U256::ZERO {
+            self.pay_payer(deal_id, payer_amount)?;
+        }
+
+        log(self.vm(), DisputeResolved {
+            dealId: deal_id,
+            payeeAmount: payee_amount,
+            payerAmount: payer_amount,
+        });
+        Ok(())
+    }
+
+    /// Take back the remainder of an undelivered, undisputed deal after its deadline (payer only)
+    #[non_reentrant]
+    pub fn refund_expired(&mut self, deal_id: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        let deal = self.deals.get(deal_id);
+        if self.vm().msg_sender() != deal.payer.get() {
+            return Err(EscrowError::OnlyPayer(OnlyPayer {}));
+        }
+        if deal.delivered.get() {
+            return Err(EscrowError::DeliveryAlreadyConfirmed(DeliveryAlreadyConfirmed {}));
+        }
+        self.require_expired(deal_id)?;
+
+        let remaining = self.remaining(deal_id);
+        self.pay_payer(deal_id, remaining)
+    }
+
+    /// Take the remainder of a delivered, undisputed deal after its deadline (payee only)
+    #[non_reentrant]
+    pub fn claim_expired(&mut self, deal_id: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        let deal = self.deals.get(deal_id);
+        if self.vm().msg_sender() != deal.payee.get() {
+            return Err(EscrowError::OnlyPayee(OnlyPayee {}));
+        }
+        if !deal.delivered.get() {
+            return Err(EscrowError::DeliveryNotConfirmed(DeliveryNotConfirmed {}));
+        }
+        self.require_expired(deal_id)?;
+
+        let remaining = self.remaining(deal_id);
+        self.pay_payee(deal_id, remaining)
+    }
+
+    /// Send the caller the ETH released or refunded to them
+    #[non_reentrant]
+    pub fn withdraw(&mut self) -> Result<(), EscrowError> {
+        let account = self.vm().msg_sender();
+        let amount = self.pending_withdrawals.get(account);
+        if amount == U256::ZERO {
+            return Err(EscrowError::NothingToWithdraw(NothingToWithdraw {}));
+        }
+
+        // Clear the balance before paying, so a re-entering withdraw gets nothing
+        self.pending_withdrawals.setter(account).set(U256::ZERO);
+        safe_transfer_eth(self, account, amount, GasStipend::All)?;
+
+        log(self.vm(), Withdrawn { account, amount });
+        Ok(())
+    }
+
+    /// Amount still held for `deal_id`
+    pub fn remaining(&self, deal_id: U256) -> U256 {
+        let deal = self.deals.get(deal_id);
+        deal.amount.get() - deal.paid_out.get()
+    }
+
+    /// Get deal parties and asset: (payer, payee, arbiter, asset, token, token_id, amount)
+    pub fn get_deal(&self, deal_id: U256) -> (Address, Address, Address, u8, Address, U256, U256) {
+        let deal = self.deals.get(deal_id);
+        (
+            deal.payer.get(),
+            deal.payee.get(),
+            deal.arbiter.get(),
+            deal.asset.get().to::<u8>(),
+            deal.token.get(),
+            deal.token_id.get(),
+            deal.amount.get(),
+        )
+    }
+
+    /// Get deal progress: (paid_out, deadline, delivered, disputed, closed)
+    pub fn get_deal_status(&self, deal_id: U256) -> (U256, U256, bool, bool, bool) {
+        let deal = self.deals.get(deal_id);
+        (
+            deal.paid_out.get(),
+            deal.deadline.get(),
+            deal.delivered.get(),
+            deal.disputed.get(),
+            deal.closed.get(),
+        )
+    }
+
+    /// Get total number of deals opened
+    pub fn get_deal_count(&self) -> U256 {
+        self.deal_count.get()
+    }
+
+    /// Get an account's ETH waiting to be withdrawn
+    pub fn get_pending_withdrawal(&self, account: Address) -> U256 {
+        self.pending_withdrawals.get(account)
+    }
+}
+
+impl Escrow {
+    /// Record a new deal paid by the caller
+    #[allow(clippy::too_many_arguments)]
+    fn open_deal(
+        &mut self,
+        payee: Address,
+        arbiter: Address,
+        asset: u8,
+        token: Address,
+        token_id: U256,
+        amount: U256,
+        deadline: U256,
+    ) -> Result<U256, EscrowError> {
+        let payer = self.vm().msg_sender();
+        if payee == Address::ZERO || arbiter == Address::ZERO || payee == payer || arbiter == payer || arbiter == payee
+        {
+            return Err(EscrowError::InvalidParties(InvalidParties {}));
+        }
+        if amount == U256::ZERO {
+            return Err(EscrowError::InvalidAmount(InvalidAmount {}));
+        }
+        if deadline <= U256::from(self.vm().block_timestamp()) {
+            return Err(EscrowError::InvalidDeadline(InvalidDeadline {}));
+        }
+
+        let deal_id = self.deal_count.get() + U256::from(1);
+        self.deal_count.set(deal_id);
+
+        let mut deal = self.deals.setter(deal_id);
+        deal.payer.set(payer);
+        deal.payee.set(payee);
+        deal.arbiter.set(arbiter);
+        deal.asset.set(U8::from(asset));
+        deal.token.set(token);
+        deal.token_id.set(token_id);
+        deal.amount.set(amount);
+        deal.deadline.set(deadline);
+
+        log(self.vm(), Deposited {
+            dealId: deal_id,
+            payer,
+            payee,
+            arbiter,
+            asset,
+            token,
+            tokenId: token_id,
+            amount,
+            deadline,
+        });
+        Ok(deal_id)
+    }
+
+    fn require_open(&self, deal_id: U256) -> Result<(), EscrowError> {
+        let deal = self.deals.get(deal_id);
+        if deal.payer.get() == Address::ZERO {
+            return Err(EscrowError::DealNotFound(DealNotFound {}));
+        }
+        if deal.closed.get() {
+            return Err(EscrowError::DealClosed(DealClosed {}));
+        }
+        Ok(())
+    }
+
+    fn require_arbiter(&self, deal_id: U256) -> Result<(), EscrowError> {
+        self.require_open(deal_id)?;
+        if self.vm().msg_sender() != self.deals.get(deal_id).arbiter.get() {
+            return Err(EscrowError::OnlyArbiter(OnlyArbiter {}));
+        }
+        Ok(())
+    }
+
+    fn require_expired(&self, deal_id: U256) -> Result<(), EscrowError> {
+        let deal = self.deals.get(deal_id);
+        if deal.disputed.get() {
+            return Err(EscrowError::DealDisputed(DealDisputed {}));
+        }
+        if U256::from(self.vm().block_timestamp()) < deal.deadline.get() {
+            return Err(EscrowError::DeadlineNotPassed(DeadlineNotPassed {}));
+        }
+        Ok(())
+    }
+
+    fn pay_payee(&mut self, deal_id: U256, amount: U256) -> Result<(), EscrowError> {
+        let payee = self.deals.get(deal_id).payee.get();
+        self.pay_out(deal_id, payee, amount)?;
+        log(self.vm(), Released { dealId: deal_id, payee, amount });
+        Ok(())
+    }
+
+    fn pay_payer(&mut self, deal_id: U256, amount: U256) -> Result<(), EscrowError> {
+        let payer = self.deals.get(deal_id).payer.get();
+        self.pay_out(deal_id, payer, amount)?;
+        log(self.vm(), Refunded { dealId: deal_id, payer, amount });
+        Ok(())
+    }
+
+    /// Move `amount` of the deal's asset to `to`, closing the deal once it's empty
+    fn pay_out(&mut self, deal_id: U256, to: Address, amount: U256) -> Result<(), EscrowError> {
+        let remaining = self.remaining(deal_id);
+        if amount == U256::ZERO {
+            return Err(EscrowError::InvalidAmount(InvalidAmount {}));
+        }
+        if amount > remaining {
+            return Err(EscrowError::ExceedsRemaining(ExceedsRemaining { remaining, amount }));
+        }
+
+        let mut deal = self.deals.setter(deal_id);
+        let paid_out = deal.paid_out.get() + amount;
+        deal.paid_out.set(paid_out);
+        if paid_out == deal.amount.get() {
+            deal.closed.set(true);
+        }
+        let asset = deal.asset.get().to::<u8>();
+        let token = deal.token.get();
+        let token_id = deal.token_id.get();
+
+        match asset {
+            ASSET_ETH => {
+                let pending = self.pending_withdrawals.get(to);
+                self.pending_withdrawals.setter(to).set(pending + amount);
+            }
+            ASSET_ERC20 => safe_erc20_transfer(token, to, amount)?,
+            _ => {
+                let escrow = self.vm().contract_address();
+                IERC721::new(token)
+                    .transfer_from(Call::new_in(self), escrow, to, token_id)
+                    .map_err(|_| EscrowError::NFTTransferFailed(NFTTransferFailed {}))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_sol_types::SolCall;
+    use stylus_sdk::testing::*;
+
+    // ERC20 and ERC721 `transferFrom` share a selector and encoding
+    sol! {
+        function transfer(address to, uint256 amount) returns (bool);
+        function transferFrom(address from, address to, uint256 amount) returns (bool);
+    }
+
+    const AMOUNT: u64 = 1_000;
+    const NOW: u64 = 10_000;
+    const DEADLINE: u64 = 20_000;
+
+    fn payer() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn payee() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn arbiter() -> Address {
+        Address::from([3u8; 20])
+    }
+
+    fn token() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn encoded_true() -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+        data[31] = 1;
+        data
+    }
+
+    fn deploy(vm: &TestVM) -> Escrow {
+        let mut contract = Escrow::from(vm);
+        contract.constructor();
+        vm.set_block_timestamp(NOW);
+        contract
+    }
+
+    /// Open an ETH deal of `AMOUNT` from the payer
+    fn deposit(contract: &mut Escrow, vm: &TestVM) -> U256 {
+        vm.set_sender(payer());
+        vm.set_value(U256::from(AMOUNT));
+        let deal_id = contract.deposit_eth(payee(), arbiter(), U256::from(DEADLINE)).unwrap();
+        vm.set_value(U256::ZERO);
+        deal_id
+    }
+
+    #[test]
+    fn test_deposit_validates_deal() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(payer());
+        vm.set_value(U256::from(AMOUNT));
+
+        let deadline = U256::from(DEADLINE);
+        for (payee, arbiter) in [(payer(), arbiter()), (payee(), payee()), (payee(), Address::ZERO)] {
+            assert!(matches!(
+                contract.deposit_eth(payee, arbiter, deadline),
+                Err(EscrowError::InvalidParties(_))
+            ));
+        }
+        assert!(matches!(
+            contract.deposit_eth(payee(), arbiter(), U256::from(NOW)),
+            Err(EscrowError::InvalidDeadline(_))
+        ));
+        vm.set_value(U256::ZERO);
+        assert!(matches!(contract.deposit_eth(payee(), arbiter(), deadline), Err(EscrowError::InvalidAmount(_))));
+
+        let deal_id = deposit(&mut contract, &vm);
+        assert_eq!(deal_id, U256::from(1));
+        assert_eq!(
+            contract.get_deal(deal_id),
+            (payer(), payee(), arbiter(), ASSET_ETH, Address::ZERO, U256::ZERO, U256::from(AMOUNT))
+        );
+        assert_eq!(contract.remaining(deal_id), U256::from(AMOUNT));
+    }
+
+    #[test]
+    fn test_release_requires_delivery() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let deal_id = deposit(&mut contract, &vm);
+
+        assert!(matches!(
+            contract.release(deal_id, U256::from(100)),
+            Err(EscrowError::DeliveryNotConfirmed(_))
+        ));
+        assert!(matches!(contract.confirm_delivery(deal_id), Err(EscrowError::OnlyPayee(_))));
+
+        vm.set_sender(payee());
+        assert!(contract.confirm_delivery(deal_id).is_ok());
+        assert!(matches!(contract.release(deal_id, U256::from(100)), Err(EscrowError::OnlyPayer(_))));
+
+        // The payer releases in parts, and the last part closes the deal
+        vm.set_sender(payer());
+        assert!(contract.release(deal_id, U256::from(400)).is_ok());
+        assert_eq!(contract.remaining(deal_id), U256::from(600));
+        assert!(matches!(
+            contract.release(deal_id, U256::from(601)),
+            Err(EscrowError::ExceedsRemaining(_))
+        ));
+        assert!(contract.release(deal_id, U256::from(600)).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(payee()), U256::from(AMOUNT));
+        assert!(contract.get_deal_status(deal_id).4);
+        assert!(matches!(contract.release(deal_id, U256::from(1)), Err(EscrowError::DealClosed(_))));
+    }
+
+    #[test]
+    fn test_arbiter_decides_without_delivery() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let deal_id = deposit(&mut contract, &vm);
+
+        vm.set_sender(payee());
+        assert!(matches!(contract.arbiter_release(deal_id, U256::from(1)), Err(EscrowError::OnlyArbiter(_))));
+
+        vm.set_sender(arbiter());
+        assert!(contract.arbiter_release(deal_id, U256::from(300)).is_ok());
+        assert!(contract.arbiter_refund(deal_id, U256::from(700)).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(payee()), U256::from(300));
+        assert_eq!(contract.get_pending_withdrawal(payer()), U256::from(700));
+        assert!(contract.get_deal_status(deal_id).4);
+    }
+
+    #[test]
+    fn test_dispute_freezes_deal_until_resolved() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let deal_id = deposit(&mut contract, &vm);
+        vm.set_sender(payee());
+        contract.confirm_delivery(deal_id).unwrap();
+
+        vm.set_sender(arbiter());
+        assert!(matches!(contract.raise_dispute(deal_id), Err(EscrowError::OnlyParty(_))));
+        assert!(matches!(contract.resolve_dispute(deal_id, U256::ZERO), Err(EscrowError::DealNotDisputed(_))));
+
+        vm.set_sender(payer());
+        assert!(contract.raise_dispute(deal_id).is_ok());
+        assert!(matches!(contract.raise_dispute(deal_id), Err(EscrowError::DealDisputed(_))));
+        assert!(matches!(contract.release(deal_id, U256::from(1)), Err(EscrowError::DealDisputed(_))));
+
+        // Delivery can't be claimed past the deadline while disputed
+        vm.set_block_timestamp(DEADLINE);
+        vm.set_sender(payee());
+        assert!(matches!(contract.claim_expired(deal_id), Err(EscrowError::DealDisputed(_))));
+
+        vm.set_sender(arbiter());
+        assert!(matches!(
+            contract.resolve_dispute(deal_id, U256::from(AMOUNT + 1)),
+            Err(EscrowError::ExceedsRemaining(_))
+        ));
+        assert!(contract.resolve_dispute(deal_id, U256::from(250)).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(payee()), U256::from(250));
+        assert_eq!(contract.get_pending_withdrawal(payer()), U256::from(750));
+        assert!(contract.get_deal_status(deal_id).4);
+    }
+
+    #[test]
+    fn test_deadline_flows() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let undelivered = deposit(&mut contract, &vm);
+        let delivered = deposit(&mut contract, &vm);
+        vm.set_sender(payee());
+        contract.confirm_delivery(delivered).unwrap();
+
+        vm.set_sender(payer());
+        assert!(matches!(contract.refund_expired(undelivered), Err(EscrowError::DeadlineNotPassed(_))));
+
+        vm.set_block_timestamp(DEADLINE);
+        assert!(matches!(
+            contract.refund_expired(delivered),
+            Err(EscrowError::DeliveryAlreadyConfirmed(_))
+        ));
+        assert!(contract.refund_expired(undelivered).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(payer()), U256::from(AMOUNT));
+
+        vm.set_sender(payee());
+        assert!(matches!(contract.claim_expired(undelivered), Err(EscrowError::DealClosed(_))));
+        assert!(contract.claim_expired(delivered).is_ok());
+        assert_eq!(contract.get_pending_withdrawal(payee()), U256::from(AMOUNT));
+    }
+
+    #[test]
+    fn test_erc20_deal_pays_out_directly() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let escrow = contract.vm().contract_address();
+        let amount = U256::from(AMOUNT);
+        let calldata = transferFromCall { from: payer(), to: escrow, amount }.abi_encode();
+        vm.mock_call(token(), calldata, U256::ZERO, Ok(encoded_true()));
+
+        vm.set_sender(payer());
+        let deal_id = contract
+            .deposit_erc20(payee(), arbiter(), token(), amount, U256::from(DEADLINE))
+            .unwrap();
+        assert_eq!(contract.get_deal(deal_id).3, ASSET_ERC20);
+
+        let calldata = transferCall { to: payee(), amount: U256::from(400) }.abi_encode();
+        vm.mock_call(token(), calldata, U256::ZERO, Ok(encoded_true()));
+        vm.set_sender(arbiter());
+        assert!(contract.arbiter_release(deal_id, U256::from(400)).is_ok());
+        assert_eq!(contract.remaining(deal_id), U256::from(600));
+        assert_eq!(contract.get_pending_withdrawal(payee()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_nft_deal_is_released_whole() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let escrow = contract.vm().contract_address();
+        let token_id = U256::from(7);
+        let calldata = transferFromCall { from: payer(), to: escrow, amount: token_id }.abi_encode();
+        vm.mock_call(token(), calldata, U256::ZERO, Ok(vec![]));
+
+        vm.set_sender(payer());
+        let deal_id = contract
+            .deposit_nft(payee(), arbiter(), token(), token_id, U256::from(DEADLINE))
+            .unwrap();
+        assert_eq!(contract.remaining(deal_id), U256::from(1));
+
+        vm.set_sender(payee());
+        contract.confirm_delivery(deal_id).unwrap();
+        vm.set_sender(payer());
+        assert!(matches!(contract.release(deal_id, U256::from(2)), Err(EscrowError::ExceedsRemaining(_))));
+
+        let calldata = transferFromCall { from: escrow, to: payee(), amount: token_id }.abi_encode();
+        vm.mock_call(token(), calldata, U256::ZERO, Ok(vec![]));
+        assert!(contract.release(deal_id, U256::from(1)).is_ok());
+        assert!(contract.get_deal_status(deal_id).4);
+    }
+
+    #[test]
+    fn test_withdraw_requires_balance() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(payee());
+        assert!(matches!(contract.withdraw(), Err(EscrowError::NothingToWithdraw(_))));
+    }
+}
@@ -0,0 +1,176 @@
+//!
+//! Multicall Contract
+//!
+//! Batches read calls against other contracts into one RPC round trip.
+//! `aggregate` reverts if any call fails, while `try_aggregate` reports each
+//! call's success next to its return data.
+//!
+//! Every call is a static call, so a batch can never change state and the
+//! multicall has no reason to hold funds or approvals.
+
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_primitives::{Address, U256},
+    alloy_sol_types::sol,
+    call::RawCall,
+    prelude::*,
+};
+
+// Custom error types
+sol! {
+    error CallFailed(uint256 index, address target, bytes returnData);
+}
+
+#[derive(SolidityError)]
+pub enum MulticallError {
+    CallFailed(CallFailed),
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct Multicall {}
+}
+
+/// Static-call `target` with `call_data`, returning its output or revert data
+fn static_call(target: Address, call_data: &[u8]) -> Result<Vec<u8>, Vec<u8>> {
+    // Safety: static call, no state is modified
+    unsafe { RawCall::new_static().call(target, call_data) }
+}
+
+#[public]
+impl Multicall {
+    /// Run every `(target, call_data)` call, reverting with `CallFailed` on the first failure
+    ///
+    /// Returns the block number the batch was read at and each call's return data.
+    pub fn aggregate(&self, calls: Vec<(Address, Bytes)>) -> Result<(U256, Vec<Bytes>), MulticallError> {
+        let mut return_data = Vec::with_capacity(calls.len());
+        for (index, (target, call_data)) in calls.into_iter().enumerate() {
+            let output = static_call(target, &call_data).map_err(|data| {
+                MulticallError::CallFailed(CallFailed {
+                    index: U256::from(index),
+                    target,
+                    returnData: data.into(),
+                })
+            })?;
+            return_data.push(Bytes::from(output));
+        }
+        Ok((U256::from(self.vm().block_number()), return_data))
+    }
+
+    /// Run every `(target, call_data)` call, returning `(success, return_data)` for each
+    ///
+    /// A failed call carries its revert data. With `require_success` set, the
+    /// first failure reverts the batch like `aggregate`.
+    pub fn try_aggregate(
+        &self,
+        require_success: bool,
+        calls: Vec<(Address, Bytes)>,
+    ) -> Result<Vec<(bool, Bytes)>, MulticallError> {
+        let mut results = Vec::with_capacity(calls.len());
+        for (index, (target, call_data)) in calls.into_iter().enumerate() {
+            let result = match static_call(target, &call_data) {
+                Ok(output) => (true, Bytes::from(output)),
+                Err(data) if require_success => {
+                    return Err(MulticallError::CallFailed(CallFailed {
+                        index: U256::from(index),
+                        target,
+                        returnData: data.into(),
+                    }));
+                }
+                Err(data) => (false, Bytes::from(data)),
+            };
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Get the current block number, to pair with a `try_aggregate` batch
+    pub fn get_block_number(&self) -> U256 {
+        U256::from(self.vm().block_number())
+    }
+
+    /// Get the current block timestamp
+    pub fn get_current_block_timestamp(&self) -> U256 {
+        U256::from(self.vm().block_timestamp())
+    }
+
+    /// Get `account`'s ETH balance
+    pub fn get_eth_balance(&self, account: Address) -> U256 {
+        self.vm().balance(account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    fn target(byte: u8) -> Address {
+        Address::from([byte; 20])
+    }
+
+    fn calls() -> Vec<(Address, Bytes)> {
+        vec![
+            (target(1), Bytes::from(vec![0xaa])),
+            (target(2), Bytes::from(vec![0xbb])),
+        ]
+    }
+
+    /// Mock the first call to succeed and the second to revert with `revert_data`
+    fn mock_calls(vm: &TestVM, revert_data: Vec<u8>) {
+        vm.mock_static_call(target(1), vec![0xaa], Ok(vec![1, 2, 3]));
+        vm.mock_static_call(target(2), vec![0xbb], Err(revert_data));
+    }
+
+    #[test]
+    fn test_aggregate_returns_all_outputs() {
+        let vm = TestVM::default();
+        let contract = Multicall::from(&vm);
+        vm.set_block_number(42);
+        vm.mock_static_call(target(1), vec![0xaa], Ok(vec![1, 2, 3]));
+        vm.mock_static_call(target(2), vec![0xbb], Ok(vec![]));
+
+        let (block_number, return_data) = contract.aggregate(calls()).unwrap();
+        assert_eq!(block_number, U256::from(42));
+        assert_eq!(return_data, vec![Bytes::from(vec![1, 2, 3]), Bytes::from(vec![])]);
+
+        assert_eq!(contract.aggregate(vec![]).unwrap().1, vec![]);
+    }
+
+    #[test]
+    fn test_aggregate_reverts_on_failure() {
+        let vm = TestVM::default();
+        let contract = Multicall::from(&vm);
+        mock_calls(&vm, vec![0xde, 0xad]);
+
+        match contract.aggregate(calls()) {
+            Err(MulticallError::CallFailed(e)) => {
+                assert_eq!(e.index, U256::from(1));
+                assert_eq!(e.target, target(2));
+                assert_eq!(e.returnData.to_vec(), vec![0xde, 0xad]);
+            }
+            _ => panic!("expected CallFailed"),
+        }
+    }
+
+    #[test]
+    fn test_try_aggregate_reports_failures() {
+        let vm = TestVM::default();
+        let contract = Multicall::from(&vm);
+        mock_calls(&vm, vec![0xde, 0xad]);
+
+        let results = contract.try_aggregate(false, calls()).unwrap();
+        assert_eq!(
+            results,
+            vec![(true, Bytes::from(vec![1, 2, 3])), (false, Bytes::from(vec![0xde, 0xad]))]
+        );
+
+        assert!(matches!(contract.try_aggregate(true, calls()), Err(MulticallError::CallFailed(_))));
+    }
+}
@@ -0,0 +1,20 @@
+//! Merkle criteria for offers on any token out of a set
+//!
+//! A criteria offer commits to the root of a merkle tree whose leaves are the
+//! acceptable token ids, e.g. every token with a given trait. The seller
+//! proves their token is in the set when accepting. Leaves are
+//! `keccak256(abi.encode(tokenId))` and proofs are checked with
+//! `stylus-merkle`, as in OpenZeppelin's `MerkleProof`, so standard tooling
+//! can build the tree.
+
+use alloy_primitives::{keccak256, B256, U256};
+
+/// Leaf of `token_id` in a criteria tree
+pub fn leaf(token_id: U256) -> B256 {
+    keccak256(token_id.to_be_bytes::<32>())
+}
+
+/// Check that `proof` places `token_id` under `root`
+pub fn verify(root: B256, token_id: U256, proof: &[B256]) -> bool {
+    stylus_merkle::verify(proof, root, leaf(token_id))
+}
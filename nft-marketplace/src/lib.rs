@@ -2,18 +2,235 @@ extern crate alloc;
 
 use stylus_sdk::{
     prelude::*,
-    alloy_sol_types::sol,
+    abi::Bytes,
+    alloy_sol_types::{sol, SolStruct},
+    call::Call,
+    storage::{StorageMap, StorageU256, StorageVec},
+    types::AddressVM,
 };
-use alloy_primitives::{U256, Address};
+use alloy_primitives::{aliases::U8, keccak256, U256, Address, B256, FixedBytes};
+use stylus_common::{AccessControl, Initializable, Pausable};
+use stylus_eip712::Nonces;
+use stylus_reentrancy_guard::{
+    non_reentrant, safe_erc20_transfer, safe_erc20_transfer_from, safe_transfer_eth, GasStipend, ReentrancyGuard,
+    ReentrancyGuarded,
+};
+
+pub mod criteria;
+pub mod order;
+pub mod roles;
+pub mod standards;
+pub mod voucher;
+
+use order::{SellOrder, ORDER_CANCELLED, ORDER_FILLED, ORDER_OPEN};
+use roles::{ADMIN_ROLE, CURATOR_ROLE, FEE_MANAGER_ROLE, MODERATOR_ROLE, PAUSER_ROLE, TREASURER_ROLE};
+use standards::{
+    ERC1155_INTERFACE_ID, ERC2981_INTERFACE_ID, ERC721_INTERFACE_ID, STANDARD_ERC1155, STANDARD_ERC721,
+};
+use voucher::MintVoucher;
+
+/// Lifecycle of a listing, stored as its `u8` value
+///
+/// Zero means the item doesn't exist. `Expired` and `Scheduled` are never
+/// stored: an `Active` listing reports them once its expiry has passed or
+/// while its start time is still ahead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ListingStatus {
+    Active = 1,
+    Sold = 2,
+    Cancelled = 3,
+    Expired = 4,
+    Scheduled = 5,
+}
+
+impl ListingStatus {
+    /// Decode a stored status, `None` for a missing item
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(ListingStatus::Active),
+            2 => Some(ListingStatus::Sold),
+            3 => Some(ListingStatus::Cancelled),
+            4 => Some(ListingStatus::Expired),
+            5 => Some(ListingStatus::Scheduled),
+            _ => None,
+        }
+    }
+}
+
+/// Status of an open swap listing
+pub const SWAP_OPEN: u8 = 1;
+/// Status of a swap settled by `accept_swap`
+pub const SWAP_COMPLETED: u8 = 2;
+/// Status of a swap withdrawn by its seller
+pub const SWAP_CANCELLED: u8 = 3;
+
+/// Negotiation state of an offer the seller hasn't countered
+pub const NEGOTIATION_NONE: u8 = 0;
+/// Negotiation state of an offer with a pending counter-offer
+pub const NEGOTIATION_COUNTERED: u8 = 1;
+/// Negotiation state of an offer settled at the counter price
+pub const NEGOTIATION_ACCEPTED: u8 = 2;
+/// Negotiation state of an offer whose counter the bidder declined
+pub const NEGOTIATION_DECLINED: u8 = 3;
+
+/// Escrow state of an item sold without buyer protection, or not sold yet
+pub const ESCROW_NONE: u8 = 0;
+/// Escrow state of a protected sale inside its dispute window
+pub const ESCROW_HELD: u8 = 1;
+/// Escrow state of a protected sale the buyer disputed, awaiting a moderator
+pub const ESCROW_DISPUTED: u8 = 2;
+/// Escrow state of a protected sale settled to the seller
+pub const ESCROW_RELEASED: u8 = 3;
+/// Escrow state of a protected sale a moderator refunded
+pub const ESCROW_REFUNDED: u8 = 4;
+
+/// Payment token address standing for ETH, the default currency of listings
+///
+/// Any other payment token is an ERC20, whose fees and proceeds are kept
+/// apart from the ETH balances.
+pub const NATIVE_TOKEN: Address = Address::ZERO;
+
+/// Highest fee in basis points, for the base fee, overrides and tiers alike
+pub const MAX_FEE_BPS: u64 = 1000;
+
+/// Notice a base fee change gets before it can apply, until the admin changes it
+pub const DEFAULT_FEE_CHANGE_DELAY: u64 = 2 * 24 * 60 * 60;
+
+/// Most seller volume tiers the owner can configure
+pub const MAX_FEE_TIERS: usize = 10;
+
+/// Most NFTs a bundle listing can hold, bounding the gas of settling it
+pub const MAX_BUNDLE_SIZE: usize = 50;
+
+/// Most items a batch call can take, for the same reason
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// Highest share of a reclaimed offer, in basis points, its reclaimer can be paid
+pub const MAX_RECLAIM_REWARD_BPS: u64 = 100;
+
+/// Most recipients a listing's payout split can name
+pub const MAX_SPLIT_RECIPIENTS: usize = 10;
+
+// ERC721 interface for escrowing listed NFTs
+sol_interface! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function ownerOf(uint256 tokenId) external view returns (address);
+    }
+
+    // Optional ERC721 metadata extension, snapshotted at listing time
+    interface IERC721Metadata {
+        function tokenURI(uint256 tokenId) external view returns (string);
+    }
+
+    // ERC-165 probe for the standards a collection implements
+    interface IERC165 {
+        function supportsInterface(bytes4 interfaceId) external view returns (bool);
+    }
+
+    // ERC1155 collections, listed one unit at a time without escrow
+    interface IERC1155 {
+        function safeTransferFrom(address from, address to, uint256 id, uint256 value, bytes calldata data) external;
+        function balanceOf(address account, uint256 id) external view returns (uint256);
+        function isApprovedForAll(address account, address operator) external view returns (bool);
+    }
+
+    // ERC2981 royalties, paid out of the seller's proceeds
+    interface IERC2981 {
+        function royaltyInfo(uint256 tokenId, uint256 salePrice) external view returns (address, uint256);
+    }
+
+    // Collections that name an owner, who can set their listing policy
+    interface IOwnable {
+        function owner() external view returns (address);
+    }
+
+    // Creator-deployed hook deciding who may list a collection's tokens
+    interface IListingPolicy {
+        function canList(address nftContract, address seller, uint256 tokenId) external view returns (bool);
+    }
+
+    // ERC20 payment tokens with ERC-2612 permit, for gasless buyer approvals
+    interface IERC20Permit {
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s) external;
+        function allowance(address owner, address spender) external view returns (uint256);
+    }
+
+    // Creator collections that let the marketplace mint on voucher redemption
+    interface ILazyMintable {
+        function lazyMint(address to, string calldata uri, address royaltyReceiver, uint256 royaltyBps) external returns (uint256);
+        function owner() external view returns (address);
+    }
+}
 
 sol_storage! {
     #[entrypoint]
     pub struct NFTMarketplace {
-        address owner;
         uint256 fee_percentage;
+        uint256 accrued_fees;
         uint256 item_count;
         mapping(uint256 => MarketItem) market_items;
-        mapping(uint256 => bool) sold_items;
+        mapping(address => mapping(uint256 => uint256)) listed_items;
+        mapping(address => uint256[]) seller_items;
+        mapping(address => uint256[]) collection_items;
+        uint256 offer_count;
+        mapping(uint256 => Offer) offers;
+        mapping(address => mapping(uint256 => uint256[])) token_offers;
+        mapping(address => mapping(uint256 => bool)) used_voucher_nonces;
+        uint256 sale_count;
+        mapping(uint256 => Sale) sales;
+        mapping(address => mapping(uint256 => uint256[])) token_sales;
+        mapping(address => uint256) collection_fees;
+        mapping(address => bool) has_collection_fee;
+        uint256[] tier_volumes;
+        uint256[] tier_fees;
+        mapping(address => uint256) seller_volume;
+        bool verified_only;
+        address[] verified_collections;
+        mapping(address => uint256) verified_positions;
+        uint256 swap_count;
+        mapping(uint256 => SwapListing) swaps;
+        Nonces order_nonces;
+        mapping(bytes32 => uint8) order_status;
+        uint256 referral_share_bps;
+        mapping(bytes32 => address) referral_owners;
+        mapping(bytes32 => uint256) referral_earnings;
+        mapping(address => uint256) affiliate_balances;
+        mapping(address => uint256) pending_proceeds;
+        mapping(address => bool) flagged_collections;
+        mapping(address => mapping(uint256 => bool)) flagged_tokens;
+        mapping(address => uint256[]) collection_offers;
+        mapping(address => uint256) collection_sale_count;
+        mapping(address => uint256) collection_volume;
+        uint256 escrow_window;
+        address staking_sink;
+        uint256 staking_share_bps;
+        address treasury_sink;
+        uint256 treasury_share_bps;
+        mapping(address => uint256) sink_distributed;
+        mapping(address => uint256) buyer_volume;
+        uint256[] loyalty_volumes;
+        uint256[] loyalty_discounts;
+        Initializable init;
+        uint256 fee_change_delay;
+        uint256 pending_fee;
+        uint256 pending_fee_at;
+        uint256 reclaim_reward_bps;
+        mapping(address => address) listing_policies;
+        uint256[] active_items;
+        mapping(uint256 => uint256) active_positions;
+        mapping(address => uint256[]) seller_active_items;
+        mapping(address => mapping(uint256 => uint256)) seller_active_positions;
+        mapping(address => uint256[]) collection_active_items;
+        mapping(address => mapping(uint256 => uint256)) collection_active_positions;
+        AccessControl roles;
+        Pausable pausable;
+        ReentrancyGuard guard;
+        mapping(address => uint256) token_fees;
+        mapping(address => mapping(address => uint256)) token_proceeds;
+        mapping(address => mapping(uint256 => mapping(uint256 => uint256))) token_offer_positions;
+        mapping(address => mapping(uint256 => uint256)) collection_offer_positions;
     }
 
     pub struct MarketItem {
@@ -23,56 +240,241 @@ sol_storage! {
         address seller;
         address owner;
         uint256 price;
-        bool sold;
+        uint8 status;
+        uint256 expires_at;
+        address[] bundle_contracts;
+        uint256[] bundle_token_ids;
+        uint256 previous_item_id;
+        uint256 next_item_id;
+        string[] token_uris;
+        bytes32[] token_uri_hashes;
+        bool escrow_protected;
+        uint8 escrow_state;
+        uint256 escrow_release_at;
+        uint256 starts_at;
+        address[] split_recipients;
+        uint256[] split_bps;
+        uint8[] token_standards;
+        bool royalties;
+        address payment_token;
+        bytes32 referral_code;
+    }
+
+    pub struct SwapListing {
+        address seller;
+        address offered_contract;
+        uint256 offered_token_id;
+        address wanted_contract;
+        uint256 wanted_token_id;
+        bool any_token;
+        uint256 top_up;
+        bool seller_pays_top_up;
+        address counterparty;
+        uint256 counter_token_id;
+        uint8 status;
+    }
+
+    pub struct Sale {
+        address nft_contract;
+        uint256 token_id;
+        uint256 item_id;
+        address seller;
+        address buyer;
+        uint256 price;
+        uint256 timestamp;
+    }
+
+    pub struct Offer {
+        uint256 offer_id;
+        address nft_contract;
+        uint256 token_id;
+        address bidder;
+        uint256 amount;
+        uint256 expiry;
+        bool active;
+        address counter_seller;
+        uint256 counter_price;
+        uint256 counter_expiry;
+        uint8 negotiation;
+        bytes32 criteria_root;
+    }
+}
+
+impl ReentrancyGuarded for NFTMarketplace {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.guard
     }
 }
 
 #[public]
 impl NFTMarketplace {
-    /// Initialize marketplace
-    pub fn new(&mut self, fee_percentage: U256) -> Result<(), Vec<u8>> {
-        if fee_percentage > U256::from(1000) { // Max 10%
+    /// Initialize marketplace at deployment
+    ///
+    /// The deployer starts with every role and can hand each one to its own
+    /// multisig with `grant_role` and `revoke_role`. Runs once; the init
+    /// guard keeps it from being replayed to seize the roles.
+    #[constructor]
+    pub fn constructor(&mut self, fee_percentage: U256) -> Result<(), Vec<u8>> {
+        self.init.initializer()?;
+        if fee_percentage > U256::from(MAX_FEE_BPS) { // Max 10%
             return Err("Fee too high".as_bytes().to_vec());
         }
         
-        self.owner.set(self.vm().msg_sender());
+        let deployer = self.vm().msg_sender();
+        for role in [ADMIN_ROLE, FEE_MANAGER_ROLE, PAUSER_ROLE, CURATOR_ROLE, MODERATOR_ROLE, TREASURER_ROLE] {
+            self.roles.grant(role, deployer);
+        }
         self.fee_percentage.set(fee_percentage);
+        self.fee_change_delay.set(U256::from(DEFAULT_FEE_CHANGE_DELAY));
         self.item_count.set(U256::from(0));
+        self.guard.init();
         
         Ok(())
     }
 
     /// List NFT for sale
+    ///
+    /// The NFT is escrowed in the marketplace until it is sold or the listing
+    /// is cancelled, so the seller must approve the marketplace first.
+    #[non_reentrant]
     pub fn create_market_item(
         &mut self,
         nft_contract: Address,
         token_id: U256,
         price: U256,
     ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        self.require_listable(nft_contract)?;
+        let sender = self.vm().msg_sender();
+        self.list_item(nft_contract, token_id, price, sender)
+    }
+
+    /// List an NFT that goes on sale at `starts_at`
+    ///
+    /// Works like `create_market_item`, but the item can't be bought and is
+    /// left out of `get_active_items` until then. The seller can still
+    /// update or cancel it meanwhile.
+    #[non_reentrant]
+    pub fn create_scheduled_market_item(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        price: U256,
+        starts_at: U256,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        self.require_listable(nft_contract)?;
+        if starts_at <= U256::from(self.vm().block_timestamp()) {
+            return Err("Start must be in the future".as_bytes().to_vec());
+        }
+        let sender = self.vm().msg_sender();
+        let item_id = self.list_item(nft_contract, token_id, price, sender)?;
+        self.market_items.setter(item_id).starts_at.set(starts_at);
+        
+        log(self.vm(), ListingStartUpdated {
+            item_id,
+            starts_at,
+        });
+        
+        Ok(item_id)
+    }
+
+    /// List many NFTs of one collection at once
+    ///
+    /// `token_ids[i]` is listed at `prices[i]`, each as its own item with the
+    /// same checks as `create_market_item`. Any invalid entry reverts the
+    /// batch. Returns the new item ids, in order.
+    #[non_reentrant]
+    pub fn create_market_items_batch(
+        &mut self,
+        nft_contract: Address,
+        token_ids: Vec<U256>,
+        prices: Vec<U256>,
+    ) -> Result<Vec<U256>, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if token_ids.len() != prices.len() {
+            return Err("Batch arrays length mismatch".as_bytes().to_vec());
+        }
+        if token_ids.is_empty() || token_ids.len() > MAX_BATCH_SIZE {
+            return Err("Invalid batch size".as_bytes().to_vec());
+        }
+        self.require_listable(nft_contract)?;
+        
+        let sender = self.vm().msg_sender();
+        let mut item_ids = Vec::with_capacity(token_ids.len());
+        for (token_id, price) in token_ids.into_iter().zip(prices) {
+            item_ids.push(self.list_item(nft_contract, token_id, price, sender)?);
+        }
+        
+        Ok(item_ids)
+    }
+
+    /// List several NFTs as one item sold for a single price
+    ///
+    /// `nft_contracts[i]` and `token_ids[i]` name each NFT, which are all
+    /// escrowed together. `get_market_item` reports the first one; use
+    /// `get_bundle` for the rest. Bundled tokens can't take offers while listed.
+    #[non_reentrant]
+    pub fn create_bundle_item(
+        &mut self,
+        nft_contracts: Vec<Address>,
+        token_ids: Vec<U256>,
+        price: U256,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
         if price <= U256::from(0) {
             return Err("Price must be greater than zero".as_bytes().to_vec());
         }
+        if nft_contracts.len() != token_ids.len() {
+            return Err("Bundle arrays length mismatch".as_bytes().to_vec());
+        }
+        if nft_contracts.is_empty() || nft_contracts.len() > MAX_BUNDLE_SIZE {
+            return Err("Invalid bundle size".as_bytes().to_vec());
+        }
+        for (nft_contract, token_id) in nft_contracts.iter().zip(&token_ids) {
+            self.require_listable(*nft_contract)?;
+            self.require_unflagged(*nft_contract, *token_id)?;
+        }
         
         let item_id = self.item_count.get() + U256::from(1);
         let sender = self.vm().msg_sender();
+        for (nft_contract, token_id) in nft_contracts.iter().zip(&token_ids) {
+            self.require_permitted(*nft_contract, sender, *token_id)?;
+        }
         
         let mut market_item = self.market_items.setter(item_id);
         market_item.item_id.set(item_id);
-        market_item.nft_contract.set(nft_contract);
-        market_item.token_id.set(token_id);
+        market_item.nft_contract.set(nft_contracts[0]);
+        market_item.token_id.set(token_ids[0]);
         market_item.seller.set(sender);
-        market_item.owner.set(Address::ZERO);
+        market_item.owner.set(self.vm().contract_address());
         market_item.price.set(price);
-        market_item.sold.set(false);
+        for (nft_contract, token_id) in nft_contracts.iter().zip(&token_ids) {
+            market_item.bundle_contracts.push(*nft_contract);
+            market_item.bundle_token_ids.push(*token_id);
+        }
         
         self.item_count.set(item_id);
-        self.sold_items.setter(item_id).set(false);
+        self.set_status(item_id, ListingStatus::Active);
+        self.seller_items.setter(sender).push(item_id);
+        let mut collections: Vec<Address> = Vec::new();
+        for nft_contract in &nft_contracts {
+            if !collections.contains(nft_contract) {
+                collections.push(*nft_contract);
+                self.collection_items.setter(*nft_contract).push(item_id);
+            }
+        }
         
-        log(self.vm(), MarketItemCreated {
+        let marketplace = self.vm().contract_address();
+        self.snapshot_token_uris(item_id);
+        self.detect_standards(item_id, sender)?;
+        self.transfer_item(item_id, sender, marketplace)?;
+        
+        log(self.vm(), BundleItemCreated {
             item_id,
-            nft_contract,
-            token_id,
             seller: sender,
+            nft_contracts,
+            token_ids,
             price,
         });
         
@@ -80,50 +482,408 @@ impl NFTMarketplace {
     }
 
     /// Buy NFT from marketplace
+    #[payable]
+    #[non_reentrant]
     pub fn buy_market_item(&mut self, item_id: U256) -> Result<(), Vec<u8>> {
-        // First, check conditions and capture needed values
-        let (is_sold, price, nft_contract, token_id, seller) = {
+        self.pausable.when_not_paused()?;
+        self.purchase(item_id).map(|_| ())
+    }
+
+    /// Buy an ERC20-priced item for at most `max_price` of its token
+    ///
+    /// The price is pulled from the buyer, who must approve the marketplace
+    /// first. `max_price` keeps a standing allowance from being drained by a
+    /// seller who raises the price before the purchase lands.
+    #[non_reentrant]
+    pub fn buy_with_token(&mut self, item_id: U256, max_price: U256) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        self.purchase_in_token(item_id, max_price)
+    }
+
+    /// Buy an ERC20-priced item, approving the payment with an ERC-2612 permit
+    ///
+    /// Calls the token's `permit` for the buyer, the marketplace and the
+    /// current price before pulling it, so no separate approval transaction
+    /// is needed. A permit already submitted by someone else is fine as long
+    /// as the allowance covers the price. Reverts if the price is above
+    /// `max_price`, as with `buy_with_token`.
+    #[non_reentrant]
+    pub fn buy_with_permit(
+        &mut self,
+        item_id: U256,
+        max_price: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let (payment_token, price) = {
             let item = self.market_items.get(item_id);
-            (
-                item.sold.get(),
-                item.price.get(),
-                item.nft_contract.get(),
-                item.token_id.get(),
-                item.seller.get(),
-            )
+            (item.payment_token.get(), item.price.get())
         };
+        if payment_token == NATIVE_TOKEN {
+            return Err("Listing is priced in ETH".as_bytes().to_vec());
+        }
         
-        if is_sold {
-            return Err("Item already sold".as_bytes().to_vec());
+        let buyer = self.vm().msg_sender();
+        let marketplace = self.vm().contract_address();
+        let token = IERC20Permit::new(payment_token);
+        if token.permit(Call::new_in(self), buyer, marketplace, price, deadline, v, r, s).is_err() {
+            let allowance = token
+                .allowance(Call::new_in(self), buyer, marketplace)
+                .map_err(|_| "Permit failed".as_bytes().to_vec())?;
+            if allowance < price {
+                return Err("Permit failed".as_bytes().to_vec());
+            }
         }
-        if self.vm().msg_value() != price {
-            return Err("Incorrect payment amount".as_bytes().to_vec());
+        
+        self.purchase_in_token(item_id, max_price)
+    }
+
+    /// Buy NFT from marketplace on an affiliate's referral
+    ///
+    /// Works like `buy_market_item` for ETH-priced items; the affiliate behind `code` is credited
+    /// the referral share of the marketplace fee, to withdraw with
+    /// `withdraw_affiliate_earnings`. Buyers can't use their own code. A
+    /// protected sale takes its fee when the escrow is released, so the
+    /// code is kept on the item and the affiliate is paid then; a refunded
+    /// sale pays nothing.
+    #[payable]
+    #[non_reentrant]
+    pub fn buy_market_item_with_referral(&mut self, item_id: U256, code: B256) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        // Affiliates are paid out of the ETH fees
+        if self.market_items.get(item_id).payment_token.get() != NATIVE_TOKEN {
+            return Err("Listing is priced in a token".as_bytes().to_vec());
+        }
+        let affiliate = self.referral_owners.get(code);
+        if affiliate == Address::ZERO {
+            return Err("Unknown referral code".as_bytes().to_vec());
+        }
+        if affiliate == self.vm().msg_sender() {
+            return Err("Cannot use own referral code".as_bytes().to_vec());
+        }
+        
+        let fee = self.purchase(item_id)?;
+        if self.market_items.get(item_id).escrow_state.get().to::<u8>() == ESCROW_HELD {
+            self.market_items.setter(item_id).referral_code.set(code);
+            return Ok(());
+        }
+        self.pay_referral(code, item_id, fee);
+        
+        Ok(())
+    }
+
+    /// Register `code` as the caller's referral code
+    ///
+    /// Codes are first come, first served; an affiliate can hold several.
+    pub fn register_referral_code(&mut self, code: B256) -> Result<(), Vec<u8>> {
+        if code == B256::ZERO {
+            return Err("Invalid referral code".as_bytes().to_vec());
+        }
+        if self.referral_owners.get(code) != Address::ZERO {
+            return Err("Referral code taken".as_bytes().to_vec());
+        }
+        
+        let affiliate = self.vm().msg_sender();
+        self.referral_owners.setter(code).set(affiliate);
+        
+        log(self.vm(), ReferralCodeRegistered {
+            code,
+            affiliate,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the share of the marketplace fee paid to referrers, in basis points of the fee (fee manager only)
+    pub fn set_referral_share(&mut self, share_bps: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if share_bps > U256::from(10000) {
+            return Err("Share too high".as_bytes().to_vec());
+        }
+        
+        self.referral_share_bps.set(share_bps);
+        
+        log(self.vm(), ReferralShareUpdated {
+            share_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Send the caller their referral earnings
+    #[non_reentrant]
+    pub fn withdraw_affiliate_earnings(&mut self) -> Result<(), Vec<u8>> {
+        let affiliate = self.vm().msg_sender();
+        let amount = self.affiliate_balances.get(affiliate);
+        if amount == U256::ZERO {
+            return Err("No earnings to withdraw".as_bytes().to_vec());
+        }
+        
+        self.affiliate_balances.setter(affiliate).set(U256::ZERO);
+        safe_transfer_eth(self, affiliate, amount, GasStipend::All)?;
+        
+        log(self.vm(), AffiliateEarningsWithdrawn {
+            affiliate,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the affiliate a referral code belongs to, zero if unregistered
+    pub fn get_referral_code_owner(&self, code: B256) -> Address {
+        self.referral_owners.get(code)
+    }
+
+    /// Get everything a referral code has earned, withdrawn or not
+    pub fn get_referral_earnings(&self, code: B256) -> U256 {
+        self.referral_earnings.get(code)
+    }
+
+    /// Get an affiliate's withdrawable referral earnings
+    pub fn get_affiliate_balance(&self, affiliate: Address) -> U256 {
+        self.affiliate_balances.get(affiliate)
+    }
+
+    /// Get the referral share of the marketplace fee in basis points
+    pub fn get_referral_share(&self) -> U256 {
+        self.referral_share_bps.get()
+    }
+
+    /// Send the caller their pending sale proceeds
+    ///
+    /// Sellers, creators and swap counterparties are credited at settlement
+    /// rather than paid, so a recipient that can't take ETH never blocks a sale.
+    #[non_reentrant]
+    pub fn withdraw_proceeds(&mut self) -> Result<(), Vec<u8>> {
+        let account = self.vm().msg_sender();
+        self.pay_proceeds(account, GasStipend::All)
+    }
+
+    /// Push an EOA's pending proceeds to it on its behalf
+    ///
+    /// Anyone can call this, e.g. a keeper paying out sellers. Contracts must
+    /// withdraw themselves, since their `receive` could revert or re-enter.
+    #[non_reentrant]
+    pub fn push_proceeds(&mut self, account: Address) -> Result<(), Vec<u8>> {
+        if account.has_code() {
+            return Err("Contracts must withdraw proceeds".as_bytes().to_vec());
+        }
+        self.pay_proceeds(account, GasStipend::Transfer)
+    }
+
+    /// Get an account's sale proceeds waiting to be withdrawn
+    pub fn get_pending_proceeds(&self, account: Address) -> U256 {
+        self.pending_proceeds.get(account)
+    }
+
+    /// Send the caller their pending proceeds in the ERC20 `token`
+    #[non_reentrant]
+    pub fn withdraw_token_proceeds(&mut self, token: Address) -> Result<(), Vec<u8>> {
+        let account = self.vm().msg_sender();
+        let amount = self.token_proceeds.getter(token).get(account);
+        if amount == U256::ZERO {
+            return Err("No proceeds to withdraw".as_bytes().to_vec());
+        }
+        
+        self.token_proceeds.setter(token).setter(account).set(U256::ZERO);
+        safe_erc20_transfer(token, account, amount)?;
+        
+        log(self.vm(), TokenProceedsWithdrawn {
+            token,
+            account,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Get an account's sale proceeds in the ERC20 `token` waiting to be withdrawn
+    pub fn get_token_proceeds(&self, token: Address, account: Address) -> U256 {
+        self.token_proceeds.getter(token).get(account)
+    }
+
+    /// Buy several items in one transaction
+    ///
+    /// Send the sum of the items' prices. Items that can no longer be bought
+    /// (sold, cancelled, expired or missing) are skipped and their price is
+    /// refunded instead of reverting the whole cart. Returns whether each
+    /// item was bought, in order.
+    #[payable]
+    #[non_reentrant]
+    pub fn buy_items(&mut self, item_ids: Vec<U256>) -> Result<Vec<bool>, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if item_ids.is_empty() || item_ids.len() > MAX_BATCH_SIZE {
+            return Err("Invalid batch size".as_bytes().to_vec());
         }
         
-        let fee = (price * self.fee_percentage.get()) / U256::from(10000);
-        let _seller_amount = price - fee;
+        let mut total = U256::ZERO;
+        for item_id in &item_ids {
+            let item = self.market_items.get(*item_id);
+            if item.payment_token.get() != NATIVE_TOKEN {
+                return Err("Listing is priced in a token".as_bytes().to_vec());
+            }
+            total += item.price.get();
+        }
+        if self.vm().msg_value() < total {
+            return Err("Insufficient payment".as_bytes().to_vec());
+        }
         
-        // Now we can safely get the mutable reference
         let buyer = self.vm().msg_sender();
-        let mut item_setter = self.market_items.setter(item_id);
-        item_setter.owner.set(buyer);
-        item_setter.sold.set(true);
-        self.sold_items.setter(item_id).set(true);
+        let mut refund = self.vm().msg_value() - total;
+        let mut filled = Vec::with_capacity(item_ids.len());
+        for item_id in item_ids {
+            let (price, nft_contract, token_id, seller) = {
+                let item = self.market_items.get(item_id);
+                (
+                    item.price.get(),
+                    item.nft_contract.get(),
+                    item.token_id.get(),
+                    item.seller.get(),
+                )
+            };
+            if self.status_of(item_id) != Some(ListingStatus::Active) || self.require_item_unflagged(item_id).is_err() {
+                refund += price;
+                filled.push(false);
+                continue;
+            }
+            self.settle_purchase(item_id, nft_contract, token_id, seller, buyer, price)?;
+            filled.push(true);
+        }
         
-        log(self.vm(), MarketItemSold {
-            item_id,
-            nft_contract,
-            token_id,
-            seller,
-            buyer,
+        if refund > U256::ZERO {
+            safe_transfer_eth(self, buyer, refund, GasStipend::All)?;
+        }
+        
+        Ok(filled)
+    }
+
+    /// Relist a purchased item at `price` (its buyer only)
+    ///
+    /// Opens a new listing of the same NFTs linked to `item_id`, escrowing
+    /// them again, so the buyer must still own them and approve the
+    /// marketplace. Each sale can be relisted once. Returns the new item id.
+    #[non_reentrant]
+    pub fn relist_item(&mut self, item_id: U256, price: U256) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if price <= U256::from(0) {
+            return Err("Price must be greater than zero".as_bytes().to_vec());
+        }
+        self.require_status(item_id, &[ListingStatus::Sold])?;
+        let sender = self.vm().msg_sender();
+        {
+            let item = self.market_items.get(item_id);
+            if item.owner.get() != sender {
+                return Err("Only buyer can relist item".as_bytes().to_vec());
+            }
+            if item.next_item_id.get() != U256::ZERO {
+                return Err("Item already relisted".as_bytes().to_vec());
+            }
+            let escrow_state = item.escrow_state.get().to::<u8>();
+            if escrow_state == ESCROW_HELD || escrow_state == ESCROW_DISPUTED {
+                return Err("Item in escrow".as_bytes().to_vec());
+            }
+        }
+        
+        let tokens = self.item_tokens(item_id);
+        for (nft_contract, token_id) in &tokens {
+            self.require_listable(*nft_contract)?;
+            self.require_unflagged(*nft_contract, *token_id)?;
+            self.require_permitted(*nft_contract, sender, *token_id)?;
+        }
+        let is_bundle = self.is_bundle(item_id);
+        let new_item_id = self.item_count.get() + U256::from(1);
+        
+        let mut market_item = self.market_items.setter(new_item_id);
+        market_item.item_id.set(new_item_id);
+        market_item.nft_contract.set(tokens[0].0);
+        market_item.token_id.set(tokens[0].1);
+        market_item.seller.set(sender);
+        market_item.owner.set(self.vm().contract_address());
+        market_item.price.set(price);
+        market_item.previous_item_id.set(item_id);
+        if is_bundle {
+            for (nft_contract, token_id) in &tokens {
+                market_item.bundle_contracts.push(*nft_contract);
+                market_item.bundle_token_ids.push(*token_id);
+            }
+        }
+        
+        self.market_items.setter(item_id).next_item_id.set(new_item_id);
+        self.item_count.set(new_item_id);
+        self.set_status(new_item_id, ListingStatus::Active);
+        if !is_bundle {
+            self.listed_items.setter(tokens[0].0).setter(tokens[0].1).set(new_item_id);
+        }
+        self.seller_items.setter(sender).push(new_item_id);
+        let mut collections: Vec<Address> = Vec::new();
+        for (nft_contract, _) in &tokens {
+            if !collections.contains(nft_contract) {
+                collections.push(*nft_contract);
+                self.collection_items.setter(*nft_contract).push(new_item_id);
+            }
+        }
+        
+        let marketplace = self.vm().contract_address();
+        self.snapshot_token_uris(new_item_id);
+        self.detect_standards(new_item_id, sender)?;
+        self.transfer_item(new_item_id, sender, marketplace)?;
+        
+        log(self.vm(), ItemRelisted {
+            item_id: new_item_id,
+            previous_item_id: item_id,
+            seller: sender,
             price,
         });
         
-        Ok(())
+        Ok(new_item_id)
+    }
+
+    /// Get the listings linked to an item as `(previous_item_id, next_item_id)`
+    ///
+    /// Zero where the item wasn't a relisting or hasn't been relisted.
+    pub fn get_item_links(&self, item_id: U256) -> (U256, U256) {
+        let item = self.market_items.get(item_id);
+        (item.previous_item_id.get(), item.next_item_id.get())
+    }
+
+    /// Get ids of every sale of a token through the marketplace, oldest first
+    pub fn get_sale_history(&self, nft_contract: Address, token_id: U256) -> Vec<U256> {
+        let ids = self.token_sales.getter(nft_contract);
+        let ids = ids.getter(token_id);
+        (0..ids.len()).filter_map(|i| ids.get(i)).collect()
+    }
+
+    /// Get sale details as `(nft_contract, token_id, item_id, seller, buyer, price, timestamp)`
+    ///
+    /// `item_id` is zero for sales outside a listing (offers on unlisted
+    /// tokens and voucher mints); `price` is the whole bundle's for bundles.
+    pub fn get_sale(&self, sale_id: U256) -> (Address, U256, U256, Address, Address, U256, U256) {
+        let sale = self.sales.get(sale_id);
+        (
+            sale.nft_contract.get(),
+            sale.token_id.get(),
+            sale.item_id.get(),
+            sale.seller.get(),
+            sale.buyer.get(),
+            sale.price.get(),
+            sale.timestamp.get(),
+        )
+    }
+
+    /// Get total sales count
+    pub fn get_sale_count(&self) -> U256 {
+        self.sale_count.get()
     }
 
     /// Get market item details
-    pub fn get_market_item(&self, item_id: U256) -> (U256, Address, U256, Address, Address, U256, bool) {
+    ///
+    /// The last field is the `ListingStatus` (1 active, 2 sold, 3 cancelled,
+    /// 4 expired, 5 scheduled), zero if the item doesn't exist.
+    pub fn get_market_item(&self, item_id: U256) -> (U256, Address, U256, Address, Address, U256, u8) {
         let item = self.market_items.get(item_id);
         (
             item.item_id.get(),
@@ -132,20 +892,18 @@ impl NFTMarketplace {
             item.seller.get(),
             item.owner.get(),
             item.price.get(),
-            item.sold.get(),
+            self.get_listing_status(item_id),
         )
     }
 
     /// Update listing price
     pub fn update_listing_price(&mut self, item_id: U256, new_price: U256) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
         let item = self.market_items.get(item_id);
         
         if item.seller.get() != self.vm().msg_sender() {
             return Err("Only seller can update price".as_bytes().to_vec());
         }
-        if item.sold.get() {
-            return Err("Item already sold".as_bytes().to_vec());
-        }
         if new_price <= U256::from(0) {
             return Err("Price must be greater than zero".as_bytes().to_vec());
         }
@@ -162,20 +920,61 @@ impl NFTMarketplace {
         Ok(())
     }
 
-    /// Cancel listing
+    /// Change a listing's price, payment token and expiry in one call (seller only)
+    ///
+    /// Same rules as `update_listing_price` and `set_listing_expiry`, with a
+    /// single `ListingUpdated` event. `payment_token` is `NATIVE_TOKEN` (the
+    /// zero address) for ETH or an ERC20 contract; the price is in its units.
+    /// A token without code can't be paid in, so such a listing can't sell.
+    pub fn update_listing(
+        &mut self,
+        item_id: U256,
+        price: U256,
+        payment_token: Address,
+        expires_at: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        if self.market_items.get(item_id).seller.get() != self.vm().msg_sender() {
+            return Err("Only seller can update listing".as_bytes().to_vec());
+        }
+        if price <= U256::from(0) {
+            return Err("Price must be greater than zero".as_bytes().to_vec());
+        }
+        let mut item = self.market_items.setter(item_id);
+        item.price.set(price);
+        item.payment_token.set(payment_token);
+        item.expires_at.set(expires_at);
+        
+        log(self.vm(), ListingUpdated {
+            item_id,
+            price,
+            payment_token,
+            expires_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Cancel listing, returning the escrowed NFTs to the seller
+    #[non_reentrant]
     pub fn cancel_listing(&mut self, item_id: U256) -> Result<(), Vec<u8>> {
         let item = self.market_items.get(item_id);
+        let seller = item.seller.get();
+        let nft_contract = item.nft_contract.get();
+        let token_id = item.token_id.get();
         
-        if item.seller.get() != self.vm().msg_sender() {
+        if seller != self.vm().msg_sender() {
             return Err("Only seller can cancel listing".as_bytes().to_vec());
         }
-        if item.sold.get() {
-            return Err("Item already sold".as_bytes().to_vec());
-        }
+        // Scheduled and expired listings are cancelled the same way to reclaim the NFTs
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
         
-        // Mark as sold to prevent further sales
-        self.market_items.setter(item_id).sold.set(true);
-        self.sold_items.setter(item_id).set(true);
+        self.market_items.setter(item_id).owner.set(seller);
+        self.set_status(item_id, ListingStatus::Cancelled);
+        self.unlist(item_id, nft_contract, token_id);
+        
+        let marketplace = self.vm().contract_address();
+        self.transfer_item(item_id, marketplace, seller)?;
         
         log(self.vm(), ListingCancelled {
             item_id,
@@ -185,43 +984,2922 @@ impl NFTMarketplace {
         Ok(())
     }
 
-    /// Withdraw marketplace fees (owner only)
-    pub fn withdraw_fees(&mut self) -> Result<(), Vec<u8>> {
-        if self.vm().msg_sender() != self.owner.get() {
-            return Err("Only owner can withdraw fees".as_bytes().to_vec());
+    /// Turn buyer protection on or off for a listing (seller only)
+    ///
+    /// A protected item bought through `buy_market_item` or `buy_items`
+    /// stays in escrow, NFTs and payment alike, for the escrow window. The
+    /// buyer can dispute it meanwhile and a moderator can refund it; otherwise
+    /// `release_escrow` settles it afterwards. Accepted offers settle at once.
+    pub fn set_listing_escrow(&mut self, item_id: U256, enabled: bool) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        if self.market_items.get(item_id).seller.get() != self.vm().msg_sender() {
+            return Err("Only seller can update escrow".as_bytes().to_vec());
         }
         
-        // In a real implementation, would check contract balance
-        // and transfer ETH to owner using call or transfer
-        let balance = U256::from(0); // Placeholder - would get actual contract balance
+        self.market_items.setter(item_id).escrow_protected.set(enabled);
         
-        log(self.vm(), FeesWithdrawn {
-            owner: self.vm().msg_sender(),
-            amount: balance,
+        log(self.vm(), ListingEscrowUpdated {
+            item_id,
+            enabled,
         });
         
         Ok(())
     }
 
-    /// Check if item is sold
-    pub fn is_item_sold(&self, item_id: U256) -> bool {
-        self.sold_items.get(item_id)
-    }
-
-    /// Get marketplace fee percentage
-    pub fn get_fee_percentage(&self) -> U256 {
-        self.fee_percentage.get()
+    /// Set how long protected sales are held before release, in seconds (admin only)
+    ///
+    /// Applies to sales made after the change.
+    pub fn set_escrow_window(&mut self, window: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(ADMIN_ROLE)?;
+        
+        self.escrow_window.set(window);
+        
+        log(self.vm(), EscrowWindowUpdated {
+            window,
+        });
+        
+        Ok(())
     }
 
-    /// Check if item exists
+    /// Dispute a protected purchase within its window (buyer only)
+    ///
+    /// The escrow can then only be settled by a moderator.
+    pub fn raise_dispute(&mut self, item_id: U256) -> Result<(), Vec<u8>> {
+        let item = self.market_items.get(item_id);
+        let buyer = item.owner.get();
+        if buyer != self.vm().msg_sender() {
+            return Err("Only buyer can dispute".as_bytes().to_vec());
+        }
+        if item.escrow_state.get().to::<u8>() != ESCROW_HELD {
+            return Err("Escrow not held".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) >= item.escrow_release_at.get() {
+            return Err("Dispute window closed".as_bytes().to_vec());
+        }
+        
+        self.market_items.setter(item_id).escrow_state.set(U8::from(ESCROW_DISPUTED));
+        
+        log(self.vm(), DisputeRaised {
+            item_id,
+            buyer,
+        });
+        
+        Ok(())
+    }
+
+    /// Settle an undisputed protected sale once its window has passed
+    ///
+    /// Anyone can call this; the NFTs go to the buyer and the proceeds to the seller.
+    #[non_reentrant]
+    pub fn release_escrow(&mut self, item_id: U256) -> Result<(), Vec<u8>> {
+        let (state, release_at) = {
+            let item = self.market_items.get(item_id);
+            (item.escrow_state.get().to::<u8>(), item.escrow_release_at.get())
+        };
+        if state != ESCROW_HELD {
+            return Err("Escrow not held".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) < release_at {
+            return Err("Dispute window open".as_bytes().to_vec());
+        }
+        
+        self.release(item_id)
+    }
+
+    /// Settle a held or disputed protected sale either way (moderator only)
+    ///
+    /// With `refund`, the buyer is credited the price and the NFTs go back to
+    /// the seller, closing the listing as cancelled. Otherwise the sale
+    /// completes as in `release_escrow`.
+    #[non_reentrant]
+    pub fn resolve_escrow(&mut self, item_id: U256, refund: bool) -> Result<(), Vec<u8>> {
+        let moderator = self.roles.only_role(MODERATOR_ROLE)?;
+        let state = self.market_items.get(item_id).escrow_state.get().to::<u8>();
+        if state != ESCROW_HELD && state != ESCROW_DISPUTED {
+            return Err("Escrow not held".as_bytes().to_vec());
+        }
+        if !refund {
+            return self.release(item_id);
+        }
+        
+        self.refund_escrow(item_id, moderator)
+    }
+
+    /// Get an item's buyer protection as `(protected, state, release_at)`
+    ///
+    /// `state` is one of the `ESCROW_*` constants; the buyer is the item's owner.
+    pub fn get_escrow(&self, item_id: U256) -> (bool, u8, U256) {
+        let item = self.market_items.get(item_id);
+        (
+            item.escrow_protected.get(),
+            item.escrow_state.get().to::<u8>(),
+            item.escrow_release_at.get(),
+        )
+    }
+
+    /// Get the dispute window of protected sales in seconds
+    pub fn get_escrow_window(&self) -> U256 {
+        self.escrow_window.get()
+    }
+
+    /// Withdraw `amount` of the accrued marketplace fees to the caller (treasurer only)
+    ///
+    /// Partial withdrawals leave the rest accrued for later.
+    #[non_reentrant]
+    pub fn withdraw_fees(&mut self, amount: U256) -> Result<(), Vec<u8>> {
+        let treasurer = self.roles.only_role(TREASURER_ROLE)?;
+        
+        let accrued = self.accrued_fees.get();
+        if amount == U256::ZERO {
+            return Err("Amount must be greater than zero".as_bytes().to_vec());
+        }
+        if amount > accrued {
+            return Err("Amount exceeds accrued fees".as_bytes().to_vec());
+        }
+        
+        self.accrued_fees.set(accrued - amount);
+        safe_transfer_eth(self, treasurer, amount, GasStipend::All)?;
+        
+        log(self.vm(), FeesWithdrawn {
+            treasurer,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw `amount` of the fees accrued in the ERC20 `token` to the caller (treasurer only)
+    ///
+    /// Fee sinks only take ETH fees, so token fees are always withdrawn here.
+    #[non_reentrant]
+    pub fn withdraw_token_fees(&mut self, token: Address, amount: U256) -> Result<(), Vec<u8>> {
+        let treasurer = self.roles.only_role(TREASURER_ROLE)?;
+        
+        let accrued = self.token_fees.get(token);
+        if amount == U256::ZERO {
+            return Err("Amount must be greater than zero".as_bytes().to_vec());
+        }
+        if amount > accrued {
+            return Err("Amount exceeds accrued fees".as_bytes().to_vec());
+        }
+        
+        self.token_fees.setter(token).set(accrued - amount);
+        safe_erc20_transfer(token, treasurer, amount)?;
+        
+        log(self.vm(), TokenFeesWithdrawn {
+            token,
+            treasurer,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Route shares of the accrued fees to a staking contract and a treasury (admin only)
+    ///
+    /// Shares are in basis points of the accrued fees at each
+    /// `distribute_fees` call; whatever isn't routed stays for treasurers to
+    /// withdraw. A sink with a zero share may be the zero address.
+    pub fn set_fee_sinks(
+        &mut self,
+        staking_sink: Address,
+        staking_share_bps: U256,
+        treasury_sink: Address,
+        treasury_share_bps: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.roles.only_role(ADMIN_ROLE)?;
+        if staking_share_bps + treasury_share_bps > U256::from(10000) {
+            return Err("Shares too high".as_bytes().to_vec());
+        }
+        if (staking_sink == Address::ZERO && staking_share_bps > U256::ZERO)
+            || (treasury_sink == Address::ZERO && treasury_share_bps > U256::ZERO)
+        {
+            return Err("Invalid fee sink".as_bytes().to_vec());
+        }
+        
+        self.staking_sink.set(staking_sink);
+        self.staking_share_bps.set(staking_share_bps);
+        self.treasury_sink.set(treasury_sink);
+        self.treasury_share_bps.set(treasury_share_bps);
+        
+        log(self.vm(), FeeSinksUpdated {
+            staking_sink,
+            staking_share_bps,
+            treasury_sink,
+            treasury_share_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Send the sinks their shares of the fees accrued so far
+    ///
+    /// Anyone can call this; the destinations and shares are fixed by the
+    /// admin. Returns `(staking_amount, treasury_amount)`.
+    #[non_reentrant]
+    pub fn distribute_fees(&mut self) -> Result<(U256, U256), Vec<u8>> {
+        let accrued = self.accrued_fees.get();
+        let staking_sink = self.staking_sink.get();
+        let treasury_sink = self.treasury_sink.get();
+        let staking_amount = accrued * self.staking_share_bps.get() / U256::from(10000);
+        let treasury_amount = accrued * self.treasury_share_bps.get() / U256::from(10000);
+        if staking_amount + treasury_amount == U256::ZERO {
+            return Err("Nothing to distribute".as_bytes().to_vec());
+        }
+        
+        self.accrued_fees.set(accrued - staking_amount - treasury_amount);
+        for (sink, amount) in [(staking_sink, staking_amount), (treasury_sink, treasury_amount)] {
+            if amount > U256::ZERO {
+                let distributed = self.sink_distributed.get(sink);
+                self.sink_distributed.setter(sink).set(distributed + amount);
+                safe_transfer_eth(self, sink, amount, GasStipend::All)?;
+            }
+        }
+        
+        log(self.vm(), FeesDistributed {
+            staking_sink,
+            staking_amount,
+            treasury_sink,
+            treasury_amount,
+        });
+        
+        Ok((staking_amount, treasury_amount))
+    }
+
+    /// Get the fee sinks as `(staking_sink, staking_share_bps, treasury_sink, treasury_share_bps)`
+    pub fn get_fee_sinks(&self) -> (Address, U256, Address, U256) {
+        (
+            self.staking_sink.get(),
+            self.staking_share_bps.get(),
+            self.treasury_sink.get(),
+            self.treasury_share_bps.get(),
+        )
+    }
+
+    /// Get everything `distribute_fees` has sent to a sink
+    pub fn get_sink_distributed(&self, sink: Address) -> U256 {
+        self.sink_distributed.get(sink)
+    }
+
+    /// Give `account` a role (admin only)
+    ///
+    /// Roles are `ADMIN_ROLE`, `FEE_MANAGER_ROLE`, `PAUSER_ROLE`,
+    /// `CURATOR_ROLE`, `MODERATOR_ROLE` and `TREASURER_ROLE`. Granting a
+    /// role the account already holds does nothing.
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        Ok(self.roles.grant_role(role, account)?)
+    }
+
+    /// Take a role away from `account` (admin only)
+    ///
+    /// The last admin can't be revoked, so the roles always stay manageable.
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        self.roles.only_role(ADMIN_ROLE)?;
+        self.keep_last_admin(role, account)?;
+        Ok(self.roles.revoke_role(role, account)?)
+    }
+
+    /// Give up one of the caller's own roles
+    pub fn renounce_role(&mut self, role: B256) -> Result<(), Vec<u8>> {
+        let sender = self.vm().msg_sender();
+        self.keep_last_admin(role, sender)?;
+        Ok(self.roles.renounce_role(role, sender)?)
+    }
+
+    /// Check if `account` holds `role`
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.roles.has_role(role, account)
+    }
+
+    /// Get every account holding `role`
+    pub fn get_role_members(&self, role: B256) -> Vec<Address> {
+        self.roles.members(role)
+    }
+
+    /// Check if an account holds the pauser role
+    pub fn is_pauser(&self, account: Address) -> bool {
+        self.has_role(PAUSER_ROLE, account)
+    }
+
+    /// Stop listings and purchases (pauser only)
+    ///
+    /// Cancelling listings, offers and vouchers, and withdrawing fees, stay open.
+    pub fn pause(&mut self) -> Result<(), Vec<u8>> {
+        self.roles.only_role(PAUSER_ROLE)?;
+        Ok(self.pausable.pause()?)
+    }
+
+    /// Resume listings and purchases (pauser only)
+    pub fn unpause(&mut self) -> Result<(), Vec<u8>> {
+        self.roles.only_role(PAUSER_ROLE)?;
+        Ok(self.pausable.unpause()?)
+    }
+
+    /// Check if the marketplace is paused
+    pub fn paused(&self) -> bool {
+        self.pausable.paused()
+    }
+
+    /// Check if an account holds the curator role
+    pub fn is_curator(&self, account: Address) -> bool {
+        self.has_role(CURATOR_ROLE, account)
+    }
+
+    /// Check if an account holds the moderator role
+    pub fn is_moderator(&self, account: Address) -> bool {
+        self.has_role(MODERATOR_ROLE, account)
+    }
+
+    /// Flag or unflag a single token as stolen or compromised (moderator only)
+    ///
+    /// A flagged token can't be listed or bought. Listings and offers already
+    /// open stay in place so their owners can still cancel them.
+    pub fn set_token_flagged(&mut self, nft_contract: Address, token_id: U256, flagged: bool) -> Result<(), Vec<u8>> {
+        let moderator = self.roles.only_role(MODERATOR_ROLE)?;
+        
+        self.flagged_tokens.setter(nft_contract).setter(token_id).set(flagged);
+        
+        log(self.vm(), TokenFlagged {
+            nft_contract,
+            token_id,
+            flagged,
+            moderator,
+        });
+        
+        Ok(())
+    }
+
+    /// Flag or unflag a whole collection as compromised (moderator only)
+    pub fn set_collection_flagged(&mut self, nft_contract: Address, flagged: bool) -> Result<(), Vec<u8>> {
+        let moderator = self.roles.only_role(MODERATOR_ROLE)?;
+        
+        self.flagged_collections.setter(nft_contract).set(flagged);
+        
+        log(self.vm(), CollectionFlagged {
+            nft_contract,
+            flagged,
+            moderator,
+        });
+        
+        Ok(())
+    }
+
+    /// Get a token's flags as `(collection_flagged, token_flagged)`, for frontends to warn on either
+    pub fn get_flag_status(&self, nft_contract: Address, token_id: U256) -> (bool, bool) {
+        (
+            self.flagged_collections.get(nft_contract),
+            self.flagged_tokens.getter(nft_contract).get(token_id),
+        )
+    }
+
+    /// Check if a token is blocked, by itself or through its collection
+    pub fn is_flagged(&self, nft_contract: Address, token_id: U256) -> bool {
+        let (collection_flagged, token_flagged) = self.get_flag_status(nft_contract, token_id);
+        collection_flagged || token_flagged
+    }
+
+    /// Mark a collection as verified or not (curator only)
+    pub fn set_collection_verified(&mut self, nft_contract: Address, verified: bool) -> Result<(), Vec<u8>> {
+        self.roles.only_role(CURATOR_ROLE)?;
+        if verified == self.is_collection_verified(nft_contract) {
+            return Ok(());
+        }
+        
+        if verified {
+            self.verified_collections.push(nft_contract);
+            let position = U256::from(self.verified_collections.len());
+            self.verified_positions.setter(nft_contract).set(position);
+        } else {
+            // Swap-remove: move the last collection into the freed slot
+            let index = self.verified_positions.get(nft_contract).saturating_to::<usize>() - 1;
+            let last_index = self.verified_collections.len() - 1;
+            if index != last_index {
+                let last = self.verified_collections.get(last_index).unwrap_or_default();
+                if let Some(mut slot) = self.verified_collections.setter(index) {
+                    slot.set(last);
+                }
+                self.verified_positions.setter(last).set(U256::from(index + 1));
+            }
+            self.verified_collections.pop();
+            self.verified_positions.setter(nft_contract).set(U256::ZERO);
+        }
+        
+        log(self.vm(), CollectionVerificationUpdated {
+            nft_contract,
+            verified,
+        });
+        
+        Ok(())
+    }
+
+    /// Check if a collection is verified
+    pub fn is_collection_verified(&self, nft_contract: Address) -> bool {
+        self.verified_positions.get(nft_contract) != U256::ZERO
+    }
+
+    /// Restrict who may list a verified collection's tokens (collection owner only)
+    ///
+    /// Every new listing, bundled or relisted, of the collection must then be
+    /// approved by `policy.canList(nft_contract, seller, token_id)`, e.g. to
+    /// allow only long-time holders or an allowlist. The caller must be the
+    /// collection's `owner()`. Pass the zero address to lift the policy.
+    /// Existing listings are unaffected.
+    pub fn set_listing_policy(&mut self, nft_contract: Address, policy: Address) -> Result<(), Vec<u8>> {
+        if !self.is_collection_verified(nft_contract) {
+            return Err("Collection not verified".as_bytes().to_vec());
+        }
+        let owner = IOwnable::new(nft_contract)
+            .owner(Call::new_in(self))
+            .map_err(|_| "Collection owner lookup failed".as_bytes().to_vec())?;
+        if owner != self.vm().msg_sender() {
+            return Err("Only collection owner can set policy".as_bytes().to_vec());
+        }
+        if policy != Address::ZERO && !policy.has_code() {
+            return Err("Policy must be a contract".as_bytes().to_vec());
+        }
+        
+        self.listing_policies.setter(nft_contract).set(policy);
+        
+        log(self.vm(), ListingPolicyUpdated {
+            nft_contract,
+            policy,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the listing policy of a collection, zero if anyone may list
+    pub fn get_listing_policy(&self, nft_contract: Address) -> Address {
+        self.listing_policies.get(nft_contract)
+    }
+
+    /// Get every verified collection, in no particular order
+    pub fn get_verified_collections(&self) -> Vec<Address> {
+        (0..self.verified_collections.len())
+            .filter_map(|i| self.verified_collections.get(i))
+            .collect()
+    }
+
+    /// Only accept listings, offers and voucher mints for verified collections (curator only)
+    pub fn set_verified_only(&mut self, enabled: bool) -> Result<(), Vec<u8>> {
+        self.roles.only_role(CURATOR_ROLE)?;
+        
+        self.verified_only.set(enabled);
+        
+        log(self.vm(), VerifiedOnlyModeUpdated { enabled });
+        
+        Ok(())
+    }
+
+    /// Check if the marketplace only accepts verified collections
+    pub fn is_verified_only(&self) -> bool {
+        self.verified_only.get()
+    }
+
+    /// Get marketplace fees collected and not yet withdrawn
+    pub fn get_accrued_fees(&self) -> U256 {
+        self.accrued_fees.get()
+    }
+
+    /// Get the marketplace fees accrued in the ERC20 `token`
+    pub fn get_accrued_token_fees(&self, token: Address) -> U256 {
+        self.token_fees.get(token)
+    }
+
+    /// Get the token an item is priced in, `NATIVE_TOKEN` for ETH
+    pub fn get_payment_token(&self, item_id: U256) -> Address {
+        self.market_items.get(item_id).payment_token.get()
+    }
+
+    /// Check if item is sold
+    pub fn is_item_sold(&self, item_id: U256) -> bool {
+        self.status_of(item_id) == Some(ListingStatus::Sold)
+    }
+
+    /// Get the `ListingStatus` of an item, zero if it doesn't exist
+    pub fn get_listing_status(&self, item_id: U256) -> u8 {
+        self.status_of(item_id).map_or(0, |status| status as u8)
+    }
+
+    /// Make a listing expire at `expires_at`, zero for never (seller only)
+    pub fn set_listing_expiry(&mut self, item_id: U256, expires_at: U256) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        if self.market_items.get(item_id).seller.get() != self.vm().msg_sender() {
+            return Err("Only seller can update expiry".as_bytes().to_vec());
+        }
+        
+        self.market_items.setter(item_id).expires_at.set(expires_at);
+        
+        log(self.vm(), ListingExpiryUpdated {
+            item_id,
+            expires_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Split a listing's proceeds among `recipients` (seller only)
+    ///
+    /// `shares_bps[i]` is the share of `recipients[i]` in basis points of the
+    /// proceeds after fees, and the shares must sum to 10000. Each recipient
+    /// is credited when the item sells, to withdraw like any seller. Pass
+    /// empty arrays to pay the seller alone again.
+    pub fn set_payout_split(
+        &mut self,
+        item_id: U256,
+        recipients: Vec<Address>,
+        shares_bps: Vec<U256>,
+    ) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        if self.market_items.get(item_id).seller.get() != self.vm().msg_sender() {
+            return Err("Only seller can set payout split".as_bytes().to_vec());
+        }
+        if recipients.len() != shares_bps.len() {
+            return Err("Split arrays length mismatch".as_bytes().to_vec());
+        }
+        if recipients.len() > MAX_SPLIT_RECIPIENTS {
+            return Err("Too many split recipients".as_bytes().to_vec());
+        }
+        if recipients.contains(&Address::ZERO) || shares_bps.contains(&U256::ZERO) {
+            return Err("Invalid split recipient".as_bytes().to_vec());
+        }
+        let total = shares_bps.iter().fold(U256::ZERO, |sum, bps| sum.saturating_add(*bps));
+        if !recipients.is_empty() && total != U256::from(10000) {
+            return Err("Split shares must sum to 10000".as_bytes().to_vec());
+        }
+        
+        let mut item = self.market_items.setter(item_id);
+        item.split_recipients.erase();
+        item.split_bps.erase();
+        for (recipient, bps) in recipients.iter().zip(&shares_bps) {
+            item.split_recipients.push(*recipient);
+            item.split_bps.push(*bps);
+        }
+        
+        log(self.vm(), PayoutSplitUpdated {
+            item_id,
+            recipients,
+            shares_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Get a listing's payout split as `(recipients, shares_bps)`, empty if the seller takes everything
+    pub fn get_payout_split(&self, item_id: U256) -> (Vec<Address>, Vec<U256>) {
+        let item = self.market_items.get(item_id);
+        (0..item.split_recipients.len())
+            .filter_map(|i| Some((item.split_recipients.get(i)?, item.split_bps.get(i)?)))
+            .unzip()
+    }
+
+    /// Get how an item settles as `(standards, royalties)`
+    ///
+    /// `standards` follows `get_bundle`: 1 for ERC721, 2 for ERC1155.
+    /// `royalties` is true if the sale pays the collection's ERC2981 royalty.
+    pub fn get_token_standards(&self, item_id: U256) -> (Vec<u8>, bool) {
+        (self.item_standards(item_id), self.market_items.get(item_id).royalties.get())
+    }
+
+    /// Hand control of a listing to `new_seller` (seller only)
+    ///
+    /// The new seller manages the listing and is credited its sale from now
+    /// on, e.g. when an estate or DAO module takes over. The item moves from
+    /// the old seller's index to the end of the new seller's. Any payout
+    /// split was the old seller's choice, so it is cleared.
+    pub fn transfer_listing(&mut self, item_id: U256, new_seller: Address) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        let old_seller = self.market_items.get(item_id).seller.get();
+        if old_seller != self.vm().msg_sender() {
+            return Err("Only seller can transfer listing".as_bytes().to_vec());
+        }
+        if new_seller == Address::ZERO || new_seller == old_seller {
+            return Err("Invalid new seller".as_bytes().to_vec());
+        }
+        // ERC1155 tokens stay with the seller, so they can't change hands here
+        if self.item_standards(item_id).contains(&STANDARD_ERC1155) {
+            return Err("Listing holds unescrowed tokens".as_bytes().to_vec());
+        }
+        
+        let mut item = self.market_items.setter(item_id);
+        item.seller.set(new_seller);
+        let had_split = !item.split_recipients.is_empty();
+        item.split_recipients.erase();
+        item.split_bps.erase();
+        remove_id(&mut self.seller_items.setter(old_seller), item_id);
+        self.seller_items.setter(new_seller).push(item_id);
+        index_remove(
+            &mut self.seller_active_items.setter(old_seller),
+            &mut self.seller_active_positions.setter(old_seller),
+            item_id,
+        );
+        index_insert(
+            &mut self.seller_active_items.setter(new_seller),
+            &mut self.seller_active_positions.setter(new_seller),
+            item_id,
+        );
+        
+        log(self.vm(), ListingTransferred {
+            item_id,
+            old_seller,
+            new_seller,
+        });
+        if had_split {
+            log(self.vm(), PayoutSplitUpdated {
+                item_id,
+                recipients: vec![],
+                shares_bps: vec![],
+            });
+        }
+        
+        Ok(())
+    }
+
+    /// Make a listing go on sale at `starts_at`, zero for now (seller only)
+    pub fn set_listing_start(&mut self, item_id: U256, starts_at: U256) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active, ListingStatus::Scheduled, ListingStatus::Expired])?;
+        if self.market_items.get(item_id).seller.get() != self.vm().msg_sender() {
+            return Err("Only seller can update start".as_bytes().to_vec());
+        }
+        
+        self.market_items.setter(item_id).starts_at.set(starts_at);
+        
+        log(self.vm(), ListingStartUpdated {
+            item_id,
+            starts_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the time a listing goes on sale, zero if it was live from the start
+    pub fn get_listing_start(&self, item_id: U256) -> U256 {
+        self.market_items.get(item_id).starts_at.get()
+    }
+
+    /// Get marketplace fee percentage
+    pub fn get_fee_percentage(&self) -> U256 {
+        self.fee_percentage.get()
+    }
+
+    /// Propose a new base fee in basis points (fee manager only)
+    ///
+    /// The change can be applied with `apply_fee_change` once the fee change
+    /// delay has passed, giving traders notice. A new proposal replaces the
+    /// pending one and restarts the delay.
+    pub fn set_fee_percentage(&mut self, new_fee: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if new_fee > U256::from(MAX_FEE_BPS) {
+            return Err("Fee too high".as_bytes().to_vec());
+        }
+        
+        let effective_at = U256::from(self.vm().block_timestamp()) + self.fee_change_delay.get();
+        self.pending_fee.set(new_fee);
+        self.pending_fee_at.set(effective_at);
+        
+        log(self.vm(), FeeChangeProposed {
+            old_fee: self.fee_percentage.get(),
+            new_fee,
+            effective_at,
+        });
+        
+        Ok(())
+    }
+
+    /// Apply the pending base fee once its delay has passed (anyone)
+    pub fn apply_fee_change(&mut self) -> Result<(), Vec<u8>> {
+        let effective_at = self.pending_fee_at.get();
+        if effective_at == U256::ZERO {
+            return Err("No pending fee change".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) < effective_at {
+            return Err("Fee change not yet effective".as_bytes().to_vec());
+        }
+        
+        let old_fee = self.fee_percentage.get();
+        let new_fee = self.pending_fee.get();
+        self.fee_percentage.set(new_fee);
+        self.pending_fee.set(U256::ZERO);
+        self.pending_fee_at.set(U256::ZERO);
+        
+        log(self.vm(), FeeChanged {
+            old_fee,
+            new_fee,
+        });
+        
+        Ok(())
+    }
+
+    /// Drop the pending base fee change (fee manager only)
+    pub fn cancel_fee_change(&mut self) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if self.pending_fee_at.get() == U256::ZERO {
+            return Err("No pending fee change".as_bytes().to_vec());
+        }
+        
+        let new_fee = self.pending_fee.get();
+        self.pending_fee.set(U256::ZERO);
+        self.pending_fee_at.set(U256::ZERO);
+        
+        log(self.vm(), FeeChangeCancelled {
+            new_fee,
+        });
+        
+        Ok(())
+    }
+
+    /// Set how long a proposed base fee waits before it can apply (admin only)
+    ///
+    /// Only affects later proposals.
+    pub fn set_fee_change_delay(&mut self, delay: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(ADMIN_ROLE)?;
+        
+        self.fee_change_delay.set(delay);
+        
+        log(self.vm(), FeeChangeDelayUpdated {
+            delay,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the pending base fee as `(new_fee, effective_at)`, `effective_at` zero if none
+    pub fn get_pending_fee(&self) -> (U256, U256) {
+        (self.pending_fee.get(), self.pending_fee_at.get())
+    }
+
+    /// Get how long a proposed base fee waits before it can apply
+    pub fn get_fee_change_delay(&self) -> U256 {
+        self.fee_change_delay.get()
+    }
+
+    /// Charge `fee_bps` on every sale of a collection, e.g. zero for partners (fee manager only)
+    ///
+    /// An override takes precedence over the base fee and seller tiers.
+    pub fn set_collection_fee(&mut self, nft_contract: Address, fee_bps: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if fee_bps > U256::from(MAX_FEE_BPS) {
+            return Err("Fee too high".as_bytes().to_vec());
+        }
+        
+        self.collection_fees.setter(nft_contract).set(fee_bps);
+        self.has_collection_fee.setter(nft_contract).set(true);
+        
+        log(self.vm(), CollectionFeeUpdated {
+            nft_contract,
+            fee_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Remove a collection's fee override (fee manager only)
+    pub fn clear_collection_fee(&mut self, nft_contract: Address) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        
+        self.collection_fees.setter(nft_contract).set(U256::ZERO);
+        self.has_collection_fee.setter(nft_contract).set(false);
+        
+        log(self.vm(), CollectionFeeCleared { nft_contract });
+        
+        Ok(())
+    }
+
+    /// Get a collection's fee override as `(is_set, fee_bps)`
+    pub fn get_collection_fee(&self, nft_contract: Address) -> (bool, U256) {
+        (self.has_collection_fee.get(nft_contract), self.collection_fees.get(nft_contract))
+    }
+
+    /// Replace the seller volume tiers (fee manager only)
+    ///
+    /// A seller whose lifetime sales reach `min_volumes[i]` pays `fees_bps[i]`
+    /// instead of the base fee. Volumes must be strictly ascending; pass
+    /// empty arrays to remove every tier.
+    pub fn set_fee_tiers(&mut self, min_volumes: Vec<U256>, fees_bps: Vec<U256>) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if min_volumes.len() != fees_bps.len() {
+            return Err("Tier arrays length mismatch".as_bytes().to_vec());
+        }
+        if min_volumes.len() > MAX_FEE_TIERS {
+            return Err("Too many tiers".as_bytes().to_vec());
+        }
+        if min_volumes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Tier volumes must ascend".as_bytes().to_vec());
+        }
+        if fees_bps.iter().any(|fee| *fee > U256::from(MAX_FEE_BPS)) {
+            return Err("Fee too high".as_bytes().to_vec());
+        }
+        
+        self.tier_volumes.erase();
+        self.tier_fees.erase();
+        for (min_volume, fee_bps) in min_volumes.iter().zip(&fees_bps) {
+            self.tier_volumes.push(*min_volume);
+            self.tier_fees.push(*fee_bps);
+        }
+        
+        log(self.vm(), FeeTiersUpdated {
+            min_volumes,
+            fees_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the seller volume tiers as `(min_volumes, fees_bps)`
+    pub fn get_fee_tiers(&self) -> (Vec<U256>, Vec<U256>) {
+        (0..self.tier_volumes.len())
+            .filter_map(|i| Some((self.tier_volumes.get(i)?, self.tier_fees.get(i)?)))
+            .unzip()
+    }
+
+    /// Get a seller's lifetime sale volume through the marketplace
+    pub fn get_seller_volume(&self, seller: Address) -> U256 {
+        self.seller_volume.get(seller)
+    }
+
+    /// Get a buyer's lifetime purchase volume through the marketplace
+    pub fn get_buyer_volume(&self, buyer: Address) -> U256 {
+        self.buyer_volume.get(buyer)
+    }
+
+    /// Replace the loyalty tiers (fee manager only)
+    ///
+    /// A user whose lifetime volume, bought plus sold, reaches `min_volumes[i]`
+    /// gets `discounts_bps[i]` off the fee of their sales, in basis points of
+    /// the fee. Volumes must be strictly ascending; pass empty arrays to
+    /// remove every tier.
+    pub fn set_loyalty_tiers(&mut self, min_volumes: Vec<U256>, discounts_bps: Vec<U256>) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if min_volumes.len() != discounts_bps.len() {
+            return Err("Tier arrays length mismatch".as_bytes().to_vec());
+        }
+        if min_volumes.len() > MAX_FEE_TIERS {
+            return Err("Too many tiers".as_bytes().to_vec());
+        }
+        if min_volumes.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Tier volumes must ascend".as_bytes().to_vec());
+        }
+        if discounts_bps.iter().any(|discount| *discount > U256::from(10000)) {
+            return Err("Discount too high".as_bytes().to_vec());
+        }
+        
+        self.loyalty_volumes.erase();
+        self.loyalty_discounts.erase();
+        for (min_volume, discount_bps) in min_volumes.iter().zip(&discounts_bps) {
+            self.loyalty_volumes.push(*min_volume);
+            self.loyalty_discounts.push(*discount_bps);
+        }
+        
+        log(self.vm(), LoyaltyTiersUpdated {
+            min_volumes,
+            discounts_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the loyalty tiers as `(min_volumes, discounts_bps)`
+    pub fn get_loyalty_tiers(&self) -> (Vec<U256>, Vec<U256>) {
+        (0..self.loyalty_volumes.len())
+            .filter_map(|i| Some((self.loyalty_volumes.get(i)?, self.loyalty_discounts.get(i)?)))
+            .unzip()
+    }
+
+    /// Get a user's loyalty standing as `(tier, discount_bps)`
+    ///
+    /// `tier` counts from 1 for the first loyalty tier and is zero below it.
+    pub fn get_loyalty_tier(&self, user: Address) -> (U256, U256) {
+        let volume = self.get_lifetime_volume(user);
+        (0..self.loyalty_volumes.len())
+            .rev()
+            .find(|i| self.loyalty_volumes.get(*i).is_some_and(|min_volume| volume >= min_volume))
+            .map(|i| (U256::from(i + 1), self.loyalty_discounts.get(i).unwrap_or_default()))
+            .unwrap_or_default()
+    }
+
+    /// Get a user's lifetime volume, bought plus sold, which sets their loyalty tier
+    pub fn get_lifetime_volume(&self, user: Address) -> U256 {
+        self.seller_volume.get(user) + self.buyer_volume.get(user)
+    }
+
+    /// Get the fee in basis points a sale of `nft_contract` by `seller` pays
+    ///
+    /// The collection override if set, else the tier of the seller's volume,
+    /// else the base fee; then reduced by the seller's loyalty discount.
+    pub fn get_effective_fee(&self, nft_contract: Address, seller: Address) -> U256 {
+        let fee = if self.has_collection_fee.get(nft_contract) {
+            self.collection_fees.get(nft_contract)
+        } else {
+            let volume = self.seller_volume.get(seller);
+            (0..self.tier_volumes.len())
+                .rev()
+                .find(|i| self.tier_volumes.get(*i).is_some_and(|min_volume| volume >= min_volume))
+                .and_then(|i| self.tier_fees.get(i))
+                .unwrap_or_else(|| self.fee_percentage.get())
+        };
+        let (_, discount_bps) = self.get_loyalty_tier(seller);
+        fee * (U256::from(10000) - discount_bps) / U256::from(10000)
+    }
+
+    /// Get the fee taken from a sale of `nft_contract` by `seller` at `price`
+    ///
+    /// Bundles are priced by the collection of their first NFT.
+    pub fn get_sale_fee(&self, nft_contract: Address, seller: Address, price: U256) -> U256 {
+        (price * self.get_effective_fee(nft_contract, seller)) / U256::from(10000)
+    }
+
+    /// Check if item exists
     pub fn item_exists(&self, item_id: U256) -> bool {
         item_id <= self.item_count.get() && item_id > U256::from(0)
     }    
 
-    /// Get total items count
-    pub fn get_item_count(&self) -> U256 {
-        self.item_count.get()
+    /// Get total items count
+    pub fn get_item_count(&self) -> U256 {
+        self.item_count.get()
+    }
+
+    /// Get active listings from positions `offset` to `offset + limit` of the open-listing index
+    ///
+    /// The index holds every listing that hasn't sold or been cancelled, in
+    /// no particular order, so a page costs O(limit) however many items were
+    /// ever listed. Expired and scheduled listings stay indexed, since their
+    /// sellers can revive them, but are left out of the page, which can
+    /// therefore come back shorter than `limit`. Page on until `offset`
+    /// reaches `get_open_item_count()`.
+    pub fn get_active_items(&self, offset: U256, limit: U256) -> Vec<U256> {
+        self.live_page(&self.active_items, offset, limit)
+    }
+
+    /// Get a seller's active listings, paged over their open-listing index like `get_active_items`
+    pub fn get_active_items_by_seller(&self, seller: Address, offset: U256, limit: U256) -> Vec<U256> {
+        self.live_page(&self.seller_active_items.getter(seller), offset, limit)
+    }
+
+    /// Get a collection's active listings, paged over its open-listing index like `get_active_items`
+    pub fn get_active_items_by_collection(&self, nft_contract: Address, offset: U256, limit: U256) -> Vec<U256> {
+        self.live_page(&self.collection_active_items.getter(nft_contract), offset, limit)
+    }
+
+    /// Get the size of the open-listing index, expired and scheduled listings included
+    pub fn get_open_item_count(&self) -> U256 {
+        U256::from(self.active_items.len())
+    }
+
+    /// Get up to `limit` ids of a seller's listings, oldest first, skipping the first `offset`
+    pub fn get_items_by_seller(&self, seller: Address, offset: U256, limit: U256) -> Vec<U256> {
+        page(&self.seller_items.getter(seller), offset, limit)
+    }
+
+    /// Get up to `limit` ids of a collection's listings, oldest first, skipping the first `offset`
+    pub fn get_items_by_collection(&self, nft_contract: Address, offset: U256, limit: U256) -> Vec<U256> {
+        page(&self.collection_items.getter(nft_contract), offset, limit)
+    }
+
+    /// Get a collection's market as `(floor_price, floor_item_id, listed_count,
+    /// best_offer, best_offer_id, offer_count, sale_count, volume)`
+    ///
+    /// Floor and listed count cover active, unexpired single-token listings in ETH;
+    /// best offer and offer count cover active, unexpired offers. Both are
+    /// derived from the collection's listing and offer indexes on each call,
+    /// so this is meant for off-chain reads. Sale count and volume are kept
+    /// as sales settle and exclude bundles and token-priced sales. Ids are
+    /// zero when there is none.
+    pub fn get_collection_stats(&self, nft_contract: Address) -> (U256, U256, U256, U256, U256, U256, U256, U256) {
+        let mut floor_price = U256::ZERO;
+        let mut floor_item_id = U256::ZERO;
+        let mut listed_count = U256::ZERO;
+        let items = self.collection_active_items.getter(nft_contract);
+        for i in 0..items.len() {
+            let Some(item_id) = items.get(i) else { continue };
+            if self.is_bundle(item_id)
+                || self.get_payment_token(item_id) != NATIVE_TOKEN
+                || self.status_of(item_id) != Some(ListingStatus::Active)
+            {
+                continue;
+            }
+            let price = self.market_items.get(item_id).price.get();
+            if floor_item_id == U256::ZERO || price < floor_price {
+                floor_price = price;
+                floor_item_id = item_id;
+            }
+            listed_count += U256::from(1);
+        }
+        
+        let mut best_offer = U256::ZERO;
+        let mut best_offer_id = U256::ZERO;
+        let mut offer_count = U256::ZERO;
+        let now = U256::from(self.vm().block_timestamp());
+        let offer_ids = self.collection_offers.getter(nft_contract);
+        for i in 0..offer_ids.len() {
+            let Some(offer_id) = offer_ids.get(i) else { continue };
+            let offer = self.offers.get(offer_id);
+            if !offer.active.get() || offer.expiry.get() <= now {
+                continue;
+            }
+            let amount = offer.amount.get();
+            if amount > best_offer {
+                best_offer = amount;
+                best_offer_id = offer_id;
+            }
+            offer_count += U256::from(1);
+        }
+        
+        (
+            floor_price,
+            floor_item_id,
+            listed_count,
+            best_offer,
+            best_offer_id,
+            offer_count,
+            self.collection_sale_count.get(nft_contract),
+            self.collection_volume.get(nft_contract),
+        )
+    }
+
+    /// Get the `tokenURI` an item's NFT had when it was listed, as `(uri, keccak256(uri))`
+    ///
+    /// `index` follows `get_bundle` for bundles and is zero otherwise. The
+    /// hash is zero if the collection didn't answer `tokenURI`.
+    pub fn get_token_uri_snapshot(&self, item_id: U256, index: U256) -> (String, B256) {
+        let item = self.market_items.get(item_id);
+        let index = index.saturating_to::<usize>();
+        let uri = item.token_uris.get(index).map(|uri| uri.get_string()).unwrap_or_default();
+        let hash = item.token_uri_hashes.get(index).unwrap_or_default();
+        (uri, hash)
+    }
+
+    /// Check if an item is a bundle listing
+    pub fn is_bundle(&self, item_id: U256) -> bool {
+        !self.market_items.get(item_id).bundle_contracts.is_empty()
+    }
+
+    /// Get every NFT of an item as `(nft_contracts, token_ids)`
+    ///
+    /// A single listing is reported as a bundle of one.
+    pub fn get_bundle(&self, item_id: U256) -> (Vec<Address>, Vec<U256>) {
+        self.item_tokens(item_id).into_iter().unzip()
+    }
+
+    /// Get the active listing of a token, zero if it isn't listed
+    pub fn get_listed_item(&self, nft_contract: Address, token_id: U256) -> U256 {
+        self.listed_items.getter(nft_contract).get(token_id)
+    }
+
+    /// Offer `amount` ETH for a token, listed or not, until `expiry`
+    ///
+    /// The offer is escrowed in the marketplace, so `msg.value` must equal
+    /// `amount`. Returns the offer id.
+    #[payable]
+    #[non_reentrant]
+    pub fn make_offer(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        amount: U256,
+        expiry: U256,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if amount == U256::ZERO {
+            return Err("Offer must be greater than zero".as_bytes().to_vec());
+        }
+        self.require_listable(nft_contract)?;
+        if self.vm().msg_value() != amount {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        if expiry <= U256::from(self.vm().block_timestamp()) {
+            return Err("Expiry must be in the future".as_bytes().to_vec());
+        }
+        
+        let offer_id = self.offer_count.get() + U256::from(1);
+        let bidder = self.vm().msg_sender();
+        
+        let mut offer = self.offers.setter(offer_id);
+        offer.offer_id.set(offer_id);
+        offer.nft_contract.set(nft_contract);
+        offer.token_id.set(token_id);
+        offer.bidder.set(bidder);
+        offer.amount.set(amount);
+        offer.expiry.set(expiry);
+        offer.active.set(true);
+        
+        self.offer_count.set(offer_id);
+        self.index_offer(offer_id);
+        
+        log(self.vm(), OfferMade {
+            offer_id,
+            nft_contract,
+            token_id,
+            bidder,
+            amount,
+            expiry,
+        });
+        
+        Ok(offer_id)
+    }
+
+    /// Offer `amount` ETH for any token of a collection in a set, until `expiry`
+    ///
+    /// `criteria_root` is the merkle root of the acceptable token ids (see
+    /// `criteria`), and the seller proves their token is in it with
+    /// `accept_criteria_offer`. Escrowed like `make_offer`; criteria offers
+    /// can't be countered. Returns the offer id.
+    #[payable]
+    #[non_reentrant]
+    pub fn make_criteria_offer(
+        &mut self,
+        nft_contract: Address,
+        criteria_root: B256,
+        amount: U256,
+        expiry: U256,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if amount == U256::ZERO {
+            return Err("Offer must be greater than zero".as_bytes().to_vec());
+        }
+        if criteria_root == B256::ZERO {
+            return Err("Criteria root required".as_bytes().to_vec());
+        }
+        self.require_listable(nft_contract)?;
+        if self.vm().msg_value() != amount {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        if expiry <= U256::from(self.vm().block_timestamp()) {
+            return Err("Expiry must be in the future".as_bytes().to_vec());
+        }
+        
+        let offer_id = self.offer_count.get() + U256::from(1);
+        let bidder = self.vm().msg_sender();
+        
+        let mut offer = self.offers.setter(offer_id);
+        offer.offer_id.set(offer_id);
+        offer.nft_contract.set(nft_contract);
+        offer.bidder.set(bidder);
+        offer.amount.set(amount);
+        offer.expiry.set(expiry);
+        offer.active.set(true);
+        offer.criteria_root.set(criteria_root);
+        
+        self.offer_count.set(offer_id);
+        self.index_offer(offer_id);
+        
+        log(self.vm(), CriteriaOfferMade {
+            offer_id,
+            nft_contract,
+            criteria_root,
+            bidder,
+            amount,
+            expiry,
+        });
+        
+        Ok(offer_id)
+    }
+
+    /// Sell `token_id` to a criteria offer's bidder (current owner only)
+    ///
+    /// `proof` shows the token is in the offer's criteria. Otherwise settles
+    /// like `accept_offer`.
+    #[non_reentrant]
+    pub fn accept_criteria_offer(&mut self, offer_id: U256, token_id: U256, proof: Vec<B256>) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let (nft_contract, bidder, amount, expiry, active, criteria_root) = {
+            let offer = self.offers.get(offer_id);
+            (
+                offer.nft_contract.get(),
+                offer.bidder.get(),
+                offer.amount.get(),
+                offer.expiry.get(),
+                offer.active.get(),
+                offer.criteria_root.get(),
+            )
+        };
+        
+        if !active {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        if criteria_root == B256::ZERO {
+            return Err("Not a criteria offer".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) >= expiry {
+            return Err("Offer expired".as_bytes().to_vec());
+        }
+        if !criteria::verify(criteria_root, token_id, &proof) {
+            return Err("Token not in offer criteria".as_bytes().to_vec());
+        }
+        
+        let seller = self.vm().msg_sender();
+        self.settle_offer(offer_id, nft_contract, token_id, seller, bidder, amount)
+    }
+
+    /// Withdraw an offer, refunding the escrowed ETH (bidder only)
+    ///
+    /// Expired offers are cancelled the same way to reclaim the escrow.
+    #[non_reentrant]
+    pub fn cancel_offer(&mut self, offer_id: U256) -> Result<(), Vec<u8>> {
+        let offer = self.offers.get(offer_id);
+        let bidder = offer.bidder.get();
+        let amount = offer.amount.get();
+        
+        if bidder != self.vm().msg_sender() {
+            return Err("Only bidder can cancel offer".as_bytes().to_vec());
+        }
+        if !offer.active.get() {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        
+        self.offers.setter(offer_id).active.set(false);
+        self.unindex_offer(offer_id);
+        safe_transfer_eth(self, bidder, amount, GasStipend::All)?;
+        
+        log(self.vm(), OfferCancelled {
+            offer_id,
+            bidder,
+        });
+        
+        Ok(())
+    }
+
+    /// Return an expired offer's escrow to its bidder (anyone)
+    ///
+    /// Keeps the offer book clean without the bidder having to show up. The
+    /// caller is paid the reclaim reward out of the escrow, unless they are
+    /// the bidder, and the offer leaves the token and collection indexes.
+    #[non_reentrant]
+    pub fn reclaim_expired_offer(&mut self, offer_id: U256) -> Result<(), Vec<u8>> {
+        let (bidder, amount, expiry, active) = {
+            let offer = self.offers.get(offer_id);
+            (
+                offer.bidder.get(),
+                offer.amount.get(),
+                offer.expiry.get(),
+                offer.active.get(),
+            )
+        };
+        
+        if !active {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) < expiry {
+            return Err("Offer not expired".as_bytes().to_vec());
+        }
+        
+        let caller = self.vm().msg_sender();
+        let reward = if caller == bidder {
+            U256::ZERO
+        } else {
+            amount * self.reclaim_reward_bps.get() / U256::from(10000)
+        };
+        
+        self.offers.setter(offer_id).active.set(false);
+        self.unindex_offer(offer_id);
+        safe_transfer_eth(self, bidder, amount - reward, GasStipend::All)?;
+        if reward > U256::ZERO {
+            safe_transfer_eth(self, caller, reward, GasStipend::All)?;
+        }
+        
+        log(self.vm(), OfferReclaimed {
+            offer_id,
+            bidder,
+            reclaimer: caller,
+            reward,
+        });
+        
+        Ok(())
+    }
+
+    /// Set the share of a reclaimed offer paid to whoever reclaims it (fee manager only)
+    pub fn set_reclaim_reward(&mut self, reward_bps: U256) -> Result<(), Vec<u8>> {
+        self.roles.only_role(FEE_MANAGER_ROLE)?;
+        if reward_bps > U256::from(MAX_RECLAIM_REWARD_BPS) {
+            return Err("Reward too high".as_bytes().to_vec());
+        }
+        
+        self.reclaim_reward_bps.set(reward_bps);
+        
+        log(self.vm(), ReclaimRewardUpdated {
+            reward_bps,
+        });
+        
+        Ok(())
+    }
+
+    /// Get the share of a reclaimed offer paid to its reclaimer, in basis points
+    pub fn get_reclaim_reward(&self) -> U256 {
+        self.reclaim_reward_bps.get()
+    }
+
+    /// Sell the token to the bidder at the offered amount (current owner only)
+    ///
+    /// If the token is listed, its seller accepts and the NFT leaves escrow,
+    /// closing the listing. Otherwise the caller must own the token and have
+    /// approved the marketplace. The seller is credited the offer minus the fee.
+    #[non_reentrant]
+    pub fn accept_offer(&mut self, offer_id: U256) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let (nft_contract, token_id, bidder, amount, expiry, active, criteria_root) = {
+            let offer = self.offers.get(offer_id);
+            (
+                offer.nft_contract.get(),
+                offer.token_id.get(),
+                offer.bidder.get(),
+                offer.amount.get(),
+                offer.expiry.get(),
+                offer.active.get(),
+                offer.criteria_root.get(),
+            )
+        };
+        
+        if !active {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        if criteria_root != B256::ZERO {
+            return Err("Use accept_criteria_offer".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) >= expiry {
+            return Err("Offer expired".as_bytes().to_vec());
+        }
+        
+        let seller = self.vm().msg_sender();
+        self.settle_offer(offer_id, nft_contract, token_id, seller, bidder, amount)
+    }
+
+    /// Answer an offer with the price the caller would sell at (current owner only)
+    ///
+    /// The bidder can take the counter with `accept_counter_offer` until
+    /// `expiry`, paying only the difference to the offer. A new counter
+    /// replaces the previous one; the seller can still accept the original
+    /// offer meanwhile.
+    pub fn counter_offer(&mut self, offer_id: U256, price: U256, expiry: U256) -> Result<(), Vec<u8>> {
+        let (nft_contract, token_id, amount, offer_expiry, active, criteria_root) = {
+            let offer = self.offers.get(offer_id);
+            (
+                offer.nft_contract.get(),
+                offer.token_id.get(),
+                offer.amount.get(),
+                offer.expiry.get(),
+                offer.active.get(),
+                offer.criteria_root.get(),
+            )
+        };
+        
+        if !active {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        if criteria_root != B256::ZERO {
+            return Err("Criteria offers can't be countered".as_bytes().to_vec());
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        if now >= offer_expiry {
+            return Err("Offer expired".as_bytes().to_vec());
+        }
+        if price <= amount {
+            return Err("Counter must exceed offer".as_bytes().to_vec());
+        }
+        if expiry <= now {
+            return Err("Expiry must be in the future".as_bytes().to_vec());
+        }
+        let seller = self.vm().msg_sender();
+        self.seller_item(nft_contract, token_id, seller)?;
+        
+        let mut offer = self.offers.setter(offer_id);
+        offer.counter_seller.set(seller);
+        offer.counter_price.set(price);
+        offer.counter_expiry.set(expiry);
+        offer.negotiation.set(U8::from(NEGOTIATION_COUNTERED));
+        
+        log(self.vm(), CounterOfferMade {
+            offer_id,
+            seller,
+            price,
+            expiry,
+        });
+        
+        Ok(())
+    }
+
+    /// Buy at the seller's counter price (bidder only)
+    ///
+    /// Send the counter price minus the escrowed offer. The token settles in
+    /// the same call, so the seller must still hold it and have approved the
+    /// marketplace, or still have it listed.
+    #[payable]
+    #[non_reentrant]
+    pub fn accept_counter_offer(&mut self, offer_id: U256) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let (nft_contract, token_id, bidder, amount, active, seller, price, expiry, negotiation) = {
+            let offer = self.offers.get(offer_id);
+            (
+                offer.nft_contract.get(),
+                offer.token_id.get(),
+                offer.bidder.get(),
+                offer.amount.get(),
+                offer.active.get(),
+                offer.counter_seller.get(),
+                offer.counter_price.get(),
+                offer.counter_expiry.get(),
+                offer.negotiation.get().to::<u8>(),
+            )
+        };
+        
+        if bidder != self.vm().msg_sender() {
+            return Err("Only bidder can accept counter".as_bytes().to_vec());
+        }
+        if !active {
+            return Err("Offer not active".as_bytes().to_vec());
+        }
+        if negotiation != NEGOTIATION_COUNTERED {
+            return Err("No pending counter-offer".as_bytes().to_vec());
+        }
+        if U256::from(self.vm().block_timestamp()) >= expiry {
+            return Err("Counter-offer expired".as_bytes().to_vec());
+        }
+        if self.vm().msg_value() != price - amount {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        
+        let mut offer = self.offers.setter(offer_id);
+        offer.amount.set(price);
+        offer.negotiation.set(U8::from(NEGOTIATION_ACCEPTED));
+        self.settle_offer(offer_id, nft_contract, token_id, seller, bidder, price)
+    }
+
+    /// Turn down a pending counter-offer, keeping the original offer open (bidder only)
+    pub fn decline_counter_offer(&mut self, offer_id: U256) -> Result<(), Vec<u8>> {
+        let offer = self.offers.get(offer_id);
+        let bidder = offer.bidder.get();
+        if bidder != self.vm().msg_sender() {
+            return Err("Only bidder can decline counter".as_bytes().to_vec());
+        }
+        if offer.negotiation.get().to::<u8>() != NEGOTIATION_COUNTERED {
+            return Err("No pending counter-offer".as_bytes().to_vec());
+        }
+        
+        self.offers.setter(offer_id).negotiation.set(U8::from(NEGOTIATION_DECLINED));
+        
+        log(self.vm(), CounterOfferDeclined {
+            offer_id,
+            bidder,
+        });
+        
+        Ok(())
+    }
+
+    /// Get an offer's negotiation as `(state, counter_seller, counter_price, counter_expiry)`
+    ///
+    /// `state` is one of the `NEGOTIATION_*` constants; the counter fields
+    /// keep the latest counter once it is accepted or declined.
+    pub fn get_counter_offer(&self, offer_id: U256) -> (u8, Address, U256, U256) {
+        let offer = self.offers.get(offer_id);
+        (
+            offer.negotiation.get().to::<u8>(),
+            offer.counter_seller.get(),
+            offer.counter_price.get(),
+            offer.counter_expiry.get(),
+        )
+    }
+
+    /// Buy a lazy-minted token with the creator's signed voucher
+    ///
+    /// Verifies the EIP-712 signature over the voucher, checks the signer
+    /// owns `nft_contract`, mints the token to the buyer with the voucher's
+    /// royalty and credits the creator the price minus the fee. `msg.value` must
+    /// equal `price`. Returns the minted token id.
+    #[payable]
+    #[non_reentrant]
+    #[allow(clippy::too_many_arguments)]
+    pub fn redeem_voucher(
+        &mut self,
+        nft_contract: Address,
+        uri: String,
+        price: U256,
+        royalty_bps: U256,
+        creator: Address,
+        nonce: U256,
+        signature: Bytes,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if price <= U256::from(0) {
+            return Err("Price must be greater than zero".as_bytes().to_vec());
+        }
+        if self.vm().msg_value() != price {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        if royalty_bps > U256::from(10000) {
+            return Err("Royalty too high".as_bytes().to_vec());
+        }
+        self.require_listable(nft_contract)?;
+        if self.flagged_collections.get(nft_contract) {
+            return Err("Asset flagged".as_bytes().to_vec());
+        }
+        if self.is_voucher_nonce_used(creator, nonce) {
+            return Err("Voucher already used".as_bytes().to_vec());
+        }
+        
+        let digest = self.get_voucher_hash(nft_contract, uri.clone(), price, royalty_bps, creator, nonce);
+        if stylus_eip712::recover_signature(digest, &signature) != Some(creator) {
+            return Err("Invalid voucher signature".as_bytes().to_vec());
+        }
+        let collection = ILazyMintable::new(nft_contract);
+        let collection_owner = collection
+            .owner(Call::new_in(self))
+            .map_err(|_| "Collection owner lookup failed".as_bytes().to_vec())?;
+        if collection_owner != creator {
+            return Err("Creator does not own collection".as_bytes().to_vec());
+        }
+        
+        let fee = self.sale_fee(nft_contract, creator, self.vm().msg_sender(), price);
+        self.used_voucher_nonces.setter(creator).setter(nonce).set(true);
+        self.accrued_fees.set(self.accrued_fees.get() + fee);
+        
+        let buyer = self.vm().msg_sender();
+        let token_id = collection
+            .lazy_mint(Call::new_in(self), buyer, uri, creator, royalty_bps)
+            .map_err(|_| "NFT mint failed".as_bytes().to_vec())?;
+        self.record_sale(nft_contract, token_id, U256::ZERO, creator, buyer, price);
+        self.credit_proceeds(creator, price - fee);
+        
+        log(self.vm(), VoucherRedeemed {
+            nft_contract,
+            token_id,
+            creator,
+            buyer,
+            price,
+            nonce,
+        });
+        log(self.vm(), SaleSettled {
+            item_id: U256::ZERO,
+            nft_contract,
+            token_id,
+            seller: creator,
+            buyer,
+            payment_token: NATIVE_TOKEN,
+            price,
+            fee,
+            royalty_receiver: Address::ZERO,
+            royalty: U256::ZERO,
+            seller_proceeds: price - fee,
+        });
+        
+        Ok(token_id)
+    }
+
+    /// Invalidate one of the caller's voucher nonces before it is redeemed
+    pub fn cancel_voucher(&mut self, nonce: U256) -> Result<(), Vec<u8>> {
+        let creator = self.vm().msg_sender();
+        if self.is_voucher_nonce_used(creator, nonce) {
+            return Err("Voucher already used".as_bytes().to_vec());
+        }
+        
+        self.used_voucher_nonces.setter(creator).setter(nonce).set(true);
+        
+        log(self.vm(), VoucherCancelled {
+            creator,
+            nonce,
+        });
+        
+        Ok(())
+    }
+
+    /// Check if a creator's voucher nonce was redeemed or cancelled
+    pub fn is_voucher_nonce_used(&self, creator: Address, nonce: U256) -> bool {
+        self.used_voucher_nonces.getter(creator).get(nonce)
+    }
+
+    /// Get the EIP-712 digest a creator signs for a voucher
+    pub fn get_voucher_hash(
+        &self,
+        nft_contract: Address,
+        uri: String,
+        price: U256,
+        royalty_bps: U256,
+        creator: Address,
+        nonce: U256,
+    ) -> B256 {
+        let voucher = MintVoucher {
+            nftContract: nft_contract,
+            uri,
+            price,
+            royaltyBps: royalty_bps,
+            creator,
+            nonce,
+        };
+        let domain = voucher::domain(self.vm().chain_id(), self.vm().contract_address());
+        voucher.eip712_signing_hash(&domain)
+    }
+
+    /// Buy an NFT through a seller-signed off-chain order
+    ///
+    /// Send exactly `price`. The order must be unexpired (`expiry` zero never
+    /// expires), signed under the seller's current order nonce and not filled
+    /// or cancelled. The NFT goes straight from the seller, who must have
+    /// approved the marketplace, to the buyer.
+    #[payable]
+    #[non_reentrant]
+    #[allow(clippy::too_many_arguments)]
+    pub fn fill_order(
+        &mut self,
+        seller: Address,
+        nft_contract: Address,
+        token_id: U256,
+        price: U256,
+        expiry: U256,
+        nonce: U256,
+        salt: U256,
+        signature: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        if price <= U256::from(0) {
+            return Err("Price must be greater than zero".as_bytes().to_vec());
+        }
+        if self.vm().msg_value() != price {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        if expiry != U256::ZERO && U256::from(self.vm().block_timestamp()) >= expiry {
+            return Err("Order expired".as_bytes().to_vec());
+        }
+        self.require_listable(nft_contract)?;
+        self.require_unflagged(nft_contract, token_id)?;
+        if nonce != self.order_nonces.nonces(seller) {
+            return Err("Order nonce invalidated".as_bytes().to_vec());
+        }
+        
+        let order_hash = self.get_order_hash(seller, nft_contract, token_id, price, expiry, nonce, salt);
+        match self.get_order_status(order_hash) {
+            ORDER_FILLED => return Err("Order already filled".as_bytes().to_vec()),
+            ORDER_CANCELLED => return Err("Order cancelled".as_bytes().to_vec()),
+            _ => {}
+        }
+        if stylus_eip712::recover_signature(order_hash, &signature) != Some(seller) {
+            return Err("Invalid order signature".as_bytes().to_vec());
+        }
+        
+        let fee = self.sale_fee(nft_contract, seller, self.vm().msg_sender(), price);
+        self.order_status.setter(order_hash).set(U8::from(ORDER_FILLED));
+        self.accrued_fees.set(self.accrued_fees.get() + fee);
+        
+        let buyer = self.vm().msg_sender();
+        self.transfer_nft(nft_contract, seller, buyer, token_id)?;
+        self.record_sale(nft_contract, token_id, U256::ZERO, seller, buyer, price);
+        self.credit_proceeds(seller, price - fee);
+        
+        log(self.vm(), OrderFilled {
+            order_hash,
+            seller,
+            buyer,
+            nft_contract,
+            token_id,
+            price,
+        });
+        log(self.vm(), SaleSettled {
+            item_id: U256::ZERO,
+            nft_contract,
+            token_id,
+            seller,
+            buyer,
+            payment_token: NATIVE_TOKEN,
+            price,
+            fee,
+            royalty_receiver: Address::ZERO,
+            royalty: U256::ZERO,
+            seller_proceeds: price - fee,
+        });
+        
+        Ok(())
+    }
+
+    /// Cancel one of the caller's signed orders before it is filled
+    pub fn cancel_order(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        price: U256,
+        expiry: U256,
+        nonce: U256,
+        salt: U256,
+    ) -> Result<(), Vec<u8>> {
+        let seller = self.vm().msg_sender();
+        let order_hash = self.get_order_hash(seller, nft_contract, token_id, price, expiry, nonce, salt);
+        if self.get_order_status(order_hash) != ORDER_OPEN {
+            return Err("Order not open".as_bytes().to_vec());
+        }
+        
+        self.order_status.setter(order_hash).set(U8::from(ORDER_CANCELLED));
+        
+        log(self.vm(), OrderCancelled {
+            order_hash,
+            seller,
+        });
+        
+        Ok(())
+    }
+
+    /// Invalidate every order the caller signed under their current nonce
+    ///
+    /// Returns the new nonce, which new orders must be signed under.
+    pub fn increment_nonce(&mut self) -> U256 {
+        let seller = self.vm().msg_sender();
+        let nonce = self.order_nonces.use_nonce(seller) + U256::from(1);
+        
+        log(self.vm(), OrderNonceIncremented {
+            seller,
+            nonce,
+        });
+        
+        nonce
+    }
+
+    /// Get the nonce a seller's orders must currently be signed under
+    pub fn get_order_nonce(&self, seller: Address) -> U256 {
+        self.order_nonces.nonces(seller)
+    }
+
+    /// Get the EIP-712 digest a seller signs for an order
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_order_hash(
+        &self,
+        seller: Address,
+        nft_contract: Address,
+        token_id: U256,
+        price: U256,
+        expiry: U256,
+        nonce: U256,
+        salt: U256,
+    ) -> B256 {
+        let order = SellOrder {
+            seller,
+            nftContract: nft_contract,
+            tokenId: token_id,
+            price,
+            expiry,
+            nonce,
+            salt,
+        };
+        let domain = voucher::domain(self.vm().chain_id(), self.vm().contract_address());
+        order.eip712_signing_hash(&domain)
+    }
+
+    /// Get an order's status: `ORDER_OPEN`, `ORDER_FILLED` or `ORDER_CANCELLED`
+    ///
+    /// An open order may still be unfillable if it expired or its nonce was
+    /// invalidated.
+    pub fn get_order_status(&self, order_hash: B256) -> u8 {
+        self.order_status.get(order_hash).to::<u8>()
+    }
+
+    /// Offer an NFT in exchange for another, with an optional ETH top-up
+    ///
+    /// The offered NFT is escrowed. The seller wants `wanted_token_id` of
+    /// `wanted_contract`, or any token of it if `any_token` is set. If
+    /// `seller_pays_top_up`, the seller sends `top_up` with this call and it
+    /// goes to the counterparty; otherwise the counterparty pays it to the
+    /// seller. Returns the swap id.
+    #[payable]
+    #[non_reentrant]
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_swap(
+        &mut self,
+        offered_contract: Address,
+        offered_token_id: U256,
+        wanted_contract: Address,
+        wanted_token_id: U256,
+        any_token: bool,
+        top_up: U256,
+        seller_pays_top_up: bool,
+    ) -> Result<U256, Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let expected_value = if seller_pays_top_up { top_up } else { U256::ZERO };
+        if self.vm().msg_value() != expected_value {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        if wanted_contract == Address::ZERO {
+            return Err("Invalid wanted collection".as_bytes().to_vec());
+        }
+        self.require_listable(offered_contract)?;
+        self.require_listable(wanted_contract)?;
+        self.require_unflagged(offered_contract, offered_token_id)?;
+        
+        let swap_id = self.swap_count.get() + U256::from(1);
+        let seller = self.vm().msg_sender();
+        
+        let mut swap = self.swaps.setter(swap_id);
+        swap.seller.set(seller);
+        swap.offered_contract.set(offered_contract);
+        swap.offered_token_id.set(offered_token_id);
+        swap.wanted_contract.set(wanted_contract);
+        swap.wanted_token_id.set(wanted_token_id);
+        swap.any_token.set(any_token);
+        swap.top_up.set(top_up);
+        swap.seller_pays_top_up.set(seller_pays_top_up);
+        swap.status.set(U8::from(SWAP_OPEN));
+        self.swap_count.set(swap_id);
+        
+        let marketplace = self.vm().contract_address();
+        self.transfer_nft(offered_contract, seller, marketplace, offered_token_id)?;
+        
+        log(self.vm(), SwapCreated {
+            swap_id,
+            seller,
+            offered_contract,
+            offered_token_id,
+            wanted_contract,
+            wanted_token_id,
+            any_token,
+            top_up,
+            seller_pays_top_up,
+        });
+        
+        Ok(swap_id)
+    }
+
+    /// Take a swap by giving `token_id` of the wanted collection
+    ///
+    /// Both NFTs and the top-up change hands in this call. The counterparty
+    /// must approve the marketplace for `token_id`, and send the top-up if the
+    /// seller asked for one. The usual fee is taken from the top-up.
+    #[payable]
+    #[non_reentrant]
+    pub fn accept_swap(&mut self, swap_id: U256, token_id: U256) -> Result<(), Vec<u8>> {
+        self.pausable.when_not_paused()?;
+        let (seller, offered_contract, offered_token_id, wanted_contract, top_up, seller_pays_top_up) = {
+            let swap = self.swaps.get(swap_id);
+            if swap.status.get().to::<u8>() != SWAP_OPEN {
+                return Err("Swap not open".as_bytes().to_vec());
+            }
+            if !swap.any_token.get() && swap.wanted_token_id.get() != token_id {
+                return Err("Token not accepted".as_bytes().to_vec());
+            }
+            (
+                swap.seller.get(),
+                swap.offered_contract.get(),
+                swap.offered_token_id.get(),
+                swap.wanted_contract.get(),
+                swap.top_up.get(),
+                swap.seller_pays_top_up.get(),
+            )
+        };
+        
+        self.require_unflagged(offered_contract, offered_token_id)?;
+        self.require_unflagged(wanted_contract, token_id)?;
+        let counterparty = self.vm().msg_sender();
+        let expected_value = if seller_pays_top_up { U256::ZERO } else { top_up };
+        if self.vm().msg_value() != expected_value {
+            return Err("Incorrect payment amount".as_bytes().to_vec());
+        }
+        
+        let mut swap = self.swaps.setter(swap_id);
+        swap.counterparty.set(counterparty);
+        swap.counter_token_id.set(token_id);
+        swap.status.set(U8::from(SWAP_COMPLETED));
+        
+        let marketplace = self.vm().contract_address();
+        self.transfer_nft(wanted_contract, counterparty, seller, token_id)?;
+        self.transfer_nft(offered_contract, marketplace, counterparty, offered_token_id)?;
+        if top_up > U256::ZERO {
+            let (payer_collection, payer, receiver) = if seller_pays_top_up {
+                (wanted_contract, seller, counterparty)
+            } else {
+                (offered_contract, counterparty, seller)
+            };
+            let fee = self.sale_fee(payer_collection, receiver, payer, top_up);
+            self.accrued_fees.set(self.accrued_fees.get() + fee);
+            self.credit_proceeds(receiver, top_up - fee);
+        }
+        
+        log(self.vm(), SwapAccepted {
+            swap_id,
+            counterparty,
+            token_id,
+        });
+        
+        Ok(())
+    }
+
+    /// Withdraw a swap, returning the escrowed NFT and any top-up (seller only)
+    #[non_reentrant]
+    pub fn cancel_swap(&mut self, swap_id: U256) -> Result<(), Vec<u8>> {
+        let (seller, offered_contract, offered_token_id, top_up, seller_pays_top_up, status) = {
+            let swap = self.swaps.get(swap_id);
+            (
+                swap.seller.get(),
+                swap.offered_contract.get(),
+                swap.offered_token_id.get(),
+                swap.top_up.get(),
+                swap.seller_pays_top_up.get(),
+                swap.status.get().to::<u8>(),
+            )
+        };
+        
+        if seller != self.vm().msg_sender() {
+            return Err("Only seller can cancel swap".as_bytes().to_vec());
+        }
+        if status != SWAP_OPEN {
+            return Err("Swap not open".as_bytes().to_vec());
+        }
+        
+        self.swaps.setter(swap_id).status.set(U8::from(SWAP_CANCELLED));
+        
+        let marketplace = self.vm().contract_address();
+        self.transfer_nft(offered_contract, marketplace, seller, offered_token_id)?;
+        if seller_pays_top_up && top_up > U256::ZERO {
+            safe_transfer_eth(self, seller, top_up, GasStipend::All)?;
+        }
+        
+        log(self.vm(), SwapCancelled { swap_id });
+        
+        Ok(())
+    }
+
+    /// Get swap details as `(seller, offered_contract, offered_token_id,
+    /// wanted_contract, wanted_token_id, any_token, top_up, seller_pays_top_up, status)`
+    ///
+    /// `status` is `SWAP_OPEN`, `SWAP_COMPLETED` or `SWAP_CANCELLED`, zero
+    /// if the swap doesn't exist.
+    pub fn get_swap(&self, swap_id: U256) -> (Address, Address, U256, Address, U256, bool, U256, bool, u8) {
+        let swap = self.swaps.get(swap_id);
+        (
+            swap.seller.get(),
+            swap.offered_contract.get(),
+            swap.offered_token_id.get(),
+            swap.wanted_contract.get(),
+            swap.wanted_token_id.get(),
+            swap.any_token.get(),
+            swap.top_up.get(),
+            swap.seller_pays_top_up.get(),
+            swap.status.get().to::<u8>(),
+        )
+    }
+
+    /// Get how a swap settled as `(counterparty, counter_token_id)`, zero until accepted
+    pub fn get_swap_settlement(&self, swap_id: U256) -> (Address, U256) {
+        let swap = self.swaps.get(swap_id);
+        (swap.counterparty.get(), swap.counter_token_id.get())
+    }
+
+    /// Get total swaps count
+    pub fn get_swap_count(&self) -> U256 {
+        self.swap_count.get()
+    }
+
+    /// Get offer details
+    pub fn get_offer(&self, offer_id: U256) -> (U256, Address, U256, Address, U256, U256, bool) {
+        let offer = self.offers.get(offer_id);
+        (
+            offer.offer_id.get(),
+            offer.nft_contract.get(),
+            offer.token_id.get(),
+            offer.bidder.get(),
+            offer.amount.get(),
+            offer.expiry.get(),
+            offer.active.get(),
+        )
+    }
+
+    /// Get the merkle root of an offer's acceptable token ids, zero for a single-token offer
+    pub fn get_offer_criteria(&self, offer_id: U256) -> B256 {
+        self.offers.get(offer_id).criteria_root.get()
+    }
+
+    /// Get ids of the active, unexpired offers on a token, in no particular order
+    pub fn get_token_offers(&self, nft_contract: Address, token_id: U256) -> Vec<U256> {
+        let now = U256::from(self.vm().block_timestamp());
+        let ids = self.token_offers.getter(nft_contract);
+        let ids = ids.getter(token_id);
+        (0..ids.len())
+            .filter_map(|i| ids.get(i))
+            .filter(|id| {
+                let offer = self.offers.get(*id);
+                offer.active.get() && offer.expiry.get() > now
+            })
+            .collect()
+    }
+
+    /// Get total offers count
+    pub fn get_offer_count(&self) -> U256 {
+        self.offer_count.get()
+    }
+}
+
+impl NFTMarketplace {
+    /// Revert if taking `role` from `account` would leave no admin
+    fn keep_last_admin(&self, role: B256, account: Address) -> Result<(), Vec<u8>> {
+        if role == ADMIN_ROLE && self.roles.has_role(role, account) && self.roles.member_count(role) == 1 {
+            return Err("Cannot remove last admin".as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// Open a listing of one NFT for `seller`, escrowing it
+    fn list_item(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        price: U256,
+        seller: Address,
+    ) -> Result<U256, Vec<u8>> {
+        if price <= U256::from(0) {
+            return Err("Price must be greater than zero".as_bytes().to_vec());
+        }
+        self.require_unflagged(nft_contract, token_id)?;
+        self.require_permitted(nft_contract, seller, token_id)?;
+        
+        let item_id = self.item_count.get() + U256::from(1);
+        
+        let mut market_item = self.market_items.setter(item_id);
+        market_item.item_id.set(item_id);
+        market_item.nft_contract.set(nft_contract);
+        market_item.token_id.set(token_id);
+        market_item.seller.set(seller);
+        market_item.owner.set(self.vm().contract_address());
+        market_item.price.set(price);
+        
+        self.item_count.set(item_id);
+        self.set_status(item_id, ListingStatus::Active);
+        self.listed_items.setter(nft_contract).setter(token_id).set(item_id);
+        self.seller_items.setter(seller).push(item_id);
+        self.collection_items.setter(nft_contract).push(item_id);
+        
+        let marketplace = self.vm().contract_address();
+        self.snapshot_token_uris(item_id);
+        self.detect_standards(item_id, seller)?;
+        self.transfer_item(item_id, seller, marketplace)?;
+        
+        log(self.vm(), MarketItemCreated {
+            item_id,
+            nft_contract,
+            token_id,
+            seller,
+            price,
+        });
+        
+        Ok(item_id)
+    }
+
+    /// Listing of a token `seller` may sell, zero if it is unlisted but they own it
+    fn seller_item(&mut self, nft_contract: Address, token_id: U256, seller: Address) -> Result<U256, Vec<u8>> {
+        let item_id = self.get_listed_item(nft_contract, token_id);
+        if item_id != U256::ZERO {
+            if self.market_items.get(item_id).seller.get() != seller {
+                return Err("Only seller can accept offer".as_bytes().to_vec());
+            }
+            return Ok(item_id);
+        }
+        let owner = IERC721::new(nft_contract)
+            .owner_of(Call::new_in(self), token_id)
+            .map_err(|_| "NFT owner lookup failed".as_bytes().to_vec())?;
+        if owner != seller {
+            return Err("Only token owner can accept offer".as_bytes().to_vec());
+        }
+        Ok(item_id)
+    }
+
+    /// Sell a token to an offer's bidder for `amount`, already escrowed
+    ///
+    /// A listed token leaves escrow, closing its listing; an unlisted one is
+    /// pulled from `seller`. The seller is credited `amount` minus the fee.
+    fn settle_offer(
+        &mut self,
+        offer_id: U256,
+        nft_contract: Address,
+        token_id: U256,
+        seller: Address,
+        bidder: Address,
+        amount: U256,
+    ) -> Result<(), Vec<u8>> {
+        self.require_unflagged(nft_contract, token_id)?;
+        let item_id = self.seller_item(nft_contract, token_id, seller)?;
+        let from = if item_id == U256::ZERO {
+            seller
+        } else {
+            self.market_items.setter(item_id).owner.set(bidder);
+            self.set_status(item_id, ListingStatus::Sold);
+            self.unlist(item_id, nft_contract, token_id);
+            self.vm().contract_address()
+        };
+        
+        let fee = self.sale_fee(nft_contract, seller, bidder, amount);
+        self.offers.setter(offer_id).active.set(false);
+        self.unindex_offer(offer_id);
+        self.accrued_fees.set(self.accrued_fees.get() + fee);
+        
+        self.record_sale(nft_contract, token_id, item_id, seller, bidder, amount);
+        let (royalty_receiver, royalty) = if item_id == U256::ZERO {
+            self.transfer_nft(nft_contract, from, bidder, token_id)?;
+            self.credit_proceeds(seller, amount - fee);
+            (Address::ZERO, U256::ZERO)
+        } else {
+            self.transfer_item(item_id, from, bidder)?;
+            let (receiver, royalty) = self.pay_royalty(item_id, NATIVE_TOKEN, amount, amount - fee);
+            self.credit_sale(item_id, NATIVE_TOKEN, seller, amount - fee - royalty);
+            (receiver, royalty)
+        };
+        
+        log(self.vm(), OfferAccepted {
+            offer_id,
+            nft_contract,
+            token_id,
+            seller,
+            bidder,
+            amount,
+        });
+        log(self.vm(), SaleSettled {
+            item_id,
+            nft_contract,
+            token_id,
+            seller,
+            buyer: bidder,
+            payment_token: NATIVE_TOKEN,
+            price: amount,
+            fee,
+            royalty_receiver,
+            royalty,
+            seller_proceeds: amount - fee - royalty,
+        });
+        
+        Ok(())
+    }
+
+    /// Owe `account` `amount` of sale proceeds, to be withdrawn later
+    fn credit_proceeds(&mut self, account: Address, amount: U256) {
+        if amount == U256::ZERO {
+            return;
+        }
+        let pending = self.pending_proceeds.get(account);
+        self.pending_proceeds.setter(account).set(pending + amount);
+        
+        log(self.vm(), ProceedsCredited {
+            account,
+            amount,
+        });
+    }
+
+    /// Owe `account` `amount` of `payment_token`, ETH or an ERC20, to be withdrawn later
+    fn credit(&mut self, payment_token: Address, account: Address, amount: U256) {
+        if payment_token == NATIVE_TOKEN {
+            self.credit_proceeds(account, amount);
+            return;
+        }
+        if amount == U256::ZERO {
+            return;
+        }
+        let pending = self.token_proceeds.getter(payment_token).get(account);
+        self.token_proceeds.setter(payment_token).setter(account).set(pending + amount);
+        
+        log(self.vm(), TokenProceedsCredited {
+            token: payment_token,
+            account,
+            amount,
+        });
+    }
+
+    /// Credit an item's sale proceeds to its payout split, or all to `seller` without one
+    ///
+    /// Rounding dust goes to the first recipient.
+    fn credit_sale(&mut self, item_id: U256, payment_token: Address, seller: Address, amount: U256) {
+        let (recipients, shares_bps) = self.get_payout_split(item_id);
+        if recipients.is_empty() {
+            self.credit(payment_token, seller, amount);
+            return;
+        }
+        let payouts: Vec<U256> = shares_bps.iter().map(|bps| amount * *bps / U256::from(10000)).collect();
+        let dust = amount - payouts.iter().fold(U256::ZERO, |sum, payout| sum + *payout);
+        for (i, (recipient, payout)) in recipients.into_iter().zip(payouts).enumerate() {
+            let payout = if i == 0 { payout + dust } else { payout };
+            self.credit(payment_token, recipient, payout);
+            log(self.vm(), SplitPayout {
+                item_id,
+                recipient,
+                amount: payout,
+            });
+        }
+    }
+
+    /// Pay out and clear everything owed to `account`
+    fn pay_proceeds(&mut self, account: Address, stipend: GasStipend) -> Result<(), Vec<u8>> {
+        let amount = self.pending_proceeds.get(account);
+        if amount == U256::ZERO {
+            return Err("No proceeds to withdraw".as_bytes().to_vec());
+        }
+        
+        self.pending_proceeds.setter(account).set(U256::ZERO);
+        safe_transfer_eth(self, account, amount, stipend)?;
+        
+        log(self.vm(), ProceedsWithdrawn {
+            account,
+            amount,
+        });
+        
+        Ok(())
+    }
+
+    /// Buy an active item for the caller with `msg.value`, returning the fee taken
+    ///
+    /// Paying more than the price is allowed, e.g. when the seller lowered
+    /// it meanwhile, and the excess is refunded.
+    fn purchase(&mut self, item_id: U256) -> Result<U256, Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active])?;
+        self.require_item_unflagged(item_id)?;
+        let (price, nft_contract, token_id, seller, payment_token) = {
+            let item = self.market_items.get(item_id);
+            (
+                item.price.get(),
+                item.nft_contract.get(),
+                item.token_id.get(),
+                item.seller.get(),
+                item.payment_token.get(),
+            )
+        };
+        
+        if payment_token != NATIVE_TOKEN {
+            return Err("Listing is priced in a token".as_bytes().to_vec());
+        }
+        
+        let buyer = self.vm().msg_sender();
+        let paid = self.vm().msg_value();
+        if paid < price {
+            return Err("Insufficient payment".as_bytes().to_vec());
+        }
+        
+        let fee = self.settle_purchase(item_id, nft_contract, token_id, seller, buyer, price)?;
+        let refunded = paid - price;
+        if refunded > U256::ZERO {
+            safe_transfer_eth(self, buyer, refunded, GasStipend::All)?;
+        }
+        
+        log(self.vm(), PaymentCharged {
+            item_id,
+            buyer,
+            charged: price,
+            refunded,
+        });
+        
+        Ok(fee)
+    }
+
+    /// Buy an active ERC20-priced item for the caller, pulling its price
+    fn purchase_in_token(&mut self, item_id: U256, max_price: U256) -> Result<(), Vec<u8>> {
+        self.require_status(item_id, &[ListingStatus::Active])?;
+        self.require_item_unflagged(item_id)?;
+        let (price, nft_contract, token_id, seller, payment_token) = {
+            let item = self.market_items.get(item_id);
+            (
+                item.price.get(),
+                item.nft_contract.get(),
+                item.token_id.get(),
+                item.seller.get(),
+                item.payment_token.get(),
+            )
+        };
+        if payment_token == NATIVE_TOKEN {
+            return Err("Listing is priced in ETH".as_bytes().to_vec());
+        }
+        if price > max_price {
+            return Err("Price above maximum".as_bytes().to_vec());
+        }
+        
+        let buyer = self.vm().msg_sender();
+        let marketplace = self.vm().contract_address();
+        safe_erc20_transfer_from(payment_token, buyer, marketplace, price)?;
+        self.settle_purchase(item_id, nft_contract, token_id, seller, buyer, price)?;
+        
+        log(self.vm(), PaymentCharged {
+            item_id,
+            buyer,
+            charged: price,
+            refunded: U256::ZERO,
+        });
+        
+        Ok(())
+    }
+
+    /// Complete the purchase of an active item paid in full by `buyer`
+    ///
+    /// Takes the fee, records the sale, releases the escrowed NFTs to the
+    /// buyer and credits the seller's proceeds. Returns the fee. Items under
+    /// buyer protection are held instead and settle once their escrow is
+    /// released, returning no fee now.
+    fn settle_purchase(
+        &mut self,
+        item_id: U256,
+        nft_contract: Address,
+        token_id: U256,
+        seller: Address,
+        buyer: Address,
+        price: U256,
+    ) -> Result<U256, Vec<u8>> {
+        let mut item_setter = self.market_items.setter(item_id);
+        item_setter.owner.set(buyer);
+        self.set_status(item_id, ListingStatus::Sold);
+        self.unlist(item_id, nft_contract, token_id);
+        
+        if self.market_items.get(item_id).escrow_protected.get() {
+            let release_at = U256::from(self.vm().block_timestamp()) + self.escrow_window.get();
+            let mut item = self.market_items.setter(item_id);
+            item.escrow_state.set(U8::from(ESCROW_HELD));
+            item.escrow_release_at.set(release_at);
+            
+            log(self.vm(), EscrowOpened {
+                item_id,
+                buyer,
+                release_at,
+            });
+            
+            return Ok(U256::ZERO);
+        }
+        self.complete_sale(item_id, nft_contract, token_id, seller, buyer, price)
+    }
+
+    /// Complete a protected sale held in escrow, paying its referrer if it had one
+    ///
+    /// ERC1155 units stay with the seller during the window. If the seller
+    /// has since moved one or revoked the marketplace's approval, the sale
+    /// can't be delivered and the buyer is refunded instead.
+    fn release(&mut self, item_id: U256) -> Result<(), Vec<u8>> {
+        if !self.deliverable(item_id) {
+            return self.refund_escrow(item_id, Address::ZERO);
+        }
+        let (nft_contract, token_id, seller, buyer, price, referral_code) = {
+            let item = self.market_items.get(item_id);
+            (
+                item.nft_contract.get(),
+                item.token_id.get(),
+                item.seller.get(),
+                item.owner.get(),
+                item.price.get(),
+                item.referral_code.get(),
+            )
+        };
+        
+        self.market_items.setter(item_id).escrow_state.set(U8::from(ESCROW_RELEASED));
+        let fee = self.complete_sale(item_id, nft_contract, token_id, seller, buyer, price)?;
+        if referral_code != B256::ZERO {
+            self.pay_referral(referral_code, item_id, fee);
+        }
+        
+        log(self.vm(), EscrowReleased {
+            item_id,
+            buyer,
+        });
+        
+        Ok(())
+    }
+
+    /// Refund a protected sale, crediting the buyer the price and returning
+    /// the NFTs to the seller; `moderator` is zero if the sale was undeliverable
+    fn refund_escrow(&mut self, item_id: U256, moderator: Address) -> Result<(), Vec<u8>> {
+        let (seller, buyer, price, payment_token) = {
+            let item = self.market_items.get(item_id);
+            (item.seller.get(), item.owner.get(), item.price.get(), item.payment_token.get())
+        };
+        
+        let mut item = self.market_items.setter(item_id);
+        item.escrow_state.set(U8::from(ESCROW_REFUNDED));
+        item.owner.set(seller);
+        self.set_status(item_id, ListingStatus::Cancelled);
+        
+        let marketplace = self.vm().contract_address();
+        self.transfer_item(item_id, marketplace, seller)?;
+        self.credit(payment_token, buyer, price);
+        
+        log(self.vm(), EscrowRefunded {
+            item_id,
+            buyer,
+            moderator,
+        });
+        
+        Ok(())
+    }
+
+    /// Whether the seller still holds every ERC1155 unit of an item, with the marketplace approved
+    fn deliverable(&mut self, item_id: U256) -> bool {
+        let seller = self.market_items.get(item_id).seller.get();
+        let marketplace = self.vm().contract_address();
+        let standards = self.item_standards(item_id);
+        for ((nft_contract, token_id), standard) in self.item_tokens(item_id).into_iter().zip(standards) {
+            if standard != STANDARD_ERC1155 {
+                continue;
+            }
+            let token = IERC1155::new(nft_contract);
+            let balance = token.balance_of(Call::new_in(self), seller, token_id).unwrap_or_default();
+            let approved = token
+                .is_approved_for_all(Call::new_in(self), seller, marketplace)
+                .unwrap_or(false);
+            if balance == U256::ZERO || !approved {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Move the referral share of an ETH sale's `fee` from the accrued fees to the affiliate behind `code`
+    fn pay_referral(&mut self, code: B256, item_id: U256, fee: U256) {
+        let affiliate = self.referral_owners.get(code);
+        let reward = fee * self.referral_share_bps.get() / U256::from(10000);
+        if reward > U256::ZERO {
+            self.accrued_fees.set(self.accrued_fees.get() - reward);
+            let balance = self.affiliate_balances.get(affiliate);
+            self.affiliate_balances.setter(affiliate).set(balance + reward);
+            let earnings = self.referral_earnings.get(code);
+            self.referral_earnings.setter(code).set(earnings + reward);
+        }
+        
+        log(self.vm(), ReferralFeePaid {
+            code,
+            item_id,
+            affiliate,
+            amount: reward,
+        });
+    }
+
+    /// Take the fee, record the sale, hand a sold item's NFTs to the buyer
+    /// and credit the seller. Returns the fee.
+    fn complete_sale(
+        &mut self,
+        item_id: U256,
+        nft_contract: Address,
+        token_id: U256,
+        seller: Address,
+        buyer: Address,
+        price: U256,
+    ) -> Result<U256, Vec<u8>> {
+        let payment_token = self.market_items.get(item_id).payment_token.get();
+        // Volume tiers are in ETH, so token sales are charged without counting toward them
+        let fee = if payment_token == NATIVE_TOKEN {
+            let fee = self.sale_fee(nft_contract, seller, buyer, price);
+            self.accrued_fees.set(self.accrued_fees.get() + fee);
+            fee
+        } else {
+            let fee = self.get_sale_fee(nft_contract, seller, price);
+            let accrued = self.token_fees.get(payment_token);
+            self.token_fees.setter(payment_token).set(accrued + fee);
+            fee
+        };
+        let seller_amount = price - fee;
+        
+        // Release the escrowed NFTs and credit the seller; the fee stays in the marketplace
+        let marketplace = self.vm().contract_address();
+        for (nft_contract, token_id) in self.item_tokens(item_id) {
+            self.record_sale(nft_contract, token_id, item_id, seller, buyer, price);
+        }
+        self.transfer_item(item_id, marketplace, buyer)?;
+        let (royalty_receiver, royalty) = self.pay_royalty(item_id, payment_token, price, seller_amount);
+        self.credit_sale(item_id, payment_token, seller, seller_amount - royalty);
+        
+        log(self.vm(), MarketItemSold {
+            item_id,
+            nft_contract,
+            token_id,
+            seller,
+            buyer,
+            price,
+        });
+        log(self.vm(), SaleSettled {
+            item_id,
+            nft_contract,
+            token_id,
+            seller,
+            buyer,
+            payment_token,
+            price,
+            fee,
+            royalty_receiver,
+            royalty,
+            seller_proceeds: seller_amount - royalty,
+        });
+        
+        Ok(fee)
+    }
+
+    /// Fee of a sale at the seller's current tier, counting the sale toward
+    /// the seller's and the buyer's volume
+    fn sale_fee(&mut self, nft_contract: Address, seller: Address, buyer: Address, price: U256) -> U256 {
+        let fee = self.get_sale_fee(nft_contract, seller, price);
+        let volume = self.seller_volume.get(seller);
+        self.seller_volume.setter(seller).set(volume + price);
+        let volume = self.buyer_volume.get(buyer);
+        self.buyer_volume.setter(buyer).set(volume + price);
+        fee
+    }
+
+    /// Revert if the token or its collection is flagged
+    fn require_unflagged(&self, nft_contract: Address, token_id: U256) -> Result<(), Vec<u8>> {
+        if self.is_flagged(nft_contract, token_id) {
+            return Err("Asset flagged".as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// Revert if any NFT of an item is flagged
+    fn require_item_unflagged(&self, item_id: U256) -> Result<(), Vec<u8>> {
+        for (nft_contract, token_id) in self.item_tokens(item_id) {
+            self.require_unflagged(nft_contract, token_id)?;
+        }
+        Ok(())
+    }
+
+    /// Revert unless the collection's listing policy, if any, lets `seller` list `token_id`
+    ///
+    /// A policy that reverts or doesn't answer counts as a refusal.
+    fn require_permitted(&mut self, nft_contract: Address, seller: Address, token_id: U256) -> Result<(), Vec<u8>> {
+        let policy = self.listing_policies.get(nft_contract);
+        if policy == Address::ZERO {
+            return Ok(());
+        }
+        let allowed = IListingPolicy::new(policy)
+            .can_list(Call::new_in(self), nft_contract, seller, token_id)
+            .unwrap_or(false);
+        if !allowed {
+            return Err("Listing not permitted by collection".as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// Revert if verified-only mode is on and the collection isn't verified
+    fn require_listable(&self, nft_contract: Address) -> Result<(), Vec<u8>> {
+        if self.verified_only.get() && !self.is_collection_verified(nft_contract) {
+            return Err("Collection not verified".as_bytes().to_vec());
+        }
+        Ok(())
+    }
+
+    /// Current status of an item, `None` if it doesn't exist
+    fn status_of(&self, item_id: U256) -> Option<ListingStatus> {
+        let item = self.market_items.get(item_id);
+        let status = ListingStatus::from_u8(item.status.get().to::<u8>())?;
+        if status != ListingStatus::Active {
+            return Some(status);
+        }
+        let now = U256::from(self.vm().block_timestamp());
+        let expires_at = item.expires_at.get();
+        if expires_at != U256::ZERO && now >= expires_at {
+            return Some(ListingStatus::Expired);
+        }
+        if now < item.starts_at.get() {
+            return Some(ListingStatus::Scheduled);
+        }
+        Some(status)
+    }
+
+    /// Revert unless the item is in one of `allowed`
+    fn require_status(&self, item_id: U256, allowed: &[ListingStatus]) -> Result<ListingStatus, Vec<u8>> {
+        match self.status_of(item_id) {
+            None => Err("Item does not exist".as_bytes().to_vec()),
+            Some(status) if allowed.contains(&status) => Ok(status),
+            Some(ListingStatus::Sold) => Err("Item already sold".as_bytes().to_vec()),
+            Some(ListingStatus::Cancelled) => Err("Listing cancelled".as_bytes().to_vec()),
+            Some(ListingStatus::Expired) => Err("Listing expired".as_bytes().to_vec()),
+            Some(ListingStatus::Active) => Err("Listing still active".as_bytes().to_vec()),
+            Some(ListingStatus::Scheduled) => Err("Listing not live yet".as_bytes().to_vec()),
+        }
+    }
+
+    /// Store a new status for an item
+    fn set_status(&mut self, item_id: U256, status: ListingStatus) {
+        self.market_items.setter(item_id).status.set(U8::from(status as u8));
+        if status == ListingStatus::Active {
+            self.index_active(item_id);
+        } else {
+            self.unindex_active(item_id);
+        }
+        
+        log(self.vm(), ListingStatusChanged {
+            item_id,
+            status: status as u8,
+        });
+    }
+
+    /// Add an open listing to the all-active, seller and collection indexes
+    fn index_active(&mut self, item_id: U256) {
+        let seller = self.market_items.get(item_id).seller.get();
+        index_insert(&mut self.active_items, &mut self.active_positions, item_id);
+        index_insert(
+            &mut self.seller_active_items.setter(seller),
+            &mut self.seller_active_positions.setter(seller),
+            item_id,
+        );
+        for nft_contract in self.item_collections(item_id) {
+            index_insert(
+                &mut self.collection_active_items.setter(nft_contract),
+                &mut self.collection_active_positions.setter(nft_contract),
+                item_id,
+            );
+        }
+    }
+
+    /// Drop a closed listing from the active indexes; a no-op if it isn't in them
+    fn unindex_active(&mut self, item_id: U256) {
+        let seller = self.market_items.get(item_id).seller.get();
+        index_remove(&mut self.active_items, &mut self.active_positions, item_id);
+        index_remove(
+            &mut self.seller_active_items.setter(seller),
+            &mut self.seller_active_positions.setter(seller),
+            item_id,
+        );
+        for nft_contract in self.item_collections(item_id) {
+            index_remove(
+                &mut self.collection_active_items.setter(nft_contract),
+                &mut self.collection_active_positions.setter(nft_contract),
+                item_id,
+            );
+        }
+    }
+
+    /// Add an open offer to its collection's offer index, and to its token's unless it is a criteria offer
+    fn index_offer(&mut self, offer_id: U256) {
+        let (nft_contract, token_id, criteria_root) = {
+            let offer = self.offers.get(offer_id);
+            (offer.nft_contract.get(), offer.token_id.get(), offer.criteria_root.get())
+        };
+        if criteria_root == B256::ZERO {
+            let mut offers = self.token_offers.setter(nft_contract);
+            let mut positions = self.token_offer_positions.setter(nft_contract);
+            index_insert(&mut offers.setter(token_id), &mut positions.setter(token_id), offer_id);
+        }
+        index_insert(
+            &mut self.collection_offers.setter(nft_contract),
+            &mut self.collection_offer_positions.setter(nft_contract),
+            offer_id,
+        );
+    }
+
+    /// Drop a closed offer from the token and collection offer indexes
+    fn unindex_offer(&mut self, offer_id: U256) {
+        let (nft_contract, token_id) = {
+            let offer = self.offers.get(offer_id);
+            (offer.nft_contract.get(), offer.token_id.get())
+        };
+        let mut offers = self.token_offers.setter(nft_contract);
+        let mut positions = self.token_offer_positions.setter(nft_contract);
+        index_remove(&mut offers.setter(token_id), &mut positions.setter(token_id), offer_id);
+        index_remove(
+            &mut self.collection_offers.setter(nft_contract),
+            &mut self.collection_offer_positions.setter(nft_contract),
+            offer_id,
+        );
+    }
+
+    /// Distinct collections of an item's NFTs
+    fn item_collections(&self, item_id: U256) -> Vec<Address> {
+        let mut collections: Vec<Address> = Vec::new();
+        for (nft_contract, _) in self.item_tokens(item_id) {
+            if !collections.contains(&nft_contract) {
+                collections.push(nft_contract);
+            }
+        }
+        collections
+    }
+
+    /// Open listings among `ids`, skipping expired and scheduled ones
+    fn live_page(&self, ids: &StorageVec<StorageU256>, offset: U256, limit: U256) -> Vec<U256> {
+        page(ids, offset, limit)
+            .into_iter()
+            .filter(|id| self.status_of(*id) == Some(ListingStatus::Active))
+            .collect()
+    }
+
+    /// Append a sale to the token's history
+    fn record_sale(
+        &mut self,
+        nft_contract: Address,
+        token_id: U256,
+        item_id: U256,
+        seller: Address,
+        buyer: Address,
+        price: U256,
+    ) {
+        let sale_id = self.sale_count.get() + U256::from(1);
+        let timestamp = U256::from(self.vm().block_timestamp());
+        
+        let mut sale = self.sales.setter(sale_id);
+        sale.nft_contract.set(nft_contract);
+        sale.token_id.set(token_id);
+        sale.item_id.set(item_id);
+        sale.seller.set(seller);
+        sale.buyer.set(buyer);
+        sale.price.set(price);
+        sale.timestamp.set(timestamp);
+        
+        self.sale_count.set(sale_id);
+        self.token_sales.setter(nft_contract).setter(token_id).push(sale_id);
+        
+        // A bundle's price covers all its tokens, so it can't count toward one
+        // collection; token-priced sales aren't in ETH, so they can't either
+        if !self.is_bundle(item_id) && self.get_payment_token(item_id) == NATIVE_TOKEN {
+            let sales = self.collection_sale_count.get(nft_contract);
+            self.collection_sale_count.setter(nft_contract).set(sales + U256::from(1));
+            let volume = self.collection_volume.get(nft_contract);
+            self.collection_volume.setter(nft_contract).set(volume + price);
+        }
+    }
+
+    /// Every NFT of an item, bundled or not
+    fn item_tokens(&self, item_id: U256) -> Vec<(Address, U256)> {
+        let item = self.market_items.get(item_id);
+        if item.bundle_contracts.is_empty() {
+            return vec![(item.nft_contract.get(), item.token_id.get())];
+        }
+        (0..item.bundle_contracts.len())
+            .filter_map(|i| Some((item.bundle_contracts.get(i)?, item.bundle_token_ids.get(i)?)))
+            .collect()
+    }
+
+    /// Record the current `tokenURI` of every NFT of a new item
+    ///
+    /// Metadata is optional in ERC721, so a collection that doesn't answer
+    /// gets an empty URI and a zero hash instead of blocking the listing.
+    fn snapshot_token_uris(&mut self, item_id: U256) {
+        for (nft_contract, token_id) in self.item_tokens(item_id) {
+            let uri = IERC721Metadata::new(nft_contract).token_uri(Call::new_in(self), token_id);
+            let (uri, hash) = match uri {
+                Ok(uri) => {
+                    let hash = keccak256(uri.as_bytes());
+                    (uri, hash)
+                }
+                Err(_) => (String::new(), B256::ZERO),
+            };
+            let mut item = self.market_items.setter(item_id);
+            item.token_uris.grow().set_str(&uri);
+            item.token_uri_hashes.push(hash);
+        }
+    }
+
+    /// Record the standard of every NFT of a new item and whether it pays royalties
+    ///
+    /// ERC1155 tokens aren't escrowed, so `seller` must hold each one. Only
+    /// single listings pay ERC2981 royalties; a bundle has no per-token price.
+    fn detect_standards(&mut self, item_id: U256, seller: Address) -> Result<(), Vec<u8>> {
+        let tokens = self.item_tokens(item_id);
+        for (nft_contract, token_id) in &tokens {
+            let standard = self.detect_standard(*nft_contract)?;
+            if standard == STANDARD_ERC1155 {
+                let balance = IERC1155::new(*nft_contract)
+                    .balance_of(Call::new_in(self), seller, *token_id)
+                    .unwrap_or_default();
+                if balance == U256::ZERO {
+                    return Err("Seller does not hold token".as_bytes().to_vec());
+                }
+            }
+            self.market_items.setter(item_id).token_standards.push(U8::from(standard));
+        }
+        if !self.is_bundle(item_id) {
+            let royalties = self.supports_interface(tokens[0].0, ERC2981_INTERFACE_ID) == Some(true);
+            self.market_items.setter(item_id).royalties.set(royalties);
+        }
+        Ok(())
+    }
+
+    /// Standard of a collection, by ERC-165
+    ///
+    /// A collection without ERC-165 is taken for ERC721; one that answers
+    /// but supports neither ERC721 nor ERC1155 can't be listed.
+    fn detect_standard(&mut self, nft_contract: Address) -> Result<u8, Vec<u8>> {
+        match self.supports_interface(nft_contract, ERC721_INTERFACE_ID) {
+            None | Some(true) => Ok(STANDARD_ERC721),
+            Some(false) if self.supports_interface(nft_contract, ERC1155_INTERFACE_ID) == Some(true) => {
+                Ok(STANDARD_ERC1155)
+            }
+            Some(false) => Err("Unsupported NFT standard".as_bytes().to_vec()),
+        }
+    }
+
+    /// Ask a contract whether it supports `interface_id`, `None` if it doesn't answer
+    fn supports_interface(&mut self, nft_contract: Address, interface_id: FixedBytes<4>) -> Option<bool> {
+        IERC165::new(nft_contract)
+            .supports_interface(Call::new_in(self), interface_id)
+            .ok()
+    }
+
+    /// Standard of every NFT of an item, in `item_tokens` order
+    ///
+    /// Items listed before detection was recorded are ERC721.
+    fn item_standards(&self, item_id: U256) -> Vec<u8> {
+        let item = self.market_items.get(item_id);
+        (0..self.item_tokens(item_id).len())
+            .map(|i| item.token_standards.get(i).map_or(STANDARD_ERC721, |standard| standard.to::<u8>()))
+            .collect()
+    }
+
+    /// Pay an item's ERC2981 royalty on `price` out of `proceeds`, in `payment_token`
+    ///
+    /// Returns the receiver and amount paid, zero if none. The royalty is
+    /// capped at the proceeds. A collection that fails to answer
+    /// `royaltyInfo` gets nothing rather than blocking the sale.
+    fn pay_royalty(&mut self, item_id: U256, payment_token: Address, price: U256, proceeds: U256) -> (Address, U256) {
+        let (royalties, nft_contract, token_id) = {
+            let item = self.market_items.get(item_id);
+            (item.royalties.get(), item.nft_contract.get(), item.token_id.get())
+        };
+        if !royalties {
+            return (Address::ZERO, U256::ZERO);
+        }
+        let Ok((receiver, amount)) = IERC2981::new(nft_contract).royalty_info(Call::new_in(self), token_id, price) else {
+            return (Address::ZERO, U256::ZERO);
+        };
+        let amount = amount.min(proceeds);
+        if receiver == Address::ZERO || amount == U256::ZERO {
+            return (Address::ZERO, U256::ZERO);
+        }
+        self.credit(payment_token, receiver, amount);
+        
+        log(self.vm(), RoyaltyPaid {
+            item_id,
+            receiver,
+            amount,
+        });
+        
+        (receiver, amount)
+    }
+
+    /// Move every NFT of an item from `from` to `to`
+    ///
+    /// ERC1155 tokens aren't escrowed: the marketplace's side of a move
+    /// stands for the seller, who keeps holding them, and a move from the
+    /// seller to itself is skipped.
+    fn transfer_item(&mut self, item_id: U256, from: Address, to: Address) -> Result<(), Vec<u8>> {
+        let marketplace = self.vm().contract_address();
+        let seller = self.market_items.get(item_id).seller.get();
+        let standards = self.item_standards(item_id);
+        for ((nft_contract, token_id), standard) in self.item_tokens(item_id).into_iter().zip(standards) {
+            if standard != STANDARD_ERC1155 {
+                self.transfer_nft(nft_contract, from, to, token_id)?;
+                continue;
+            }
+            let from = if from == marketplace { seller } else { from };
+            let to = if to == marketplace { seller } else { to };
+            if from != to {
+                IERC1155::new(nft_contract)
+                    .safe_transfer_from(Call::new_in(self), from, to, token_id, U256::from(1), Bytes::from(Vec::<u8>::new()))
+                    .map_err(|_| "NFT transfer failed".as_bytes().to_vec())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget `item_id` as the active listing of its token
+    fn unlist(&mut self, item_id: U256, nft_contract: Address, token_id: U256) {
+        let mut listed = self.listed_items.setter(nft_contract);
+        let mut listed = listed.setter(token_id);
+        if listed.get() == item_id {
+            listed.set(U256::ZERO);
+        }
+    }
+
+    /// Move `token_id` of `nft_contract` from `from` to `to`
+    ///
+    /// Pulling from a seller needs the marketplace to be approved; releasing
+    /// from escrow needs nothing, since the marketplace owns the token.
+    fn transfer_nft(&mut self, nft_contract: Address, from: Address, to: Address, token_id: U256) -> Result<(), Vec<u8>> {
+        IERC721::new(nft_contract)
+            .transfer_from(Call::new_in(self), from, to, token_id)
+            .map_err(|_| "NFT transfer failed".as_bytes().to_vec())
+    }
+}
+
+/// Read `limit` ids from an index array, starting at `offset`
+fn page(ids: &StorageVec<StorageU256>, offset: U256, limit: U256) -> Vec<U256> {
+    let start = offset.saturating_to::<usize>().min(ids.len());
+    let end = start.saturating_add(limit.saturating_to::<usize>()).min(ids.len());
+    (start..end).filter_map(|i| ids.get(i)).collect()
+}
+
+/// Add `id` to an unordered index, recording its position + 1
+fn index_insert(ids: &mut StorageVec<StorageU256>, positions: &mut StorageMap<U256, StorageU256>, id: U256) {
+    if positions.get(id) != U256::ZERO {
+        return;
+    }
+    ids.push(id);
+    positions.setter(id).set(U256::from(ids.len()));
+}
+
+/// Remove `id` from an unordered index by moving the last id into its slot
+fn index_remove(ids: &mut StorageVec<StorageU256>, positions: &mut StorageMap<U256, StorageU256>, id: U256) {
+    let position = positions.get(id);
+    if position == U256::ZERO {
+        return;
+    }
+    let index = position.saturating_to::<usize>() - 1;
+    let last_index = ids.len() - 1;
+    if index != last_index {
+        let last = ids.get(last_index).unwrap_or_default();
+        if let Some(mut slot) = ids.setter(index) {
+            slot.set(last);
+        }
+        positions.setter(last).set(position);
     }
+    ids.pop();
+    positions.setter(id).set(U256::ZERO);
+}
+
+/// Remove `id` from an index array, keeping the order of the rest
+fn remove_id(ids: &mut StorageVec<StorageU256>, id: U256) {
+    let Some(position) = (0..ids.len()).find(|i| ids.get(*i) == Some(id)) else {
+        return;
+    };
+    for i in position..ids.len() - 1 {
+        let next = ids.get(i + 1).unwrap_or_default();
+        if let Some(mut slot) = ids.setter(i) {
+            slot.set(next);
+        }
+    }
+    ids.pop();
 }
 
 sol! {
@@ -232,15 +3910,1476 @@ sol! {
         address seller,
         uint256 price
     );
-    event MarketItemSold(
-        uint256 indexed item_id,
+    event MarketItemSold(
+        uint256 indexed item_id,
+        address indexed nft_contract,
+        uint256 indexed token_id,
+        address seller,
+        address buyer,
+        uint256 price
+    );
+    event PaymentCharged(uint256 indexed item_id, address indexed buyer, uint256 charged, uint256 refunded);
+    event SaleSettled(
+        uint256 indexed item_id,
+        address indexed nft_contract,
+        uint256 indexed token_id,
+        address seller,
+        address buyer,
+        address payment_token,
+        uint256 price,
+        uint256 fee,
+        address royalty_receiver,
+        uint256 royalty,
+        uint256 seller_proceeds
+    );
+    event BundleItemCreated(
+        uint256 indexed item_id,
+        address indexed seller,
+        address[] nft_contracts,
+        uint256[] token_ids,
+        uint256 price
+    );
+    event ListingPriceUpdated(uint256 indexed item_id, uint256 old_price, uint256 new_price);
+    event ListingCancelled(uint256 indexed item_id, address indexed seller);
+    event ItemRelisted(uint256 indexed item_id, uint256 indexed previous_item_id, address indexed seller, uint256 price);
+    event ListingStatusChanged(uint256 indexed item_id, uint8 status);
+    event ListingExpiryUpdated(uint256 indexed item_id, uint256 expires_at);
+    event ListingStartUpdated(uint256 indexed item_id, uint256 starts_at);
+    event ListingTransferred(uint256 indexed item_id, address indexed old_seller, address indexed new_seller);
+    event PayoutSplitUpdated(uint256 indexed item_id, address[] recipients, uint256[] shares_bps);
+    event SplitPayout(uint256 indexed item_id, address indexed recipient, uint256 amount);
+    event RoyaltyPaid(uint256 indexed item_id, address indexed receiver, uint256 amount);
+    event OfferReclaimed(uint256 indexed offer_id, address indexed bidder, address reclaimer, uint256 reward);
+    event ReclaimRewardUpdated(uint256 reward_bps);
+    event ListingPolicyUpdated(address indexed nft_contract, address policy);
+    event ListingEscrowUpdated(uint256 indexed item_id, bool enabled);
+    event EscrowWindowUpdated(uint256 window);
+    event EscrowOpened(uint256 indexed item_id, address indexed buyer, uint256 release_at);
+    event DisputeRaised(uint256 indexed item_id, address indexed buyer);
+    event EscrowReleased(uint256 indexed item_id, address indexed buyer);
+    event EscrowRefunded(uint256 indexed item_id, address indexed buyer, address moderator);
+    event ListingUpdated(uint256 indexed item_id, uint256 price, address payment_token, uint256 expires_at);
+    event FeesWithdrawn(address indexed treasurer, uint256 amount);
+    event CollectionFeeUpdated(address indexed nft_contract, uint256 fee_bps);
+    event FeeChangeProposed(uint256 old_fee, uint256 new_fee, uint256 effective_at);
+    event FeeChanged(uint256 old_fee, uint256 new_fee);
+    event FeeChangeCancelled(uint256 new_fee);
+    event FeeChangeDelayUpdated(uint256 delay);
+    event CollectionFeeCleared(address indexed nft_contract);
+    event FeeTiersUpdated(uint256[] min_volumes, uint256[] fees_bps);
+    event LoyaltyTiersUpdated(uint256[] min_volumes, uint256[] discounts_bps);
+    event CollectionVerificationUpdated(address indexed nft_contract, bool verified);
+    event VerifiedOnlyModeUpdated(bool enabled);
+    event FeeSinksUpdated(address staking_sink, uint256 staking_share_bps, address treasury_sink, uint256 treasury_share_bps);
+    event FeesDistributed(address indexed staking_sink, uint256 staking_amount, address indexed treasury_sink, uint256 treasury_amount);
+    event OfferMade(
+        uint256 indexed offer_id,
+        address indexed nft_contract,
+        uint256 indexed token_id,
+        address bidder,
+        uint256 amount,
+        uint256 expiry
+    );
+    event CriteriaOfferMade(
+        uint256 indexed offer_id,
+        address indexed nft_contract,
+        bytes32 indexed criteria_root,
+        address bidder,
+        uint256 amount,
+        uint256 expiry
+    );
+    event VoucherRedeemed(
+        address indexed nft_contract,
+        uint256 indexed token_id,
+        address indexed creator,
+        address buyer,
+        uint256 price,
+        uint256 nonce
+    );
+    event VoucherCancelled(address indexed creator, uint256 nonce);
+    event OrderFilled(
+        bytes32 indexed order_hash,
+        address indexed seller,
+        address indexed buyer,
+        address nft_contract,
+        uint256 token_id,
+        uint256 price
+    );
+    event OrderCancelled(bytes32 indexed order_hash, address indexed seller);
+    event OrderNonceIncremented(address indexed seller, uint256 nonce);
+    event SwapCreated(
+        uint256 indexed swap_id,
+        address indexed seller,
+        address offered_contract,
+        uint256 offered_token_id,
+        address wanted_contract,
+        uint256 wanted_token_id,
+        bool any_token,
+        uint256 top_up,
+        bool seller_pays_top_up
+    );
+    event SwapAccepted(uint256 indexed swap_id, address indexed counterparty, uint256 token_id);
+    event SwapCancelled(uint256 indexed swap_id);
+    event OfferCancelled(uint256 indexed offer_id, address indexed bidder);
+    event OfferAccepted(
+        uint256 indexed offer_id,
         address indexed nft_contract,
         uint256 indexed token_id,
         address seller,
-        address buyer,
-        uint256 price
+        address bidder,
+        uint256 amount
     );
-    event ListingPriceUpdated(uint256 indexed item_id, uint256 old_price, uint256 new_price);
-    event ListingCancelled(uint256 indexed item_id, address indexed seller);
-    event FeesWithdrawn(address indexed owner, uint256 amount);
-}
\ No newline at end of file
+    event TokenFlagged(address indexed nft_contract, uint256 indexed token_id, bool flagged, address moderator);
+    event CollectionFlagged(address indexed nft_contract, bool flagged, address moderator);
+    event ProceedsCredited(address indexed account, uint256 amount);
+    event ProceedsWithdrawn(address indexed account, uint256 amount);
+    event TokenProceedsCredited(address indexed token, address indexed account, uint256 amount);
+    event TokenProceedsWithdrawn(address indexed token, address indexed account, uint256 amount);
+    event TokenFeesWithdrawn(address indexed token, address indexed treasurer, uint256 amount);
+    event ReferralCodeRegistered(bytes32 indexed code, address indexed affiliate);
+    event ReferralShareUpdated(uint256 share_bps);
+    event ReferralFeePaid(bytes32 indexed code, uint256 indexed item_id, address indexed affiliate, uint256 amount);
+    event AffiliateEarningsWithdrawn(address indexed affiliate, uint256 amount);
+    event CounterOfferMade(uint256 indexed offer_id, address indexed seller, uint256 price, uint256 expiry);
+    event CounterOfferDeclined(uint256 indexed offer_id, address indexed bidder);
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_common::{AccessControlError, AccessControlUnauthorizedAccount, InitializableError, InvalidInitialization};
+    use stylus_sdk::alloy_sol_types::SolCall;
+    use stylus_sdk::testing::*;
+
+    sol! {
+        function transferFrom(address from, address to, uint256 tokenId);
+        function ownerOf(uint256 tokenId);
+        function permit(address owner, address spender, uint256 value, uint256 deadline, uint8 v, bytes32 r, bytes32 s);
+        function allowance(address owner, address spender);
+        function supportsInterface(bytes4 interfaceId);
+        function balanceOf(address account, uint256 id);
+        function isApprovedForAll(address account, address operator);
+        function royaltyInfo(uint256 tokenId, uint256 salePrice);
+        function owner();
+        function lazyMint(address to, string uri, address royaltyReceiver, uint256 royaltyBps);
+    }
+
+    fn owner() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn seller() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn buyer() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn nft() -> Address {
+        Address::from([7u8; 20])
+    }
+
+    const TOKEN_ID: u64 = 42;
+    const PRICE: u64 = 1_000;
+
+    /// Deploy the marketplace from `owner()` with a 2.5% fee
+    fn deploy(vm: &TestVM) -> NFTMarketplace {
+        vm.set_sender(owner());
+        vm.set_block_timestamp(1_000);
+        let mut contract = NFTMarketplace::from(vm);
+        assert!(contract.constructor(U256::from(250)).is_ok());
+        contract
+    }
+
+    /// Digest of the test order from `seller()` with the given expiry, nonce and salt
+    fn order_hash(contract: &NFTMarketplace, expiry: u64, nonce: u64, salt: u64) -> B256 {
+        contract.get_order_hash(
+            seller(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(PRICE),
+            U256::from(expiry),
+            U256::from(nonce),
+            U256::from(salt),
+        )
+    }
+
+    /// A signature that the mocked ecrecover attributes to `signer` for `hash`
+    fn sign(vm: &TestVM, hash: B256, signer: Address) -> Bytes {
+        let (r, s, v) = ([1u8; 32], [2u8; 32], 27u8);
+        let mut input = hash.to_vec();
+        input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+        input.extend_from_slice(&r);
+        input.extend_from_slice(&s);
+        let mut output = vec![0u8; 12];
+        output.extend_from_slice(signer.as_slice());
+        vm.mock_static_call(Address::with_last_byte(1), input, Ok(output));
+
+        let mut signature = r.to_vec();
+        signature.extend_from_slice(&s);
+        signature.push(v);
+        Bytes::from(signature)
+    }
+
+    /// Fill the test order as `buyer()`, paying `PRICE`
+    fn fill(
+        contract: &mut NFTMarketplace,
+        vm: &TestVM,
+        expiry: u64,
+        nonce: u64,
+        salt: u64,
+        signature: Bytes,
+    ) -> Result<(), Vec<u8>> {
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        contract.fill_order(
+            seller(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(PRICE),
+            U256::from(expiry),
+            U256::from(nonce),
+            U256::from(salt),
+            signature,
+        )
+    }
+
+    /// Let `token_id` of `nft()` move from `from` to `to`
+    fn mock_transfer(vm: &TestVM, from: Address, to: Address, token_id: u64) {
+        let calldata = transferFromCall {
+            from,
+            to,
+            tokenId: U256::from(token_id),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+    }
+
+    /// List `TOKEN_ID` from `seller()` at `PRICE`, letting it leave escrow to `buyer()`
+    fn list(vm: &TestVM, contract: &mut NFTMarketplace) -> U256 {
+        let marketplace = contract.vm().contract_address();
+        mock_transfer(vm, seller(), marketplace, TOKEN_ID);
+        mock_transfer(vm, marketplace, buyer(), TOKEN_ID);
+        vm.set_sender(seller());
+        contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap()
+    }
+
+    fn err(message: &str) -> Result<(), Vec<u8>> {
+        Err(message.as_bytes().to_vec())
+    }
+
+    fn missing_role() -> Result<(), Vec<u8>> {
+        Err(AccessControlError::from(AccessControlUnauthorizedAccount {}).into())
+    }
+
+    fn already_initialized() -> Result<(), Vec<u8>> {
+        Err(InitializableError::from(InvalidInitialization {}).into())
+    }
+
+    #[test]
+    fn test_order_hash_binds_every_field() {
+        let vm = TestVM::default();
+        let contract = deploy(&vm);
+
+        let hash = order_hash(&contract, 0, 0, 1);
+        assert_eq!(hash, order_hash(&contract, 0, 0, 1));
+        assert_ne!(hash, order_hash(&contract, 0, 0, 2));
+        assert_ne!(hash, order_hash(&contract, 0, 1, 1));
+        assert_ne!(hash, order_hash(&contract, 5_000, 0, 1));
+
+        let other_seller = contract.get_order_hash(
+            buyer(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(PRICE),
+            U256::ZERO,
+            U256::ZERO,
+            U256::from(1),
+        );
+        assert_ne!(hash, other_seller);
+    }
+
+    #[test]
+    fn test_order_hash_binds_domain() {
+        let vm = TestVM::default();
+        let contract = deploy(&vm);
+        let hash = order_hash(&contract, 0, 0, 1);
+
+        // An order signed for one chain can't be replayed on another
+        vm.set_chain_id(1);
+        assert_ne!(hash, order_hash(&contract, 0, 0, 1));
+    }
+
+    #[test]
+    fn test_fill_order_rejects_wrong_payment() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let signature = sign(&vm, order_hash(&contract, 0, 0, 1), seller());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE - 1));
+        let result = contract.fill_order(
+            seller(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(PRICE),
+            U256::ZERO,
+            U256::ZERO,
+            U256::from(1),
+            signature,
+        );
+        assert_eq!(result, err("Incorrect payment amount"));
+    }
+
+    #[test]
+    fn test_fill_order_rejects_expired() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let signature = sign(&vm, order_hash(&contract, 1_000, 0, 1), seller());
+
+        // Expiry is exclusive
+        assert_eq!(fill(&mut contract, &vm, 1_000, 0, 1, signature), err("Order expired"));
+    }
+
+    #[test]
+    fn test_fill_order_rejects_bad_signature() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        // Malformed signatures never reach ecrecover
+        let short = Bytes::from(vec![1u8; 64]);
+        assert_eq!(fill(&mut contract, &vm, 0, 0, 1, short), err("Invalid order signature"));
+
+        // A well-formed signature from someone other than the seller
+        let forged = sign(&vm, order_hash(&contract, 0, 0, 1), buyer());
+        assert_eq!(fill(&mut contract, &vm, 0, 0, 1, forged), err("Invalid order signature"));
+
+        // A seller signature over different terms
+        let other_terms = sign(&vm, order_hash(&contract, 0, 0, 2), seller());
+        assert_eq!(fill(&mut contract, &vm, 0, 0, 1, other_terms), err("Invalid order signature"));
+    }
+
+    #[test]
+    fn test_fill_order_settles_with_valid_signature() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let hash = order_hash(&contract, 5_000, 0, 1);
+        let signature = sign(&vm, hash, seller());
+
+        // A valid order gets as far as moving the NFT, which fails without approval
+        let calldata = transferFromCall {
+            from: seller(),
+            to: buyer(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Err(vec![]));
+        assert_eq!(fill(&mut contract, &vm, 5_000, 0, 1, signature), err("NFT transfer failed"));
+    }
+
+    #[test]
+    fn test_increment_nonce_invalidates_orders() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let signature = sign(&vm, order_hash(&contract, 0, 0, 1), seller());
+
+        vm.set_sender(seller());
+        assert_eq!(contract.increment_nonce(), U256::from(1));
+        assert_eq!(contract.get_order_nonce(seller()), U256::from(1));
+        assert_eq!(contract.get_order_nonce(buyer()), U256::ZERO);
+
+        assert_eq!(fill(&mut contract, &vm, 0, 0, 1, signature), err("Order nonce invalidated"));
+
+        // Orders signed under the new nonce pass every check up to settlement
+        let signature = sign(&vm, order_hash(&contract, 0, 1, 1), seller());
+        let calldata = transferFromCall {
+            from: seller(),
+            to: buyer(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Err(vec![]));
+        assert_eq!(fill(&mut contract, &vm, 0, 1, 1, signature), err("NFT transfer failed"));
+    }
+
+    #[test]
+    fn test_cancel_order() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let hash = order_hash(&contract, 0, 0, 1);
+        let signature = sign(&vm, hash, seller());
+        let cancel = |contract: &mut NFTMarketplace| {
+            contract.cancel_order(
+                nft(),
+                U256::from(TOKEN_ID),
+                U256::from(PRICE),
+                U256::ZERO,
+                U256::ZERO,
+                U256::from(1),
+            )
+        };
+
+        // Anyone else cancelling the same terms only cancels their own order
+        vm.set_sender(buyer());
+        assert!(cancel(&mut contract).is_ok());
+        assert_eq!(contract.get_order_status(hash), ORDER_OPEN);
+
+        vm.set_sender(seller());
+        assert!(cancel(&mut contract).is_ok());
+        assert_eq!(contract.get_order_status(hash), ORDER_CANCELLED);
+        assert_eq!(cancel(&mut contract), err("Order not open"));
+
+        assert_eq!(fill(&mut contract, &vm, 0, 0, 1, signature), err("Order cancelled"));
+    }
+
+    #[test]
+    fn test_fill_order_when_paused() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let signature = sign(&vm, order_hash(&contract, 0, 0, 1), seller());
+
+        assert!(contract.pause().is_ok());
+        assert!(fill(&mut contract, &vm, 0, 0, 1, signature).is_err());
+    }
+
+    #[test]
+    fn test_update_listing_switches_payment_token() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let token = Address::from([4u8; 20]);
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        assert_eq!(contract.get_payment_token(item_id), NATIVE_TOKEN);
+
+        assert!(contract.update_listing(item_id, U256::from(PRICE), token, U256::ZERO).is_ok());
+        assert_eq!(contract.get_payment_token(item_id), token);
+
+        // Priced in the token, the listing takes no ETH
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert_eq!(contract.buy_market_item(item_id), err("Listing is priced in a token"));
+
+        // Switched back, it sells for ETH with the proceeds credited in ETH
+        vm.set_sender(seller());
+        vm.set_value(U256::ZERO);
+        assert!(contract.update_listing(item_id, U256::from(PRICE), NATIVE_TOKEN, U256::ZERO).is_ok());
+        assert_eq!(contract.get_payment_token(item_id), NATIVE_TOKEN);
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+        assert_eq!(contract.get_token_proceeds(token, seller()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_token_priced_purchase_settles_in_token() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let token = Address::from([4u8; 20]);
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        assert!(contract.update_listing(item_id, U256::from(PRICE), token, U256::ZERO).is_ok());
+
+        // Token listings stay out of carts, whose total is in ETH
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert_eq!(contract.buy_items(vec![item_id]), Err("Listing is priced in a token".as_bytes().to_vec()));
+        vm.set_value(U256::ZERO);
+
+        let pull = transferFromCall {
+            from: buyer(),
+            to: marketplace,
+            tokenId: U256::from(PRICE),
+        }
+        .abi_encode();
+        vm.mock_call(token, pull, U256::ZERO, Ok(U256::from(1).to_be_bytes::<32>().to_vec()));
+        assert_eq!(contract.buy_market_item(item_id), err("Listing is priced in a token"));
+
+        // The seller raising the price past the buyer's bound makes the buy revert
+        vm.set_sender(seller());
+        assert!(contract.update_listing(item_id, U256::from(PRICE * 2), token, U256::ZERO).is_ok());
+        vm.set_sender(buyer());
+        assert_eq!(contract.buy_with_token(item_id, U256::from(PRICE)), err("Price above maximum"));
+        vm.set_sender(seller());
+        assert!(contract.update_listing(item_id, U256::from(PRICE), token, U256::ZERO).is_ok());
+        vm.set_sender(buyer());
+        assert!(contract.buy_with_token(item_id, U256::from(PRICE)).is_ok());
+
+        // Fee and proceeds are kept in the token, apart from ETH
+        assert_eq!(contract.get_accrued_token_fees(token), U256::from(25));
+        assert_eq!(contract.get_token_proceeds(token, seller()), U256::from(975));
+        assert_eq!(contract.get_accrued_fees(), U256::ZERO);
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_roles() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        // The deployer starts with every role
+        for role in [ADMIN_ROLE, FEE_MANAGER_ROLE, PAUSER_ROLE, CURATOR_ROLE, MODERATOR_ROLE, TREASURER_ROLE] {
+            assert!(contract.has_role(role, owner()));
+        }
+
+        assert!(contract.grant_role(PAUSER_ROLE, seller()).is_ok());
+        assert!(contract.grant_role(PAUSER_ROLE, buyer()).is_ok());
+        assert_eq!(contract.get_role_members(PAUSER_ROLE), vec![owner(), seller(), buyer()]);
+
+        // Revoking swaps the last member into the gap
+        assert!(contract.revoke_role(PAUSER_ROLE, owner()).is_ok());
+        assert_eq!(contract.get_role_members(PAUSER_ROLE), vec![buyer(), seller()]);
+        assert!(!contract.is_pauser(owner()));
+        assert_eq!(contract.pause(), missing_role());
+
+        vm.set_sender(seller());
+        assert!(contract.pause().is_ok());
+        assert_eq!(contract.grant_role(ADMIN_ROLE, seller()), missing_role());
+        assert!(contract.renounce_role(PAUSER_ROLE).is_ok());
+        assert_eq!(contract.get_role_members(PAUSER_ROLE), vec![buyer()]);
+
+        // The last admin stays
+        vm.set_sender(owner());
+        assert_eq!(contract.renounce_role(ADMIN_ROLE), err("Cannot remove last admin"));
+        assert!(contract.grant_role(ADMIN_ROLE, seller()).is_ok());
+        assert!(contract.renounce_role(ADMIN_ROLE).is_ok());
+        assert_eq!(contract.get_role_members(ADMIN_ROLE), vec![seller()]);
+    }
+
+    #[test]
+    fn test_scheduled_listing_goes_live_at_start() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let calldata = transferFromCall {
+            from: seller(),
+            to: contract.vm().contract_address(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+
+        vm.set_sender(seller());
+        let now = U256::from(1_000);
+        let listing = contract.create_scheduled_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE), now);
+        assert_eq!(listing, Err("Start must be in the future".as_bytes().to_vec()));
+        let item_id = contract
+            .create_scheduled_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE), U256::from(2_000))
+            .unwrap();
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Scheduled as u8);
+        assert!(contract.get_active_items(U256::ZERO, U256::from(10)).is_empty());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert_eq!(contract.buy_market_item(item_id), err("Listing not live yet"));
+
+        // The start is inclusive
+        vm.set_block_timestamp(2_000);
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Active as u8);
+        assert_eq!(contract.get_active_items(U256::ZERO, U256::from(10)), vec![item_id]);
+    }
+
+    #[test]
+    fn test_criteria_offer_requires_proof() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let leaf = |token_id: u64| criteria::leaf(U256::from(token_id));
+        // Two-leaf tree of tokens 42 and 43
+        let mut pair = [leaf(TOKEN_ID), leaf(TOKEN_ID + 1)];
+        pair.sort();
+        let root = keccak256([pair[0].as_slice(), pair[1].as_slice()].concat());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        let offer_id = contract
+            .make_criteria_offer(nft(), root, U256::from(PRICE), U256::from(5_000))
+            .unwrap();
+        assert_eq!(contract.get_offer_criteria(offer_id), root);
+
+        vm.set_sender(seller());
+        assert_eq!(contract.accept_offer(offer_id), err("Use accept_criteria_offer"));
+        assert_eq!(
+            contract.counter_offer(offer_id, U256::from(PRICE * 2), U256::from(2_000)),
+            err("Criteria offers can't be countered"),
+        );
+        let outside = contract.accept_criteria_offer(offer_id, U256::from(TOKEN_ID + 2), vec![leaf(TOKEN_ID)]);
+        assert_eq!(outside, err("Token not in offer criteria"));
+        let wrong_proof = contract.accept_criteria_offer(offer_id, U256::from(TOKEN_ID), vec![leaf(TOKEN_ID)]);
+        assert_eq!(wrong_proof, err("Token not in offer criteria"));
+
+        // A valid proof gets as far as checking the seller owns the token
+        let calldata = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        vm.mock_static_call(nft(), calldata, Err(vec![]));
+        let proof = vec![leaf(TOKEN_ID + 1)];
+        assert!(criteria::verify(root, U256::from(TOKEN_ID), &proof));
+        let result = contract.accept_criteria_offer(offer_id, U256::from(TOKEN_ID), proof);
+        assert_eq!(result, err("NFT owner lookup failed"));
+    }
+
+    #[test]
+    fn test_constructor_runs_once() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        // Replaying it can't seize the roles or reset the fee
+        vm.set_sender(buyer());
+        assert_eq!(contract.constructor(U256::ZERO), already_initialized());
+        assert!(!contract.has_role(ADMIN_ROLE, buyer()));
+        assert_eq!(contract.get_fee_percentage(), U256::from(250));
+
+        vm.set_sender(owner());
+        assert_eq!(contract.constructor(U256::from(250)), already_initialized());
+        assert_eq!(contract.get_role_members(ADMIN_ROLE), vec![owner()]);
+    }
+
+    #[test]
+    fn test_constructor_rejects_high_fee() {
+        let vm = TestVM::default();
+        vm.set_sender(owner());
+        let mut contract = NFTMarketplace::from(&vm);
+        assert_eq!(contract.constructor(U256::from(MAX_FEE_BPS + 1)), err("Fee too high"));
+
+        // A failed initialization leaves it open
+        assert!(contract.constructor(U256::from(MAX_FEE_BPS)).is_ok());
+        assert_eq!(contract.get_fee_percentage(), U256::from(MAX_FEE_BPS));
+    }
+
+    #[test]
+    fn test_fee_change_waits_for_delay() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let delay = DEFAULT_FEE_CHANGE_DELAY;
+        assert_eq!(contract.get_fee_change_delay(), U256::from(delay));
+        assert_eq!(contract.apply_fee_change(), err("No pending fee change"));
+
+        assert_eq!(contract.set_fee_percentage(U256::from(MAX_FEE_BPS + 1)), err("Fee too high"));
+        assert!(contract.set_fee_percentage(U256::from(500)).is_ok());
+        assert_eq!(contract.get_pending_fee(), (U256::from(500), U256::from(1_000 + delay)));
+
+        vm.set_sender(buyer());
+        assert_eq!(contract.set_fee_percentage(U256::ZERO), missing_role());
+
+        // Anyone can apply it, but only once the delay has passed
+        vm.set_block_timestamp(1_000 + delay - 1);
+        assert_eq!(contract.apply_fee_change(), err("Fee change not yet effective"));
+        assert_eq!(contract.get_fee_percentage(), U256::from(250));
+        vm.set_block_timestamp(1_000 + delay);
+        assert!(contract.apply_fee_change().is_ok());
+        assert_eq!(contract.get_fee_percentage(), U256::from(500));
+        assert_eq!(contract.get_pending_fee(), (U256::ZERO, U256::ZERO));
+
+        // A cancelled proposal can't be applied
+        vm.set_sender(owner());
+        assert!(contract.set_fee_percentage(U256::from(100)).is_ok());
+        assert!(contract.cancel_fee_change().is_ok());
+        vm.set_block_timestamp(1_000 + 2 * delay);
+        assert_eq!(contract.apply_fee_change(), err("No pending fee change"));
+        assert_eq!(contract.get_fee_percentage(), U256::from(500));
+    }
+
+    #[test]
+    fn test_purchase_rejects_underpayment() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let calldata = transferFromCall {
+            from: seller(),
+            to: contract.vm().contract_address(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE - 1));
+        assert_eq!(contract.buy_market_item(item_id), err("Insufficient payment"));
+        assert_eq!(contract.buy_items(vec![item_id]), Err("Insufficient payment".as_bytes().to_vec()));
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Active as u8);
+    }
+
+    #[test]
+    fn test_transfer_listing() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(seller());
+        for token_id in [TOKEN_ID, TOKEN_ID + 1] {
+            let calldata = transferFromCall {
+                from: seller(),
+                to: contract.vm().contract_address(),
+                tokenId: U256::from(token_id),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+            assert!(contract.create_market_item(nft(), U256::from(token_id), U256::from(PRICE)).is_ok());
+        }
+        let all = U256::from(10);
+
+        vm.set_sender(buyer());
+        assert_eq!(contract.transfer_listing(U256::from(1), buyer()), err("Only seller can transfer listing"));
+
+        vm.set_sender(seller());
+        assert_eq!(contract.transfer_listing(U256::from(1), Address::ZERO), err("Invalid new seller"));
+        assert!(contract.transfer_listing(U256::from(1), owner()).is_ok());
+        assert_eq!(contract.get_market_item(U256::from(1)).3, owner());
+        assert_eq!(contract.get_items_by_seller(seller(), U256::ZERO, all), vec![U256::from(2)]);
+        assert_eq!(contract.get_items_by_seller(owner(), U256::ZERO, all), vec![U256::from(1)]);
+
+        // Only the new seller manages it now
+        assert_eq!(contract.update_listing_price(U256::from(1), U256::from(1)), err("Only seller can update price"));
+        vm.set_sender(owner());
+        assert!(contract.update_listing_price(U256::from(1), U256::from(1)).is_ok());
+    }
+
+    #[test]
+    fn test_transferred_listing_credits_new_seller() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        assert!(contract.transfer_listing(item_id, owner()).is_ok());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        assert_eq!(contract.get_pending_proceeds(owner()), U256::from(975));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_payout_split_credits_each_recipient() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let calldata = transferFromCall {
+            from: seller(),
+            to: marketplace,
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+
+        let collaborator = Address::from([3u8; 20]);
+        let split = |contract: &mut NFTMarketplace, shares: [u64; 2]| {
+            contract.set_payout_split(
+                item_id,
+                vec![seller(), collaborator],
+                shares.iter().map(|bps| U256::from(*bps)).collect(),
+            )
+        };
+        assert_eq!(split(&mut contract, [7_000, 2_000]), err("Split shares must sum to 10000"));
+        assert_eq!(split(&mut contract, [10_000, 0]), err("Invalid split recipient"));
+        assert!(split(&mut contract, [7_000, 3_000]).is_ok());
+        assert_eq!(contract.get_payout_split(item_id).0, vec![seller(), collaborator]);
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        let offer_id = contract
+            .make_offer(nft(), U256::from(TOKEN_ID), U256::from(PRICE), U256::from(5_000))
+            .unwrap();
+        let calldata = transferFromCall {
+            from: marketplace,
+            to: buyer(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        vm.set_sender(seller());
+        vm.set_value(U256::ZERO);
+        assert!(contract.accept_offer(offer_id).is_ok());
+
+        // 975 after the 2.5% fee, with the rounding dust to the first recipient
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(683));
+        assert_eq!(contract.get_pending_proceeds(collaborator), U256::from(292));
+    }
+
+    #[test]
+    fn test_transfer_listing_clears_payout_split() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        let collaborator = Address::from([3u8; 20]);
+        let shares = vec![U256::from(5_000), U256::from(5_000)];
+        assert!(contract.set_payout_split(item_id, vec![seller(), collaborator], shares).is_ok());
+
+        assert!(contract.transfer_listing(item_id, owner()).is_ok());
+        assert_eq!(contract.get_payout_split(item_id), (vec![], vec![]));
+
+        // The old seller's split recipients get nothing; the new seller takes it all
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        assert_eq!(contract.get_pending_proceeds(owner()), U256::from(975));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::ZERO);
+        assert_eq!(contract.get_pending_proceeds(collaborator), U256::ZERO);
+    }
+
+    #[test]
+    fn test_reclaim_expired_offer() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert_eq!(contract.set_reclaim_reward(U256::from(MAX_RECLAIM_REWARD_BPS + 1)), err("Reward too high"));
+        assert!(contract.set_reclaim_reward(U256::from(MAX_RECLAIM_REWARD_BPS)).is_ok());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        let offer_id = contract
+            .make_offer(nft(), U256::from(TOKEN_ID), U256::from(PRICE), U256::from(2_000))
+            .unwrap();
+        vm.set_value(U256::ZERO);
+
+        vm.set_sender(seller());
+        assert_eq!(contract.reclaim_expired_offer(offer_id), err("Offer not expired"));
+        assert_eq!(contract.reclaim_expired_offer(offer_id + U256::from(1)), err("Offer not active"));
+        assert_eq!(contract.get_reclaim_reward(), U256::from(MAX_RECLAIM_REWARD_BPS));
+
+        vm.set_sender(buyer());
+        assert_eq!(contract.set_reclaim_reward(U256::ZERO), missing_role());
+    }
+
+    #[test]
+    fn test_listing_policy_needs_verified_collection() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let policy = Address::from([5u8; 20]);
+
+        assert_eq!(contract.set_listing_policy(nft(), policy), err("Collection not verified"));
+        assert_eq!(contract.get_listing_policy(nft()), Address::ZERO);
+    }
+
+    #[test]
+    fn test_active_indexes_follow_listings() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        vm.set_sender(seller());
+        for token_id in [TOKEN_ID, TOKEN_ID + 1, TOKEN_ID + 2] {
+            for (from, to) in [(seller(), marketplace), (marketplace, seller())] {
+                let calldata = transferFromCall {
+                    from,
+                    to,
+                    tokenId: U256::from(token_id),
+                }
+                .abi_encode();
+                vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+            }
+            assert!(contract.create_market_item(nft(), U256::from(token_id), U256::from(PRICE)).is_ok());
+        }
+        let all = U256::from(10);
+        assert_eq!(contract.get_open_item_count(), U256::from(3));
+
+        // Cancelling swaps the last listing into the freed slot
+        assert!(contract.cancel_listing(U256::from(1)).is_ok());
+        let expected = vec![U256::from(3), U256::from(2)];
+        assert_eq!(contract.get_active_items(U256::ZERO, all), expected);
+        assert_eq!(contract.get_active_items_by_seller(seller(), U256::ZERO, all), expected);
+        assert_eq!(contract.get_active_items_by_collection(nft(), U256::ZERO, all), expected);
+        assert_eq!(contract.get_active_items(U256::from(1), U256::from(1)), vec![U256::from(2)]);
+
+        // Expired listings stay indexed but are skipped
+        assert!(contract.set_listing_expiry(U256::from(3), U256::from(1_500)).is_ok());
+        vm.set_block_timestamp(1_500);
+        assert_eq!(contract.get_active_items(U256::ZERO, all), vec![U256::from(2)]);
+        assert_eq!(contract.get_open_item_count(), U256::from(2));
+    }
+
+    #[test]
+    fn test_buy_with_permit_settles_in_token() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let token = Address::from([4u8; 20]);
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+
+        let (deadline, v, r, s) = (U256::from(2_000), 27u8, B256::repeat_byte(1), B256::repeat_byte(2));
+        vm.set_sender(buyer());
+        let max_price = U256::from(PRICE);
+        assert_eq!(contract.buy_with_permit(item_id, max_price, deadline, v, r, s), err("Listing is priced in ETH"));
+
+        vm.set_sender(seller());
+        assert!(contract.update_listing(item_id, U256::from(PRICE), token, U256::ZERO).is_ok());
+        vm.set_sender(buyer());
+        let permit = permitCall {
+            owner: buyer(),
+            spender: marketplace,
+            value: U256::from(PRICE),
+            deadline,
+            v,
+            r,
+            s,
+        }
+        .abi_encode();
+        let allowance = allowanceCall {
+            owner: buyer(),
+            spender: marketplace,
+        }
+        .abi_encode();
+
+        // A failed permit only passes if the allowance is already there
+        vm.mock_call(token, permit.clone(), U256::ZERO, Err(vec![]));
+        vm.mock_static_call(token, allowance, Ok(U256::ZERO.to_be_bytes::<32>().to_vec()));
+        assert_eq!(contract.buy_with_permit(item_id, max_price, deadline, v, r, s), err("Permit failed"));
+
+        vm.mock_call(token, permit, U256::ZERO, Ok(vec![]));
+        let pull = transferFromCall {
+            from: buyer(),
+            to: marketplace,
+            tokenId: U256::from(PRICE),
+        }
+        .abi_encode();
+        vm.mock_call(token, pull, U256::ZERO, Ok(U256::from(1).to_be_bytes::<32>().to_vec()));
+        let below = max_price - U256::from(1);
+        assert_eq!(contract.buy_with_permit(item_id, below, deadline, v, r, s), err("Price above maximum"));
+        assert!(contract.buy_with_permit(item_id, max_price, deadline, v, r, s).is_ok());
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Sold as u8);
+        assert_eq!(contract.get_accrued_token_fees(token), U256::from(25));
+        assert_eq!(contract.get_token_proceeds(token, seller()), U256::from(975));
+    }
+
+    #[test]
+    fn test_settled_offers_leave_indexes() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        let offer_ids: Vec<U256> = (0..3)
+            .map(|_| contract.make_offer(nft(), U256::from(TOKEN_ID), U256::from(PRICE), U256::from(5_000)).unwrap())
+            .collect();
+        assert_eq!(contract.get_token_offers(nft(), U256::from(TOKEN_ID)), offer_ids);
+
+        // The seller holds the token unlisted, so it moves straight to the bidder
+        let owner_of = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        vm.mock_static_call(nft(), owner_of, Ok(seller().into_word().to_vec()));
+        let calldata = transferFromCall {
+            from: seller(),
+            to: buyer(),
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        vm.set_sender(seller());
+        vm.set_value(U256::ZERO);
+        assert!(contract.accept_offer(offer_ids[0]).is_ok());
+
+        // The last offer is swapped into the settled one's slot
+        let expected = vec![offer_ids[2], offer_ids[1]];
+        assert_eq!(contract.get_token_offers(nft(), U256::from(TOKEN_ID)), expected);
+        assert_eq!(contract.get_collection_stats(nft()).5, U256::from(2));
+        assert_eq!(contract.accept_offer(offer_ids[0]), err("Offer not active"));
+    }
+
+    #[test]
+    fn test_referral_on_protected_sale_pays_at_release() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        for (from, to) in [(seller(), marketplace), (marketplace, buyer())] {
+            let calldata = transferFromCall {
+                from,
+                to,
+                tokenId: U256::from(TOKEN_ID),
+            }
+            .abi_encode();
+            vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+        }
+        assert!(contract.set_referral_share(U256::from(2_000)).is_ok());
+        let affiliate = Address::from([3u8; 20]);
+        let code = B256::repeat_byte(3);
+        vm.set_sender(affiliate);
+        assert!(contract.register_referral_code(code).is_ok());
+
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        assert!(contract.set_listing_escrow(item_id, true).is_ok());
+
+        // No fee is taken while the sale is held, so there is nothing to share yet
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item_with_referral(item_id, code).is_ok());
+        assert_eq!(contract.get_escrow(item_id).1, ESCROW_HELD);
+        assert_eq!(contract.get_affiliate_balance(affiliate), U256::ZERO);
+        vm.set_value(U256::ZERO);
+
+        // 20% of the 25 fee once the escrow is released
+        assert!(contract.release_escrow(item_id).is_ok());
+        assert_eq!(contract.get_affiliate_balance(affiliate), U256::from(5));
+        assert_eq!(contract.get_referral_earnings(code), U256::from(5));
+        assert_eq!(contract.get_accrued_fees(), U256::from(20));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+    }
+
+    #[test]
+    fn test_accept_offer_on_listed_token() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let item_id = list(&vm, &mut contract);
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(800));
+        let offer_id = contract
+            .make_offer(nft(), U256::from(TOKEN_ID), U256::from(800), U256::from(5_000))
+            .unwrap();
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.accept_offer(offer_id), err("Only seller can accept offer"));
+
+        // The listing closes and the NFT leaves escrow to the bidder
+        vm.set_sender(seller());
+        assert!(contract.accept_offer(offer_id).is_ok());
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Sold as u8);
+        assert_eq!(contract.get_market_item(item_id).4, buyer());
+        assert_eq!(contract.get_listed_item(nft(), U256::from(TOKEN_ID)), U256::ZERO);
+        assert!(!contract.get_offer(offer_id).6);
+
+        // 2.5% of the offer stays as fees, the rest waits for the seller
+        assert_eq!(contract.get_accrued_fees(), U256::from(20));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(780));
+        assert_eq!(contract.get_seller_volume(seller()), U256::from(800));
+        let sale = contract.get_sale(U256::from(1));
+        assert_eq!((sale.2, sale.3, sale.4, sale.5), (item_id, seller(), buyer(), U256::from(800)));
+        assert_eq!(contract.accept_offer(offer_id), err("Offer not active"));
+    }
+
+    #[test]
+    fn test_purchase_pays_royalty_then_split() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let creator = Address::from([5u8; 20]);
+        let collaborator = Address::from([3u8; 20]);
+        let erc2981 = supportsInterfaceCall { interfaceId: ERC2981_INTERFACE_ID }.abi_encode();
+        vm.mock_static_call(nft(), erc2981, Ok(U256::from(1).to_be_bytes::<32>().to_vec()));
+        let royalty_info = royaltyInfoCall {
+            tokenId: U256::from(TOKEN_ID),
+            salePrice: U256::from(PRICE),
+        }
+        .abi_encode();
+        let royalty = [creator.into_word().to_vec(), U256::from(50).to_be_bytes::<32>().to_vec()].concat();
+        vm.mock_static_call(nft(), royalty_info, Ok(royalty));
+
+        let item_id = list(&vm, &mut contract);
+        assert_eq!(contract.get_token_standards(item_id), (vec![STANDARD_ERC721], true));
+        let shares = vec![U256::from(7_000), U256::from(3_000)];
+        assert!(contract.set_payout_split(item_id, vec![seller(), collaborator], shares).is_ok());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+
+        // The royalty comes out of the 975 left after the fee, and the split shares the remaining 925
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(creator), U256::from(50));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(648));
+        assert_eq!(contract.get_pending_proceeds(collaborator), U256::from(277));
+        assert_eq!(contract.get_collection_stats(nft()).6, U256::from(1));
+    }
+
+    #[test]
+    fn test_resolve_escrow_refunds_or_releases() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        assert!(contract.set_escrow_window(U256::from(100)).is_ok());
+        mock_transfer(&vm, marketplace, seller(), TOKEN_ID);
+        let item_id = list(&vm, &mut contract);
+        assert!(contract.set_listing_escrow(item_id, true).is_ok());
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.get_escrow(item_id), (true, ESCROW_HELD, U256::from(1_100)));
+        assert_eq!(contract.release_escrow(item_id), err("Dispute window open"));
+        assert_eq!(contract.get_accrued_fees(), U256::ZERO);
+
+        assert!(contract.raise_dispute(item_id).is_ok());
+        vm.set_block_timestamp(1_100);
+        assert_eq!(contract.release_escrow(item_id), err("Escrow not held"));
+        assert_eq!(contract.resolve_escrow(item_id, true), missing_role());
+
+        // A refund sends the NFT back to the seller and owes the buyer the full price
+        vm.set_sender(owner());
+        assert!(contract.resolve_escrow(item_id, true).is_ok());
+        assert_eq!(contract.get_escrow(item_id).1, ESCROW_REFUNDED);
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Cancelled as u8);
+        assert_eq!(contract.get_market_item(item_id).4, seller());
+        assert_eq!(contract.get_pending_proceeds(buyer()), U256::from(PRICE));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::ZERO);
+        assert_eq!(contract.get_accrued_fees(), U256::ZERO);
+        assert_eq!(contract.resolve_escrow(item_id, true), err("Escrow not held"));
+
+        // Resolving the other way completes the sale as a release would
+        let item_id = list(&vm, &mut contract);
+        assert!(contract.set_listing_escrow(item_id, true).is_ok());
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        vm.set_sender(owner());
+        vm.set_value(U256::ZERO);
+        assert!(contract.resolve_escrow(item_id, false).is_ok());
+        assert_eq!(contract.get_escrow(item_id).1, ESCROW_RELEASED);
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+    }
+
+    #[test]
+    fn test_release_refunds_undeliverable_erc1155() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        assert!(contract.set_escrow_window(U256::from(100)).is_ok());
+        let (no, yes) = (U256::ZERO.to_be_bytes::<32>().to_vec(), U256::from(1).to_be_bytes::<32>().to_vec());
+        let erc721 = supportsInterfaceCall { interfaceId: ERC721_INTERFACE_ID }.abi_encode();
+        vm.mock_static_call(nft(), erc721, Ok(no.clone()));
+        let erc1155 = supportsInterfaceCall { interfaceId: ERC1155_INTERFACE_ID }.abi_encode();
+        vm.mock_static_call(nft(), erc1155, Ok(yes.clone()));
+        let balance_of = balanceOfCall {
+            account: seller(),
+            id: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+        vm.mock_static_call(nft(), balance_of.clone(), Ok(yes.clone()));
+        let approved = isApprovedForAllCall {
+            account: seller(),
+            operator: marketplace,
+        }
+        .abi_encode();
+        vm.mock_static_call(nft(), approved, Ok(yes));
+
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE))
+            .unwrap();
+        assert_eq!(contract.get_token_standards(item_id).0, vec![STANDARD_ERC1155]);
+        assert!(contract.set_listing_escrow(item_id, true).is_ok());
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        vm.set_value(U256::ZERO);
+
+        // The seller moves the unit away during the window, so releasing refunds the buyer
+        vm.mock_static_call(nft(), balance_of, Ok(no));
+        vm.set_block_timestamp(1_100);
+        assert!(contract.release_escrow(item_id).is_ok());
+        assert_eq!(contract.get_escrow(item_id).1, ESCROW_REFUNDED);
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Cancelled as u8);
+        assert_eq!(contract.get_pending_proceeds(buyer()), U256::from(PRICE));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::ZERO);
+        assert_eq!(contract.get_accrued_fees(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_accept_counter_offer_pays_the_difference() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let owner_of = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        vm.mock_static_call(nft(), owner_of, Ok(seller().into_word().to_vec()));
+        mock_transfer(&vm, seller(), buyer(), TOKEN_ID);
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(800));
+        let offer_id = contract
+            .make_offer(nft(), U256::from(TOKEN_ID), U256::from(800), U256::from(5_000))
+            .unwrap();
+        assert_eq!(contract.accept_counter_offer(offer_id), err("No pending counter-offer"));
+
+        vm.set_sender(seller());
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.counter_offer(offer_id, U256::from(800), U256::from(2_000)), err("Counter must exceed offer"));
+        assert!(contract.counter_offer(offer_id, U256::from(PRICE), U256::from(2_000)).is_ok());
+
+        // Only the 200 on top of the escrowed 800 is paid now
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert_eq!(contract.accept_counter_offer(offer_id), err("Incorrect payment amount"));
+        vm.set_value(U256::from(200));
+        assert!(contract.accept_counter_offer(offer_id).is_ok());
+
+        assert_eq!(contract.get_counter_offer(offer_id).0, NEGOTIATION_ACCEPTED);
+        assert_eq!(contract.get_offer(offer_id).4, U256::from(PRICE));
+        assert!(!contract.get_offer(offer_id).6);
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+        assert_eq!(contract.get_sale(U256::from(1)).5, U256::from(PRICE));
+    }
+
+    #[test]
+    fn test_buy_items_refunds_skipped_items() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let item_id = list(&vm, &mut contract);
+        mock_transfer(&vm, seller(), marketplace, TOKEN_ID + 1);
+        mock_transfer(&vm, marketplace, seller(), TOKEN_ID + 1);
+        let cancelled = contract
+            .create_market_item(nft(), U256::from(TOKEN_ID + 1), U256::from(PRICE))
+            .unwrap();
+        assert!(contract.cancel_listing(cancelled).is_ok());
+
+        // The cancelled item's price and the overpayment come back in one transfer
+        let paid = U256::from(2 * PRICE + 50);
+        vm.set_balance(marketplace, paid);
+        vm.mock_call(buyer(), vec![], U256::from(PRICE + 50), Ok(vec![]));
+        vm.set_sender(buyer());
+        vm.set_value(paid);
+        assert_eq!(contract.buy_items(vec![item_id, cancelled]), Ok(vec![true, false]));
+
+        assert_eq!(contract.get_listing_status(item_id), ListingStatus::Sold as u8);
+        assert_eq!(contract.get_listing_status(cancelled), ListingStatus::Cancelled as u8);
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+    }
+
+    #[test]
+    fn test_distribute_fees_to_sinks() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let (staking, treasury) = (Address::from([5u8; 20]), Address::from([6u8; 20]));
+        let item_id = list(&vm, &mut contract);
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.distribute_fees(), Err("Nothing to distribute".as_bytes().to_vec()));
+
+        vm.set_sender(owner());
+        let sinks = |contract: &mut NFTMarketplace, staking_bps: u64, treasury_bps: u64| {
+            contract.set_fee_sinks(staking, U256::from(staking_bps), treasury, U256::from(treasury_bps))
+        };
+        assert_eq!(sinks(&mut contract, 8_000, 2_001), err("Shares too high"));
+        assert!(sinks(&mut contract, 4_000, 2_000).is_ok());
+
+        // Anyone can push the shares of the 25 accrued; the rest stays for treasurers
+        vm.set_balance(marketplace, U256::from(PRICE));
+        vm.mock_call(staking, vec![], U256::from(10), Ok(vec![]));
+        vm.mock_call(treasury, vec![], U256::from(5), Ok(vec![]));
+        vm.set_sender(buyer());
+        assert_eq!(contract.distribute_fees(), Ok((U256::from(10), U256::from(5))));
+        assert_eq!(contract.get_accrued_fees(), U256::from(10));
+        assert_eq!(contract.get_sink_distributed(staking), U256::from(10));
+        assert_eq!(contract.get_sink_distributed(treasury), U256::from(5));
+    }
+
+    #[test]
+    fn test_withdraw_fees() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let item_id = list(&vm, &mut contract);
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.withdraw_fees(U256::from(10)), missing_role());
+
+        vm.set_sender(owner());
+        assert_eq!(contract.withdraw_fees(U256::ZERO), err("Amount must be greater than zero"));
+        assert_eq!(contract.withdraw_fees(U256::from(26)), err("Amount exceeds accrued fees"));
+
+        // A partial withdrawal leaves the rest accrued
+        vm.set_balance(contract.vm().contract_address(), U256::from(PRICE));
+        vm.mock_call(owner(), vec![], U256::from(10), Ok(vec![]));
+        assert!(contract.withdraw_fees(U256::from(10)).is_ok());
+        assert_eq!(contract.get_accrued_fees(), U256::from(15));
+    }
+
+    #[test]
+    fn test_bundle_purchase_moves_every_token() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let token_ids = vec![U256::from(TOKEN_ID), U256::from(TOKEN_ID + 1)];
+        for token_id in [TOKEN_ID, TOKEN_ID + 1] {
+            mock_transfer(&vm, seller(), marketplace, token_id);
+            mock_transfer(&vm, marketplace, buyer(), token_id);
+        }
+        vm.set_sender(seller());
+        let item_id = contract
+            .create_bundle_item(vec![nft(), nft()], token_ids.clone(), U256::from(PRICE))
+            .unwrap();
+        assert_eq!(contract.get_bundle(item_id), (vec![nft(), nft()], token_ids.clone()));
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert!(contract.buy_market_item(item_id).is_ok());
+
+        // One sale per token, but bundles stay out of the collection's stats
+        assert_eq!(contract.get_sale_count(), U256::from(2));
+        assert_eq!(contract.get_sale_history(nft(), token_ids[1]), vec![U256::from(2)]);
+        assert_eq!(contract.get_collection_stats(nft()).6, U256::ZERO);
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+    }
+
+    #[test]
+    fn test_accept_swap_with_top_up() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        mock_transfer(&vm, seller(), marketplace, TOKEN_ID);
+        mock_transfer(&vm, buyer(), seller(), TOKEN_ID + 1);
+        mock_transfer(&vm, marketplace, buyer(), TOKEN_ID);
+
+        // The seller asks for token 43 plus 100 on top
+        vm.set_sender(seller());
+        let swap_id = contract
+            .create_swap(nft(), U256::from(TOKEN_ID), nft(), U256::from(TOKEN_ID + 1), false, U256::from(100), false)
+            .unwrap();
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(100));
+        assert_eq!(contract.accept_swap(swap_id, U256::from(TOKEN_ID + 2)), err("Token not accepted"));
+        vm.set_value(U256::ZERO);
+        assert_eq!(contract.accept_swap(swap_id, U256::from(TOKEN_ID + 1)), err("Incorrect payment amount"));
+        vm.set_value(U256::from(100));
+        assert!(contract.accept_swap(swap_id, U256::from(TOKEN_ID + 1)).is_ok());
+
+        assert_eq!(contract.get_swap(swap_id).8, SWAP_COMPLETED);
+        assert_eq!(contract.get_swap_settlement(swap_id), (buyer(), U256::from(TOKEN_ID + 1)));
+        // The fee is taken from the top-up only
+        assert_eq!(contract.get_accrued_fees(), U256::from(2));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(98));
+        assert_eq!(contract.accept_swap(swap_id, U256::from(TOKEN_ID + 1)), err("Swap not open"));
+    }
+
+    #[test]
+    fn test_redeem_voucher_mints_and_credits_creator() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let uri = String::from("ipfs://token");
+        let (price, royalty_bps, nonce) = (U256::from(PRICE), U256::from(500), U256::from(1));
+        let digest = contract.get_voucher_hash(nft(), uri.clone(), price, royalty_bps, seller(), nonce);
+        let signature = sign(&vm, digest, seller());
+        vm.mock_static_call(nft(), ownerCall {}.abi_encode(), Ok(seller().into_word().to_vec()));
+        let mint = lazyMintCall {
+            to: buyer(),
+            uri: uri.clone(),
+            royaltyReceiver: seller(),
+            royaltyBps: royalty_bps,
+        }
+        .abi_encode();
+        vm.mock_call(nft(), mint, U256::ZERO, Ok(U256::from(7).to_be_bytes::<32>().to_vec()));
+
+        vm.set_sender(buyer());
+        vm.set_value(price);
+        let redeem = |contract: &mut NFTMarketplace, signature: Bytes| {
+            contract.redeem_voucher(nft(), uri.clone(), price, royalty_bps, seller(), nonce, signature)
+        };
+        assert_eq!(redeem(&mut contract, signature.clone()), Ok(U256::from(7)));
+
+        // The creator is paid like a seller, and the voucher can't be replayed
+        assert_eq!(contract.get_accrued_fees(), U256::from(25));
+        assert_eq!(contract.get_pending_proceeds(seller()), U256::from(975));
+        assert_eq!(contract.get_sale_history(nft(), U256::from(7)), vec![U256::from(1)]);
+        assert!(contract.is_voucher_nonce_used(seller(), nonce));
+        assert_eq!(redeem(&mut contract, signature), Err("Voucher already used".as_bytes().to_vec()));
+    }
+
+    #[test]
+    fn test_listing_escrows_nft_until_cancelled() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        let escrow = transferFromCall {
+            from: seller(),
+            to: marketplace,
+            tokenId: U256::from(TOKEN_ID),
+        }
+        .abi_encode();
+
+        // Without approval the NFT can't be escrowed, so nothing is listed
+        vm.mock_call(nft(), escrow, U256::ZERO, Err(vec![]));
+        vm.set_sender(seller());
+        let listing = contract.create_market_item(nft(), U256::from(TOKEN_ID), U256::from(PRICE));
+        assert_eq!(listing, Err("NFT transfer failed".as_bytes().to_vec()));
+
+        // A failed call doesn't roll storage back under test, so start over
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let marketplace = contract.vm().contract_address();
+        mock_transfer(&vm, marketplace, seller(), TOKEN_ID);
+        let item_id = list(&vm, &mut contract);
+        assert_eq!(contract.get_market_item(item_id).4, marketplace);
+        assert_eq!(contract.get_listed_item(nft(), U256::from(TOKEN_ID)), item_id);
+
+        vm.set_sender(buyer());
+        assert_eq!(contract.cancel_listing(item_id), err("Only seller can cancel listing"));
+        vm.set_sender(seller());
+        assert!(contract.cancel_listing(item_id).is_ok());
+        assert_eq!(contract.get_market_item(item_id).4, seller());
+        assert_eq!(contract.get_listed_item(nft(), U256::from(TOKEN_ID)), U256::ZERO);
+
+        vm.set_sender(buyer());
+        vm.set_value(U256::from(PRICE));
+        assert_eq!(contract.buy_market_item(item_id), err("Listing cancelled"));
+    }
+}
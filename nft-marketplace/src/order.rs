@@ -0,0 +1,29 @@
+//! EIP-712 sell orders settled without a listing transaction
+//!
+//! A seller signs a `SellOrder` off-chain and keeps the NFT, only approving the
+//! marketplace. Any buyer can fill it with `fill_order` until it expires, is
+//! filled or cancelled, or the seller bumps their order nonce, which
+//! invalidates every order signed under the old one at once. Orders share the
+//! marketplace's EIP-712 domain with mint vouchers.
+
+use alloy_sol_types::sol;
+
+sol! {
+    /// Seller-signed offer to sell one token at a fixed price
+    struct SellOrder {
+        address seller;
+        address nftContract;
+        uint256 tokenId;
+        uint256 price;
+        uint256 expiry;
+        uint256 nonce;
+        uint256 salt;
+    }
+}
+
+/// Status of an order that can still be filled
+pub const ORDER_OPEN: u8 = 0;
+/// Status of an order settled by `fill_order`
+pub const ORDER_FILLED: u8 = 1;
+/// Status of an order withdrawn by its seller
+pub const ORDER_CANCELLED: u8 = 2;
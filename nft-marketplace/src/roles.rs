@@ -0,0 +1,28 @@
+//! Role ids for the marketplace
+//!
+//! Every administrative power is its own role, so fee settings, pausing,
+//! curation, moderation and fee withdrawal can each sit with a different
+//! multisig instead of one owner key. Role ids follow OpenZeppelin's
+//! AccessControl: `keccak256("<NAME>_ROLE")`, with the admin role at zero.
+//! Membership is kept by `stylus_common::AccessControl`.
+
+use alloy_primitives::{b256, B256};
+use stylus_common::DEFAULT_ADMIN_ROLE;
+
+/// Grants and revokes every role, and sets marketplace policy
+pub const ADMIN_ROLE: B256 = DEFAULT_ADMIN_ROLE;
+
+/// `keccak256("FEE_MANAGER_ROLE")`: sets fee overrides, tiers and shares
+pub const FEE_MANAGER_ROLE: B256 = b256!("6c0757dc3e6b28b2580c03fd9e96c274acf4f99d91fbec9b418fa1d70604ff1c");
+
+/// `keccak256("PAUSER_ROLE")`: pauses and unpauses the marketplace
+pub const PAUSER_ROLE: B256 = b256!("65d7a28e3265b37a6474929f336521b332c1681b933f6cb9f3376673440d862a");
+
+/// `keccak256("CURATOR_ROLE")`: verifies collections and toggles verified-only mode
+pub const CURATOR_ROLE: B256 = b256!("850d585eb7f024ccee5e68e55f2c26cc72e1e6ee456acf62135757a5eb9d4a10");
+
+/// `keccak256("MODERATOR_ROLE")`: flags assets and resolves escrow disputes
+pub const MODERATOR_ROLE: B256 = b256!("71f3d55856e4058ed06ee057d79ada615f65cdf5f9ee88181b914225088f834f");
+
+/// `keccak256("TREASURER_ROLE")`: withdraws accrued fees
+pub const TREASURER_ROLE: B256 = b256!("3496e2e73c4d42b75d702e60d9e48102720b8691234415963a5a857b86425d07");
@@ -0,0 +1,26 @@
+//! ERC-165 detection of the token standard behind a listed NFT
+//!
+//! Each NFT's standard is detected when it is listed and stored on the
+//! listing, so settlement moves it with the matching transfer call instead of
+//! assuming ERC721. A collection that doesn't implement ERC-165 at all is
+//! treated as ERC721, as many early ones predate it. ERC2981 support is
+//! recorded the same way so the royalty can be paid at settlement.
+
+use alloy_primitives::{fixed_bytes, FixedBytes};
+
+/// ERC721 interface id
+pub const ERC721_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("80ac58cd");
+
+/// ERC1155 interface id
+pub const ERC1155_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("d9b67a26");
+
+/// ERC2981 royalty interface id
+pub const ERC2981_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("2a55205a");
+
+/// `onERC1155Received.selector`, returned to accept an ERC1155 transfer
+pub const ERC1155_RECEIVED: FixedBytes<4> = fixed_bytes!("f23a6e61");
+
+/// Standard of an NFT moved with `transferFrom`
+pub const STANDARD_ERC721: u8 = 1;
+/// Standard of an NFT moved with `safeTransferFrom` one unit at a time
+pub const STANDARD_ERC1155: u8 = 2;
@@ -0,0 +1,26 @@
+//! EIP-712 mint vouchers for lazy-minted listings
+//!
+//! Instead of minting and listing, a creator signs a `MintVoucher` off-chain.
+//! The buyer submits it with the payment and the marketplace mints the token
+//! on the creator's contract. The EIP-712 domain binds vouchers to this
+//! marketplace and chain, and each creator nonce can be used once.
+
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, Eip712Domain};
+
+sol! {
+    /// Creator-signed authorization to mint and sell one token
+    struct MintVoucher {
+        address nftContract;
+        string uri;
+        uint256 price;
+        uint256 royaltyBps;
+        address creator;
+        uint256 nonce;
+    }
+}
+
+/// EIP-712 domain of the marketplace deployed at `marketplace`
+pub fn domain(chain_id: u64, marketplace: Address) -> Eip712Domain {
+    stylus_eip712::domain("NFTMarketplace", "1", chain_id, marketplace)
+}
@@ -0,0 +1,251 @@
+//! Attack-simulation tests for the vault
+//!
+//! `MaliciousReceiver` stands in for an attacking contract. The TestVM can't
+//! run a recipient's code, so the test build's `SendEth` impl below hands
+//! control to the installed receiver's `receive` instead of making the
+//! transfer, and the receiver calls straight back into the vault as a
+//! contract on-chain would. Other recipients still go through the VM.
+
+use super::*;
+use alloy_primitives::keccak256;
+use core::cell::RefCell;
+use stylus_sdk::testing::*;
+
+/// Vault function the attacker re-enters from its `receive`
+#[derive(Clone, Copy)]
+pub enum AttackTarget {
+    Vulnerable,
+    Safe,
+    Emergency,
+}
+
+/// Contract that re-enters the vault whenever it is sent ETH
+pub struct MaliciousReceiver {
+    address: Address,
+    target: AttackTarget,
+    max_reentries: u32,
+    reentries: u32,
+    received: U256,
+    revert_data: Option<Vec<u8>>,
+}
+
+thread_local! {
+    static RECEIVER: RefCell<Option<MaliciousReceiver>> = const { RefCell::new(None) };
+}
+
+/// Keeps a `MaliciousReceiver` installed until dropped
+#[must_use]
+pub struct Installed;
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        RECEIVER.with(|cell| *cell.borrow_mut() = None);
+    }
+}
+
+impl MaliciousReceiver {
+    /// Deploy the attacker at `address`, re-entering `target` up to `max_reentries` times
+    pub fn install(address: Address, target: AttackTarget, max_reentries: u32) -> Installed {
+        RECEIVER.with(|cell| {
+            *cell.borrow_mut() = Some(MaliciousReceiver {
+                address,
+                target,
+                max_reentries,
+                reentries: 0,
+                received: U256::ZERO,
+                revert_data: None,
+            })
+        });
+        Installed
+    }
+
+    /// Total ETH the attacker has received
+    pub fn received() -> U256 {
+        with_receiver(|receiver| receiver.received)
+    }
+
+    /// Revert data of the re-entrant call, if the vault rejected it
+    pub fn revert_data() -> Option<Vec<u8>> {
+        with_receiver(|receiver| receiver.revert_data.clone())
+    }
+}
+
+/// Pays the installed `MaliciousReceiver` by running its `receive`, everyone else with a plain call
+impl SendEth for VaultContract {
+    fn send_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        on_receive(self, to, amount).unwrap_or_else(|| self.call_eth(to, amount))
+    }
+}
+
+fn with_receiver<R>(f: impl FnOnce(&mut MaliciousReceiver) -> R) -> R {
+    RECEIVER.with(|cell| f(cell.borrow_mut().as_mut().expect("no receiver installed")))
+}
+
+/// The attacker's `receive`, or `None` if `to` is not the attacker
+///
+/// An `Err` reverts the receive, bubbling up the vault's revert data, so the
+/// vault's transfer fails as it would on-chain.
+fn on_receive(vault: &mut VaultContract, to: Address, amount: U256) -> Option<Result<(), Vec<u8>>> {
+    // Release the borrow before re-entering, since the vault calls back in here
+    let reenter = RECEIVER.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let receiver = cell.as_mut().filter(|receiver| receiver.address == to)?;
+        let reenter = receiver.reentries < receiver.max_reentries;
+        if reenter {
+            receiver.reentries += 1;
+        }
+        Some(reenter.then_some(receiver.target))
+    })?;
+
+    if let Some(target) = reenter {
+        let result = match target {
+            AttackTarget::Vulnerable => vault.withdraw_vulnerable(amount),
+            AttackTarget::Safe => vault.withdraw_safe(amount).map(|_| ()),
+            AttackTarget::Emergency => vault.emergency_withdraw(amount).map(|_| ()),
+        };
+        if let Err(err) = result {
+            let data: Vec<u8> = err.into();
+            with_receiver(|receiver| receiver.revert_data = Some(data.clone()));
+            return Some(Err(data));
+        }
+    }
+
+    with_receiver(|receiver| receiver.received += amount);
+    Some(Ok(()))
+}
+
+fn victim() -> Address {
+    Address::from([1u8; 20])
+}
+
+fn attacker() -> Address {
+    Address::from([2u8; 20])
+}
+
+fn ether(n: u64) -> U256 {
+    U256::from(n) * U256::from(10).pow(U256::from(18))
+}
+
+/// Vault holding 10 ETH from the victim and 1 ETH from the attacker
+fn funded_vault(vm: &TestVM) -> VaultContract {
+    let mut vault = crate::tests::deploy(vm);
+
+    vm.set_sender(victim());
+    vm.set_value(ether(10));
+    assert!(vault.deposit().is_ok());
+
+    vm.set_sender(attacker());
+    vm.set_value(ether(1));
+    assert!(vault.deposit().is_ok());
+
+    vm.set_value(U256::ZERO);
+    vault
+}
+
+fn reentrant_call_error() -> Vec<u8> {
+    keccak256("ReentrancyGuardReentrantCall()")[..4].to_vec()
+}
+
+#[test]
+fn test_vulnerable_withdraw_is_drained() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+    let _receiver = MaliciousReceiver::install(attacker(), AttackTarget::Vulnerable, 5);
+
+    assert!(vault.withdraw_vulnerable(ether(1)).is_ok());
+
+    // One withdrawal plus five re-entries, all against a 1 ETH balance
+    assert_eq!(MaliciousReceiver::received(), ether(6));
+    assert_eq!(MaliciousReceiver::revert_data(), None);
+    assert_eq!(vault.balance_of(attacker()), U256::ZERO);
+
+    // The ledger no longer covers what the victim is owed
+    let total = vault.get_total_balance().ok().unwrap();
+    assert_eq!(total, ether(5));
+    assert!(vault.balance_of(victim()) > vault.total_supply());
+}
+
+#[test]
+fn test_safe_withdraw_reverts_reentry() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+    let _receiver = MaliciousReceiver::install(attacker(), AttackTarget::Safe, 5);
+
+    // The re-entrant call reverts, so the outer withdrawal burns and pays nothing
+    assert_eq!(vault.withdraw_safe(ether(1)).ok(), Some(U256::ZERO));
+    assert_eq!(MaliciousReceiver::revert_data(), Some(reentrant_call_error()));
+    assert_eq!(MaliciousReceiver::received(), U256::ZERO);
+    assert_eq!(vault.get_balance(attacker()), ether(1));
+    assert_eq!(vault.get_total_balance().ok(), Some(ether(11)));
+    assert!(!vault.is_reentrancy_guard_entered());
+
+    // Since the outer call doesn't revert, the attempt stays on record
+    assert_eq!(vault.get_blocked_attempts(), U256::from(1));
+}
+
+#[test]
+fn test_blocked_reentry_leaves_limits_and_cap() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+    vm.set_sender(crate::tests::owner());
+    assert!(vault.set_rate_limits(ether(5), U256::ZERO, ether(20)).is_ok());
+    assert!(vault.set_default_cap(ether(3)).is_ok());
+    vm.set_block_timestamp(1000);
+    vm.set_sender(attacker());
+    let _receiver = MaliciousReceiver::install(attacker(), AttackTarget::Safe, 5);
+
+    // Nothing was paid, so nothing is drawn from the allowance or the cap
+    assert_eq!(vault.withdraw_safe(ether(1)).ok(), Some(U256::ZERO));
+    assert_eq!(vault.get_blocked_attempts(), U256::from(1));
+    assert_eq!(vault.remaining_allowance(attacker()), ether(5));
+    assert_eq!(vault.remaining_withdrawal_capacity(attacker()), ether(3));
+}
+
+#[test]
+fn test_failed_transfer_is_not_an_attempt() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+
+    // A receiver that reverts for any other reason fails the withdrawal
+    vm.mock_call(attacker(), vec![], ether(1), Err(vec![]));
+    assert!(matches!(
+        vault.withdraw_safe(ether(1)),
+        Err(VaultError::WithdrawalFailed(_))
+    ));
+    assert_eq!(vault.get_balance(attacker()), ether(1));
+    assert_eq!(vault.get_blocked_attempts(), U256::ZERO);
+}
+
+#[test]
+fn test_emergency_withdraw_reverts_reentry() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+    let guardian = Address::from([3u8; 20]);
+    vm.set_sender(crate::tests::owner());
+    assert!(vault.set_guardian(guardian).is_ok());
+    vm.set_sender(guardian);
+    assert!(vault.emergency_mode().is_ok());
+    vm.set_sender(attacker());
+    let _receiver = MaliciousReceiver::install(attacker(), AttackTarget::Emergency, 5);
+
+    // The RAII lock is held while the receiver runs and released afterwards
+    assert_eq!(vault.emergency_withdraw(ether(1)).ok(), Some(U256::ZERO));
+    assert_eq!(MaliciousReceiver::revert_data(), Some(reentrant_call_error()));
+    assert_eq!(MaliciousReceiver::received(), U256::ZERO);
+    assert_eq!(vault.balance_of(attacker()), ether(1));
+    assert!(!vault.is_reentrancy_guard_entered());
+    assert_eq!(vault.get_blocked_attempts(), U256::from(1));
+}
+
+#[test]
+fn test_safe_withdraw_to_honest_receiver() {
+    let vm = TestVM::default();
+    let mut vault = funded_vault(&vm);
+    let _receiver = MaliciousReceiver::install(attacker(), AttackTarget::Safe, 0);
+
+    assert_eq!(vault.withdraw_safe(ether(1)).ok(), Some(ether(1)));
+    assert_eq!(MaliciousReceiver::received(), ether(1));
+    assert_eq!(vault.get_balance(attacker()), U256::ZERO);
+    assert_eq!(vault.get_total_balance().ok(), Some(ether(10)));
+    assert_eq!(vault.get_blocked_attempts(), U256::ZERO);
+}
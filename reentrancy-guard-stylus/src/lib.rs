@@ -1,7 +1,9 @@
 //! Example Stylus contract demonstrating ReentrancyGuard usage
 //! 
 //! This contract shows how to use the ReentrancyGuard to protect against
-//! reentrancy attacks in a simple vault contract.
+//! reentrancy attacks in a simple ETH yield vault. Deposits mint shares and
+//! withdrawals burn them at an exchange rate driven by total assets, following
+//! ERC-4626 with native ETH as the underlying asset.
 
 #![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 extern crate alloc;
@@ -9,58 +11,180 @@ extern crate alloc;
 use alloc::vec::Vec;
 use alloy_primitives::{Address, U256};
 use stylus_sdk::{
-    alloy_sol_types::sol,
+    alloy_sol_types::{sol, SolCall},
     call::Call,
-    contract,
-    msg,
     prelude::*,
-    storage::{StorageAddress, StorageU256, StorageMap},
+    storage::{StorageAddress, StorageBool, StorageMap, StorageU256},
 };
 
 // Import our reentrancy guard
-mod reentrancy;
-use reentrancy::{ReentrancyGuard, ReentrancyGuarded, ReentrancyError};
+use stylus_reentrancy_guard::{
+    is_reentrant_call_revert, non_reentrant, EnforcedPause, ExpectedPause, GlobalRateLimitExceeded, Ownable2Step,
+    OwnableError, OwnableInvalidOwner, OwnableUnauthorizedAccount, Pausable, PausableError, RateLimitError,
+    RateLimiter, ReentrancyError, ReentrancyGuard, ReentrancyGuardReadOnlyReentrantCall,
+    ReentrancyGuardReentrantCall, ReentrancyGuarded, ReentrancyTelemetry, Selector, UserRateLimitExceeded,
+    WithdrawalCapError, WithdrawalCapExceeded, WithdrawalCaps,
+};
+
+#[cfg(test)]
+mod attack;
+pub mod queue;
+
+use queue::WithdrawalQueue;
+
+/// Delay applied to queued withdrawals until the owner changes it
+pub const DEFAULT_WITHDRAWAL_DELAY: u64 = 24 * 60 * 60;
+
+/// Period over which per-user withdrawal caps apply
+pub const WITHDRAWAL_CAP_PERIOD: u64 = 24 * 60 * 60;
+
+/// Time the vault stays in emergency mode before the owner can recover it
+pub const EMERGENCY_RECOVERY_DELAY: u64 = 3 * 24 * 60 * 60;
 
 // Solidity interface definitions
 sol! {
-    event Deposit(address indexed user, uint256 amount);
-    event Withdrawal(address indexed user, uint256 amount);
+    event Deposit(address indexed sender, address indexed owner, uint256 assets, uint256 shares);
+    event Withdraw(address indexed sender, address indexed receiver, address indexed owner, uint256 assets, uint256 shares);
+    event YieldReceived(address indexed sender, uint256 assets);
+    event WithdrawalRequested(address indexed owner, uint256 indexed request_id, uint256 shares, uint256 ready_at);
+    event WithdrawalCancelled(address indexed owner, uint256 indexed request_id, uint256 shares);
+    event WithdrawalDelayUpdated(uint256 delay);
+    event RateLimitsUpdated(uint256 capacity, uint256 refill_rate, uint256 daily_cap);
+    event DefaultWithdrawalCapUpdated(uint256 cap);
+    event UserWithdrawalCapUpdated(address indexed user, uint256 cap);
+    event UserWithdrawalCapCleared(address indexed user);
+    event GuardianUpdated(address indexed previous_guardian, address indexed new_guardian);
+    event EmergencyModeActivated(address indexed guardian, uint256 recoverable_at);
+    event EmergencyModeRecovered(address indexed owner);
     
     error InsufficientBalance();
     error WithdrawalFailed();
+    error IncorrectPayment();
+    error NoClaimableWithdrawal();
+    error UnknownWithdrawalRequest();
+    error UnauthorizedGuardian();
+    error EmergencyModeActive();
+    error EmergencyModeInactive();
+    error EmergencyRecoveryLocked();
+}
+
+sol! {
+    /// Guarded withdrawal entrypoints, whose selectors tag blocked re-entry attempts
+    interface IVaultWithdrawals {
+        function withdrawSafe(uint256 amount) external returns (uint256);
+        function redeem(uint256 shares) external returns (uint256);
+        function claimWithdrawal() external returns (uint256);
+        function emergencyWithdraw(uint256 shares) external returns (uint256);
+    }
 }
 
 /// Contract errors
+///
+/// Guard, ownership, pause and rate-limit errors reuse the library's `sol!`
+/// error structs, so the vault reverts with the same selectors.
 #[derive(SolidityError)]
 pub enum VaultError {
-    #[solidity(error = "InsufficientBalance()")]
-    InsufficientBalance,
-    
-    #[solidity(error = "WithdrawalFailed()")]
-    WithdrawalFailed,
-    
-    #[solidity(error = "ReentrancyGuardReentrantCall()")]
-    ReentrantCall,
+    InsufficientBalance(InsufficientBalance),
+    WithdrawalFailed(WithdrawalFailed),
+    IncorrectPayment(IncorrectPayment),
+    OwnableUnauthorizedAccount(OwnableUnauthorizedAccount),
+    OwnableInvalidOwner(OwnableInvalidOwner),
+    NoClaimableWithdrawal(NoClaimableWithdrawal),
+    UnknownWithdrawalRequest(UnknownWithdrawalRequest),
+    UnauthorizedGuardian(UnauthorizedGuardian),
+    EmergencyModeActive(EmergencyModeActive),
+    EmergencyModeInactive(EmergencyModeInactive),
+    EmergencyRecoveryLocked(EmergencyRecoveryLocked),
+    UserRateLimitExceeded(UserRateLimitExceeded),
+    GlobalRateLimitExceeded(GlobalRateLimitExceeded),
+    WithdrawalCapExceeded(WithdrawalCapExceeded),
+    EnforcedPause(EnforcedPause),
+    ExpectedPause(ExpectedPause),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall),
 }
 
 impl From<ReentrancyError> for VaultError {
     fn from(err: ReentrancyError) -> Self {
         match err {
-            ReentrancyError::ReentrantCall => VaultError::ReentrantCall,
+            ReentrancyError::ReentrantCall(e) => VaultError::ReentrantCall(e),
+            ReentrancyError::ReadOnlyReentrantCall(e) => VaultError::ReadOnlyReentrantCall(e),
+        }
+    }
+}
+
+impl From<RateLimitError> for VaultError {
+    fn from(err: RateLimitError) -> Self {
+        match err {
+            RateLimitError::UserRateLimitExceeded(e) => VaultError::UserRateLimitExceeded(e),
+            RateLimitError::GlobalRateLimitExceeded(e) => VaultError::GlobalRateLimitExceeded(e),
+        }
+    }
+}
+
+impl From<WithdrawalCapError> for VaultError {
+    fn from(err: WithdrawalCapError) -> Self {
+        match err {
+            WithdrawalCapError::CapExceeded(e) => VaultError::WithdrawalCapExceeded(e),
+        }
+    }
+}
+
+impl From<OwnableError> for VaultError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::UnauthorizedAccount(e) => VaultError::OwnableUnauthorizedAccount(e),
+            OwnableError::InvalidOwner(e) => VaultError::OwnableInvalidOwner(e),
+        }
+    }
+}
+
+impl From<PausableError> for VaultError {
+    fn from(err: PausableError) -> Self {
+        match err {
+            PausableError::EnforcedPause(e) => VaultError::EnforcedPause(e),
+            PausableError::ExpectedPause(e) => VaultError::ExpectedPause(e),
         }
     }
 }
 
+/// Rounding direction for share conversions
+#[derive(Clone, Copy)]
+enum Rounding {
+    Down,
+    Up,
+}
+
 /// Main contract storage
 #[entrypoint]
 #[storage]
 pub struct VaultContract {
     /// ReentrancyGuard for protection
     guard: ReentrancyGuard,
-    /// User balances
-    balances: StorageMap<Address, StorageU256>,
-    /// Total contract balance
-    total_balance: StorageU256,
+    /// Vault shares held by each user
+    shares: StorageMap<Address, StorageU256>,
+    /// Total vault shares in circulation
+    total_shares: StorageU256,
+    /// Total ETH managed by the vault
+    total_assets: StorageU256,
+    /// Two-step ownership of the vault's admin functions
+    ownership: Ownable2Step,
+    /// Two-phase withdrawals waiting out their delay
+    queue: WithdrawalQueue,
+    /// Per-user and global limits on immediate withdrawals
+    limits: RateLimiter,
+    /// Per-user caps on withdrawals per period, across all withdrawal paths
+    caps: WithdrawalCaps,
+    /// Emergency stop for deposits and withdrawals
+    pausable: Pausable,
+    /// Account allowed to put the vault into emergency mode
+    guardian: StorageAddress,
+    /// Whether only pro-rata emergency withdrawals are open
+    emergency: StorageBool,
+    /// Timestamp emergency mode was entered
+    emergency_since: StorageU256,
+    /// Re-entry attempts blocked during withdrawals
+    telemetry: ReentrancyTelemetry,
 }
 
 impl ReentrancyGuarded for VaultContract {
@@ -74,33 +198,66 @@ impl ReentrancyGuarded for VaultContract {
 impl VaultContract {
     /// Constructor - initializes the ReentrancyGuard
     #[constructor]
-    pub fn constructor(&mut self) {
+    pub fn constructor(&mut self) -> Result<(), VaultError> {
         self.guard.init();
-        self.total_balance.set(U256::ZERO);
+        self.total_assets.set(U256::ZERO);
+        self.ownership.init(self.vm().msg_sender())?;
+        self.queue.set_delay(U256::from(DEFAULT_WITHDRAWAL_DELAY));
+        self.caps.set_period(U256::from(WITHDRAWAL_CAP_PERIOD));
+        Ok(())
     }
 
-    /// Deposit ETH into the vault
+    /// Deposit ETH into the vault, minting shares to the caller
     /// 
-    /// This function is protected against reentrancy attacks.
+    /// This function is protected against reentrancy attacks by the
+    /// `#[non_reentrant]` attribute. Returns the shares minted.
     #[payable]
-    pub fn deposit(&mut self) -> Result<(), VaultError> {
-        let caller = msg::sender();
-        let amount = msg::value();
+    #[non_reentrant]
+    pub fn deposit(&mut self) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let assets = self.vm().msg_value();
+        let shares = self.to_shares(assets, Rounding::Down);
         
-        // Use reentrancy protection
-        self.with_non_reentrant(|contract| {
-            let current_balance = contract.balances.getter(caller).get();
-            let new_balance = current_balance + amount;
-            
-            contract.balances.setter(caller).set(new_balance);
-            contract.total_balance.set(contract.total_balance.get() + amount);
-            
-            // Emit deposit event
-            evm::log(Deposit {
-                user: caller,
-                amount,
-            });
-        })
+        self.mint_shares(caller, assets, shares);
+        Ok(shares)
+    }
+
+    /// Mint exactly `shares` to the caller, paying `preview_mint(shares)` in ETH
+    #[payable]
+    #[non_reentrant]
+    pub fn mint(&mut self, shares: U256) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let assets = self.to_assets(shares, Rounding::Up);
+        
+        if self.vm().msg_value() != assets {
+            return Err(VaultError::IncorrectPayment(IncorrectPayment {}));
+        }
+        
+        self.mint_shares(caller, assets, shares);
+        Ok(assets)
+    }
+
+    /// Add ETH to the vault without minting shares
+    ///
+    /// Stands in for strategy yield: it raises the value of every share.
+    #[payable]
+    #[non_reentrant]
+    pub fn donate(&mut self) -> Result<(), VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let assets = self.vm().msg_value();
+        self.total_assets.set(self.total_assets.get() + assets);
+        
+        log(self.vm(), YieldReceived {
+            sender: self.vm().msg_sender(),
+            assets,
+        });
+        
+        Ok(())
     }
 
     /// Withdraw ETH from the vault (VULNERABLE VERSION - for demonstration)
@@ -108,29 +265,33 @@ impl VaultContract {
     /// This version is intentionally vulnerable to show what happens without protection.
     /// DO NOT USE THIS IN PRODUCTION!
     pub fn withdraw_vulnerable(&mut self, amount: U256) -> Result<(), VaultError> {
-        let caller = msg::sender();
-        let balance = self.balances.getter(caller).get();
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        let balance = self.shares.getter(caller).get();
+        let shares = self.to_shares(amount, Rounding::Up);
         
-        if balance < amount {
-            return Err(VaultError::InsufficientBalance);
+        if balance < shares {
+            return Err(VaultError::InsufficientBalance(InsufficientBalance {}));
         }
         
         // Update balance AFTER external call - VULNERABLE!
-        let call_result = Call::new_in(self)
-            .value(amount)
-            .call(caller, &[]);
-        
-        if call_result.is_err() {
-            return Err(VaultError::WithdrawalFailed);
-        }
+        self.send_eth(caller, amount)
+            .map_err(|_| VaultError::WithdrawalFailed(WithdrawalFailed {}))?;
         
         // State changes after external call - vulnerable to reentrancy
-        self.balances.setter(caller).set(balance - amount);
-        self.total_balance.set(self.total_balance.get() - amount);
+        self.caps.consume(caller, amount, now)?;
+        self.shares.setter(caller).set(balance - shares);
+        self.total_shares.set(self.total_shares.get() - shares);
+        self.total_assets.set(self.total_assets.get() - amount);
         
-        evm::log(Withdrawal {
-            user: caller,
-            amount,
+        log(self.vm(), Withdraw {
+            sender: caller,
+            receiver: caller,
+            owner: caller,
+            assets: amount,
+            shares,
         });
         
         Ok(())
@@ -139,50 +300,467 @@ impl VaultContract {
     /// Withdraw ETH from the vault (SAFE VERSION with ReentrancyGuard)
     /// 
     /// This version uses the ReentrancyGuard to prevent reentrancy attacks.
-    pub fn withdraw_safe(&mut self, amount: U256) -> Result<(), VaultError> {
-        let caller = msg::sender();
+    /// Burns `preview_withdraw(amount)` shares from the caller, subject to the
+    /// rate limits and the caller's withdrawal cap. Returns the shares burned,
+    /// zero if the caller tried to re-enter (see `get_blocked_attempts`).
+    pub fn withdraw_safe(&mut self, amount: U256) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
         
         // Use reentrancy protection
-        self.with_non_reentrant(|contract| {
-            let balance = contract.balances.getter(caller).get();
+        self.with_non_reentrant_result(|contract| {
+            let shares = contract.to_shares(amount, Rounding::Up);
+            let selector = IVaultWithdrawals::withdrawSafeCall::SELECTOR;
+            let paid = contract.burn_and_send(caller, amount, shares, now, selector)?;
+            Ok(if paid { shares } else { U256::ZERO })
+        })
+    }
+
+    /// Burn `shares` from the caller and send them the ETH they are worth
+    ///
+    /// Returns the ETH sent, zero if the caller tried to re-enter.
+    pub fn redeem(&mut self, shares: U256) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        
+        self.with_non_reentrant_result(|contract| {
+            let assets = contract.to_assets(shares, Rounding::Down);
+            let paid = contract.burn_and_send(caller, assets, shares, now, IVaultWithdrawals::redeemCall::SELECTOR)?;
+            Ok(if paid { assets } else { U256::ZERO })
+        })
+    }
+
+    /// Escrow the shares for `amount` ETH and start the withdrawal delay
+    ///
+    /// Returns the request id. The escrowed shares keep earning yield until
+    /// they are claimed.
+    pub fn request_withdrawal(&mut self, amount: U256) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        
+        self.with_non_reentrant_result(|contract| {
+            let shares = contract.to_shares(amount, Rounding::Up);
+            let balance = contract.shares.getter(caller).get();
             
-            if balance < amount {
-                return Err(VaultError::InsufficientBalance);
+            if balance < shares {
+                return Err(VaultError::InsufficientBalance(InsufficientBalance {}));
             }
             
-            // Update state BEFORE external call - follows CEI pattern
-            contract.balances.setter(caller).set(balance - amount);
-            contract.total_balance.set(contract.total_balance.get() - amount);
+            contract.shares.setter(caller).set(balance - shares);
+            let request_id = contract.queue.enqueue(caller, shares, now);
+            let (_, _, ready_at) = contract.queue.request(request_id);
             
-            // External call after state changes
-            let call_result = Call::new_in(contract)
-                .value(amount)
-                .call(caller, &[]);
+            log(contract.vm(), WithdrawalRequested {
+                owner: caller,
+                request_id,
+                shares,
+                ready_at,
+            });
+            
+            Ok(request_id)
+        })
+    }
+
+    /// Pay out every matured withdrawal request of the caller
+    ///
+    /// Returns the ETH sent, priced at the current exchange rate. If the
+    /// caller tries to re-enter, nothing is sent and the claimed shares stay
+    /// with them, as if the requests were cancelled.
+    pub fn claim_withdrawal(&mut self) -> Result<U256, VaultError> {
+        self.pausable.when_not_paused()?;
+        self.when_not_emergency()?;
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
+        
+        self.with_non_reentrant_result(|contract| {
+            let request_ids = contract.queue.matured_ids(caller, now);
+            if request_ids.is_empty() {
+                return Err(VaultError::NoClaimableWithdrawal(NoClaimableWithdrawal {}));
+            }
             
-            if call_result.is_err() {
-                // Revert state changes if call fails
-                contract.balances.setter(caller).set(balance);
-                contract.total_balance.set(contract.total_balance.get() + amount);
-                return Err(VaultError::WithdrawalFailed);
+            let mut shares = U256::ZERO;
+            for request_id in request_ids {
+                shares += contract.queue.remove(caller, request_id).unwrap_or_default();
             }
             
-            evm::log(Withdrawal {
-                user: caller,
-                amount,
+            // Release the escrow so burn_and_send burns the shares as usual
+            let balance = contract.shares.getter(caller).get();
+            contract.shares.setter(caller).set(balance + shares);
+            
+            let assets = contract.to_assets(shares, Rounding::Down);
+            let selector = IVaultWithdrawals::claimWithdrawalCall::SELECTOR;
+            let paid = contract.burn_and_send(caller, assets, shares, now, selector)?;
+            Ok(if paid { assets } else { U256::ZERO })
+        })
+    }
+
+    /// Cancel one of the caller's open requests, returning its shares
+    pub fn cancel_withdrawal(&mut self, request_id: U256) -> Result<(), VaultError> {
+        let caller = self.vm().msg_sender();
+        
+        self.with_non_reentrant_result(|contract| {
+            let shares = contract
+                .queue
+                .remove(caller, request_id)
+                .ok_or(VaultError::UnknownWithdrawalRequest(UnknownWithdrawalRequest {}))?;
+            
+            let balance = contract.shares.getter(caller).get();
+            contract.shares.setter(caller).set(balance + shares);
+            
+            log(contract.vm(), WithdrawalCancelled {
+                owner: caller,
+                request_id,
+                shares,
             });
             
             Ok(())
-        })?
+        })
+    }
+
+    /// Set the delay for new withdrawal requests (owner only)
+    pub fn set_withdrawal_delay(&mut self, delay: U256) -> Result<(), VaultError> {
+        self.only_owner()?;
+        self.queue.set_delay(delay);
+        
+        log(self.vm(), WithdrawalDelayUpdated { delay });
+        Ok(())
+    }
+
+    /// Seconds between requesting and claiming a withdrawal
+    pub fn get_withdrawal_delay(&self) -> U256 {
+        self.queue.delay()
+    }
+
+    /// Ids of a user's open withdrawal requests
+    pub fn get_withdrawal_requests(&self, user: Address) -> Vec<U256> {
+        self.queue.pending_ids(user)
+    }
+
+    /// Ids of a user's withdrawal requests that can be claimed now
+    pub fn get_claimable_withdrawals(&self, user: Address) -> Vec<U256> {
+        self.queue
+            .matured_ids(user, U256::from(self.vm().block_timestamp()))
+    }
+
+    /// Withdrawal request details as `(owner, shares, ready_at)`
+    ///
+    /// `shares` is zero once the request is claimed or cancelled.
+    pub fn get_withdrawal_request(&self, request_id: U256) -> (Address, U256, U256) {
+        self.queue.request(request_id)
+    }
+
+    /// Set withdrawal rate limits (owner only)
+    ///
+    /// `capacity` and `refill_rate` (per second) shape each user's token
+    /// bucket; `daily_cap` bounds total withdrawals per day. Zero disables a
+    /// limit.
+    pub fn set_rate_limits(&mut self, capacity: U256, refill_rate: U256, daily_cap: U256) -> Result<(), VaultError> {
+        self.only_owner()?;
+        self.limits.configure(capacity, refill_rate, daily_cap);
+        
+        log(self.vm(), RateLimitsUpdated {
+            capacity,
+            refill_rate,
+            daily_cap,
+        });
+        Ok(())
+    }
+
+    /// Current rate limits as `(capacity, refill_rate, daily_cap)`
+    pub fn get_rate_limits(&self) -> (U256, U256, U256) {
+        self.limits.limits()
+    }
+
+    /// ETH `user` may still withdraw right now under both rate limits
+    pub fn remaining_allowance(&self, user: Address) -> U256 {
+        self.limits
+            .remaining_allowance(user, U256::from(self.vm().block_timestamp()))
+    }
+
+    /// Set the per-period withdrawal cap for users without an override (owner only)
+    ///
+    /// Zero disables the default cap.
+    pub fn set_default_cap(&mut self, cap: U256) -> Result<(), VaultError> {
+        self.only_owner()?;
+        self.caps.set_default_cap(cap);
+        
+        log(self.vm(), DefaultWithdrawalCapUpdated { cap });
+        Ok(())
+    }
+
+    /// Override the per-period withdrawal cap for `user` (owner only)
+    ///
+    /// Zero freezes the user's withdrawals; `U256::MAX` exempts them.
+    pub fn set_user_cap(&mut self, user: Address, cap: U256) -> Result<(), VaultError> {
+        self.only_owner()?;
+        self.caps.set_user_cap(user, cap);
+        
+        log(self.vm(), UserWithdrawalCapUpdated { user, cap });
+        Ok(())
+    }
+
+    /// Return `user` to the default withdrawal cap (owner only)
+    pub fn clear_user_cap(&mut self, user: Address) -> Result<(), VaultError> {
+        self.only_owner()?;
+        self.caps.clear_user_cap(user);
+        
+        log(self.vm(), UserWithdrawalCapCleared { user });
+        Ok(())
+    }
+
+    /// Per-period withdrawal cap for users without an override
+    pub fn get_default_cap(&self) -> U256 {
+        self.caps.default_cap()
+    }
+
+    /// Withdrawal cap that applies to `user` (`U256::MAX` if uncapped)
+    pub fn get_user_cap(&self, user: Address) -> U256 {
+        self.caps.cap_of(user)
+    }
+
+    /// ETH `user` may still withdraw in the current cap period
+    pub fn remaining_withdrawal_capacity(&self, user: Address) -> U256 {
+        self.caps
+            .remaining(user, U256::from(self.vm().block_timestamp()))
+    }
+
+    /// Set the account allowed to trigger emergency mode (owner only)
+    ///
+    /// `address(0)` leaves nobody able to trigger it.
+    pub fn set_guardian(&mut self, guardian: Address) -> Result<(), VaultError> {
+        self.only_owner()?;
+        let previous_guardian = self.guardian.get();
+        self.guardian.set(guardian);
+        
+        log(self.vm(), GuardianUpdated {
+            previous_guardian,
+            new_guardian: guardian,
+        });
+        Ok(())
+    }
+
+    /// Account allowed to trigger emergency mode
+    pub fn guardian(&self) -> Address {
+        self.guardian.get()
+    }
+
+    /// Enter emergency mode (guardian only)
+    ///
+    /// Blocks deposits and every normal withdrawal path; users can only exit
+    /// pro rata through `emergency_withdraw`. The owner can recover the vault
+    /// after `EMERGENCY_RECOVERY_DELAY`.
+    pub fn emergency_mode(&mut self) -> Result<(), VaultError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.guardian.get() || caller.is_zero() {
+            return Err(VaultError::UnauthorizedGuardian(UnauthorizedGuardian {}));
+        }
+        self.when_not_emergency()?;
+        
+        let now = U256::from(self.vm().block_timestamp());
+        self.emergency.set(true);
+        self.emergency_since.set(now);
+        
+        log(self.vm(), EmergencyModeActivated {
+            guardian: caller,
+            recoverable_at: now + U256::from(EMERGENCY_RECOVERY_DELAY),
+        });
+        Ok(())
+    }
+
+    /// Leave emergency mode once the recovery timelock has passed (owner only)
+    pub fn recover_from_emergency(&mut self) -> Result<(), VaultError> {
+        self.only_owner()?;
+        if !self.emergency.get() {
+            return Err(VaultError::EmergencyModeInactive(EmergencyModeInactive {}));
+        }
+        if U256::from(self.vm().block_timestamp()) < self.emergency_recoverable_at() {
+            return Err(VaultError::EmergencyRecoveryLocked(EmergencyRecoveryLocked {}));
+        }
+        
+        self.emergency.set(false);
+        self.emergency_since.set(U256::ZERO);
+        
+        log(self.vm(), EmergencyModeRecovered {
+            owner: self.vm().msg_sender(),
+        });
+        Ok(())
+    }
+
+    /// Check if the vault is in emergency mode
+    pub fn is_emergency_mode(&self) -> bool {
+        self.emergency.get()
+    }
+
+    /// Timestamp from which the owner can recover the vault, zero outside emergency mode
+    pub fn emergency_recoverable_at(&self) -> U256 {
+        if !self.emergency.get() {
+            return U256::ZERO;
+        }
+        self.emergency_since.get() + U256::from(EMERGENCY_RECOVERY_DELAY)
+    }
+
+    /// Halt deposits and withdrawals (owner only)
+    ///
+    /// Emergency withdrawals are not pausable, so users can always exit once
+    /// the guardian triggers emergency mode.
+    pub fn pause(&mut self) -> Result<(), VaultError> {
+        self.only_owner()?;
+        Ok(self.pausable.pause()?)
+    }
+
+    /// Resume deposits and withdrawals (owner only)
+    pub fn unpause(&mut self) -> Result<(), VaultError> {
+        self.only_owner()?;
+        Ok(self.pausable.unpause()?)
+    }
+
+    /// Check if the vault is paused
+    pub fn paused(&self) -> bool {
+        self.pausable.paused()
+    }
+
+    /// Get the vault owner
+    pub fn owner(&self) -> Address {
+        self.ownership.owner()
+    }
+
+    /// Get the nominated owner waiting to accept
+    pub fn pending_owner(&self) -> Address {
+        self.ownership.pending_owner()
+    }
+
+    /// Nominate a new owner, who must call `accept_ownership` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), VaultError> {
+        Ok(self.ownership.transfer_ownership(new_owner)?)
+    }
+
+    /// Accept a pending ownership transfer (nominated owner only)
+    pub fn accept_ownership(&mut self) -> Result<(), VaultError> {
+        Ok(self.ownership.accept_ownership()?)
     }
 
-    /// Get the balance of a user
+    /// Give up ownership, disabling every admin function (owner only)
+    pub fn renounce_ownership(&mut self) -> Result<(), VaultError> {
+        Ok(self.ownership.renounce_ownership()?)
+    }
+
+    /// Get the ETH value of a user's shares
     pub fn get_balance(&self, user: Address) -> U256 {
-        self.balances.getter(user).get()
+        self.to_assets(self.shares.getter(user).get(), Rounding::Down)
+    }
+
+    /// Get the total ETH managed by the vault
+    ///
+    /// Reverts while a protected function is executing, so integrators can't
+    /// price against a half-updated total (read-only reentrancy).
+    pub fn get_total_balance(&self) -> Result<U256, VaultError> {
+        self.total_assets()
+    }
+
+    /// ERC-4626: underlying asset, `address(0)` for native ETH
+    pub fn asset(&self) -> Address {
+        Address::ZERO
+    }
+
+    /// ERC-4626: total ETH managed by the vault
+    ///
+    /// Like every view below that prices shares, reverts while a protected
+    /// function is executing.
+    pub fn total_assets(&self) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.total_assets.get())
+    }
+
+    /// ERC-4626: total shares in circulation
+    pub fn total_supply(&self) -> U256 {
+        self.total_shares.get()
+    }
+
+    /// ERC-4626: shares held by `owner`
+    pub fn balance_of(&self, owner: Address) -> U256 {
+        self.shares.getter(owner).get()
+    }
+
+    /// ERC-4626: shares `assets` are worth at the current rate
+    pub fn convert_to_shares(&self, assets: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_shares(assets, Rounding::Down))
+    }
+
+    /// ERC-4626: ETH `shares` are worth at the current rate
+    pub fn convert_to_assets(&self, shares: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_assets(shares, Rounding::Down))
+    }
+
+    /// ERC-4626: maximum ETH `receiver` can deposit, zero while paused or in emergency mode
+    pub fn max_deposit(&self, _receiver: Address) -> U256 {
+        if self.pausable.paused() || self.emergency.get() {
+            return U256::ZERO;
+        }
+        U256::MAX
+    }
+
+    /// ERC-4626: maximum shares `receiver` can mint, zero while paused or in emergency mode
+    pub fn max_mint(&self, _receiver: Address) -> U256 {
+        if self.pausable.paused() || self.emergency.get() {
+            return U256::ZERO;
+        }
+        U256::MAX
+    }
+
+    /// ERC-4626: maximum ETH `owner` can withdraw, zero in emergency mode
+    pub fn max_withdraw(&self, owner: Address) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        if self.emergency.get() {
+            return Ok(U256::ZERO);
+        }
+        Ok(self.to_assets(self.shares.getter(owner).get(), Rounding::Down))
+    }
+
+    /// ERC-4626: maximum shares `owner` can redeem, zero in emergency mode
+    pub fn max_redeem(&self, owner: Address) -> U256 {
+        if self.emergency.get() {
+            return U256::ZERO;
+        }
+        self.shares.getter(owner).get()
+    }
+
+    /// ERC-4626: shares minted by depositing `assets`
+    pub fn preview_deposit(&self, assets: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_shares(assets, Rounding::Down))
     }
 
-    /// Get the total contract balance
-    pub fn get_total_balance(&self) -> U256 {
-        self.total_balance.get()
+    /// ERC-4626: ETH required to mint `shares`
+    pub fn preview_mint(&self, shares: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_assets(shares, Rounding::Up))
+    }
+
+    /// ERC-4626: shares burned by withdrawing `assets`
+    pub fn preview_withdraw(&self, assets: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_shares(assets, Rounding::Up))
+    }
+
+    /// ERC-4626: ETH received for redeeming `shares`
+    pub fn preview_redeem(&self, shares: U256) -> Result<U256, VaultError> {
+        self.guard.non_reentrant_view()?;
+        Ok(self.to_assets(shares, Rounding::Down))
+    }
+
+    /// Number of re-entry attempts the guard blocked during withdrawals
+    ///
+    /// Each one also emits `ReentrancyAttemptBlocked(selector, caller)`.
+    pub fn get_blocked_attempts(&self) -> U256 {
+        self.telemetry.blocked_attempts()
     }
 
     /// Check if the contract is currently in a reentrant call
@@ -192,58 +770,224 @@ impl VaultContract {
         self.guard.reentrancy_guard_entered()
     }
 
-    /// Emergency function that demonstrates manual reentrancy protection
+    /// Burn `shares` for their pro-rata cut of total assets (emergency mode only)
     /// 
-    /// This shows how to use the guard manually without the trait helper.
-    pub fn emergency_withdraw(&mut self, amount: U256) -> Result<(), VaultError> {
-        let caller = msg::sender();
-        
-        // Manual reentrancy protection
-        self.guard.non_reentrant_before()?;
+    /// Pays `shares * total_assets / total_supply` with no virtual offset, so
+    /// every holder exits at the same rate however the run unfolds. Not
+    /// pausable, but subject to the rate limits and the caller's withdrawal
+    /// cap like every other withdrawal path. Returns the ETH sent, zero if
+    /// the caller tried to re-enter.
+    ///
+    /// Demonstrates the RAII reentrancy lock: it is released when `vault` goes
+    /// out of scope, so an error from the withdrawal can't leave the guard
+    /// stuck in ENTERED.
+    pub fn emergency_withdraw(&mut self, shares: U256) -> Result<U256, VaultError> {
+        if !self.emergency.get() {
+            return Err(VaultError::EmergencyModeInactive(EmergencyModeInactive {}));
+        }
+        let caller = self.vm().msg_sender();
+        let now = U256::from(self.vm().block_timestamp());
         
-        let balance = self.balances.getter(caller).get();
+        let mut vault = self.lock()?;
         
-        if balance < amount {
-            self.guard.non_reentrant_after();
-            return Err(VaultError::InsufficientBalance);
+        if vault.shares.getter(caller).get() < shares {
+            return Err(VaultError::InsufficientBalance(InsufficientBalance {}));
+        }
+        if shares.is_zero() {
+            return Ok(U256::ZERO);
+        }
+        let assets = mul_div(
+            shares,
+            vault.total_assets.get(),
+            vault.total_shares.get(),
+            Rounding::Down,
+        );
+        let selector = IVaultWithdrawals::emergencyWithdrawCall::SELECTOR;
+        let paid = vault.burn_and_send(caller, assets, shares, now, selector)?;
+        Ok(if paid { assets } else { U256::ZERO })
+    }
+}
+
+impl VaultContract {
+    fn only_owner(&self) -> Result<(), VaultError> {
+        Ok(self.ownership.only_owner()?)
+    }
+
+    fn when_not_emergency(&self) -> Result<(), VaultError> {
+        if self.emergency.get() {
+            return Err(VaultError::EmergencyModeActive(EmergencyModeActive {}));
         }
+        Ok(())
+    }
+
+    /// Convert assets to shares with one virtual share and asset
+    ///
+    /// The virtual offset keeps the first depositor from inflating the share
+    /// price to round later deposits down to zero shares.
+    fn to_shares(&self, assets: U256, rounding: Rounding) -> U256 {
+        mul_div(
+            assets,
+            self.total_shares.get() + U256::from(1),
+            self.total_assets.get() + U256::from(1),
+            rounding,
+        )
+    }
+
+    /// Convert shares to assets with one virtual share and asset
+    fn to_assets(&self, shares: U256, rounding: Rounding) -> U256 {
+        mul_div(
+            shares,
+            self.total_assets.get() + U256::from(1),
+            self.total_shares.get() + U256::from(1),
+            rounding,
+        )
+    }
+
+    fn mint_shares(&mut self, owner: Address, assets: U256, shares: U256) {
+        let balance = self.shares.getter(owner).get();
+        self.shares.setter(owner).set(balance + shares);
+        self.total_shares.set(self.total_shares.get() + shares);
+        self.total_assets.set(self.total_assets.get() + assets);
         
-        // Update state before external call
-        self.balances.setter(caller).set(balance - amount);
-        self.total_balance.set(self.total_balance.get() - amount);
+        log(self.vm(), Deposit {
+            sender: owner,
+            owner,
+            assets,
+            shares,
+        });
+    }
+
+    /// Burn `shares` from `owner` and send them `assets` at `now`, following CEI
+    ///
+    /// Callers must hold the reentrancy guard. Returns whether `assets` were
+    /// sent: if `owner` tried to re-enter, the burn is undone and the attempt
+    /// recorded against `selector` instead. That path doesn't revert, so the
+    /// record survives; the rejected re-entry itself reverted. The rate limits
+    /// and the withdrawal cap are checked up front but only drawn down once
+    /// the ETH is sent, so a blocked attempt leaves them as they were.
+    fn burn_and_send(
+        &mut self,
+        owner: Address,
+        assets: U256,
+        shares: U256,
+        now: U256,
+        selector: Selector,
+    ) -> Result<bool, VaultError> {
+        self.check_limits(owner, assets, now)?;
+        let balance = self.shares.getter(owner).get();
         
-        // External call
-        let call_result = Call::new_in(self)
-            .value(amount)
-            .call(caller, &[]);
+        if balance < shares {
+            return Err(VaultError::InsufficientBalance(InsufficientBalance {}));
+        }
         
-        // Always clean up reentrancy guard
-        self.guard.non_reentrant_after();
+        // Update state BEFORE external call - follows CEI pattern
+        self.shares.setter(owner).set(balance - shares);
+        self.total_shares.set(self.total_shares.get() - shares);
+        self.total_assets.set(self.total_assets.get() - assets);
         
-        if call_result.is_err() {
-            // Revert state if call failed
-            self.balances.setter(caller).set(balance);
-            self.total_balance.set(self.total_balance.get() + amount);
-            return Err(VaultError::WithdrawalFailed);
+        // External call after state changes
+        if let Err(revert_data) = self.send_eth(owner, assets) {
+            // Revert state changes if call fails
+            self.shares.setter(owner).set(balance);
+            self.total_shares.set(self.total_shares.get() + shares);
+            self.total_assets.set(self.total_assets.get() + assets);
+            
+            if is_reentrant_call_revert(&revert_data) {
+                self.telemetry.record(selector, owner);
+                return Ok(false);
+            }
+            return Err(VaultError::WithdrawalFailed(WithdrawalFailed {}));
         }
         
-        evm::log(Withdrawal {
-            user: caller,
-            amount,
+        // Checked above, and the guard keeps anything else from drawing on them meanwhile
+        self.limits.consume(owner, assets, now)?;
+        self.caps.consume(owner, assets, now)?;
+        
+        log(self.vm(), Withdraw {
+            sender: owner,
+            receiver: owner,
+            owner,
+            assets,
+            shares,
         });
         
+        Ok(true)
+    }
+
+    /// Revert if withdrawing `assets` at `now` would break the rate limits or `owner`'s cap
+    ///
+    /// Fails like `limits.consume` and `caps.consume` would, without recording anything.
+    fn check_limits(&self, owner: Address, assets: U256, now: U256) -> Result<(), VaultError> {
+        if assets > self.limits.user_allowance(owner, now) {
+            return Err(VaultError::UserRateLimitExceeded(UserRateLimitExceeded {}));
+        }
+        if assets > self.limits.global_allowance(now) {
+            return Err(VaultError::GlobalRateLimitExceeded(GlobalRateLimitExceeded {}));
+        }
+        if assets > self.caps.remaining(owner, now) {
+            return Err(VaultError::WithdrawalCapExceeded(WithdrawalCapExceeded {}));
+        }
         Ok(())
     }
+
+    /// Send ETH to `to` with a plain call, returning the revert data if it fails
+    fn call_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        Call::new_in(self)
+            .value(amount)
+            .call(to, &[])
+            .map(|_| ())
+            .map_err(Vec::<u8>::from)
+    }
+}
+
+/// How the vault pays out a withdrawal
+///
+/// Every withdrawal path hands control to external code through here. The
+/// vault makes a plain call; the attack tests supply their own impl, since
+/// the TestVM can't run a recipient's code.
+trait SendEth {
+    /// Send `amount` to `to`, returning the revert data if it fails
+    fn send_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>>;
+}
+
+#[cfg(not(test))]
+impl SendEth for VaultContract {
+    fn send_eth(&mut self, to: Address, amount: U256) -> Result<(), Vec<u8>> {
+        self.call_eth(to, amount)
+    }
+}
+
+/// `a * b / denominator` with the given rounding
+fn mul_div(a: U256, b: U256, denominator: U256, rounding: Rounding) -> U256 {
+    let product = a * b;
+    let quotient = product / denominator;
+    match rounding {
+        Rounding::Up if !(product % denominator).is_zero() => quotient + U256::from(1),
+        _ => quotient,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use stylus_sdk::testing::*;
+
+    pub(crate) fn owner() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    /// Deploy the vault from `owner()`
+    pub(crate) fn deploy(vm: &TestVM) -> VaultContract {
+        vm.set_sender(owner());
+        let mut contract = VaultContract::from(vm);
+        assert!(contract.constructor().is_ok());
+        contract
+    }
 
     #[test]
     fn test_deposit() {
-        let mut contract = VaultContract::default();
-        contract.constructor();
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
         
         // Simulate a deposit (in real tests, you'd use stylus test helpers)
         let user = Address::ZERO;
@@ -255,8 +999,8 @@ mod tests {
 
     #[test]
     fn test_reentrancy_protection() {
-        let mut contract = VaultContract::default();
-        contract.constructor();
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
         
         // Verify guard is initialized
         assert!(!contract.is_reentrancy_guard_entered());
@@ -272,4 +1016,428 @@ mod tests {
         contract.guard.non_reentrant_after();
         assert!(!contract.is_reentrancy_guard_entered());
     }
+
+    #[test]
+    fn test_share_accounting() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let alice = Address::from([1u8; 20]);
+        let bob = Address::from([2u8; 20]);
+
+        // First deposit mints 1:1
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert_eq!(contract.deposit().ok(), Some(U256::from(1000)));
+
+        // Yield doubles the value of every share
+        vm.set_value(U256::from(1000));
+        assert!(contract.donate().is_ok());
+        assert_eq!(contract.get_balance(alice), U256::from(1999));
+
+        // Later depositors get fewer shares for the same ETH
+        vm.set_sender(bob);
+        assert_eq!(contract.deposit().ok(), Some(U256::from(500)));
+        assert_eq!(contract.total_supply(), U256::from(1500));
+        assert_eq!(contract.total_assets().ok(), Some(U256::from(3000)));
+
+        // Previews round in the vault's favour
+        assert_eq!(contract.preview_redeem(U256::from(1)).ok(), Some(U256::from(1)));
+        assert_eq!(contract.preview_mint(U256::from(1)).ok(), Some(U256::from(2)));
+        assert_eq!(contract.preview_withdraw(U256::from(1)).ok(), Some(U256::from(1)));
+
+        // Mint requires exactly the previewed payment
+        vm.set_value(U256::from(1));
+        assert!(matches!(contract.mint(U256::from(1)), Err(VaultError::IncorrectPayment(_))));
+        vm.set_value(U256::from(2));
+        assert_eq!(contract.mint(U256::from(1)).ok(), Some(U256::from(2)));
+        assert_eq!(contract.balance_of(bob), U256::from(501));
+    }
+
+    #[test]
+    fn test_withdrawal_queue() {
+        let vm = TestVM::default();
+        let owner = owner();
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        vm.set_sender(owner);
+        assert_eq!(contract.get_withdrawal_delay(), U256::from(DEFAULT_WITHDRAWAL_DELAY));
+
+        // Only the owner configures the delay
+        assert!(contract.set_withdrawal_delay(U256::from(100)).is_ok());
+        vm.set_sender(alice);
+        assert!(matches!(
+            contract.set_withdrawal_delay(U256::ZERO),
+            Err(VaultError::OwnableUnauthorizedAccount(_))
+        ));
+
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+
+        // Requesting escrows the shares
+        vm.set_block_timestamp(1000);
+        let first = contract.request_withdrawal(U256::from(300)).ok().unwrap();
+        let second = contract.request_withdrawal(U256::from(200)).ok().unwrap();
+        assert_eq!(contract.balance_of(alice), U256::from(500));
+        assert_eq!(contract.get_withdrawal_requests(alice), vec![first, second]);
+        assert!(matches!(
+            contract.request_withdrawal(U256::from(501)),
+            Err(VaultError::InsufficientBalance(_))
+        ));
+
+        // Nothing can be claimed before the delay elapses
+        assert!(matches!(contract.claim_withdrawal(), Err(VaultError::NoClaimableWithdrawal(_))));
+
+        // Cancelling returns the shares
+        assert!(contract.cancel_withdrawal(second).is_ok());
+        assert_eq!(contract.balance_of(alice), U256::from(700));
+        assert!(matches!(
+            contract.cancel_withdrawal(second),
+            Err(VaultError::UnknownWithdrawalRequest(_))
+        ));
+
+        vm.set_block_timestamp(1100);
+        assert_eq!(contract.get_claimable_withdrawals(alice), vec![first]);
+
+        vm.mock_call(alice, vec![], U256::from(300), Ok(vec![]));
+        assert_eq!(contract.claim_withdrawal().ok(), Some(U256::from(300)));
+        assert_eq!(contract.total_assets().ok(), Some(U256::from(700)));
+        assert!(contract.get_withdrawal_requests(alice).is_empty());
+        assert_eq!(contract.total_supply(), U256::from(700));
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let vm = TestVM::default();
+        let owner = owner();
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        vm.set_sender(owner);
+        assert!(contract
+            .set_rate_limits(U256::from(100), U256::from(1), U256::from(1000))
+            .is_ok());
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            contract.set_rate_limits(U256::ZERO, U256::ZERO, U256::ZERO),
+            Err(VaultError::OwnableUnauthorizedAccount(_))
+        ));
+
+        vm.set_block_timestamp(1000);
+        vm.mock_call(alice, vec![], U256::from(60), Ok(vec![]));
+        vm.mock_call(alice, vec![], U256::from(40), Ok(vec![]));
+        assert!(contract.withdraw_safe(U256::from(60)).is_ok());
+        assert_eq!(contract.remaining_allowance(alice), U256::from(40));
+
+        assert!(matches!(
+            contract.withdraw_safe(U256::from(41)),
+            Err(VaultError::UserRateLimitExceeded(_))
+        ));
+        assert!(!contract.is_reentrancy_guard_entered());
+        assert!(contract.withdraw_safe(U256::from(40)).is_ok());
+
+        vm.set_block_timestamp(1030);
+        assert_eq!(contract.remaining_allowance(alice), U256::from(30));
+    }
+
+    #[test]
+    fn test_rate_limits_cover_redeem_and_queued_claims() {
+        let vm = TestVM::default();
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        assert!(contract.set_withdrawal_delay(U256::from(100)).is_ok());
+        assert!(contract
+            .set_rate_limits(U256::from(100), U256::ZERO, U256::from(1000))
+            .is_ok());
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+        vm.set_block_timestamp(1000);
+        vm.mock_call(alice, vec![], U256::from(80), Ok(vec![]));
+
+        // Redeeming drains the same bucket as withdraw_safe
+        assert_eq!(contract.redeem(U256::from(80)).ok(), Some(U256::from(80)));
+        assert_eq!(contract.remaining_allowance(alice), U256::from(20));
+        assert!(matches!(
+            contract.redeem(U256::from(21)),
+            Err(VaultError::UserRateLimitExceeded(_))
+        ));
+
+        // So does claiming a queued withdrawal once its delay is over
+        assert!(contract.request_withdrawal(U256::from(50)).is_ok());
+        vm.set_block_timestamp(1100);
+        assert!(matches!(
+            contract.claim_withdrawal(),
+            Err(VaultError::UserRateLimitExceeded(_))
+        ));
+        assert!(!contract.is_reentrancy_guard_entered());
+        assert_eq!(contract.total_assets().ok(), Some(U256::from(920)));
+    }
+
+    #[test]
+    fn test_withdrawal_caps() {
+        let vm = TestVM::default();
+        let owner = owner();
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        vm.set_sender(owner);
+        assert!(contract.set_default_cap(U256::from(100)).is_ok());
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+        assert!(matches!(
+            contract.set_user_cap(alice, U256::MAX),
+            Err(VaultError::OwnableUnauthorizedAccount(_))
+        ));
+
+        let day = WITHDRAWAL_CAP_PERIOD;
+        vm.set_block_timestamp(day * 2);
+        vm.mock_call(alice, vec![], U256::from(60), Ok(vec![]));
+        vm.mock_call(alice, vec![], U256::from(40), Ok(vec![]));
+        vm.mock_call(alice, vec![], U256::from(500), Ok(vec![]));
+        assert!(contract.withdraw_safe(U256::from(60)).is_ok());
+        assert_eq!(contract.remaining_withdrawal_capacity(alice), U256::from(40));
+
+        // Every withdrawal path draws from the same cap
+        assert!(matches!(
+            contract.redeem(U256::from(50)),
+            Err(VaultError::WithdrawalCapExceeded(_))
+        ));
+        assert!(matches!(
+            contract.withdraw_vulnerable(U256::from(41)),
+            Err(VaultError::WithdrawalCapExceeded(_))
+        ));
+        assert!(contract.redeem(U256::from(40)).is_ok());
+        assert_eq!(contract.remaining_withdrawal_capacity(alice), U256::ZERO);
+
+        // The cap resets each period
+        vm.set_block_timestamp(day * 3);
+        assert_eq!(contract.remaining_withdrawal_capacity(alice), U256::from(100));
+
+        // An owner override lifts it for one user
+        vm.set_sender(owner);
+        assert!(contract.set_user_cap(alice, U256::MAX).is_ok());
+        assert_eq!(contract.get_user_cap(alice), U256::MAX);
+        vm.set_sender(alice);
+        assert!(contract.withdraw_safe(U256::from(500)).is_ok());
+
+        vm.set_sender(owner);
+        assert!(contract.clear_user_cap(alice).is_ok());
+        assert_eq!(contract.get_user_cap(alice), U256::from(100));
+    }
+
+    #[test]
+    fn test_pause() {
+        let vm = TestVM::default();
+        let owner = owner();
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        vm.set_sender(owner);
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(100));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+        assert!(matches!(contract.pause(), Err(VaultError::OwnableUnauthorizedAccount(_))));
+
+        vm.set_sender(owner);
+        assert!(contract.pause().is_ok());
+        assert!(contract.paused());
+        assert!(matches!(contract.pause(), Err(VaultError::EnforcedPause(_))));
+        assert_eq!(contract.max_deposit(alice), U256::ZERO);
+
+        vm.set_sender(alice);
+        assert!(matches!(contract.deposit(), Err(VaultError::EnforcedPause(_))));
+        assert!(matches!(contract.withdraw_safe(U256::from(10)), Err(VaultError::EnforcedPause(_))));
+        assert!(matches!(contract.redeem(U256::from(10)), Err(VaultError::EnforcedPause(_))));
+        assert!(!contract.is_reentrancy_guard_entered());
+
+        vm.set_sender(owner);
+        assert!(contract.unpause().is_ok());
+        assert!(matches!(contract.unpause(), Err(VaultError::ExpectedPause(_))));
+        vm.set_sender(alice);
+        vm.mock_call(alice, vec![], U256::from(10), Ok(vec![]));
+        assert!(contract.withdraw_safe(U256::from(10)).is_ok());
+    }
+
+    #[test]
+    fn test_emergency_mode() {
+        let vm = TestVM::default();
+        let owner = owner();
+        let guardian = Address::from([3u8; 20]);
+        let alice = Address::from([1u8; 20]);
+        let bob = Address::from([2u8; 20]);
+        let mut contract = deploy(&vm);
+        assert!(contract.set_guardian(guardian).is_ok());
+        assert_eq!(contract.guardian(), guardian);
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_sender(bob);
+        vm.set_value(U256::from(3000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+
+        // Emergency withdrawals are closed outside emergency mode
+        assert!(matches!(
+            contract.emergency_withdraw(U256::from(10)),
+            Err(VaultError::EmergencyModeInactive(_))
+        ));
+
+        // Only the guardian can trigger it, not even the owner
+        assert!(matches!(contract.emergency_mode(), Err(VaultError::UnauthorizedGuardian(_))));
+        vm.set_sender(owner);
+        assert!(matches!(contract.emergency_mode(), Err(VaultError::UnauthorizedGuardian(_))));
+
+        vm.set_block_timestamp(1000);
+        vm.set_sender(guardian);
+        assert!(contract.emergency_mode().is_ok());
+        assert!(contract.is_emergency_mode());
+        assert_eq!(
+            contract.emergency_recoverable_at(),
+            U256::from(1000 + EMERGENCY_RECOVERY_DELAY)
+        );
+        assert!(matches!(contract.emergency_mode(), Err(VaultError::EmergencyModeActive(_))));
+
+        // Deposits and normal withdrawals are blocked
+        vm.set_sender(alice);
+        vm.set_value(U256::from(100));
+        assert!(matches!(contract.deposit(), Err(VaultError::EmergencyModeActive(_))));
+        vm.set_value(U256::ZERO);
+        assert!(matches!(contract.withdraw_safe(U256::from(10)), Err(VaultError::EmergencyModeActive(_))));
+        assert!(matches!(contract.redeem(U256::from(10)), Err(VaultError::EmergencyModeActive(_))));
+        assert!(matches!(
+            contract.request_withdrawal(U256::from(10)),
+            Err(VaultError::EmergencyModeActive(_))
+        ));
+        assert_eq!(contract.max_deposit(alice), U256::ZERO);
+        assert_eq!(contract.max_redeem(alice), U256::ZERO);
+
+        // Exits are pro rata, even while paused
+        vm.set_sender(owner);
+        assert!(contract.pause().is_ok());
+        vm.set_sender(alice);
+        vm.mock_call(alice, vec![], U256::from(500), Ok(vec![]));
+        vm.mock_call(alice, vec![], U256::from(100), Ok(vec![]));
+        assert!(matches!(
+            contract.emergency_withdraw(U256::from(1001)),
+            Err(VaultError::InsufficientBalance(_))
+        ));
+        assert_eq!(contract.emergency_withdraw(U256::from(500)).ok(), Some(U256::from(500)));
+        assert_eq!(contract.balance_of(alice), U256::from(500));
+        assert_eq!(contract.total_assets().ok(), Some(U256::from(3500)));
+
+        // Recovery waits out the timelock
+        vm.set_sender(owner);
+        assert!(contract.unpause().is_ok());
+        vm.set_block_timestamp(1000 + EMERGENCY_RECOVERY_DELAY - 1);
+        assert!(matches!(
+            contract.recover_from_emergency(),
+            Err(VaultError::EmergencyRecoveryLocked(_))
+        ));
+        vm.set_sender(guardian);
+        assert!(matches!(
+            contract.recover_from_emergency(),
+            Err(VaultError::OwnableUnauthorizedAccount(_))
+        ));
+
+        vm.set_block_timestamp(1000 + EMERGENCY_RECOVERY_DELAY);
+        vm.set_sender(owner);
+        assert!(contract.recover_from_emergency().is_ok());
+        assert!(!contract.is_emergency_mode());
+        assert_eq!(contract.emergency_recoverable_at(), U256::ZERO);
+        assert!(matches!(
+            contract.recover_from_emergency(),
+            Err(VaultError::EmergencyModeInactive(_))
+        ));
+
+        vm.set_sender(alice);
+        assert!(contract.withdraw_safe(U256::from(100)).is_ok());
+    }
+
+    #[test]
+    fn test_emergency_withdraw_respects_limits() {
+        let vm = TestVM::default();
+        let guardian = Address::from([3u8; 20]);
+        let alice = Address::from([1u8; 20]);
+        let mut contract = deploy(&vm);
+        assert!(contract.set_guardian(guardian).is_ok());
+        assert!(contract.set_rate_limits(U256::from(100), U256::ZERO, U256::ZERO).is_ok());
+        assert!(contract.set_default_cap(U256::from(200)).is_ok());
+
+        vm.set_sender(alice);
+        vm.set_value(U256::from(1000));
+        assert!(contract.deposit().is_ok());
+        vm.set_value(U256::ZERO);
+        vm.set_block_timestamp(1000);
+        vm.set_sender(guardian);
+        assert!(contract.emergency_mode().is_ok());
+
+        // Emergency exits draw from the same bucket and cap as normal withdrawals
+        vm.set_sender(alice);
+        assert!(matches!(
+            contract.emergency_withdraw(U256::from(150)),
+            Err(VaultError::UserRateLimitExceeded(_))
+        ));
+        vm.set_sender(owner());
+        assert!(contract.set_rate_limits(U256::from(1000), U256::ZERO, U256::ZERO).is_ok());
+        vm.set_sender(alice);
+        assert!(matches!(
+            contract.emergency_withdraw(U256::from(201)),
+            Err(VaultError::WithdrawalCapExceeded(_))
+        ));
+        assert!(!contract.is_reentrancy_guard_entered());
+
+        vm.mock_call(alice, vec![], U256::from(200), Ok(vec![]));
+        assert_eq!(contract.emergency_withdraw(U256::from(200)).ok(), Some(U256::from(200)));
+        assert_eq!(contract.remaining_withdrawal_capacity(alice), U256::ZERO);
+    }
+
+    #[test]
+    fn test_ownership_transfer() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        let alice = Address::from([1u8; 20]);
+        assert_eq!(contract.owner(), owner());
+
+        assert!(contract.transfer_ownership(alice).is_ok());
+        assert_eq!(contract.pending_owner(), alice);
+        assert!(contract.set_withdrawal_delay(U256::from(1)).is_ok());
+
+        vm.set_sender(alice);
+        assert!(contract.accept_ownership().is_ok());
+        assert!(contract.set_withdrawal_delay(U256::from(2)).is_ok());
+
+        // The previous owner lost the admin functions
+        vm.set_sender(owner());
+        assert!(matches!(
+            contract.set_withdrawal_delay(U256::from(3)),
+            Err(VaultError::OwnableUnauthorizedAccount(_))
+        ));
+    }
+
+    #[test]
+    fn test_price_views_block_read_only_reentrancy() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        contract.guard.non_reentrant_before().unwrap();
+
+        assert!(matches!(contract.total_assets(), Err(VaultError::ReadOnlyReentrantCall(_))));
+        assert!(matches!(
+            contract.convert_to_assets(U256::from(1)),
+            Err(VaultError::ReadOnlyReentrantCall(_))
+        ));
+        assert!(matches!(
+            contract.preview_withdraw(U256::from(1)),
+            Err(VaultError::ReadOnlyReentrantCall(_))
+        ));
+    }
 }
\ No newline at end of file
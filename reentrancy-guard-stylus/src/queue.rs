@@ -0,0 +1,150 @@
+//! Two-phase withdrawal queue for the vault
+//!
+//! `request_withdrawal` escrows shares under a request that matures after the
+//! configured delay; `claim_withdrawal` pays out every matured request. The
+//! delay gives large treasuries a reaction window even though each step is
+//! already reentrancy protected. This module only keeps the bookkeeping; the
+//! vault moves shares and ETH.
+
+use alloc::vec::Vec;
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{
+    prelude::*,
+    storage::{StorageAddress, StorageMap, StorageU256, StorageVec},
+};
+
+/// Storage structure for a single withdrawal request
+#[storage]
+pub struct WithdrawalRequest {
+    /// Account that made the request
+    owner: StorageAddress,
+    /// Shares held in escrow for the request (zero once claimed or cancelled)
+    shares: StorageU256,
+    /// Timestamp from which the request can be claimed
+    ready_at: StorageU256,
+}
+
+/// Storage structure for the withdrawal queue
+#[storage]
+pub struct WithdrawalQueue {
+    /// Seconds between requesting and claiming a withdrawal
+    delay: StorageU256,
+    /// Id assigned to the next request
+    next_id: StorageU256,
+    /// Requests by id
+    requests: StorageMap<U256, WithdrawalRequest>,
+    /// Ids of each account's open requests
+    pending: StorageMap<Address, StorageVec<StorageU256>>,
+}
+
+impl WithdrawalQueue {
+    /// Seconds between requesting and claiming a withdrawal
+    pub fn delay(&self) -> U256 {
+        self.delay.get()
+    }
+
+    /// Set the delay applied to new requests
+    pub fn set_delay(&mut self, delay: U256) {
+        self.delay.set(delay);
+    }
+
+    /// Open a request for `shares`, returning its id
+    pub fn enqueue(&mut self, owner: Address, shares: U256, now: U256) -> U256 {
+        let id = self.next_id.get() + U256::from(1);
+        self.next_id.set(id);
+
+        let mut request = self.requests.setter(id);
+        request.owner.set(owner);
+        request.shares.set(shares);
+        request.ready_at.set(now + self.delay.get());
+
+        self.pending.setter(owner).push(id);
+        id
+    }
+
+    /// Request details as `(owner, shares, ready_at)`
+    pub fn request(&self, id: U256) -> (Address, U256, U256) {
+        let request = self.requests.getter(id);
+        (request.owner.get(), request.shares.get(), request.ready_at.get())
+    }
+
+    /// Ids of `owner`'s open requests
+    pub fn pending_ids(&self, owner: Address) -> Vec<U256> {
+        let ids = self.pending.getter(owner);
+        (0..ids.len()).filter_map(|i| ids.get(i)).collect()
+    }
+
+    /// Ids of `owner`'s requests that can be claimed at `now`
+    pub fn matured_ids(&self, owner: Address, now: U256) -> Vec<U256> {
+        self.pending_ids(owner)
+            .into_iter()
+            .filter(|id| self.requests.getter(*id).ready_at.get() <= now)
+            .collect()
+    }
+
+    /// Close `owner`'s request `id`, returning the escrowed shares
+    ///
+    /// Returns `None` if `id` isn't an open request of `owner`.
+    pub fn remove(&mut self, owner: Address, id: U256) -> Option<U256> {
+        let mut ids = self.pending.setter(owner);
+        let index = (0..ids.len()).find(|i| ids.get(*i) == Some(id))?;
+
+        // Swap-remove from the account's open requests
+        let last = ids.len() - 1;
+        if index != last {
+            let moved = ids.get(last)?;
+            ids.setter(index)?.set(moved);
+        }
+        ids.pop();
+
+        let mut request = self.requests.setter(id);
+        let shares = request.shares.get();
+        request.shares.set(U256::ZERO);
+        Some(shares)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        queue: WithdrawalQueue,
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    #[test]
+    fn test_requests_mature_after_delay() {
+        let mut contract = TestContract::default();
+        contract.queue.set_delay(U256::from(100));
+
+        let first = contract.queue.enqueue(alice(), U256::from(10), U256::from(1000));
+        let second = contract.queue.enqueue(alice(), U256::from(20), U256::from(1050));
+        assert_eq!(contract.queue.request(first), (alice(), U256::from(10), U256::from(1100)));
+        assert_eq!(contract.queue.pending_ids(alice()), vec![first, second]);
+
+        assert!(contract.queue.matured_ids(alice(), U256::from(1099)).is_empty());
+        assert_eq!(contract.queue.matured_ids(alice(), U256::from(1100)), vec![first]);
+        assert_eq!(contract.queue.matured_ids(alice(), U256::from(1150)), vec![first, second]);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut contract = TestContract::default();
+
+        let first = contract.queue.enqueue(alice(), U256::from(10), U256::ZERO);
+        let second = contract.queue.enqueue(alice(), U256::from(20), U256::ZERO);
+
+        assert_eq!(contract.queue.remove(alice(), first), Some(U256::from(10)));
+        assert_eq!(contract.queue.pending_ids(alice()), vec![second]);
+        assert_eq!(contract.queue.request(first).1, U256::ZERO);
+
+        // Already removed, or owned by someone else
+        assert_eq!(contract.queue.remove(alice(), first), None);
+        assert_eq!(contract.queue.remove(Address::ZERO, second), None);
+    }
+}
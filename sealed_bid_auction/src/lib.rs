@@ -0,0 +1,848 @@
+//!
+//! Sealed-Bid Auction Contract
+//!
+//! A Vickrey (second-price) auction of one escrowed NFT, run in two phases.
+//! During the commit phase bidders submit only the hash of their bid and a
+//! secret salt, along with a deposit that covers the bid and may exceed it to
+//! hide its size. During the reveal phase they open their commitment. The
+//! highest revealed bid wins, but pays the second-highest bid (or the reserve
+//! price if that is higher), so bidding one's true value is the best strategy.
+//!
+//! Deposits are never pushed: after settlement losers, bidders who didn't
+//! reveal, the winner's change and the seller's proceeds are all pulled with
+//! `claim`.
+
+// Allow `cargo stylus export-abi` to generate a main function.
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+use stylus_common::{Initializable, InitializableError, InvalidInitialization};
+use stylus_reentrancy_guard::{
+    non_reentrant, safe_transfer_eth, GasStipend, ReentrancyError, ReentrancyGuard, ReentrancyGuardReentrantCall,
+    ReentrancyGuarded, SafeTransferError, SafeTransferEthRejected, SafeTransferEthReverted,
+    SafeTransferInsufficientBalance,
+};
+use stylus_sdk::{
+    alloy_primitives::{keccak256, Address, B256, U256},
+    alloy_sol_types::sol,
+    call::Call,
+    prelude::*,
+};
+
+// ERC721 interface for NFT transfers
+sol_interface! {
+    interface IERC721 {
+        function transferFrom(address from, address to, uint256 tokenId) external;
+        function ownerOf(uint256 tokenId) external view returns (address);
+    }
+}
+
+// Events and custom error types
+sol! {
+    event AuctionCreated(address indexed seller, address indexed nftContract, uint256 indexed tokenId, uint256 reservePrice, uint256 commitEnd, uint256 revealEnd);
+    event BidCommitted(address indexed bidder, bytes32 commitment, uint256 deposit);
+    event BidRevealed(address indexed bidder, uint256 amount);
+    event AuctionSettled(address indexed winner, uint256 price);
+    event AuctionCancelled();
+    event Claimed(address indexed account, uint256 amount);
+
+    error NotCommitPhase();
+    error NotRevealPhase();
+    error AuctionNotEnded();
+    error AuctionNotSettled();
+    error AuctionAlreadySettled();
+    error OnlySeller();
+    error ZeroAddress();
+    error InvalidDuration();
+    error SellerCannotBid();
+    error AlreadyCommitted();
+    error NoDeposit();
+    error NoCommitment();
+    error InvalidCommitment();
+    error AlreadyRevealed();
+    error InvalidReveal();
+    error InsufficientDeposit(uint256 deposit, uint256 amount);
+    error AuctionHasBids();
+    error NothingToClaim();
+    error NFTTransferFailed();
+    error NotNFTOwner();
+}
+
+#[derive(SolidityError)]
+pub enum SealedBidAuctionError {
+    NotCommitPhase(NotCommitPhase),
+    NotRevealPhase(NotRevealPhase),
+    AuctionNotEnded(AuctionNotEnded),
+    AuctionNotSettled(AuctionNotSettled),
+    AuctionAlreadySettled(AuctionAlreadySettled),
+    OnlySeller(OnlySeller),
+    ZeroAddress(ZeroAddress),
+    InvalidDuration(InvalidDuration),
+    SellerCannotBid(SellerCannotBid),
+    AlreadyCommitted(AlreadyCommitted),
+    NoDeposit(NoDeposit),
+    NoCommitment(NoCommitment),
+    InvalidCommitment(InvalidCommitment),
+    AlreadyRevealed(AlreadyRevealed),
+    InvalidReveal(InvalidReveal),
+    InsufficientDeposit(InsufficientDeposit),
+    AuctionHasBids(AuctionHasBids),
+    NothingToClaim(NothingToClaim),
+    NFTTransferFailed(NFTTransferFailed),
+    NotNFTOwner(NotNFTOwner),
+    InvalidInitialization(InvalidInitialization),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    InsufficientBalance(SafeTransferInsufficientBalance),
+    EthRejected(SafeTransferEthRejected),
+    EthReverted(SafeTransferEthReverted),
+}
+
+impl From<InitializableError> for SealedBidAuctionError {
+    fn from(err: InitializableError) -> Self {
+        match err {
+            InitializableError::InvalidInitialization(e) => SealedBidAuctionError::InvalidInitialization(e),
+        }
+    }
+}
+
+impl From<ReentrancyError> for SealedBidAuctionError {
+    fn from(err: ReentrancyError) -> Self {
+        match err {
+            ReentrancyError::ReentrantCall(e) => SealedBidAuctionError::ReentrantCall(e),
+            ReentrancyError::ReadOnlyReentrantCall(_) => {
+                SealedBidAuctionError::ReentrantCall(ReentrancyGuardReentrantCall {})
+            }
+        }
+    }
+}
+
+impl From<SafeTransferError> for SealedBidAuctionError {
+    fn from(err: SafeTransferError) -> Self {
+        match err {
+            SafeTransferError::InsufficientBalance(e) => SealedBidAuctionError::InsufficientBalance(e),
+            SafeTransferError::EthRejected(e) => SealedBidAuctionError::EthRejected(e),
+            SafeTransferError::EthReverted(e) => SealedBidAuctionError::EthReverted(e),
+            SafeTransferError::Erc20FailedOperation(_) => {
+                SealedBidAuctionError::InsufficientBalance(SafeTransferInsufficientBalance {})
+            }
+        }
+    }
+}
+
+/// Commitment to bidding `amount` on `auction`, hidden by `salt`
+///
+/// `keccak256(abi.encodePacked(auction, bidder, amount, salt))`. Binding the
+/// auction and bidder stops anyone replaying another bidder's commitment.
+pub fn bid_commitment(auction: Address, bidder: Address, amount: U256, salt: B256) -> B256 {
+    let mut data = Vec::with_capacity(104);
+    data.extend_from_slice(auction.as_slice());
+    data.extend_from_slice(bidder.as_slice());
+    data.extend_from_slice(&amount.to_be_bytes::<32>());
+    data.extend_from_slice(salt.as_slice());
+    keccak256(data)
+}
+
+sol_storage! {
+    #[entrypoint]
+    pub struct SealedBidAuction {
+        address seller;
+        address nft_contract;
+        uint256 token_id;
+        uint256 reserve_price;
+        uint256 commit_end;
+        uint256 reveal_end;
+        mapping(address => bytes32) commitments;
+        mapping(address => bool) revealed;
+        /// ETH each account can claim once the auction is settled: bidders'
+        /// deposits, less the price for the winner, and the seller's proceeds
+        mapping(address => uint256) deposits;
+        uint256 commitment_count;
+        address highest_bidder;
+        uint256 highest_bid;
+        uint256 second_highest_bid;
+        uint256 final_price;
+        bool settled;
+        Initializable init;
+        ReentrancyGuard guard;
+    }
+}
+
+impl ReentrancyGuarded for SealedBidAuction {
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+        &mut self.guard
+    }
+}
+
+#[public]
+impl SealedBidAuction {
+    /// Start the auction of `seller`'s NFT: commits are taken for
+    /// `commit_duration` seconds, then revealed for `reveal_duration` seconds
+    ///
+    /// Only the seller can call this. The NFT is escrowed from them, so they
+    /// must approve this contract first. Runs once.
+    pub fn initialize(
+        &mut self,
+        seller: Address,
+        nft_contract: Address,
+        token_id: U256,
+        reserve_price: U256,
+        commit_duration: U256,
+        reveal_duration: U256,
+    ) -> Result<(), SealedBidAuctionError> {
+        self.init.initializer()?;
+
+        if seller == Address::ZERO || nft_contract == Address::ZERO {
+            return Err(SealedBidAuctionError::ZeroAddress(ZeroAddress {}));
+        }
+
+        // Otherwise anyone could have an approved NFT pulled in for its owner
+        if self.vm().msg_sender() != seller {
+            return Err(SealedBidAuctionError::OnlySeller(OnlySeller {}));
+        }
+
+        if commit_duration == U256::ZERO || reveal_duration == U256::ZERO {
+            return Err(SealedBidAuctionError::InvalidDuration(InvalidDuration {}));
+        }
+
+        let commit_end = U256::from(self.vm().block_timestamp()) + commit_duration;
+        let reveal_end = commit_end + reveal_duration;
+        self.seller.set(seller);
+        self.nft_contract.set(nft_contract);
+        self.token_id.set(token_id);
+        self.reserve_price.set(reserve_price);
+        self.commit_end.set(commit_end);
+        self.reveal_end.set(reveal_end);
+        self.guard.init();
+
+        self.escrow_nft(seller)?;
+
+        log(self.vm(), AuctionCreated {
+            seller,
+            nftContract: nft_contract,
+            tokenId: token_id,
+            reservePrice: reserve_price,
+            commitEnd: commit_end,
+            revealEnd: reveal_end,
+        });
+
+        Ok(())
+    }
+
+    /// Commit to a sealed bid, depositing the sent value
+    ///
+    /// `commitment` is `bid_commitment(auction, bidder, amount, salt)`; see
+    /// `compute_commitment`. The deposit must cover the bid to be revealed and
+    /// can exceed it to hide the bid. One commitment per bidder.
+    #[payable]
+    pub fn commit(&mut self, commitment: B256) -> Result<(), SealedBidAuctionError> {
+        if !self.init.is_initialized()
+            || self.settled.get()
+            || U256::from(self.vm().block_timestamp()) >= self.commit_end.get()
+        {
+            return Err(SealedBidAuctionError::NotCommitPhase(NotCommitPhase {}));
+        }
+
+        let bidder = self.vm().msg_sender();
+        if bidder == self.seller.get() {
+            return Err(SealedBidAuctionError::SellerCannotBid(SellerCannotBid {}));
+        }
+
+        // Zero marks "no commitment", so it would let the bidder commit again
+        if commitment == B256::ZERO {
+            return Err(SealedBidAuctionError::InvalidCommitment(InvalidCommitment {}));
+        }
+
+        if self.commitments.get(bidder) != B256::ZERO {
+            return Err(SealedBidAuctionError::AlreadyCommitted(AlreadyCommitted {}));
+        }
+
+        let deposit = self.vm().msg_value();
+        if deposit == U256::ZERO {
+            return Err(SealedBidAuctionError::NoDeposit(NoDeposit {}));
+        }
+
+        self.commitments.setter(bidder).set(commitment);
+        self.deposits.setter(bidder).set(deposit);
+        self.commitment_count.set(self.commitment_count.get() + U256::from(1));
+
+        log(self.vm(), BidCommitted { bidder, commitment, deposit });
+        Ok(())
+    }
+
+    /// Open the caller's commitment during the reveal phase
+    ///
+    /// Bids under the reserve price are accepted but can't win. Unrevealed
+    /// bids don't compete and get their deposit back.
+    pub fn reveal(&mut self, amount: U256, salt: B256) -> Result<(), SealedBidAuctionError> {
+        let now = U256::from(self.vm().block_timestamp());
+        if self.settled.get() || now < self.commit_end.get() || now >= self.reveal_end.get() {
+            return Err(SealedBidAuctionError::NotRevealPhase(NotRevealPhase {}));
+        }
+
+        let bidder = self.vm().msg_sender();
+        let commitment = self.commitments.get(bidder);
+        if commitment == B256::ZERO {
+            return Err(SealedBidAuctionError::NoCommitment(NoCommitment {}));
+        }
+
+        if self.revealed.get(bidder) {
+            return Err(SealedBidAuctionError::AlreadyRevealed(AlreadyRevealed {}));
+        }
+
+        if bid_commitment(self.vm().contract_address(), bidder, amount, salt) != commitment {
+            return Err(SealedBidAuctionError::InvalidReveal(InvalidReveal {}));
+        }
+
+        let deposit = self.deposits.get(bidder);
+        if amount > deposit {
+            return Err(SealedBidAuctionError::InsufficientDeposit(InsufficientDeposit { deposit, amount }));
+        }
+
+        self.revealed.setter(bidder).set(true);
+        if amount >= self.reserve_price.get() && amount > U256::ZERO {
+            self.record_bid(bidder, amount);
+        }
+
+        log(self.vm(), BidRevealed { bidder, amount });
+        Ok(())
+    }
+
+    /// Close the auction once the reveal phase is over (anyone)
+    ///
+    /// The NFT goes to the highest revealed bidder, whose deposit pays the
+    /// second-highest bid (at least the reserve) to the seller. Without a
+    /// valid bid the NFT goes back to the seller. Deposits become claimable.
+    #[non_reentrant]
+    pub fn settle(&mut self) -> Result<(), SealedBidAuctionError> {
+        if !self.init.is_initialized() || U256::from(self.vm().block_timestamp()) < self.reveal_end.get() {
+            return Err(SealedBidAuctionError::AuctionNotEnded(AuctionNotEnded {}));
+        }
+
+        if self.settled.get() {
+            return Err(SealedBidAuctionError::AuctionAlreadySettled(AuctionAlreadySettled {}));
+        }
+
+        self.settled.set(true);
+        let seller = self.seller.get();
+        let winner = self.highest_bidder.get();
+        let price = self.clearing_price();
+
+        if winner == Address::ZERO {
+            self.release_nft(seller)?;
+        } else {
+            self.final_price.set(price);
+            let winner_deposit = self.deposits.get(winner);
+            self.deposits.setter(winner).set(winner_deposit - price);
+            self.deposits.setter(seller).set(price);
+            self.release_nft(winner)?;
+        }
+
+        log(self.vm(), AuctionSettled { winner, price });
+        Ok(())
+    }
+
+    /// Call off the auction and take the NFT back (seller only, before any commitment)
+    #[non_reentrant]
+    pub fn cancel(&mut self) -> Result<(), SealedBidAuctionError> {
+        let seller = self.seller.get();
+        if self.vm().msg_sender() != seller {
+            return Err(SealedBidAuctionError::OnlySeller(OnlySeller {}));
+        }
+
+        if self.settled.get() {
+            return Err(SealedBidAuctionError::AuctionAlreadySettled(AuctionAlreadySettled {}));
+        }
+
+        if self.commitment_count.get() != U256::ZERO {
+            return Err(SealedBidAuctionError::AuctionHasBids(AuctionHasBids {}));
+        }
+
+        self.settled.set(true);
+        self.release_nft(seller)?;
+
+        log(self.vm(), AuctionCancelled {});
+        Ok(())
+    }
+
+    /// Send the caller their deposit, the winner's change, or the seller's proceeds
+    #[non_reentrant]
+    pub fn claim(&mut self) -> Result<(), SealedBidAuctionError> {
+        if !self.settled.get() {
+            return Err(SealedBidAuctionError::AuctionNotSettled(AuctionNotSettled {}));
+        }
+
+        let account = self.vm().msg_sender();
+        let amount = self.deposits.get(account);
+        if amount == U256::ZERO {
+            return Err(SealedBidAuctionError::NothingToClaim(NothingToClaim {}));
+        }
+
+        // Clear the balance before paying, so a re-entering claim gets nothing
+        self.deposits.setter(account).set(U256::ZERO);
+        safe_transfer_eth(self, account, amount, GasStipend::All)?;
+
+        log(self.vm(), Claimed { account, amount });
+        Ok(())
+    }
+
+    /// Commitment `bidder` must submit to bid `amount` on this auction
+    ///
+    /// Compute it off-chain where possible; calling this reveals the bid to the node.
+    pub fn compute_commitment(&self, bidder: Address, amount: U256, salt: B256) -> B256 {
+        bid_commitment(self.vm().contract_address(), bidder, amount, salt)
+    }
+
+    /// Price the current highest bidder would pay: the second-highest bid, at least the reserve
+    pub fn clearing_price(&self) -> U256 {
+        if self.highest_bidder.get() == Address::ZERO {
+            return U256::ZERO;
+        }
+        self.second_highest_bid.get().max(self.reserve_price.get())
+    }
+
+    /// Current phase: 0 = commit, 1 = reveal, 2 = awaiting settlement, 3 = settled
+    pub fn phase(&self) -> u8 {
+        let now = U256::from(self.vm().block_timestamp());
+        if self.settled.get() {
+            3
+        } else if now >= self.reveal_end.get() {
+            2
+        } else if now >= self.commit_end.get() {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Get auction details
+    pub fn get_auction_details(&self) -> (Address, Address, U256, U256, U256, U256, Address, U256, bool) {
+        (
+            self.seller.get(),
+            self.nft_contract.get(),
+            self.token_id.get(),
+            self.reserve_price.get(),
+            self.commit_end.get(),
+            self.reveal_end.get(),
+            self.highest_bidder.get(),
+            self.final_price.get(),
+            self.settled.get(),
+        )
+    }
+
+    // View functions
+    pub fn seller(&self) -> Address {
+        self.seller.get()
+    }
+
+    pub fn nft_contract(&self) -> Address {
+        self.nft_contract.get()
+    }
+
+    pub fn token_id(&self) -> U256 {
+        self.token_id.get()
+    }
+
+    pub fn reserve_price(&self) -> U256 {
+        self.reserve_price.get()
+    }
+
+    pub fn commit_end(&self) -> U256 {
+        self.commit_end.get()
+    }
+
+    pub fn reveal_end(&self) -> U256 {
+        self.reveal_end.get()
+    }
+
+    pub fn commitment_of(&self, bidder: Address) -> B256 {
+        self.commitments.get(bidder)
+    }
+
+    pub fn has_revealed(&self, bidder: Address) -> bool {
+        self.revealed.get(bidder)
+    }
+
+    pub fn deposit_of(&self, account: Address) -> U256 {
+        self.deposits.get(account)
+    }
+
+    pub fn commitment_count(&self) -> U256 {
+        self.commitment_count.get()
+    }
+
+    /// Highest revealed bidder so far, the winner once settled
+    pub fn highest_bidder(&self) -> Address {
+        self.highest_bidder.get()
+    }
+
+    pub fn highest_bid(&self) -> U256 {
+        self.highest_bid.get()
+    }
+
+    pub fn second_highest_bid(&self) -> U256 {
+        self.second_highest_bid.get()
+    }
+
+    pub fn final_price(&self) -> U256 {
+        self.final_price.get()
+    }
+
+    pub fn settled(&self) -> bool {
+        self.settled.get()
+    }
+}
+
+impl SealedBidAuction {
+    /// Rank a revealed bid against the highest and second-highest bids
+    ///
+    /// On a tie the earlier reveal stays on top and pays the tied amount.
+    fn record_bid(&mut self, bidder: Address, amount: U256) {
+        let highest = self.highest_bid.get();
+        if self.highest_bidder.get() == Address::ZERO || amount > highest {
+            self.second_highest_bid.set(highest);
+            self.highest_bid.set(amount);
+            self.highest_bidder.set(bidder);
+        } else if amount > self.second_highest_bid.get() {
+            self.second_highest_bid.set(amount);
+        }
+    }
+
+    /// Take custody of the NFT, pulling it from `seller`
+    ///
+    /// An NFT already held here is never accepted, since anyone could claim
+    /// to be the seller of it.
+    fn escrow_nft(&mut self, seller: Address) -> Result<(), SealedBidAuctionError> {
+        let nft_contract = IERC721::new(self.nft_contract.get());
+        let token_id = self.token_id.get();
+        let auction = self.vm().contract_address();
+
+        let owner = nft_contract
+            .owner_of(Call::new_in(self), token_id)
+            .map_err(|_| SealedBidAuctionError::NotNFTOwner(NotNFTOwner {}))?;
+        if owner != seller {
+            return Err(SealedBidAuctionError::NotNFTOwner(NotNFTOwner {}));
+        }
+
+        nft_contract
+            .transfer_from(Call::new_in(self), seller, auction, token_id)
+            .map_err(|_| SealedBidAuctionError::NFTTransferFailed(NFTTransferFailed {}))
+    }
+
+    /// Hand the escrowed NFT to `to`
+    fn release_nft(&mut self, to: Address) -> Result<(), SealedBidAuctionError> {
+        let nft_contract = IERC721::new(self.nft_contract.get());
+        let token_id = self.token_id.get();
+        let auction = self.vm().contract_address();
+
+        nft_contract
+            .transfer_from(Call::new_in(self), auction, to, token_id)
+            .map_err(|_| SealedBidAuctionError::NFTTransferFailed(NFTTransferFailed {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::alloy_sol_types::SolCall;
+    use stylus_sdk::testing::*;
+
+    sol! {
+        function transferFrom(address from, address to, uint256 tokenId);
+        function ownerOf(uint256 tokenId);
+    }
+
+    const TOKEN_ID: u64 = 7;
+    const RESERVE: u64 = 1_000;
+    const START: u64 = 10_000;
+    const COMMIT: u64 = 3_600;
+    const REVEAL: u64 = 1_800;
+
+    fn seller() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn alice() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([3u8; 20])
+    }
+
+    fn carol() -> Address {
+        Address::from([4u8; 20])
+    }
+
+    fn nft() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn salt(bidder: Address) -> B256 {
+        keccak256(bidder.as_slice())
+    }
+
+    fn mock_transfer(vm: &TestVM, from: Address, to: Address) {
+        let calldata = transferFromCall { from, to, tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        vm.mock_call(nft(), calldata, U256::ZERO, Ok(vec![]));
+    }
+
+    /// An auction of the seller's NFT started at `START`
+    fn deploy(vm: &TestVM) -> SealedBidAuction {
+        let mut contract = SealedBidAuction::from(vm);
+        let auction = contract.vm().contract_address();
+        let calldata = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        let mut owner = vec![0u8; 12];
+        owner.extend_from_slice(seller().as_slice());
+        vm.mock_static_call(nft(), calldata, Ok(owner));
+        mock_transfer(vm, seller(), auction);
+
+        vm.set_block_timestamp(START);
+        vm.set_sender(seller());
+        assert!(contract
+            .initialize(
+                seller(),
+                nft(),
+                U256::from(TOKEN_ID),
+                U256::from(RESERVE),
+                U256::from(COMMIT),
+                U256::from(REVEAL),
+            )
+            .is_ok());
+        contract
+    }
+
+    /// Commit `bidder` to `amount`, depositing `deposit`
+    fn commit(
+        contract: &mut SealedBidAuction,
+        vm: &TestVM,
+        bidder: Address,
+        amount: u64,
+        deposit: u64,
+    ) -> Result<(), SealedBidAuctionError> {
+        let commitment = contract.compute_commitment(bidder, U256::from(amount), salt(bidder));
+        vm.set_sender(bidder);
+        vm.set_value(U256::from(deposit));
+        contract.commit(commitment)
+    }
+
+    fn reveal(
+        contract: &mut SealedBidAuction,
+        vm: &TestVM,
+        bidder: Address,
+        amount: u64,
+    ) -> Result<(), SealedBidAuctionError> {
+        vm.set_sender(bidder);
+        vm.set_value(U256::ZERO);
+        contract.reveal(U256::from(amount), salt(bidder))
+    }
+
+    #[test]
+    fn test_commitment_binds_auction_and_bidder() {
+        let (amount, salt) = (U256::from(RESERVE), B256::repeat_byte(7));
+        let commitment = bid_commitment(nft(), alice(), amount, salt);
+        assert_ne!(commitment, bid_commitment(nft(), bob(), amount, salt));
+        assert_ne!(commitment, bid_commitment(seller(), alice(), amount, salt));
+        assert_ne!(commitment, bid_commitment(nft(), alice(), amount + U256::from(1), salt));
+        assert_ne!(commitment, bid_commitment(nft(), alice(), amount, B256::repeat_byte(8)));
+    }
+
+    #[test]
+    fn test_commit_phase() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert_eq!(contract.phase(), 0);
+
+        assert!(matches!(
+            commit(&mut contract, &vm, seller(), RESERVE, RESERVE),
+            Err(SealedBidAuctionError::SellerCannotBid(_))
+        ));
+        assert!(matches!(commit(&mut contract, &vm, alice(), RESERVE, 0), Err(SealedBidAuctionError::NoDeposit(_))));
+        vm.set_value(U256::from(5_000));
+        assert!(matches!(contract.commit(B256::ZERO), Err(SealedBidAuctionError::InvalidCommitment(_))));
+        assert_eq!(contract.commitment_count(), U256::ZERO);
+        assert!(commit(&mut contract, &vm, alice(), RESERVE, 5_000).is_ok());
+        assert!(matches!(
+            commit(&mut contract, &vm, alice(), RESERVE, 5_000),
+            Err(SealedBidAuctionError::AlreadyCommitted(_))
+        ));
+        assert_eq!(contract.deposit_of(alice()), U256::from(5_000));
+        assert_eq!(contract.commitment_count(), U256::from(1));
+
+        // Commits close when the reveal phase opens, and nothing is revealed early
+        assert!(matches!(reveal(&mut contract, &vm, alice(), RESERVE), Err(SealedBidAuctionError::NotRevealPhase(_))));
+        vm.set_block_timestamp(START + COMMIT);
+        assert_eq!(contract.phase(), 1);
+        assert!(matches!(
+            commit(&mut contract, &vm, bob(), RESERVE, RESERVE),
+            Err(SealedBidAuctionError::NotCommitPhase(_))
+        ));
+    }
+
+    #[test]
+    fn test_reveal_checks_commitment_and_deposit() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), 2_000, 2_000).is_ok());
+        assert!(commit(&mut contract, &vm, bob(), 3_000, 2_500).is_ok());
+        vm.set_block_timestamp(START + COMMIT);
+
+        assert!(matches!(reveal(&mut contract, &vm, carol(), 2_000), Err(SealedBidAuctionError::NoCommitment(_))));
+        assert!(matches!(reveal(&mut contract, &vm, alice(), 2_001), Err(SealedBidAuctionError::InvalidReveal(_))));
+        assert!(reveal(&mut contract, &vm, alice(), 2_000).is_ok());
+        assert!(matches!(reveal(&mut contract, &vm, alice(), 2_000), Err(SealedBidAuctionError::AlreadyRevealed(_))));
+
+        // A bid the deposit doesn't cover can't be revealed
+        assert!(matches!(reveal(&mut contract, &vm, bob(), 3_000), Err(SealedBidAuctionError::InsufficientDeposit(_))));
+        assert_eq!(contract.highest_bidder(), alice());
+
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert_eq!(contract.phase(), 2);
+        assert!(matches!(reveal(&mut contract, &vm, bob(), 3_000), Err(SealedBidAuctionError::NotRevealPhase(_))));
+    }
+
+    #[test]
+    fn test_winner_pays_second_highest_bid() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), 2_000, 2_000).is_ok());
+        assert!(commit(&mut contract, &vm, bob(), 5_000, 9_000).is_ok());
+        assert!(commit(&mut contract, &vm, carol(), 3_000, 3_000).is_ok());
+
+        vm.set_block_timestamp(START + COMMIT);
+        for (bidder, amount) in [(alice(), 2_000), (bob(), 5_000), (carol(), 3_000)] {
+            assert!(reveal(&mut contract, &vm, bidder, amount).is_ok());
+        }
+        assert_eq!(contract.highest_bidder(), bob());
+        assert_eq!(contract.second_highest_bid(), U256::from(3_000));
+        assert_eq!(contract.clearing_price(), U256::from(3_000));
+
+        assert!(matches!(contract.settle(), Err(SealedBidAuctionError::AuctionNotEnded(_))));
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, bob());
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert!(contract.settle().is_ok());
+        assert_eq!(contract.phase(), 3);
+        assert_eq!(contract.final_price(), U256::from(3_000));
+
+        // The winner's change, the losers' deposits and the proceeds are claimable
+        assert_eq!(contract.deposit_of(bob()), U256::from(6_000));
+        assert_eq!(contract.deposit_of(alice()), U256::from(2_000));
+        assert_eq!(contract.deposit_of(carol()), U256::from(3_000));
+        assert_eq!(contract.deposit_of(seller()), U256::from(3_000));
+        assert!(matches!(contract.settle(), Err(SealedBidAuctionError::AuctionAlreadySettled(_))));
+    }
+
+    #[test]
+    fn test_single_bid_pays_reserve() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), 4_000, 4_000).is_ok());
+        assert!(commit(&mut contract, &vm, bob(), RESERVE - 1, RESERVE).is_ok());
+        assert!(commit(&mut contract, &vm, carol(), 9_000, 9_000).is_ok());
+
+        // Bob's bid is under the reserve and carol never reveals
+        vm.set_block_timestamp(START + COMMIT);
+        assert!(reveal(&mut contract, &vm, alice(), 4_000).is_ok());
+        assert!(reveal(&mut contract, &vm, bob(), RESERVE - 1).is_ok());
+        assert_eq!(contract.clearing_price(), U256::from(RESERVE));
+
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, alice());
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert!(contract.settle().is_ok());
+        assert_eq!(contract.deposit_of(alice()), U256::from(4_000 - RESERVE));
+        assert_eq!(contract.deposit_of(carol()), U256::from(9_000));
+        assert_eq!(contract.deposit_of(seller()), U256::from(RESERVE));
+    }
+
+    #[test]
+    fn test_tied_bids_pay_the_tie() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), 2_000, 2_000).is_ok());
+        assert!(commit(&mut contract, &vm, bob(), 2_000, 2_000).is_ok());
+
+        vm.set_block_timestamp(START + COMMIT);
+        assert!(reveal(&mut contract, &vm, bob(), 2_000).is_ok());
+        assert!(reveal(&mut contract, &vm, alice(), 2_000).is_ok());
+        assert_eq!(contract.highest_bidder(), bob());
+        assert_eq!(contract.clearing_price(), U256::from(2_000));
+    }
+
+    #[test]
+    fn test_settle_without_bids_returns_nft() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), 500, 500).is_ok());
+
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, seller());
+        vm.set_block_timestamp(START + COMMIT + REVEAL);
+        assert!(contract.settle().is_ok());
+        assert_eq!(contract.highest_bidder(), Address::ZERO);
+        assert_eq!(contract.deposit_of(alice()), U256::from(500));
+        assert_eq!(contract.deposit_of(seller()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_cancel_and_claim_rules() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        vm.set_sender(alice());
+        assert!(matches!(contract.cancel(), Err(SealedBidAuctionError::OnlySeller(_))));
+        assert!(matches!(contract.claim(), Err(SealedBidAuctionError::AuctionNotSettled(_))));
+
+        let auction = contract.vm().contract_address();
+        mock_transfer(&vm, auction, seller());
+        vm.set_sender(seller());
+        assert!(contract.cancel().is_ok());
+        assert!(matches!(contract.claim(), Err(SealedBidAuctionError::NothingToClaim(_))));
+
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(commit(&mut contract, &vm, alice(), RESERVE, RESERVE).is_ok());
+        vm.set_sender(seller());
+        assert!(matches!(contract.cancel(), Err(SealedBidAuctionError::AuctionHasBids(_))));
+    }
+
+    #[test]
+    fn test_only_seller_initializes() {
+        let vm = TestVM::default();
+        let mut contract = SealedBidAuction::from(&vm);
+        let auction = contract.vm().contract_address();
+
+        // With the NFT already here, a stranger can't claim to be its seller
+        let calldata = ownerOfCall { tokenId: U256::from(TOKEN_ID) }.abi_encode();
+        let mut owner = vec![0u8; 12];
+        owner.extend_from_slice(auction.as_slice());
+        vm.mock_static_call(nft(), calldata, Ok(owner));
+
+        vm.set_sender(alice());
+        let result = contract.initialize(
+            alice(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(RESERVE),
+            U256::from(COMMIT),
+            U256::from(REVEAL),
+        );
+        assert!(matches!(result, Err(SealedBidAuctionError::NotNFTOwner(_))));
+
+        // Nor start it for the real seller
+        let vm = TestVM::default();
+        let mut contract = SealedBidAuction::from(&vm);
+        vm.set_sender(alice());
+        let result = contract.initialize(
+            seller(),
+            nft(),
+            U256::from(TOKEN_ID),
+            U256::from(RESERVE),
+            U256::from(COMMIT),
+            U256::from(REVEAL),
+        );
+        assert!(matches!(result, Err(SealedBidAuctionError::OnlySeller(_))));
+    }
+
+}
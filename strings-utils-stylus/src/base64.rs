@@ -0,0 +1,146 @@
+//! Base64 encoding and decoding (RFC 4648)
+//!
+//! The standard alphabet pads its output with `=` and is what
+//! `data:application/json;base64,...` token URIs expect. The URL-safe
+//! alphabet swaps `+/` for `-_` and omits padding, matching OpenZeppelin's
+//! `Base64.encodeURL`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::StringsError;
+
+/// Standard alphabet
+const STANDARD: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// URL and filename safe alphabet
+const URL_SAFE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes bytes as Base64 with the standard alphabet and `=` padding.
+pub fn base64_encode(data: &[u8]) -> String {
+    encode(data, STANDARD, true)
+}
+
+/// Encodes bytes as Base64 with the URL-safe alphabet and no padding.
+pub fn base64_encode_url(data: &[u8]) -> String {
+    encode(data, URL_SAFE, false)
+}
+
+/// Decodes standard Base64, which must be padded to a multiple of 4 characters.
+pub fn base64_decode(input: &str) -> Result<Vec<u8>, StringsError> {
+    if input.len() % 4 != 0 {
+        return Err(StringsError::InvalidBase64Length { length: input.len() });
+    }
+    decode(input.as_bytes(), STANDARD)
+}
+
+/// Decodes URL-safe Base64, with or without padding.
+pub fn base64_decode_url(input: &str) -> Result<Vec<u8>, StringsError> {
+    decode(input.as_bytes(), URL_SAFE)
+}
+
+fn encode(data: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut buffer = Vec::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b0 = chunk[0] as u32;
+        let b1 = chunk.get(1).copied().unwrap_or(0) as u32;
+        let b2 = chunk.get(2).copied().unwrap_or(0) as u32;
+        let triple = (b0 << 16) | (b1 << 8) | b2;
+
+        // One input byte yields 2 characters, two yield 3, three yield 4
+        for i in 0..chunk.len() + 1 {
+            buffer.push(alphabet[((triple >> (18 - 6 * i)) & 0x3f) as usize]);
+        }
+        if pad {
+            buffer.extend(core::iter::repeat_n(b'=', 3 - chunk.len()));
+        }
+    }
+
+    String::from_utf8(buffer).expect("Invalid UTF-8 from base64 alphabet")
+}
+
+fn decode(input: &[u8], alphabet: &[u8; 64]) -> Result<Vec<u8>, StringsError> {
+    // Padding may only close the input, and never more than two characters
+    let data_len = input.iter().rposition(|&c| c != b'=').map_or(0, |i| i + 1);
+    let padding = input.len() - data_len;
+    if padding > 2 || data_len % 4 == 1 {
+        return Err(StringsError::InvalidBase64Length { length: input.len() });
+    }
+
+    let mut output = Vec::with_capacity(data_len * 3 / 4);
+    let mut accumulator: u32 = 0;
+    let mut bits = 0;
+    for (position, &c) in input[..data_len].iter().enumerate() {
+        let value = alphabet
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(StringsError::InvalidBase64Character { position })?;
+        accumulator = (accumulator << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((accumulator >> bits) as u8);
+            accumulator &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_encode_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(base64_encode(input.as_bytes()), expected);
+            assert_eq!(base64_decode(expected).unwrap(), input.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_base64_url_alphabet() {
+        let data = [0xfb, 0xff, 0xbf];
+        assert_eq!(base64_encode(&data), "+/+/");
+        assert_eq!(base64_encode_url(&data), "-_-_");
+        assert_eq!(base64_encode_url(b"f"), "Zg");
+        assert_eq!(base64_decode_url("-_-_").unwrap(), data);
+        assert_eq!(base64_decode_url("Zg").unwrap(), b"f");
+        assert_eq!(base64_decode_url("Zg==").unwrap(), b"f");
+    }
+
+    #[test]
+    fn test_base64_decode_rejects_invalid() {
+        assert!(matches!(base64_decode("Zg="), Err(StringsError::InvalidBase64Length { length: 3 })));
+        assert!(matches!(base64_decode("Z==="), Err(StringsError::InvalidBase64Length { .. })));
+        assert!(matches!(
+            base64_decode("Zm9-"),
+            Err(StringsError::InvalidBase64Character { position: 3 })
+        ));
+        assert!(matches!(
+            base64_decode("Zg=a"),
+            Err(StringsError::InvalidBase64Character { position: 2 })
+        ));
+        assert!(matches!(base64_decode_url("Zm+v"), Err(StringsError::InvalidBase64Character { position: 2 })));
+    }
+
+    #[test]
+    fn test_base64_data_uri_roundtrip() {
+        let json = r#"{"name":"Token #1","description":"On-chain"}"#;
+        let encoded = base64_encode(json.as_bytes());
+        let uri = format!("data:application/json;base64,{}", encoded);
+        let payload = uri.strip_prefix("data:application/json;base64,").unwrap();
+        assert_eq!(base64_decode(payload).unwrap(), json.as_bytes());
+    }
+}
@@ -0,0 +1,100 @@
+//! Conversions between strings and raw bytes
+//!
+//! Legacy contracts often store short strings such as token symbols in a
+//! `bytes32`, left-aligned and padded with zero bytes. Others, like EMS,
+//! store names as raw `bytes` that nothing has checked is valid UTF-8.
+
+use alloc::string::{String, ToString};
+
+use alloy_primitives::B256;
+
+use crate::StringsError;
+
+/// Returns true if the bytes are valid UTF-8.
+pub fn is_valid_utf8(bytes: &[u8]) -> bool {
+    core::str::from_utf8(bytes).is_ok()
+}
+
+/// Converts bytes to a string, replacing each invalid UTF-8 sequence with `U+FFFD`.
+///
+/// Valid input is returned unchanged, so this is safe to use for rendering
+/// untrusted names without reverting.
+pub fn sanitize_utf8(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Reads a left-aligned, zero-padded `bytes32` as a string.
+///
+/// Trailing zero bytes are trimmed; the rest must be valid UTF-8.
+pub fn bytes32_to_string(value: B256) -> Result<String, StringsError> {
+    let length = value.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    match core::str::from_utf8(&value[..length]) {
+        Ok(s) => Ok(s.to_string()),
+        Err(e) => Err(StringsError::InvalidUtf8 { position: e.valid_up_to() }),
+    }
+}
+
+/// Stores a string of at most 32 bytes in a `bytes32`, left-aligned and zero-padded.
+///
+/// Longer strings are an error rather than being truncated, which could also
+/// cut a multi-byte character in half.
+pub fn string_to_bytes32(s: &str) -> Result<B256, StringsError> {
+    if s.len() > 32 {
+        return Err(StringsError::TooLong { length: s.len(), max: 32 });
+    }
+    let mut value = B256::ZERO;
+    value[..s.len()].copy_from_slice(s.as_bytes());
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bytes32_roundtrip() {
+        for s in ["", "USDC", "Maker", "日本語", "exactly thirty-two bytes long!!!"] {
+            let value = string_to_bytes32(s).unwrap();
+            assert_eq!(bytes32_to_string(value).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_string_to_bytes32_layout() {
+        let value = string_to_bytes32("MKR").unwrap();
+        assert_eq!(&value[..3], b"MKR");
+        assert!(value[3..].iter().all(|&b| b == 0));
+        assert!(matches!(
+            string_to_bytes32("this string is longer than 32 bytes"),
+            Err(StringsError::TooLong { length: 35, max: 32 })
+        ));
+    }
+
+    #[test]
+    fn test_bytes32_to_string_rejects_invalid_utf8() {
+        let mut value = B256::ZERO;
+        value[..3].copy_from_slice(&[b'a', 0xff, b'b']);
+        assert!(matches!(bytes32_to_string(value), Err(StringsError::InvalidUtf8 { position: 1 })));
+        // Only trailing zeros are trimmed
+        value[..3].copy_from_slice(&[b'a', 0, b'b']);
+        assert_eq!(bytes32_to_string(value).unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn test_utf8_validation() {
+        assert!(is_valid_utf8(b""));
+        assert!(is_valid_utf8("name.eth".as_bytes()));
+        assert!(is_valid_utf8("日本語".as_bytes()));
+        assert!(!is_valid_utf8(&[0xff]));
+        // Truncated multi-byte sequence and overlong encoding
+        assert!(!is_valid_utf8(&[0xe6, 0x97]));
+        assert!(!is_valid_utf8(&[0xc0, 0xaf]));
+    }
+
+    #[test]
+    fn test_sanitize_utf8() {
+        assert_eq!(sanitize_utf8("alice".as_bytes()), "alice");
+        assert_eq!(sanitize_utf8(&[b'a', 0xff, b'b']), "a\u{fffd}b");
+        assert_eq!(sanitize_utf8(&[0xe6, 0x97]), "\u{fffd}");
+    }
+}
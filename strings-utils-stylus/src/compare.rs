@@ -0,0 +1,87 @@
+//! Deterministic string comparison and sorting
+//!
+//! Strings compare byte by byte, which for UTF-8 is the same as comparing by
+//! code point. The order doesn't depend on locale, so every node sorts labels
+//! in generated metadata or registries identically.
+
+use core::cmp::Ordering;
+
+/// Compares two strings lexicographically, returning -1, 0 or 1 like Solidity libraries do.
+pub fn compare(a: &str, b: &str) -> i8 {
+    match a.as_bytes().cmp(b.as_bytes()) {
+        Ordering::Less => -1,
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+    }
+}
+
+/// Returns the lexicographically smaller string, or `a` if they are equal.
+pub fn min_str<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if compare(b, a) < 0 {
+        b
+    } else {
+        a
+    }
+}
+
+/// Returns the lexicographically larger string, or `a` if they are equal.
+pub fn max_str<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if compare(b, a) > 0 {
+        b
+    } else {
+        a
+    }
+}
+
+/// Sorts strings in place in lexicographic order.
+///
+/// An insertion sort: stable, no allocation and only a few instructions of
+/// code, which suits the short lists a contract should be sorting. It is
+/// quadratic, so sort long lists off-chain.
+pub fn sort_strings<S: AsRef<str>>(items: &mut [S]) {
+    for i in 1..items.len() {
+        let mut j = i;
+        while j > 0 && compare(items[j - 1].as_ref(), items[j].as_ref()) > 0 {
+            items.swap(j - 1, j);
+            j -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(compare("apple", "banana"), -1);
+        assert_eq!(compare("banana", "apple"), 1);
+        assert_eq!(compare("same", "same"), 0);
+        assert_eq!(compare("app", "apple"), -1);
+        assert_eq!(compare("", "a"), -1);
+        // Uppercase sorts before lowercase, and ASCII before other characters
+        assert_eq!(compare("Zebra", "apple"), -1);
+        assert_eq!(compare("z", "é"), -1);
+    }
+
+    #[test]
+    fn test_min_max() {
+        assert_eq!(min_str("beta", "alpha"), "alpha");
+        assert_eq!(max_str("beta", "alpha"), "beta");
+        assert_eq!(min_str("x", "x"), "x");
+    }
+
+    #[test]
+    fn test_sort_strings() {
+        let mut labels = vec!["gold".to_string(), "Bronze".to_string(), "silver".to_string(), "".to_string()];
+        sort_strings(&mut labels);
+        assert_eq!(labels, vec!["", "Bronze", "gold", "silver"]);
+
+        let mut names = ["b", "a", "c", "a"];
+        sort_strings(&mut names);
+        assert_eq!(names, ["a", "a", "b", "c"]);
+
+        let mut empty: [&str; 0] = [];
+        sort_strings(&mut empty);
+    }
+}
@@ -0,0 +1,531 @@
+//! The `StringsUtils` contract
+//!
+//! Exposes the library functions as pure entrypoints for callers that can't
+//! link the crate, such as Solidity contracts and off-chain clients. Library
+//! errors revert with the matching Solidity error. Only built with the
+//! `contract` feature, so crates using the library keep their own entrypoint.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use alloy_primitives::{Address, FixedBytes, B256, I256, U256};
+use alloy_sol_types::sol;
+use stylus_sdk::{abi::Bytes, entrypoint, prelude::*};
+
+use crate::{
+    address_to_checksum_hex_string, address_to_hex_string, address_to_short_string, base64_decode,
+    base64_decode_url, base64_encode, base64_encode_url, bytes32_to_string, char_at, compare, concat, contains,
+    ends_with, equals_ignore_case, escape_html, escape_json, event_topic, format_template, is_valid_utf8, max_str,
+    min_str, normalize_name, pad_left, pad_right, parse_address, parse_decimal, parse_hex, parse_hex_signed,
+    parse_int, parse_radix, parse_uint, replace, sanitize_utf8, selector, sort_strings, split, starts_with,
+    string_to_bytes32, substring, timestamp_to_date_parts, timestamp_to_iso8601, to_abbreviated_string,
+    to_decimal_string, to_decimal_string_trimmed, to_hex_string, to_hex_string_signed, to_hex_string_with_length,
+    to_lower_ascii, to_radix_string, to_string, to_string_signed, to_upper_ascii, trim, url_decode, url_encode,
+    validate_checksum_address, zero_pad_hex, SignedHexFormat, StringsError, TemplateArg,
+};
+
+// Define the contract's external interface
+sol! {
+    interface IStringsUtils {
+        function toString(uint256 value) external pure returns (string memory);
+        function toStringSigned(int256 value) external pure returns (string memory);
+        function toHexString(uint256 value) external pure returns (string memory);
+        function toHexStringWithLength(uint256 value, uint256 length) external pure returns (string memory);
+        function toHexStringSigned(int256 value, bool twosComplement) external pure returns (string memory);
+        function parseHexSigned(string input, bool twosComplement) external pure returns (int256);
+        function addressToHexString(address addr) external pure returns (string memory);
+        function addressToChecksumHexString(address addr) external pure returns (string memory);
+        function toStringBatch(uint256[] values) external pure returns (string[] memory);
+        function addressesToHexBatch(address[] addrs) external pure returns (string[] memory);
+        function base64Encode(bytes data) external pure returns (string memory);
+        function base64EncodeUrl(bytes data) external pure returns (string memory);
+        function base64Decode(string input) external pure returns (bytes memory);
+        function base64DecodeUrl(string input) external pure returns (bytes memory);
+        function parseUint(string input) external pure returns (uint256);
+        function parseInt(string input) external pure returns (int256);
+        function parseHex(string input) external pure returns (bytes memory);
+        function parseAddress(string input) external pure returns (address);
+        function validateChecksumAddress(string input) external pure returns (address);
+        function toDecimalString(uint256 value, uint8 decimals) external pure returns (string memory);
+        function toDecimalStringTrimmed(uint256 value, uint8 decimals) external pure returns (string memory);
+        function parseDecimal(string input, uint8 decimals) external pure returns (uint256);
+        function selector(string signature) external pure returns (bytes4);
+        function eventTopic(string signature) external pure returns (bytes32);
+        function escapeJson(string s) external pure returns (string memory);
+        function escapeHtml(string s) external pure returns (string memory);
+        function normalizeName(string name) external pure returns (string memory);
+        function urlEncode(string s) external pure returns (string memory);
+        function urlDecode(string s) external pure returns (string memory);
+        function formatTemplate(string template, string[] args) external pure returns (string memory);
+        function toAbbreviatedString(uint256 value, uint8 decimals, uint8 precision) external pure returns (string memory);
+        function concat(string[] parts) external pure returns (string memory);
+        function split(string s, string delimiter) external pure returns (string[] memory);
+        function trim(string s) external pure returns (string memory);
+        function startsWith(string s, string prefix) external pure returns (bool);
+        function endsWith(string s, string suffix) external pure returns (bool);
+        function contains(string s, string needle) external pure returns (bool);
+        function replace(string s, string from, string to) external pure returns (string memory);
+        function substring(string s, uint256 start, uint256 end) external pure returns (string memory);
+        function charAt(string s, uint256 index) external pure returns (string memory);
+        function toLowerAscii(string s) external pure returns (string memory);
+        function toUpperAscii(string s) external pure returns (string memory);
+        function equalsIgnoreCase(string a, string b) external pure returns (bool);
+        function compare(string a, string b) external pure returns (int8);
+        function minStr(string a, string b) external pure returns (string memory);
+        function maxStr(string a, string b) external pure returns (string memory);
+        function sortStrings(string[] items) external pure returns (string[] memory);
+        function padLeft(string s, uint256 width, string fill) external pure returns (string memory);
+        function padRight(string s, uint256 width, string fill) external pure returns (string memory);
+        function zeroPadHex(uint256 value, uint256 bytes) external pure returns (string memory);
+        function bytes32ToString(bytes32 value) external pure returns (string memory);
+        function stringToBytes32(string s) external pure returns (bytes32);
+        function isValidUtf8(bytes data) external pure returns (bool);
+        function sanitizeUtf8(bytes data) external pure returns (string memory);
+        function toRadixString(uint256 value, uint8 radix) external pure returns (string memory);
+        function parseRadix(string input, uint8 radix) external pure returns (uint256);
+        function timestampToIso8601(uint256 timestamp) external pure returns (string memory);
+        function timestampToDateParts(uint256 timestamp) external pure returns (uint256 year, uint8 month, uint8 day, uint8 hour, uint8 minute, uint8 second);
+        function addressToShortString(address addr, uint256 prefixLen, uint256 suffixLen) external pure returns (string memory);
+    }
+}
+
+// The main contract struct
+#[entrypoint]
+#[storage]
+pub struct StringsUtils;
+
+// Define custom errors for the contract
+sol! {
+    error InsufficientHexLength(uint256 value, uint256 length);
+    error InvalidBase64Character(uint256 position);
+    error InvalidBase64Length(uint256 length);
+    error EmptyInput();
+    error InvalidCharacter(uint256 position);
+    error Overflow();
+    error InvalidHexLength(uint256 length);
+    error InvalidAddressLength(uint256 length);
+    error InvalidChecksum();
+    error ExcessDecimals(uint8 decimals);
+    error IndexOutOfBounds(uint256 index, uint256 length);
+    error InvalidRange(uint256 start, uint256 end);
+    error ExceedsWidth(uint256 length, uint256 width);
+    error InvalidFill();
+    error InvalidUtf8(uint256 position);
+    error TooLong(uint256 length, uint256 max);
+    error InvalidRadix(uint8 radix);
+    error MissingArgument(uint256 index);
+    error InvalidLabelLength(uint256 label, uint256 length);
+    error WidthTooLarge(uint256 width);
+}
+
+/// ABI-encode a library error as the matching Solidity error
+fn encode_error(error: StringsError) -> Vec<u8> {
+    match error {
+        StringsError::InsufficientHexLength { value, length } => {
+            InsufficientHexLength { value, length: U256::from(length) }.encode()
+        }
+        StringsError::InvalidBase64Character { position } => {
+            InvalidBase64Character { position: U256::from(position) }.encode()
+        }
+        StringsError::InvalidBase64Length { length } => InvalidBase64Length { length: U256::from(length) }.encode(),
+        StringsError::EmptyInput => EmptyInput {}.encode(),
+        StringsError::InvalidCharacter { position } => InvalidCharacter { position: U256::from(position) }.encode(),
+        StringsError::Overflow => Overflow {}.encode(),
+        StringsError::InvalidHexLength { length } => InvalidHexLength { length: U256::from(length) }.encode(),
+        StringsError::InvalidAddressLength { length } => InvalidAddressLength { length: U256::from(length) }.encode(),
+        StringsError::InvalidChecksum => InvalidChecksum {}.encode(),
+        StringsError::ExcessDecimals { decimals } => ExcessDecimals { decimals }.encode(),
+        StringsError::IndexOutOfBounds { index, length } => {
+            IndexOutOfBounds { index: U256::from(index), length: U256::from(length) }.encode()
+        }
+        StringsError::InvalidRange { start, end } => {
+            InvalidRange { start: U256::from(start), end: U256::from(end) }.encode()
+        }
+        StringsError::ExceedsWidth { length, width } => {
+            ExceedsWidth { length: U256::from(length), width: U256::from(width) }.encode()
+        }
+        StringsError::InvalidUtf8 { position } => InvalidUtf8 { position: U256::from(position) }.encode(),
+        StringsError::TooLong { length, max } => {
+            TooLong { length: U256::from(length), max: U256::from(max) }.encode()
+        }
+        StringsError::InvalidRadix { radix } => InvalidRadix { radix }.encode(),
+        StringsError::MissingArgument { index } => MissingArgument { index: U256::from(index) }.encode(),
+        StringsError::InvalidLabelLength { label, length } => {
+            InvalidLabelLength { label: U256::from(label), length: U256::from(length) }.encode()
+        }
+        StringsError::WidthTooLarge { width } => WidthTooLarge { width: U256::from(width) }.encode(),
+    }
+}
+
+/// Converts an index to usize, clamping huge values so they fail as out of bounds
+fn saturating_index(index: U256) -> usize {
+    index.try_into().unwrap_or(usize::MAX)
+}
+
+/// Reads a padding fill argument, which must be exactly one character
+fn single_char(fill: &str) -> Result<char, Vec<u8>> {
+    let mut chars = fill.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(InvalidFill {}.encode()),
+    }
+}
+
+fn signed_hex_format(twos_complement: bool) -> SignedHexFormat {
+    if twos_complement {
+        SignedHexFormat::TwosComplement
+    } else {
+        SignedHexFormat::SignMagnitude
+    }
+}
+
+#[public]
+impl StringsUtils {
+    /// Converts a U256 value to its ASCII decimal string representation
+    pub fn to_string(&self, value: U256) -> String {
+        to_string(value)
+    }
+
+    /// Converts an I256 value to its ASCII decimal string representation
+    pub fn to_string_signed(&self, value: I256) -> String {
+        to_string_signed(value)
+    }
+
+    /// Converts a U256 value to a string in any radix from 2 to 36
+    pub fn to_radix_string(&self, value: U256, radix: u8) -> Result<String, Vec<u8>> {
+        to_radix_string(value, radix).map_err(encode_error)
+    }
+
+    /// Converts a U256 value to its ASCII hexadecimal string representation
+    pub fn to_hex_string(&self, value: U256) -> String {
+        to_hex_string(value)
+    }
+
+    /// Converts a U256 value to its ASCII hexadecimal string representation with fixed length
+    pub fn to_hex_string_with_length(&self, value: U256, length: U256) -> Result<String, Vec<u8>> {
+        let length_usize = length.to::<usize>();
+        to_hex_string_with_length(value, length_usize).map_err(encode_error)
+    }
+
+    /// Converts an I256 value to hex, either "-0x1a" style or as the raw two's-complement word
+    pub fn to_hex_string_signed(&self, value: I256, twos_complement: bool) -> String {
+        to_hex_string_signed(value, signed_hex_format(twos_complement))
+    }
+
+    /// Parses a signed hex string written in either format
+    pub fn parse_hex_signed(&self, input: String, twos_complement: bool) -> Result<I256, Vec<u8>> {
+        parse_hex_signed(&input, signed_hex_format(twos_complement)).map_err(encode_error)
+    }
+
+    /// Converts an Address to its ASCII hexadecimal string representation
+    pub fn address_to_hex_string(&self, addr: Address) -> String {
+        address_to_hex_string(addr)
+    }
+
+    /// Converts an Address to its checksummed ASCII hexadecimal string representation
+    pub fn address_to_checksum_hex_string(&self, addr: Address) -> String {
+        address_to_checksum_hex_string(addr)
+    }
+
+    /// Converts many U256 values to decimal strings in one call
+    pub fn to_string_batch(&self, values: Vec<U256>) -> Vec<String> {
+        values.into_iter().map(to_string).collect()
+    }
+
+    /// Converts many Addresses to hexadecimal strings in one call
+    pub fn addresses_to_hex_batch(&self, addrs: Vec<Address>) -> Vec<String> {
+        addrs.into_iter().map(address_to_hex_string).collect()
+    }
+
+    /// Converts an Address to a shortened checksummed string like "0x1234…aBcD"
+    pub fn address_to_short_string(&self, addr: Address, prefix_len: U256, suffix_len: U256) -> String {
+        address_to_short_string(addr, saturating_index(prefix_len), saturating_index(suffix_len))
+    }
+
+    /// Encodes bytes as padded standard Base64
+    pub fn base64_encode(&self, data: Bytes) -> String {
+        base64_encode(&data)
+    }
+
+    /// Encodes bytes as unpadded URL-safe Base64
+    pub fn base64_encode_url(&self, data: Bytes) -> String {
+        base64_encode_url(&data)
+    }
+
+    /// Decodes padded standard Base64
+    pub fn base64_decode(&self, input: String) -> Result<Bytes, Vec<u8>> {
+        base64_decode(&input).map(Bytes::from).map_err(encode_error)
+    }
+
+    /// Decodes URL-safe Base64, padded or not
+    pub fn base64_decode_url(&self, input: String) -> Result<Bytes, Vec<u8>> {
+        base64_decode_url(&input).map(Bytes::from).map_err(encode_error)
+    }
+
+    /// Parses a decimal string into a U256
+    pub fn parse_uint(&self, input: String) -> Result<U256, Vec<u8>> {
+        parse_uint(&input).map_err(encode_error)
+    }
+
+    /// Parses a decimal string with an optional sign into an I256
+    pub fn parse_int(&self, input: String) -> Result<I256, Vec<u8>> {
+        parse_int(&input).map_err(encode_error)
+    }
+
+    /// Parses digits in any radix from 2 to 36 into a U256
+    pub fn parse_radix(&self, input: String, radix: u8) -> Result<U256, Vec<u8>> {
+        parse_radix(&input, radix).map_err(encode_error)
+    }
+
+    /// Parses a hex string, with or without a 0x prefix, into bytes
+    pub fn parse_hex(&self, input: String) -> Result<Bytes, Vec<u8>> {
+        parse_hex(&input).map(Bytes::from).map_err(encode_error)
+    }
+
+    /// Parses a hex address, ignoring capitalization
+    pub fn parse_address(&self, input: String) -> Result<Address, Vec<u8>> {
+        parse_address(&input).map_err(encode_error)
+    }
+
+    /// Parses a hex address, reverting unless it has a valid EIP-55 checksum
+    pub fn validate_checksum_address(&self, input: String) -> Result<Address, Vec<u8>> {
+        validate_checksum_address(&input).map_err(encode_error)
+    }
+
+    /// Formats a value scaled by 10^decimals with every fraction digit
+    pub fn to_decimal_string(&self, value: U256, decimals: u8) -> String {
+        to_decimal_string(value, decimals)
+    }
+
+    /// Formats a value scaled by 10^decimals without trailing fraction zeros
+    pub fn to_decimal_string_trimmed(&self, value: U256, decimals: u8) -> String {
+        to_decimal_string_trimmed(value, decimals)
+    }
+
+    /// Returns the 4-byte function selector of a signature like "transfer(address,uint256)"
+    pub fn selector(&self, signature: String) -> FixedBytes<4> {
+        selector(&signature)
+    }
+
+    /// Returns the event topic of a signature like "Transfer(address,address,uint256)"
+    pub fn event_topic(&self, signature: String) -> B256 {
+        event_topic(&signature)
+    }
+
+    /// Escapes a string for use inside a JSON string literal
+    pub fn escape_json(&self, s: String) -> String {
+        escape_json(&s)
+    }
+
+    /// Escapes a string for use in HTML or SVG
+    pub fn escape_html(&self, s: String) -> String {
+        escape_html(&s)
+    }
+
+    /// Lowercases and validates an ENS-style name, reverting if it isn't allowed
+    pub fn normalize_name(&self, name: String) -> Result<String, Vec<u8>> {
+        normalize_name(&name).map_err(encode_error)
+    }
+
+    /// Percent-encodes a string for use in a URL
+    pub fn url_encode(&self, s: String) -> String {
+        url_encode(&s)
+    }
+
+    /// Decodes a percent-encoded string
+    pub fn url_decode(&self, s: String) -> Result<String, Vec<u8>> {
+        url_decode(&s).map_err(encode_error)
+    }
+
+    /// Fills {} and {n} placeholders in a template with string arguments
+    pub fn format_template(&self, template: String, args: Vec<String>) -> Result<String, Vec<u8>> {
+        let args: Vec<TemplateArg> = args.iter().map(|arg| TemplateArg::Str(arg)).collect();
+        format_template(&template, &args).map_err(encode_error)
+    }
+
+    /// Formats a value scaled by 10^decimals like "1.2K" or "3.45M"
+    pub fn to_abbreviated_string(&self, value: U256, decimals: u8, precision: u8) -> String {
+        to_abbreviated_string(value, decimals, precision)
+    }
+
+    /// Parses a decimal string into a value scaled by 10^decimals
+    pub fn parse_decimal(&self, input: String, decimals: u8) -> Result<U256, Vec<u8>> {
+        parse_decimal(&input, decimals).map_err(encode_error)
+    }
+
+    /// Joins strings into one
+    pub fn concat(&self, parts: Vec<String>) -> String {
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        concat(&parts)
+    }
+
+    /// Splits a string on every occurrence of a delimiter
+    pub fn split(&self, s: String, delimiter: String) -> Vec<String> {
+        split(&s, &delimiter)
+    }
+
+    /// Strips leading and trailing ASCII whitespace
+    pub fn trim(&self, s: String) -> String {
+        trim(&s).to_string()
+    }
+
+    /// Checks whether a string begins with a prefix
+    pub fn starts_with(&self, s: String, prefix: String) -> bool {
+        starts_with(&s, &prefix)
+    }
+
+    /// Checks whether a string ends with a suffix
+    pub fn ends_with(&self, s: String, suffix: String) -> bool {
+        ends_with(&s, &suffix)
+    }
+
+    /// Checks whether a string contains a substring
+    pub fn contains(&self, s: String, needle: String) -> bool {
+        contains(&s, &needle)
+    }
+
+    /// Replaces every occurrence of one substring with another
+    pub fn replace(&self, s: String, from: String, to: String) -> String {
+        replace(&s, &from, &to)
+    }
+
+    /// Returns the characters between two character indices
+    pub fn substring(&self, s: String, start: U256, end: U256) -> Result<String, Vec<u8>> {
+        substring(&s, saturating_index(start), saturating_index(end))
+            .map(|slice| slice.to_string())
+            .map_err(encode_error)
+    }
+
+    /// Returns the character at a character index
+    pub fn char_at(&self, s: String, index: U256) -> Result<String, Vec<u8>> {
+        char_at(&s, saturating_index(index)).map(|c| c.to_string()).map_err(encode_error)
+    }
+
+    /// Lowercases ASCII letters, leaving other characters untouched
+    pub fn to_lower_ascii(&self, s: String) -> String {
+        to_lower_ascii(&s)
+    }
+
+    /// Uppercases ASCII letters, leaving other characters untouched
+    pub fn to_upper_ascii(&self, s: String) -> String {
+        to_upper_ascii(&s)
+    }
+
+    /// Compares two strings ignoring ASCII case
+    pub fn equals_ignore_case(&self, a: String, b: String) -> bool {
+        equals_ignore_case(&a, &b)
+    }
+
+    /// Compares two strings lexicographically, returning -1, 0 or 1
+    pub fn compare(&self, a: String, b: String) -> i8 {
+        compare(&a, &b)
+    }
+
+    /// Returns the lexicographically smaller string
+    pub fn min_str(&self, a: String, b: String) -> String {
+        min_str(&a, &b).to_string()
+    }
+
+    /// Returns the lexicographically larger string
+    pub fn max_str(&self, a: String, b: String) -> String {
+        max_str(&a, &b).to_string()
+    }
+
+    /// Sorts strings lexicographically
+    pub fn sort_strings(&self, mut items: Vec<String>) -> Vec<String> {
+        sort_strings(&mut items);
+        items
+    }
+
+    /// Left-pads a string to a width in characters; fill must be one character
+    pub fn pad_left(&self, s: String, width: U256, fill: String) -> Result<String, Vec<u8>> {
+        pad_left(&s, saturating_index(width), single_char(&fill)?).map_err(encode_error)
+    }
+
+    /// Right-pads a string to a width in characters; fill must be one character
+    pub fn pad_right(&self, s: String, width: U256, fill: String) -> Result<String, Vec<u8>> {
+        pad_right(&s, saturating_index(width), single_char(&fill)?).map_err(encode_error)
+    }
+
+    /// Formats a value as a fixed number of bytes of hex digits, without 0x
+    pub fn zero_pad_hex(&self, value: U256, bytes: U256) -> Result<String, Vec<u8>> {
+        zero_pad_hex(value, saturating_index(bytes)).map_err(encode_error)
+    }
+
+    /// Formats a Unix timestamp as an ISO-8601 UTC string
+    pub fn timestamp_to_iso8601(&self, timestamp: U256) -> String {
+        timestamp_to_iso8601(timestamp)
+    }
+
+    /// Splits a Unix timestamp into UTC year, month, day, hour, minute and second
+    pub fn timestamp_to_date_parts(&self, timestamp: U256) -> (U256, u8, u8, u8, u8, u8) {
+        let parts = timestamp_to_date_parts(timestamp);
+        (parts.year, parts.month, parts.day, parts.hour, parts.minute, parts.second)
+    }
+
+    /// Reads a zero-padded bytes32 as a string
+    pub fn bytes32_to_string(&self, value: B256) -> Result<String, Vec<u8>> {
+        bytes32_to_string(value).map_err(encode_error)
+    }
+
+    /// Stores a string of at most 32 bytes in a zero-padded bytes32
+    pub fn string_to_bytes32(&self, s: String) -> Result<B256, Vec<u8>> {
+        string_to_bytes32(&s).map_err(encode_error)
+    }
+
+    /// Checks whether bytes are valid UTF-8
+    pub fn is_valid_utf8(&self, data: Bytes) -> bool {
+        is_valid_utf8(&data)
+    }
+
+    /// Converts bytes to a string, replacing invalid UTF-8 with U+FFFD
+    pub fn sanitize_utf8(&self, data: Bytes) -> String {
+        sanitize_utf8(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_batch_conversions() {
+        let vm = TestVM::default();
+        let contract = StringsUtils::from(&vm);
+
+        let values = vec![U256::ZERO, U256::from(42), U256::MAX];
+        let expected: Vec<String> = values.iter().map(|v| to_string(*v)).collect();
+        assert_eq!(contract.to_string_batch(values), expected);
+        assert!(contract.to_string_batch(vec![]).is_empty());
+
+        let addrs = vec![Address::ZERO, Address::repeat_byte(0xab)];
+        assert_eq!(
+            contract.addresses_to_hex_batch(addrs),
+            vec![
+                "0x0000000000000000000000000000000000000000".to_string(),
+                "0xabababababababababababababababababababab".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_pad_hex_rejects_oversized_width() {
+        let vm = TestVM::default();
+        let contract = StringsUtils::from(&vm);
+
+        assert_eq!(contract.zero_pad_hex(U256::from(0xab), U256::from(2)).unwrap(), "00ab");
+        let expected = WidthTooLarge { width: U256::from(usize::MAX) }.encode();
+        assert_eq!(contract.zero_pad_hex(U256::ZERO, U256::MAX).unwrap_err(), expected);
+    }
+
+    #[test]
+    fn test_pad_rejects_oversized_width() {
+        let vm = TestVM::default();
+        let contract = StringsUtils::from(&vm);
+
+        let expected = WidthTooLarge { width: U256::from(usize::MAX) }.encode();
+        assert_eq!(contract.pad_left("a".into(), U256::MAX, " ".into()).unwrap_err(), expected);
+        assert_eq!(contract.pad_right("a".into(), U256::MAX, " ".into()).unwrap_err(), expected);
+    }
+}
@@ -0,0 +1,192 @@
+//! Fixed-point decimal formatting and parsing
+//!
+//! Token amounts are integers scaled by `10^decimals`, so 1.25 ether is
+//! `1250000000000000000` wei with 18 decimals. These helpers move between
+//! that integer and the human-readable decimal string shown in UIs, or a
+//! compact "3.45M" style abbreviation for leaderboards and metadata.
+
+use alloc::string::{String, ToString};
+
+use alloy_primitives::U256;
+
+use crate::parse::parse_digits;
+use crate::{to_string, StringsError};
+
+/// Formats a scaled integer as a decimal string with exactly `decimals` fraction digits.
+///
+/// `to_decimal_string(U256::from(1250000000000000000u64), 18)` is `"1.250000000000000000"`.
+/// With zero decimals no decimal point is added.
+pub fn to_decimal_string(value: U256, decimals: u8) -> String {
+    let decimals = decimals as usize;
+    let digits = to_string(value);
+    if decimals == 0 {
+        return digits;
+    }
+
+    // Pad so there's at least one integer digit in front of the point
+    let mut buffer = "0".repeat((decimals + 1).saturating_sub(digits.len()));
+    buffer.push_str(&digits);
+    buffer.insert(buffer.len() - decimals, '.');
+    buffer
+}
+
+/// Formats a scaled integer as a decimal string without trailing fraction zeros.
+///
+/// `to_decimal_string_trimmed(U256::from(1250000000000000000u64), 18)` is `"1.25"`, and
+/// whole amounts have no decimal point at all.
+pub fn to_decimal_string_trimmed(value: U256, decimals: u8) -> String {
+    let mut buffer = to_decimal_string(value, decimals);
+    if decimals > 0 {
+        let trimmed = buffer.trim_end_matches('0').trim_end_matches('.').len();
+        buffer.truncate(trimmed);
+    }
+    buffer
+}
+
+/// Suffixes for thousands, millions, billions and trillions
+const ABBREVIATIONS: [&str; 5] = ["", "K", "M", "B", "T"];
+
+/// Formats a scaled integer in abbreviated form like "1.2K", "3.45M" or "7.8B".
+///
+/// `decimals` is the value's scale as for `to_decimal_string`, and at most
+/// `precision` fraction digits are kept, without trailing zeros. Digits past
+/// `precision` are dropped rather than rounded, so a value is never shown
+/// larger than it is. Amounts past a thousand trillion stay in "T".
+pub fn to_abbreviated_string(value: U256, decimals: u8, precision: u8) -> String {
+    let digits = to_string(value);
+    let integer_digits = digits.len().saturating_sub(decimals as usize);
+    let tier = if integer_digits > 3 { ((integer_digits - 1) / 3).min(ABBREVIATIONS.len() - 1) } else { 0 };
+
+    // Place the decimal point `shift` digits from the right
+    let shift = decimals as usize + 3 * tier;
+    let (integer, fraction) = if shift >= digits.len() {
+        (String::from("0"), "0".repeat(shift - digits.len()) + &digits)
+    } else {
+        let (integer, fraction) = digits.split_at(digits.len() - shift);
+        (integer.to_string(), fraction.to_string())
+    };
+
+    let fraction = fraction[..fraction.len().min(precision as usize)].trim_end_matches('0');
+    let mut result = integer;
+    if !fraction.is_empty() {
+        result.push('.');
+        result.push_str(fraction);
+    }
+    result.push_str(ABBREVIATIONS[tier]);
+    result
+}
+
+/// Parses a decimal string into an integer scaled by `10^decimals`.
+///
+/// The inverse of `to_decimal_string`. The fraction may have fewer digits than
+/// `decimals` but not more, since that would silently drop precision. Both
+/// sides of the decimal point need at least one digit.
+pub fn parse_decimal(input: &str, decimals: u8) -> Result<U256, StringsError> {
+    let bytes = input.as_bytes();
+    let (integer, fraction) = match bytes.iter().position(|&c| c == b'.') {
+        Some(dot) => {
+            if dot == 0 || dot == bytes.len() - 1 {
+                return Err(StringsError::InvalidCharacter { position: dot });
+            }
+            (&bytes[..dot], Some((&bytes[dot + 1..], dot + 1)))
+        }
+        None => (bytes, None),
+    };
+
+    let mut value = scale(parse_digits(integer, 0)?, decimals as usize)?;
+    if let Some((fraction, offset)) = fraction {
+        let fraction_value = parse_digits(fraction, offset)?;
+        if fraction.len() > decimals as usize {
+            return Err(StringsError::ExcessDecimals { decimals });
+        }
+        let fraction_value = scale(fraction_value, decimals as usize - fraction.len())?;
+        value = value.checked_add(fraction_value).ok_or(StringsError::Overflow)?;
+    }
+
+    Ok(value)
+}
+
+/// Multiplies by `10^exponent`, which only overflows for a non-zero value.
+fn scale(value: U256, exponent: usize) -> Result<U256, StringsError> {
+    if value.is_zero() {
+        return Ok(value);
+    }
+    U256::from(10)
+        .checked_pow(U256::from(exponent))
+        .and_then(|factor| factor.checked_mul(value))
+        .ok_or(StringsError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ONE_AND_A_QUARTER: u64 = 1_250_000_000_000_000_000;
+
+    #[test]
+    fn test_to_decimal_string() {
+        assert_eq!(to_decimal_string(U256::from(ONE_AND_A_QUARTER), 18), "1.250000000000000000");
+        assert_eq!(to_decimal_string(U256::from(5), 18), "0.000000000000000005");
+        assert_eq!(to_decimal_string(U256::ZERO, 2), "0.00");
+        assert_eq!(to_decimal_string(U256::from(123456), 2), "1234.56");
+        assert_eq!(to_decimal_string(U256::from(123456), 0), "123456");
+        assert_eq!(to_decimal_string(U256::from(1), 80).len(), 82);
+    }
+
+    #[test]
+    fn test_to_decimal_string_trimmed() {
+        assert_eq!(to_decimal_string_trimmed(U256::from(ONE_AND_A_QUARTER), 18), "1.25");
+        assert_eq!(to_decimal_string_trimmed(U256::from(2_000_000u64), 6), "2");
+        assert_eq!(to_decimal_string_trimmed(U256::ZERO, 18), "0");
+        assert_eq!(to_decimal_string_trimmed(U256::from(100), 0), "100");
+        assert_eq!(to_decimal_string_trimmed(U256::from(1005), 3), "1.005");
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal("1.25", 18).unwrap(), U256::from(ONE_AND_A_QUARTER));
+        assert_eq!(parse_decimal("1.250000000000000000", 18).unwrap(), U256::from(ONE_AND_A_QUARTER));
+        assert_eq!(parse_decimal("2", 6).unwrap(), U256::from(2_000_000u64));
+        assert_eq!(parse_decimal("0.000001", 6).unwrap(), U256::from(1));
+        assert_eq!(parse_decimal("0.0", 200).unwrap(), U256::ZERO);
+
+        let value = U256::from(987654321u64);
+        assert_eq!(parse_decimal(&to_decimal_string(value, 4), 4).unwrap(), value);
+        assert_eq!(parse_decimal(&to_decimal_string_trimmed(value, 12), 12).unwrap(), value);
+    }
+
+    #[test]
+    fn test_parse_decimal_rejects_invalid() {
+        assert!(matches!(parse_decimal("", 18), Err(StringsError::EmptyInput)));
+        assert!(matches!(parse_decimal(".5", 18), Err(StringsError::InvalidCharacter { position: 0 })));
+        assert!(matches!(parse_decimal("5.", 18), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(parse_decimal("1.2.3", 18), Err(StringsError::InvalidCharacter { position: 3 })));
+        assert!(matches!(parse_decimal("1,5", 18), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(parse_decimal("1.234", 2), Err(StringsError::ExcessDecimals { decimals: 2 })));
+        assert!(matches!(parse_decimal("1", 78), Err(StringsError::Overflow)));
+    }
+
+    #[test]
+    fn test_to_abbreviated_string() {
+        assert_eq!(to_abbreviated_string(U256::ZERO, 0, 2), "0");
+        assert_eq!(to_abbreviated_string(U256::from(999), 0, 2), "999");
+        assert_eq!(to_abbreviated_string(U256::from(1234), 0, 1), "1.2K");
+        assert_eq!(to_abbreviated_string(U256::from(3_450_000u64), 0, 2), "3.45M");
+        assert_eq!(to_abbreviated_string(U256::from(7_800_000_000u64), 0, 2), "7.8B");
+        assert_eq!(to_abbreviated_string(U256::from(2_000_000u64), 0, 2), "2M");
+        assert_eq!(to_abbreviated_string(U256::from(123_456u64), 0, 0), "123K");
+        // Truncates rather than rounding up to "1000K"
+        assert_eq!(to_abbreviated_string(U256::from(999_999u64), 0, 1), "999.9K");
+        // Past trillions the integer part just grows
+        assert_eq!(to_abbreviated_string(U256::from(5_000_000_000_000_000u64), 0, 2), "5000T");
+    }
+
+    #[test]
+    fn test_to_abbreviated_string_with_decimals() {
+        assert_eq!(to_abbreviated_string(U256::from(ONE_AND_A_QUARTER), 18, 2), "1.25");
+        assert_eq!(to_abbreviated_string(U256::from(ONE_AND_A_QUARTER) * U256::from(1_000_000), 18, 2), "1.25M");
+        assert_eq!(to_abbreviated_string(U256::from(500_000_000_000_000_000u64), 18, 3), "0.5");
+        assert_eq!(to_abbreviated_string(U256::from(5), 18, 2), "0");
+        assert_eq!(to_abbreviated_string(U256::from(1_234_567u64), 2, 1), "12.3K");
+    }
+}
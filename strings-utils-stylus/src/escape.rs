@@ -0,0 +1,85 @@
+//! Escaping user-provided text for JSON and HTML
+//!
+//! Names and descriptions supplied by users end up in generated metadata,
+//! where an unescaped quote or angle bracket could change the document's
+//! structure. Both escapers leave everything they don't need to touch,
+//! including non-ASCII text, as is.
+
+use alloc::string::String;
+
+use crate::HEX_DIGITS;
+
+/// Escapes a string for use inside a JSON string literal, without adding the surrounding quotes.
+///
+/// Quotes, backslashes and control characters are escaped, using the short
+/// forms like `\n` where JSON has them and `\u00XX` otherwise.
+pub fn escape_json(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    push_json_escaped(&mut result, s);
+    result
+}
+
+/// Escapes a string for use in HTML or SVG text and quoted attribute values.
+///
+/// `&`, `<`, `>`, `"` and `'` become character references. Control
+/// characters other than tab, newline and carriage return aren't allowed in
+/// HTML and are removed.
+pub fn escape_html(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            '\t' | '\n' | '\r' => result.push(c),
+            c if c.is_control() => {}
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Appends `s` to `buffer` with JSON string escaping.
+pub(crate) fn push_json_escaped(buffer: &mut String, s: &str) {
+    for c in s.chars() {
+        match c {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            '\u{08}' => buffer.push_str("\\b"),
+            '\u{0c}' => buffer.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                buffer.push_str("\\u00");
+                buffer.push(HEX_DIGITS[(c as usize) >> 4] as char);
+                buffer.push(HEX_DIGITS[(c as usize) & 0xf] as char);
+            }
+            c => buffer.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_json() {
+        assert_eq!(escape_json("plain text é"), "plain text é");
+        assert_eq!(escape_json(r#"say "hi" \o/"#), r#"say \"hi\" \\o/"#);
+        assert_eq!(escape_json("a\nb\tc\r\u{8}\u{c}"), r"a\nb\tc\r\b\f");
+        assert_eq!(escape_json("\u{0}\u{1f}"), r"\u0000\u001f");
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("Alice & Bob"), "Alice &amp; Bob");
+        assert_eq!(escape_html("<script>alert('x')</script>"), "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;");
+        assert_eq!(escape_html(r#"" onload="x"#), "&quot; onload=&quot;x");
+        assert_eq!(escape_html("a\u{0}b\u{7f}\nc"), "ab\nc");
+        assert_eq!(escape_html("日本語"), "日本語");
+    }
+}
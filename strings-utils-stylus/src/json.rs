@@ -0,0 +1,232 @@
+//! Builders for JSON documents such as ERC721 metadata
+//!
+//! Keys and string values are escaped as they are added, so user-supplied
+//! names and descriptions can't break out of their string. Together with
+//! `base64_encode` this is enough for a fully on-chain `tokenURI`:
+//!
+//! ```
+//! use alloy_primitives::U256;
+//! use strings_utils_stylus::{base64_encode, JsonArrayBuilder, JsonBuilder};
+//!
+//! let level = JsonBuilder::new().string("trait_type", "Level").number("value", U256::from(5));
+//! let metadata = JsonBuilder::new()
+//!     .string("name", "Token #1")
+//!     .string("description", "Fully \"on-chain\"")
+//!     .array("attributes", JsonArrayBuilder::new().object(level))
+//!     .build();
+//! let uri = format!("data:application/json;base64,{}", base64_encode(metadata.as_bytes()));
+//! ```
+
+use alloc::string::String;
+
+use alloy_primitives::{I256, U256};
+
+use crate::escape::push_json_escaped;
+use crate::{to_string, to_string_signed};
+
+/// Builds a JSON object one member at a time.
+///
+/// Members are written in the order they are added; duplicate keys are not checked.
+#[derive(Debug, Clone)]
+pub struct JsonBuilder {
+    buffer: String,
+}
+
+impl Default for JsonBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonBuilder {
+    /// Starts an empty object.
+    pub fn new() -> Self {
+        Self { buffer: String::from("{") }
+    }
+
+    /// Adds a string member, escaping the value.
+    pub fn string(self, key: &str, value: &str) -> Self {
+        self.member(key, |buffer| push_string(buffer, value))
+    }
+
+    /// Adds an unsigned number member.
+    pub fn number(self, key: &str, value: U256) -> Self {
+        self.member(key, |buffer| buffer.push_str(&to_string(value)))
+    }
+
+    /// Adds a signed number member.
+    pub fn signed(self, key: &str, value: I256) -> Self {
+        self.member(key, |buffer| buffer.push_str(&to_string_signed(value)))
+    }
+
+    /// Adds a boolean member.
+    pub fn bool(self, key: &str, value: bool) -> Self {
+        self.member(key, |buffer| buffer.push_str(if value { "true" } else { "false" }))
+    }
+
+    /// Adds a `null` member.
+    pub fn null(self, key: &str) -> Self {
+        self.member(key, |buffer| buffer.push_str("null"))
+    }
+
+    /// Adds a nested object.
+    pub fn object(self, key: &str, value: JsonBuilder) -> Self {
+        self.member(key, |buffer| buffer.push_str(&value.build()))
+    }
+
+    /// Adds a nested array.
+    pub fn array(self, key: &str, value: JsonArrayBuilder) -> Self {
+        self.member(key, |buffer| buffer.push_str(&value.build()))
+    }
+
+    /// Finishes the object.
+    pub fn build(mut self) -> String {
+        self.buffer.push('}');
+        self.buffer
+    }
+
+    fn member(mut self, key: &str, write_value: impl FnOnce(&mut String)) -> Self {
+        if self.buffer.len() > 1 {
+            self.buffer.push(',');
+        }
+        push_string(&mut self.buffer, key);
+        self.buffer.push(':');
+        write_value(&mut self.buffer);
+        self
+    }
+}
+
+/// Builds a JSON array one element at a time.
+#[derive(Debug, Clone)]
+pub struct JsonArrayBuilder {
+    buffer: String,
+}
+
+impl Default for JsonArrayBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonArrayBuilder {
+    /// Starts an empty array.
+    pub fn new() -> Self {
+        Self { buffer: String::from("[") }
+    }
+
+    /// Appends a string, escaping it.
+    pub fn string(self, value: &str) -> Self {
+        self.element(|buffer| push_string(buffer, value))
+    }
+
+    /// Appends an unsigned number.
+    pub fn number(self, value: U256) -> Self {
+        self.element(|buffer| buffer.push_str(&to_string(value)))
+    }
+
+    /// Appends a signed number.
+    pub fn signed(self, value: I256) -> Self {
+        self.element(|buffer| buffer.push_str(&to_string_signed(value)))
+    }
+
+    /// Appends a boolean.
+    pub fn bool(self, value: bool) -> Self {
+        self.element(|buffer| buffer.push_str(if value { "true" } else { "false" }))
+    }
+
+    /// Appends `null`.
+    pub fn null(self) -> Self {
+        self.element(|buffer| buffer.push_str("null"))
+    }
+
+    /// Appends a nested object.
+    pub fn object(self, value: JsonBuilder) -> Self {
+        self.element(|buffer| buffer.push_str(&value.build()))
+    }
+
+    /// Appends a nested array.
+    pub fn array(self, value: JsonArrayBuilder) -> Self {
+        self.element(|buffer| buffer.push_str(&value.build()))
+    }
+
+    /// Finishes the array.
+    pub fn build(mut self) -> String {
+        self.buffer.push(']');
+        self.buffer
+    }
+
+    fn element(mut self, write_value: impl FnOnce(&mut String)) -> Self {
+        if self.buffer.len() > 1 {
+            self.buffer.push(',');
+        }
+        write_value(&mut self.buffer);
+        self
+    }
+}
+
+/// Writes `value` as a quoted, escaped JSON string.
+fn push_string(buffer: &mut String, value: &str) {
+    buffer.push('"');
+    push_json_escaped(buffer, value);
+    buffer.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{base64_decode, base64_encode};
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(JsonBuilder::new().build(), "{}");
+        assert_eq!(JsonArrayBuilder::new().build(), "[]");
+    }
+
+    #[test]
+    fn test_values() {
+        let json = JsonBuilder::new()
+            .string("name", "Token")
+            .number("id", U256::MAX)
+            .signed("delta", I256::try_from(-3).unwrap())
+            .bool("burned", false)
+            .null("owner")
+            .build();
+        assert_eq!(
+            json,
+            r#"{"name":"Token","id":115792089237316195423570985008687907853269984665640564039457584007913129639935,"delta":-3,"burned":false,"owner":null}"#
+        );
+    }
+
+    #[test]
+    fn test_nesting() {
+        let json = JsonBuilder::new()
+            .array(
+                "attributes",
+                JsonArrayBuilder::new()
+                    .object(JsonBuilder::new().string("trait_type", "Level").number("value", U256::from(5)))
+                    .array(JsonArrayBuilder::new().bool(true).null())
+                    .string("x"),
+            )
+            .object("empty", JsonBuilder::new())
+            .build();
+        assert_eq!(json, r#"{"attributes":[{"trait_type":"Level","value":5},[true,null],"x"],"empty":{}}"#);
+    }
+
+    #[test]
+    fn test_escaping() {
+        let json = JsonBuilder::new().string("a\"b", "say \"hi\"\\\n\t\u{1}é").build();
+        assert_eq!(json, r#"{"a\"b":"say \"hi\"\\\n\t\u0001é"}"#);
+
+        // Injection attempt stays inside the string
+        let json = JsonBuilder::new().string("name", r#"x","admin":true,"y":""#).build();
+        assert_eq!(json, r#"{"name":"x\",\"admin\":true,\"y\":\""}"#);
+    }
+
+    #[test]
+    fn test_token_uri() {
+        let metadata = JsonBuilder::new().string("name", "Token #1").string("image", "ipfs://Qm").build();
+        let uri = format!("data:application/json;base64,{}", base64_encode(metadata.as_bytes()));
+        let payload = uri.strip_prefix("data:application/json;base64,").unwrap();
+        assert_eq!(base64_decode(payload).unwrap(), metadata.as_bytes());
+    }
+}
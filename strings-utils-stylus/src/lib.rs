@@ -3,20 +3,82 @@
 //! A Rust implementation of OpenZeppelin's `Strings.sol` library for Arbitrum Stylus.
 //! Provides utility functions for converting various types to strings, matching the exact
 //! behavior of OpenZeppelin's implementation.
+//!
+//! # Using the library
+//!
+//! The functions are `no_std` and every one is re-exported from the crate root.
+//! Other contracts should depend on the crate without default features, which
+//! leaves out the `StringsUtils` contract and its entrypoint:
+//!
+//! ```toml
+//! strings-utils-stylus = { path = "../strings-utils-stylus", default-features = false }
+//! ```
+//!
+//! # Features
+//!
+//! - `contract` (default): build the `StringsUtils` contract exposing the
+//!   functions as pure entrypoints.
+//! - `export-abi`: print the contract's Solidity ABI; implies `contract`.
+
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use alloc::{format, vec};
 
 use alloy_primitives::{Address, I256, U256};
 
 #[cfg(feature = "export-abi")]
 use stylus_sdk::abi::export;
 
+pub mod base64;
+pub mod bytes;
+pub mod compare;
+#[cfg(feature = "contract")]
+pub mod contract;
+pub mod decimal;
+pub mod escape;
+pub mod json;
+pub mod manipulation;
+pub mod name;
+pub mod parse;
+pub mod signature;
+pub mod template;
+pub mod time;
+pub mod url;
+
+pub use base64::{base64_decode, base64_decode_url, base64_encode, base64_encode_url};
+pub use bytes::{bytes32_to_string, is_valid_utf8, sanitize_utf8, string_to_bytes32};
+pub use compare::{compare, max_str, min_str, sort_strings};
+pub use decimal::{parse_decimal, to_abbreviated_string, to_decimal_string, to_decimal_string_trimmed};
+pub use escape::{escape_html, escape_json};
+pub use json::{JsonArrayBuilder, JsonBuilder};
+pub use manipulation::{
+    char_at, concat, contains, ends_with, equals_ignore_case, pad_left, pad_right, replace, split, starts_with,
+    substring, to_lower_ascii, to_upper_ascii, trim, zero_pad_hex,
+};
+pub use name::normalize_name;
+pub use parse::{parse_address, parse_hex, parse_hex_signed, parse_int, parse_radix, parse_uint, validate_checksum_address};
+pub use signature::{event_topic, normalize_signature, selector};
+pub use template::{format_template, TemplateArg};
+pub use time::{timestamp_to_date_parts, timestamp_to_iso8601, DateParts};
+pub use url::{url_decode, url_encode};
+
+#[cfg(feature = "contract")]
+pub use contract::StringsUtils;
+
 #[cfg(feature = "export-abi")]
 pub fn print_from_args() {
-    export::print_abi("StringsUtils", "0.1.11");
+    export::print_from_args::<contract::StringsUtils>();
 }
 
 /// Hex digits constant used for hex string conversion
 const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
 
+/// Digits for radixes up to 36
+const RADIX_DIGITS: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
 /// Address length in bytes (20 bytes = 40 hex characters)
 const ADDRESS_LENGTH: usize = 20;
 
@@ -25,6 +87,42 @@ const ADDRESS_LENGTH: usize = 20;
 pub enum StringsError {
     /// Hex length insufficient for the given value
     InsufficientHexLength { value: U256, length: usize },
+    /// Base64 input has a character outside the alphabet at `position`
+    InvalidBase64Character { position: usize },
+    /// Base64 input length can't come from encoding
+    InvalidBase64Length { length: usize },
+    /// Nothing to parse
+    EmptyInput,
+    /// Unexpected character at `position` while parsing
+    InvalidCharacter { position: usize },
+    /// Parsed value doesn't fit the target type
+    Overflow,
+    /// Hex input has an odd number of digits
+    InvalidHexLength { length: usize },
+    /// Address input doesn't have exactly 40 hex digits
+    InvalidAddressLength { length: usize },
+    /// Address capitalization doesn't match its EIP-55 checksum
+    InvalidChecksum,
+    /// Decimal input has more fraction digits than `decimals`
+    ExcessDecimals { decimals: u8 },
+    /// Character index past the end of a string of `length` characters
+    IndexOutOfBounds { index: usize, length: usize },
+    /// Slice range starts after it ends
+    InvalidRange { start: usize, end: usize },
+    /// String of `length` characters is already wider than the padded `width`
+    ExceedsWidth { length: usize, width: usize },
+    /// Bytes stop being valid UTF-8 at `position`
+    InvalidUtf8 { position: usize },
+    /// Input of `length` bytes doesn't fit in `max` bytes
+    TooLong { length: usize, max: usize },
+    /// Radix outside 2..=36
+    InvalidRadix { radix: u8 },
+    /// Template placeholder refers to argument `index`, which wasn't supplied
+    MissingArgument { index: usize },
+    /// Name label number `label` is empty or longer than the maximum
+    InvalidLabelLength { label: usize, length: usize },
+    /// Padded width of `width` is too large to allocate
+    WidthTooLarge { width: usize },
 }
 
 /// Converts a U256 value to its ASCII decimal string representation.
@@ -65,12 +163,38 @@ pub fn to_string(value: U256) -> String {
 /// It handles negative values by prepending a minus sign.
 pub fn to_string_signed(value: I256) -> String {
     if value < I256::ZERO {
-        format!("-{}", to_string((-value).into_raw()))
+        // `unsigned_abs` rather than negation, which overflows for `I256::MIN`
+        format!("-{}", to_string(value.unsigned_abs()))
     } else {
         to_string(value.into_raw())
     }
 }
 
+/// Converts a U256 value to its string representation in any radix from 2 to 36.
+///
+/// Digits above 9 are lowercase letters and there is no prefix, so radix 2 gives
+/// "101" for 5, handy for inspecting bitmaps and flags. Returns `InvalidRadix`
+/// outside 2..=36.
+pub fn to_radix_string(value: U256, radix: u8) -> Result<String, StringsError> {
+    if !(2..=36).contains(&radix) {
+        return Err(StringsError::InvalidRadix { radix });
+    }
+    if value.is_zero() {
+        return Ok("0".to_string());
+    }
+
+    let base = U256::from(radix);
+    let mut buffer = Vec::new();
+    let mut remaining = value;
+    while !remaining.is_zero() {
+        buffer.push(RADIX_DIGITS[(remaining % base).to::<usize>()]);
+        remaining /= base;
+    }
+    buffer.reverse();
+
+    Ok(String::from_utf8(buffer).expect("Invalid UTF-8 from radix digits"))
+}
+
 /// Converts a U256 value to its ASCII hexadecimal string representation.
 /// 
 /// This function replicates OpenZeppelin's `toHexString(uint256)` function.
@@ -118,6 +242,30 @@ pub fn to_hex_string_with_length(value: U256, length: usize) -> Result<String, S
     Ok(String::from_utf8(buffer).expect("Invalid UTF-8 from hex digits"))
 }
 
+/// How `to_hex_string_signed` writes negative values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedHexFormat {
+    /// Minus sign before the magnitude, e.g. "-0x1a" for -26
+    SignMagnitude,
+    /// The raw 256-bit two's-complement word, e.g. "0xff…e6" for -26
+    TwosComplement,
+}
+
+/// Converts an I256 value to its ASCII hexadecimal string representation.
+///
+/// In `SignMagnitude` format the magnitude is written like `to_hex_string`, with a
+/// leading "-" for negative values. In `TwosComplement` format the raw word is
+/// always written as 64 hex digits, which is how the value sits in storage.
+pub fn to_hex_string_signed(value: I256, format: SignedHexFormat) -> String {
+    match format {
+        SignedHexFormat::SignMagnitude if value < I256::ZERO => {
+            format!("-{}", to_hex_string(value.unsigned_abs()))
+        }
+        SignedHexFormat::SignMagnitude => to_hex_string(value.into_raw()),
+        SignedHexFormat::TwosComplement => to_hex_string_with_length(value.into_raw(), 32).unwrap(),
+    }
+}
+
 /// Converts an Address to its ASCII hexadecimal string representation (not checksummed).
 /// 
 /// This function replicates OpenZeppelin's `toHexString(address)` function.
@@ -167,6 +315,25 @@ pub fn address_to_checksum_hex_string(addr: Address) -> String {
     String::from_utf8(buffer).expect("Invalid UTF-8 from checksum conversion")
 }
 
+/// Converts an Address to a shortened checksummed string like "0x1234…aBcD".
+///
+/// Keeps `prefix_len` hex digits after "0x" and the last `suffix_len` digits,
+/// joined by an ellipsis. If that would hide nothing, the full checksummed
+/// address is returned instead.
+pub fn address_to_short_string(addr: Address, prefix_len: usize, suffix_len: usize) -> String {
+    let checksum = address_to_checksum_hex_string(addr);
+    let digits = 2 * ADDRESS_LENGTH;
+    if prefix_len.saturating_add(suffix_len) >= digits {
+        return checksum;
+    }
+
+    let mut result = String::with_capacity(2 + prefix_len + '…'.len_utf8() + suffix_len);
+    result.push_str(&checksum[..2 + prefix_len]);
+    result.push('…');
+    result.push_str(&checksum[2 + digits - suffix_len..]);
+    result
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,8 +361,25 @@ mod tests {
         assert_eq!(to_string_signed(I256::try_from(-456).unwrap()), "-456");
         assert_eq!(to_string_signed(I256::try_from(i64::MAX).unwrap()), i64::MAX.to_string());
         assert_eq!(to_string_signed(I256::try_from(i64::MIN).unwrap()), i64::MIN.to_string());
+        assert_eq!(
+            to_string_signed(I256::MIN),
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
     }
     
+    #[test]
+    fn test_to_radix_string() {
+        assert_eq!(to_radix_string(U256::ZERO, 2).unwrap(), "0");
+        assert_eq!(to_radix_string(U256::from(5), 2).unwrap(), "101");
+        assert_eq!(to_radix_string(U256::from(8), 8).unwrap(), "10");
+        assert_eq!(to_radix_string(U256::from(255), 16).unwrap(), "ff");
+        assert_eq!(to_radix_string(U256::from(35), 36).unwrap(), "z");
+        assert_eq!(to_radix_string(U256::from(12345), 10).unwrap(), to_string(U256::from(12345)));
+        assert_eq!(to_radix_string(U256::MAX, 2).unwrap(), "1".repeat(256));
+        assert!(matches!(to_radix_string(U256::from(1), 1), Err(StringsError::InvalidRadix { radix: 1 })));
+        assert!(matches!(to_radix_string(U256::from(1), 37), Err(StringsError::InvalidRadix { radix: 37 })));
+    }
+
     #[test]
     fn test_to_hex_string_basic() {
         assert_eq!(to_hex_string(U256::ZERO), "0x00");
@@ -205,6 +389,26 @@ mod tests {
         assert_eq!(to_hex_string(U256::from(256)), "0x0100");
     }
     
+    #[test]
+    fn test_to_hex_string_signed() {
+        let minus_26 = I256::try_from(-26).unwrap();
+        assert_eq!(to_hex_string_signed(minus_26, SignedHexFormat::SignMagnitude), "-0x1a");
+        assert_eq!(to_hex_string_signed(I256::try_from(26).unwrap(), SignedHexFormat::SignMagnitude), "0x1a");
+        assert_eq!(to_hex_string_signed(I256::ZERO, SignedHexFormat::SignMagnitude), "0x00");
+        assert_eq!(
+            to_hex_string_signed(I256::MIN, SignedHexFormat::SignMagnitude),
+            format!("-0x80{}", "0".repeat(62))
+        );
+        assert_eq!(
+            to_hex_string_signed(minus_26, SignedHexFormat::TwosComplement),
+            format!("0x{}e6", "f".repeat(62))
+        );
+        assert_eq!(
+            to_hex_string_signed(I256::try_from(26).unwrap(), SignedHexFormat::TwosComplement),
+            format!("0x{}1a", "0".repeat(62))
+        );
+    }
+
     #[test]
     fn test_to_hex_string_with_length_basic() {
         assert_eq!(to_hex_string_with_length(U256::ZERO, 1).unwrap(), "0x00");
@@ -252,6 +456,19 @@ mod tests {
         assert_eq!(checksum.len(), 42);
     }
     
+    #[test]
+    fn test_address_to_short_string() {
+        let addr_bytes = [0x5a, 0xae, 0xb6, 0x05, 0x3f, 0x3e, 0x94, 0xc9,
+                         0xb9, 0xa0, 0x9f, 0x33, 0x66, 0x94, 0x35, 0xe7,
+                         0xef, 0x1b, 0xea, 0xed];
+        let addr = Address::from_slice(&addr_bytes);
+        assert_eq!(address_to_short_string(addr, 4, 4), "0x5aAe…eAed");
+        assert_eq!(address_to_short_string(addr, 6, 0), "0x5aAeb6…");
+        assert_eq!(address_to_short_string(addr, 0, 2), "0x…ed");
+        assert_eq!(address_to_short_string(addr, 20, 20), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        assert_eq!(address_to_short_string(addr, usize::MAX, 1), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+    }
+
     #[test]
     fn test_hex_digits_constant() {
         // Verify our HEX_DIGITS constant matches expectations
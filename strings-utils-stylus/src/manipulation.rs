@@ -0,0 +1,288 @@
+//! String manipulation: concatenation, splitting, trimming, searching,
+//! replacing, slicing, case conversion and padding
+//!
+//! Only `core` and `alloc` string APIs are used. Functions that can return a
+//! view of their input do, and the rest size their output buffer once up
+//! front, since every reallocation is paid for in gas.
+//!
+//! Slicing counts characters rather than bytes, so it never cuts a multi-byte
+//! UTF-8 sequence in half, and an out of range index is an error rather than
+//! a panic that would abort the contract.
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use core::iter;
+
+use alloy_primitives::U256;
+
+use crate::{to_hex_string_with_length, StringsError};
+
+/// Joins the parts into one string with a single allocation.
+pub fn concat(parts: &[&str]) -> String {
+    let mut result = String::with_capacity(parts.iter().map(|part| part.len()).sum());
+    for part in parts {
+        result.push_str(part);
+    }
+    result
+}
+
+/// Splits on every occurrence of `delimiter`.
+///
+/// Adjacent delimiters yield empty parts, so splitting always returns one more
+/// part than there are delimiters. An empty delimiter returns the whole input
+/// as the only part.
+pub fn split(s: &str, delimiter: &str) -> Vec<String> {
+    if delimiter.is_empty() {
+        return vec![s.to_string()];
+    }
+    s.split(delimiter).map(|part| part.to_string()).collect()
+}
+
+/// Strips leading and trailing ASCII whitespace.
+pub fn trim(s: &str) -> &str {
+    s.trim_matches(|c: char| c.is_ascii_whitespace())
+}
+
+/// Returns true if `s` begins with `prefix`.
+pub fn starts_with(s: &str, prefix: &str) -> bool {
+    s.as_bytes().starts_with(prefix.as_bytes())
+}
+
+/// Returns true if `s` ends with `suffix`.
+pub fn ends_with(s: &str, suffix: &str) -> bool {
+    s.as_bytes().ends_with(suffix.as_bytes())
+}
+
+/// Returns true if `needle` occurs anywhere in `s`. The empty string is always found.
+pub fn contains(s: &str, needle: &str) -> bool {
+    s.contains(needle)
+}
+
+/// Replaces every non-overlapping occurrence of `from` with `to`, scanning left to right.
+///
+/// An empty `from` matches nothing, so the input is returned unchanged.
+pub fn replace(s: &str, from: &str, to: &str) -> String {
+    if from.is_empty() {
+        return s.to_string();
+    }
+
+    let matches = s.matches(from).count();
+    let mut result = String::with_capacity(s.len() - matches * from.len() + matches * to.len());
+    let mut last = 0;
+    for (start, _) in s.match_indices(from) {
+        result.push_str(&s[last..start]);
+        result.push_str(to);
+        last = start + from.len();
+    }
+    result.push_str(&s[last..]);
+    result
+}
+
+/// Lowercases ASCII letters, leaving every other byte untouched.
+///
+/// Non-ASCII characters such as `É` are not case-folded, so this never fails
+/// and never changes the string's byte length.
+pub fn to_lower_ascii(s: &str) -> String {
+    s.to_ascii_lowercase()
+}
+
+/// Uppercases ASCII letters, leaving every other byte untouched.
+pub fn to_upper_ascii(s: &str) -> String {
+    s.to_ascii_uppercase()
+}
+
+/// Compares two strings ignoring ASCII case, without allocating.
+///
+/// Non-ASCII characters must match exactly, so `"É"` and `"é"` differ.
+pub fn equals_ignore_case(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+/// Left-pads `s` with `fill` up to `width` characters.
+///
+/// A string already longer than `width` is an error rather than being truncated, and a
+/// `width` too large to allocate fails with `WidthTooLarge`.
+pub fn pad_left(s: &str, width: usize, fill: char) -> Result<String, StringsError> {
+    let padding = padding(s, width)?;
+    let mut result = String::with_capacity(s.len() + padding * fill.len_utf8());
+    result.extend(iter::repeat_n(fill, padding));
+    result.push_str(s);
+    Ok(result)
+}
+
+/// Right-pads `s` with `fill` up to `width` characters.
+pub fn pad_right(s: &str, width: usize, fill: char) -> Result<String, StringsError> {
+    let padding = padding(s, width)?;
+    let mut result = String::with_capacity(s.len() + padding * fill.len_utf8());
+    result.push_str(s);
+    result.extend(iter::repeat_n(fill, padding));
+    Ok(result)
+}
+
+/// Formats `value` as exactly `2 * bytes` lowercase hex digits, without a `0x` prefix.
+///
+/// For fixed-width identifiers embedded in other strings. Fails like
+/// `to_hex_string_with_length` when the value needs more digits, and with
+/// `WidthTooLarge` when the digits wouldn't fit in memory.
+pub fn zero_pad_hex(value: U256, bytes: usize) -> Result<String, StringsError> {
+    if bytes > (usize::MAX - 2) / 2 {
+        return Err(StringsError::WidthTooLarge { width: bytes });
+    }
+    let mut hex = to_hex_string_with_length(value, bytes)?;
+    hex.drain(..2);
+    Ok(hex)
+}
+
+/// Number of fill characters needed to reach `width`.
+///
+/// Capping `width` at a quarter of `usize::MAX` keeps the padded byte length, at most
+/// four bytes per character, from overflowing.
+fn padding(s: &str, width: usize) -> Result<usize, StringsError> {
+    if width > usize::MAX / 4 {
+        return Err(StringsError::WidthTooLarge { width });
+    }
+    let length = s.chars().count();
+    width.checked_sub(length).ok_or(StringsError::ExceedsWidth { length, width })
+}
+
+/// Returns the characters from index `start` up to but not including `end`.
+///
+/// Indices count characters, not bytes. `end` may equal the character count.
+pub fn substring(s: &str, start: usize, end: usize) -> Result<&str, StringsError> {
+    if start > end {
+        return Err(StringsError::InvalidRange { start, end });
+    }
+    let from = byte_offset(s, start)?;
+    let to = from + byte_offset(&s[from..], end - start).map_err(|_| out_of_bounds(s, end))?;
+    Ok(&s[from..to])
+}
+
+/// Returns the character at character index `index`.
+pub fn char_at(s: &str, index: usize) -> Result<char, StringsError> {
+    s.chars().nth(index).ok_or_else(|| out_of_bounds(s, index))
+}
+
+/// Byte offset of the character at `index`, or the byte length when `index` is the character count.
+fn byte_offset(s: &str, index: usize) -> Result<usize, StringsError> {
+    s.char_indices()
+        .map(|(offset, _)| offset)
+        .chain(iter::once(s.len()))
+        .nth(index)
+        .ok_or_else(|| out_of_bounds(s, index))
+}
+
+fn out_of_bounds(s: &str, index: usize) -> StringsError {
+    StringsError::IndexOutOfBounds { index, length: s.chars().count() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(concat(&[]), "");
+        assert_eq!(concat(&["data:application/json;base64,", "eyJ9"]), "data:application/json;base64,eyJ9");
+        assert_eq!(concat(&["a", "", "b", "c"]), "abc");
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("a,b,c", ","), vec!["a", "b", "c"]);
+        assert_eq!(split("a,,b,", ","), vec!["a", "", "b", ""]);
+        assert_eq!(split("one::two", "::"), vec!["one", "two"]);
+        assert_eq!(split("", ","), vec![""]);
+        assert_eq!(split("abc", ""), vec!["abc"]);
+    }
+
+    #[test]
+    fn test_trim() {
+        assert_eq!(trim("  hello \t\n"), "hello");
+        assert_eq!(trim("   "), "");
+        assert_eq!(trim("a b"), "a b");
+    }
+
+    #[test]
+    fn test_search() {
+        assert!(starts_with("ipfs://Qm", "ipfs://"));
+        assert!(!starts_with("ipfs", "ipfs://"));
+        assert!(ends_with("image.png", ".png"));
+        assert!(!ends_with("image.png", ".jpg"));
+        assert!(contains("hello world", "o w"));
+        assert!(contains("hello", ""));
+        assert!(!contains("hello", "world"));
+    }
+
+    #[test]
+    fn test_replace() {
+        assert_eq!(replace("a-b-c", "-", "::"), "a::b::c");
+        assert_eq!(replace("aaaa", "aa", "b"), "bb");
+        assert_eq!(replace("hello", "xyz", "q"), "hello");
+        assert_eq!(replace("hello", "", "q"), "hello");
+        assert_eq!(replace("ünïcödé", "ï", "i"), "ünicödé");
+    }
+
+    #[test]
+    fn test_substring() {
+        assert_eq!(substring("hello", 1, 4).unwrap(), "ell");
+        assert_eq!(substring("hello", 0, 5).unwrap(), "hello");
+        assert_eq!(substring("hello", 5, 5).unwrap(), "");
+        // Multi-byte characters count as one index each
+        assert_eq!(substring("héllo wörld", 1, 4).unwrap(), "éll");
+        assert_eq!(substring("日本語", 1, 3).unwrap(), "本語");
+    }
+
+    #[test]
+    fn test_substring_rejects_invalid() {
+        assert!(matches!(substring("hello", 3, 2), Err(StringsError::InvalidRange { start: 3, end: 2 })));
+        assert!(matches!(
+            substring("hello", 2, 6),
+            Err(StringsError::IndexOutOfBounds { index: 6, length: 5 })
+        ));
+        assert!(matches!(substring("日本語", 4, 4), Err(StringsError::IndexOutOfBounds { index: 4, length: 3 })));
+    }
+
+    #[test]
+    fn test_char_at() {
+        assert_eq!(char_at("hello", 0).unwrap(), 'h');
+        assert_eq!(char_at("héllo", 1).unwrap(), 'é');
+        assert_eq!(char_at("日本語", 2).unwrap(), '語');
+        assert!(matches!(char_at("日本語", 3), Err(StringsError::IndexOutOfBounds { index: 3, length: 3 })));
+        assert!(matches!(char_at("", 0), Err(StringsError::IndexOutOfBounds { index: 0, length: 0 })));
+    }
+
+    #[test]
+    fn test_ascii_case() {
+        assert_eq!(to_lower_ascii("USDC"), "usdc");
+        assert_eq!(to_upper_ascii("weth9"), "WETH9");
+        assert_eq!(to_lower_ascii("ÉTH-Coin"), "Éth-coin");
+        assert_eq!(to_upper_ascii("straße"), "STRAßE");
+        assert!(equals_ignore_case("UsDc", "usdC"));
+        assert!(!equals_ignore_case("USDC", "USDT"));
+        assert!(!equals_ignore_case("É", "é"));
+        assert!(!equals_ignore_case("abc", "abcd"));
+    }
+
+    #[test]
+    fn test_padding() {
+        assert_eq!(pad_left("42", 5, '0').unwrap(), "00042");
+        assert_eq!(pad_right("ab", 4, '.').unwrap(), "ab..");
+        assert_eq!(pad_left("abc", 3, ' ').unwrap(), "abc");
+        assert_eq!(pad_left("é", 3, '·').unwrap(), "··é");
+        assert!(matches!(pad_left("abcd", 3, ' '), Err(StringsError::ExceedsWidth { length: 4, width: 3 })));
+        assert!(matches!(pad_right("日本語", 2, ' '), Err(StringsError::ExceedsWidth { length: 3, width: 2 })));
+        assert!(matches!(pad_left("a", usize::MAX, ' '), Err(StringsError::WidthTooLarge { width: usize::MAX })));
+        assert!(matches!(pad_right("a", usize::MAX / 4 + 1, ' '), Err(StringsError::WidthTooLarge { .. })));
+    }
+
+    #[test]
+    fn test_zero_pad_hex() {
+        assert_eq!(zero_pad_hex(U256::from(0xab), 4).unwrap(), "000000ab");
+        assert_eq!(zero_pad_hex(U256::ZERO, 1).unwrap(), "00");
+        assert_eq!(zero_pad_hex(U256::ZERO, 0).unwrap(), "");
+        assert!(matches!(zero_pad_hex(U256::from(0x100), 1), Err(StringsError::InsufficientHexLength { .. })));
+        assert!(matches!(zero_pad_hex(U256::ZERO, usize::MAX), Err(StringsError::WidthTooLarge { width: usize::MAX })));
+    }
+}
@@ -0,0 +1,82 @@
+//! ENS-style name validation and normalization
+//!
+//! A pragmatic ASCII subset of ENSIP-15: names are lowercased, split into
+//! dot-separated labels, and every label may only contain `a-z`, `0-9`,
+//! `-` and leading `_`. Anything outside that, including all non-ASCII
+//! characters and emoji, is rejected rather than normalized, so a name that
+//! passes here is also a valid ENSIP-15 name.
+
+use alloc::string::String;
+
+use crate::StringsError;
+
+/// Longest label accepted, in bytes
+pub const MAX_LABEL_LENGTH: usize = 63;
+
+/// Lowercases and validates a dot-separated name like "Alice.eth".
+///
+/// Returns `InvalidLabelLength` for an empty or overlong label and
+/// `InvalidCharacter` with the byte position for a forbidden character, an
+/// underscore after the start of a label, or the reserved `--` at label
+/// positions 3 and 4 (as in punycode's "xn--").
+pub fn normalize_name(name: &str) -> Result<String, StringsError> {
+    let mut offset = 0;
+    for (label_index, label) in name.split('.').enumerate() {
+        if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+            return Err(StringsError::InvalidLabelLength { label: label_index, length: label.len() });
+        }
+        validate_label(label.as_bytes(), offset)?;
+        offset += label.len() + 1;
+    }
+
+    Ok(name.to_ascii_lowercase())
+}
+
+fn validate_label(label: &[u8], offset: usize) -> Result<(), StringsError> {
+    // Underscores may only lead the label
+    let leading_underscores = label.iter().take_while(|&&c| c == b'_').count();
+    for (i, &c) in label.iter().enumerate().skip(leading_underscores) {
+        if !(c.is_ascii_alphanumeric() || c == b'-') {
+            return Err(StringsError::InvalidCharacter { position: offset + i });
+        }
+    }
+
+    if label.get(2..4) == Some(&b"--"[..]) {
+        return Err(StringsError::InvalidCharacter { position: offset + 2 });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name("alice.eth").unwrap(), "alice.eth");
+        assert_eq!(normalize_name("Alice.ETH").unwrap(), "alice.eth");
+        assert_eq!(normalize_name("sub.my-name42.eth").unwrap(), "sub.my-name42.eth");
+        assert_eq!(normalize_name("__private.eth").unwrap(), "__private.eth");
+        assert_eq!(normalize_name("a").unwrap(), "a");
+        assert_eq!(normalize_name(&"x".repeat(MAX_LABEL_LENGTH)).unwrap(), "x".repeat(MAX_LABEL_LENGTH));
+    }
+
+    #[test]
+    fn test_normalize_name_rejects_bad_labels() {
+        assert!(matches!(normalize_name(""), Err(StringsError::InvalidLabelLength { label: 0, length: 0 })));
+        assert!(matches!(normalize_name("alice..eth"), Err(StringsError::InvalidLabelLength { label: 1, length: 0 })));
+        assert!(matches!(normalize_name("alice.eth."), Err(StringsError::InvalidLabelLength { label: 2, length: 0 })));
+        let long = format!("{}.eth", "x".repeat(MAX_LABEL_LENGTH + 1));
+        assert!(matches!(normalize_name(&long), Err(StringsError::InvalidLabelLength { label: 0, length: 64 })));
+    }
+
+    #[test]
+    fn test_normalize_name_rejects_forbidden_characters() {
+        assert!(matches!(normalize_name("al ice.eth"), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(normalize_name("alice.et/h"), Err(StringsError::InvalidCharacter { position: 8 })));
+        assert!(matches!(normalize_name("alicé.eth"), Err(StringsError::InvalidCharacter { position: 4 })));
+        assert!(matches!(normalize_name("a_b.eth"), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(normalize_name("xn--abc.eth"), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(normalize_name("eth.ab--c"), Err(StringsError::InvalidCharacter { position: 6 })));
+    }
+}
@@ -0,0 +1,337 @@
+//! Parsing strings back into numbers, bytes and addresses
+//!
+//! The inverse of the formatting functions in the crate root, for handling
+//! user-supplied strings on-chain. Parsing is strict: no whitespace,
+//! separators or leading `+` on unsigned values, and input that doesn't fit
+//! the target type is an error instead of being wrapped.
+
+use alloc::vec::Vec;
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{address_to_checksum_hex_string, SignedHexFormat, StringsError, ADDRESS_LENGTH};
+
+/// Parses an ASCII decimal string into a U256.
+///
+/// The inverse of `to_string`. Leading zeros are accepted.
+pub fn parse_uint(input: &str) -> Result<U256, StringsError> {
+    parse_digits(input.as_bytes(), 0)
+}
+
+/// Parses an ASCII decimal string with an optional `-` or `+` sign into an I256.
+///
+/// The inverse of `to_string_signed`. Accepts the full range down to `I256::MIN`.
+pub fn parse_int(input: &str) -> Result<I256, StringsError> {
+    let bytes = input.as_bytes();
+    let (negative, offset) = match bytes.first() {
+        Some(b'-') => (true, 1),
+        Some(b'+') => (false, 1),
+        _ => (false, 0),
+    };
+
+    let magnitude = parse_digits(&bytes[offset..], offset)?;
+    signed_from_magnitude(magnitude, negative)
+}
+
+/// Parses a string of digits in any radix from 2 to 36 into a U256.
+///
+/// The inverse of `to_radix_string`. Letters may be either case; no prefix is accepted.
+pub fn parse_radix(input: &str, radix: u8) -> Result<U256, StringsError> {
+    if !(2..=36).contains(&radix) {
+        return Err(StringsError::InvalidRadix { radix });
+    }
+    parse_radix_digits(input.as_bytes(), radix, 0)
+}
+
+/// Parses a signed hex string written in the given format.
+///
+/// The inverse of `to_hex_string_signed`. The `0x` prefix is optional and
+/// digits may be either case. In `SignMagnitude` a leading `-` negates the
+/// value; in `TwosComplement` the digits are the raw 256-bit word, so input
+/// shorter than 64 digits is zero-extended and always non-negative.
+pub fn parse_hex_signed(input: &str, format: SignedHexFormat) -> Result<I256, StringsError> {
+    let bytes = input.as_bytes();
+    match format {
+        SignedHexFormat::SignMagnitude => {
+            let negative = bytes.first() == Some(&b'-');
+            let sign = usize::from(negative);
+            let (digits, offset) = strip_hex_prefix(&bytes[sign..]);
+            let magnitude = parse_radix_digits(digits, 16, sign + offset)?;
+            signed_from_magnitude(magnitude, negative)
+        }
+        SignedHexFormat::TwosComplement => {
+            let (digits, offset) = strip_hex_prefix(bytes);
+            Ok(I256::from_raw(parse_radix_digits(digits, 16, offset)?))
+        }
+    }
+}
+
+/// Parses a hex string, with or without a `0x` prefix, into bytes.
+///
+/// Both cases are accepted. Each byte needs two digits, so the digit count must be even.
+pub fn parse_hex(input: &str) -> Result<Vec<u8>, StringsError> {
+    let (digits, offset) = strip_hex_prefix(input.as_bytes());
+    if digits.len() % 2 != 0 {
+        return Err(StringsError::InvalidHexLength { length: digits.len() });
+    }
+
+    let mut bytes = Vec::with_capacity(digits.len() / 2);
+    for (i, pair) in digits.chunks(2).enumerate() {
+        let position = offset + 2 * i;
+        let high = hex_value(pair[0]).ok_or(StringsError::InvalidCharacter { position })?;
+        let low = hex_value(pair[1]).ok_or(StringsError::InvalidCharacter { position: position + 1 })?;
+        bytes.push((high << 4) | low);
+    }
+
+    Ok(bytes)
+}
+
+/// Parses a 40 digit hex address, with or without a `0x` prefix.
+///
+/// Capitalization is ignored; use `validate_checksum_address` to enforce EIP-55.
+pub fn parse_address(input: &str) -> Result<Address, StringsError> {
+    let (digits, _) = strip_hex_prefix(input.as_bytes());
+    if digits.len() != 2 * ADDRESS_LENGTH {
+        return Err(StringsError::InvalidAddressLength { length: digits.len() });
+    }
+
+    Ok(Address::from_slice(&parse_hex(input)?))
+}
+
+/// Parses an address and checks its EIP-55 capitalization.
+///
+/// The inverse of `address_to_checksum_hex_string`. Validation is strict, so an
+/// all-lowercase or all-uppercase address fails unless that is its checksum.
+pub fn validate_checksum_address(input: &str) -> Result<Address, StringsError> {
+    let addr = parse_address(input)?;
+    let (digits, _) = strip_hex_prefix(input.as_bytes());
+    let checksum = address_to_checksum_hex_string(addr);
+    if digits != &checksum.as_bytes()[2..] {
+        return Err(StringsError::InvalidChecksum);
+    }
+
+    Ok(addr)
+}
+
+/// Splits off a `0x` or `0X` prefix, returning the digits and their offset.
+fn strip_hex_prefix(input: &[u8]) -> (&[u8], usize) {
+    match input {
+        [b'0', b'x' | b'X', rest @ ..] => (rest, 2),
+        _ => (input, 0),
+    }
+}
+
+/// Value of a hex digit in either case.
+pub(crate) fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Parses digits in `radix`, reporting positions relative to the full input.
+fn parse_radix_digits(digits: &[u8], radix: u8, offset: usize) -> Result<U256, StringsError> {
+    if digits.is_empty() {
+        return Err(StringsError::EmptyInput);
+    }
+
+    let base = U256::from(radix);
+    let mut value = U256::ZERO;
+    for (i, &c) in digits.iter().enumerate() {
+        let digit = (c as char)
+            .to_digit(radix as u32)
+            .ok_or(StringsError::InvalidCharacter { position: offset + i })?;
+        value = value
+            .checked_mul(base)
+            .and_then(|v| v.checked_add(U256::from(digit)))
+            .ok_or(StringsError::Overflow)?;
+    }
+
+    Ok(value)
+}
+
+/// Applies a sign to a magnitude, allowing one more on the negative side for `I256::MIN`.
+fn signed_from_magnitude(magnitude: U256, negative: bool) -> Result<I256, StringsError> {
+    // |I256::MIN| is one more than I256::MAX
+    let limit = I256::MAX.into_raw() + U256::from(u8::from(negative));
+    if magnitude > limit {
+        return Err(StringsError::Overflow);
+    }
+
+    let value = I256::from_raw(magnitude);
+    Ok(if negative { value.wrapping_neg() } else { value })
+}
+
+/// Parses decimal digits, reporting positions relative to the full input.
+pub(crate) fn parse_digits(digits: &[u8], offset: usize) -> Result<U256, StringsError> {
+    if digits.is_empty() {
+        return Err(StringsError::EmptyInput);
+    }
+
+    let ten = U256::from(10);
+    let mut value = U256::ZERO;
+    for (i, &c) in digits.iter().enumerate() {
+        if !c.is_ascii_digit() {
+            return Err(StringsError::InvalidCharacter { position: offset + i });
+        }
+        value = value
+            .checked_mul(ten)
+            .and_then(|v| v.checked_add(U256::from(c - b'0')))
+            .ok_or(StringsError::Overflow)?;
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{address_to_hex_string, to_hex_string_signed, to_radix_string, to_string, to_string_signed};
+
+    #[test]
+    fn test_parse_uint() {
+        assert_eq!(parse_uint("0").unwrap(), U256::ZERO);
+        assert_eq!(parse_uint("12345").unwrap(), U256::from(12345));
+        assert_eq!(parse_uint("007").unwrap(), U256::from(7));
+        assert_eq!(parse_uint(&to_string(U256::MAX)).unwrap(), U256::MAX);
+    }
+
+    #[test]
+    fn test_parse_uint_rejects_invalid() {
+        assert!(matches!(parse_uint(""), Err(StringsError::EmptyInput)));
+        assert!(matches!(parse_uint("12a4"), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(parse_uint("+1"), Err(StringsError::InvalidCharacter { position: 0 })));
+        assert!(matches!(parse_uint(" 1"), Err(StringsError::InvalidCharacter { position: 0 })));
+        // U256::MAX + 1
+        let too_big = "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+        assert!(matches!(parse_uint(too_big), Err(StringsError::Overflow)));
+    }
+
+    #[test]
+    fn test_parse_int() {
+        assert_eq!(parse_int("0").unwrap(), I256::ZERO);
+        assert_eq!(parse_int("-0").unwrap(), I256::ZERO);
+        assert_eq!(parse_int("+42").unwrap(), I256::try_from(42).unwrap());
+        assert_eq!(parse_int("-456").unwrap(), I256::try_from(-456).unwrap());
+        assert_eq!(parse_int(&to_string_signed(I256::MAX)).unwrap(), I256::MAX);
+        assert_eq!(parse_int(&to_string_signed(I256::MIN)).unwrap(), I256::MIN);
+    }
+
+    #[test]
+    fn test_parse_int_rejects_invalid() {
+        assert!(matches!(parse_int("-"), Err(StringsError::EmptyInput)));
+        assert!(matches!(parse_int("--1"), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(parse_int("-1x"), Err(StringsError::InvalidCharacter { position: 2 })));
+
+        let above_max = to_string(I256::MAX.into_raw() + U256::from(1));
+        assert!(matches!(parse_int(&above_max), Err(StringsError::Overflow)));
+        let below_min = format!("-{}", to_string(I256::MIN.into_raw() + U256::from(1)));
+        assert!(matches!(parse_int(&below_min), Err(StringsError::Overflow)));
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("").unwrap(), Vec::<u8>::new());
+        assert_eq!(parse_hex("0x").unwrap(), Vec::<u8>::new());
+        assert_eq!(parse_hex("0x00ff").unwrap(), vec![0x00, 0xff]);
+        assert_eq!(parse_hex("DEADbeef").unwrap(), vec![0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(parse_hex("0XAb").unwrap(), vec![0xab]);
+    }
+
+    #[test]
+    fn test_parse_hex_rejects_invalid() {
+        assert!(matches!(parse_hex("0xabc"), Err(StringsError::InvalidHexLength { length: 3 })));
+        assert!(matches!(parse_hex("0x0g"), Err(StringsError::InvalidCharacter { position: 3 })));
+        assert!(matches!(parse_hex("zz"), Err(StringsError::InvalidCharacter { position: 0 })));
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let expected = Address::from_slice(&[
+            0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99,
+            0xaa, 0xbb, 0xcc,
+        ]);
+        assert_eq!(parse_address("0x123456789abcdef0112233445566778899aabbcc").unwrap(), expected);
+        assert_eq!(parse_address("123456789ABCDEF0112233445566778899AABBCC").unwrap(), expected);
+        assert_eq!(parse_address(&address_to_hex_string(expected)).unwrap(), expected);
+        assert!(matches!(parse_address("0x1234"), Err(StringsError::InvalidAddressLength { length: 4 })));
+    }
+
+    #[test]
+    fn test_validate_checksum_address() {
+        // EIP-55 test vectors
+        for input in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let addr = validate_checksum_address(input).unwrap();
+            assert_eq!(address_to_checksum_hex_string(addr), input);
+        }
+
+        let lowercase = "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        assert!(parse_address(lowercase).is_ok());
+        assert!(matches!(validate_checksum_address(lowercase), Err(StringsError::InvalidChecksum)));
+        assert!(matches!(
+            validate_checksum_address("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+            Err(StringsError::InvalidChecksum)
+        ));
+    }
+
+    #[test]
+    fn test_parse_radix() {
+        assert_eq!(parse_radix("101", 2).unwrap(), U256::from(5));
+        assert_eq!(parse_radix("777", 8).unwrap(), U256::from(511));
+        assert_eq!(parse_radix("FF", 16).unwrap(), U256::from(255));
+        assert_eq!(parse_radix("zZ", 36).unwrap(), U256::from(35 * 36 + 35));
+        for radix in [2, 7, 10, 16, 36] {
+            assert_eq!(parse_radix(&to_radix_string(U256::MAX, radix).unwrap(), radix).unwrap(), U256::MAX);
+        }
+    }
+
+    #[test]
+    fn test_parse_radix_rejects_invalid() {
+        assert!(matches!(parse_radix("1", 0), Err(StringsError::InvalidRadix { radix: 0 })));
+        assert!(matches!(parse_radix("", 2), Err(StringsError::EmptyInput)));
+        assert!(matches!(parse_radix("102", 2), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(parse_radix("0x1f", 16), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(parse_radix(&"1".repeat(257), 2), Err(StringsError::Overflow)));
+    }
+
+    #[test]
+    fn test_parse_hex_signed() {
+        let minus_26 = I256::try_from(-26).unwrap();
+        assert_eq!(parse_hex_signed("-0x1a", SignedHexFormat::SignMagnitude).unwrap(), minus_26);
+        assert_eq!(parse_hex_signed("1A", SignedHexFormat::SignMagnitude).unwrap(), I256::try_from(26).unwrap());
+        assert_eq!(parse_hex_signed(&"f".repeat(64), SignedHexFormat::TwosComplement).unwrap(), I256::MINUS_ONE);
+        assert_eq!(parse_hex_signed("0xff", SignedHexFormat::TwosComplement).unwrap(), I256::try_from(255).unwrap());
+
+        for value in [I256::ZERO, minus_26, I256::MAX, I256::MIN] {
+            for format in [SignedHexFormat::SignMagnitude, SignedHexFormat::TwosComplement] {
+                assert_eq!(parse_hex_signed(&to_hex_string_signed(value, format), format).unwrap(), value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_hex_signed_rejects_invalid() {
+        assert!(matches!(parse_hex_signed("-", SignedHexFormat::SignMagnitude), Err(StringsError::EmptyInput)));
+        assert!(matches!(
+            parse_hex_signed("-0x1g", SignedHexFormat::SignMagnitude),
+            Err(StringsError::InvalidCharacter { position: 4 })
+        ));
+        assert!(matches!(
+            parse_hex_signed("-0x1", SignedHexFormat::TwosComplement),
+            Err(StringsError::InvalidCharacter { position: 0 })
+        ));
+        let above_max = format!("0x8{}", "0".repeat(63));
+        assert!(matches!(parse_hex_signed(&above_max, SignedHexFormat::SignMagnitude), Err(StringsError::Overflow)));
+        assert!(matches!(
+            parse_hex_signed(&"f".repeat(65), SignedHexFormat::TwosComplement),
+            Err(StringsError::Overflow)
+        ));
+    }
+}
@@ -0,0 +1,85 @@
+//! Function selectors and event topics from signature strings
+//!
+//! Signatures are normalized before hashing so that the form written by hand
+//! and the canonical form agree: whitespace is removed and the `uint`/`int`
+//! aliases become `uint256`/`int256`. Parameter names are not stripped, so
+//! signatures should list types only.
+
+use alloc::string::String;
+
+use alloy_primitives::{keccak256, FixedBytes, B256};
+
+/// Returns the canonical form of a signature, e.g. `"transfer(address, uint)"`
+/// becomes `"transfer(address,uint256)"`.
+pub fn normalize_signature(signature: &str) -> String {
+    let mut result = String::with_capacity(signature.len() + 8);
+    let mut rest = signature;
+    let mut is_name = true;
+
+    while let Some(c) = rest.chars().next() {
+        if is_identifier_char(c) {
+            let end = rest.find(|c: char| !is_identifier_char(c)).unwrap_or(rest.len());
+            let token = &rest[..end];
+            // The function or event name is never an alias
+            match token {
+                "uint" if !is_name => result.push_str("uint256"),
+                "int" if !is_name => result.push_str("int256"),
+                _ => result.push_str(token),
+            }
+            is_name = false;
+            rest = &rest[end..];
+        } else {
+            if !c.is_whitespace() {
+                result.push(c);
+            }
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    result
+}
+
+/// Returns the 4-byte function selector: the first bytes of the keccak256 of the normalized signature.
+pub fn selector(signature: &str) -> FixedBytes<4> {
+    let hash = keccak256(normalize_signature(signature).as_bytes());
+    FixedBytes::from_slice(&hash[..4])
+}
+
+/// Returns the event topic: the keccak256 of the normalized signature.
+pub fn event_topic(signature: &str) -> B256 {
+    keccak256(normalize_signature(signature).as_bytes())
+}
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::{b256, fixed_bytes};
+
+    #[test]
+    fn test_normalize_signature() {
+        assert_eq!(normalize_signature("transfer(address,uint256)"), "transfer(address,uint256)");
+        assert_eq!(normalize_signature(" transfer( address , uint ) "), "transfer(address,uint256)");
+        assert_eq!(normalize_signature("f(uint[], (int, bytes32))"), "f(uint256[],(int256,bytes32))");
+        assert_eq!(normalize_signature("g(uint8,uint256,int128)"), "g(uint8,uint256,int128)");
+        // Only types are expanded, not a function called `int`
+        assert_eq!(normalize_signature("int(uint)"), "int(uint256)");
+    }
+
+    #[test]
+    fn test_selector() {
+        assert_eq!(selector("transfer(address,uint256)"), fixed_bytes!("a9059cbb"));
+        assert_eq!(selector("transfer(address, uint)"), fixed_bytes!("a9059cbb"));
+        assert_eq!(selector("balanceOf(address)"), fixed_bytes!("70a08231"));
+        assert_eq!(selector("f(uint[],(int,bytes32))"), fixed_bytes!("e89ad46f"));
+    }
+
+    #[test]
+    fn test_event_topic() {
+        let transfer = b256!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+        assert_eq!(event_topic("Transfer(address,address,uint256)"), transfer);
+        assert_eq!(event_topic("Transfer(address, address, uint)"), transfer);
+    }
+}
@@ -0,0 +1,143 @@
+//! Placeholder formatting for messages and metadata strings
+//!
+//! A small runtime counterpart of `format!`: `{}` takes the next argument,
+//! `{0}` takes an argument by index, and `{{` / `}}` are literal braces.
+//! Templates are parsed as they are filled, so a malformed placeholder is
+//! reported with its position instead of panicking.
+
+use alloc::string::String;
+
+use alloy_primitives::{Address, I256, U256};
+
+use crate::{address_to_checksum_hex_string, to_string, to_string_signed, StringsError};
+
+/// A value to substitute into a template.
+#[derive(Debug, Clone, Copy)]
+pub enum TemplateArg<'a> {
+    /// Formatted in decimal, like `to_string`
+    Uint(U256),
+    /// Formatted in decimal with a leading `-` when negative
+    Int(I256),
+    /// Formatted as an EIP-55 checksummed address
+    Address(Address),
+    /// Inserted as is
+    Str(&'a str),
+}
+
+impl TemplateArg<'_> {
+    fn write(&self, buffer: &mut String) {
+        match self {
+            TemplateArg::Uint(value) => buffer.push_str(&to_string(*value)),
+            TemplateArg::Int(value) => buffer.push_str(&to_string_signed(*value)),
+            TemplateArg::Address(addr) => buffer.push_str(&address_to_checksum_hex_string(*addr)),
+            TemplateArg::Str(s) => buffer.push_str(s),
+        }
+    }
+}
+
+/// Fills `{}` and `{n}` placeholders in `template` with `args`.
+///
+/// `{}` placeholders take arguments in order, independently of indexed ones.
+/// Unused arguments are allowed. Returns `MissingArgument` for a placeholder
+/// without an argument and `InvalidCharacter` for a malformed placeholder or
+/// a lone `}`.
+pub fn format_template(template: &str, args: &[TemplateArg]) -> Result<String, StringsError> {
+    let bytes = template.as_bytes();
+    let mut result = String::with_capacity(template.len());
+    let mut next_arg = 0;
+    let mut literal_start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'{' | b'}' if bytes.get(i + 1) == Some(&bytes[i]) => {
+                // Doubled brace: keep one
+                result.push_str(&template[literal_start..=i]);
+                i += 2;
+                literal_start = i;
+            }
+            b'{' => {
+                result.push_str(&template[literal_start..i]);
+                let close = bytes[i..]
+                    .iter()
+                    .position(|&c| c == b'}')
+                    .ok_or(StringsError::InvalidCharacter { position: i })?;
+                let inner = &bytes[i + 1..i + close];
+
+                let index = if inner.is_empty() {
+                    next_arg += 1;
+                    next_arg - 1
+                } else {
+                    let mut index: usize = 0;
+                    for (offset, &c) in inner.iter().enumerate() {
+                        if !c.is_ascii_digit() {
+                            return Err(StringsError::InvalidCharacter { position: i + 1 + offset });
+                        }
+                        index = index.saturating_mul(10).saturating_add((c - b'0') as usize);
+                    }
+                    index
+                };
+                args.get(index).ok_or(StringsError::MissingArgument { index })?.write(&mut result);
+
+                i += close + 1;
+                literal_start = i;
+            }
+            b'}' => return Err(StringsError::InvalidCharacter { position: i }),
+            _ => i += 1,
+        }
+    }
+    result.push_str(&template[literal_start..]);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_template_sequential() {
+        let owner = Address::from_slice(&[
+            0x5a, 0xae, 0xb6, 0x05, 0x3f, 0x3e, 0x94, 0xc9, 0xb9, 0xa0, 0x9f, 0x33, 0x66, 0x94, 0x35, 0xe7, 0xef,
+            0x1b, 0xea, 0xed,
+        ]);
+        let message = format_template(
+            "Token #{} owned by {} moved {} ({})",
+            &[
+                TemplateArg::Uint(U256::from(42)),
+                TemplateArg::Address(owner),
+                TemplateArg::Int(I256::try_from(-3).unwrap()),
+                TemplateArg::Str("ok"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(message, "Token #42 owned by 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed moved -3 (ok)");
+    }
+
+    #[test]
+    fn test_format_template_indexed() {
+        let args = [TemplateArg::Str("a"), TemplateArg::Str("b")];
+        assert_eq!(format_template("{1}{0}{1}", &args).unwrap(), "bab");
+        assert_eq!(format_template("{1}{}{}", &args).unwrap(), "bab");
+        assert_eq!(format_template("no placeholders", &args).unwrap(), "no placeholders");
+        assert_eq!(format_template("", &[]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_format_template_escapes() {
+        let args = [TemplateArg::Uint(U256::from(1))];
+        assert_eq!(format_template("{{\"id\":{}}}", &args).unwrap(), "{\"id\":1}");
+        assert_eq!(format_template("{{}}", &[]).unwrap(), "{}");
+        assert_eq!(format_template("日本{}語", &args).unwrap(), "日本1語");
+    }
+
+    #[test]
+    fn test_format_template_rejects_invalid() {
+        let args = [TemplateArg::Str("a")];
+        assert!(matches!(format_template("{}{}", &args), Err(StringsError::MissingArgument { index: 1 })));
+        assert!(matches!(format_template("{5}", &args), Err(StringsError::MissingArgument { index: 5 })));
+        assert!(matches!(format_template("ab{", &args), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(format_template("{x}", &args), Err(StringsError::InvalidCharacter { position: 1 })));
+        assert!(matches!(format_template("a}b", &args), Err(StringsError::InvalidCharacter { position: 1 })));
+    }
+}
@@ -0,0 +1,122 @@
+//! Unix timestamps as calendar dates
+//!
+//! Dates use the proleptic Gregorian calendar in UTC, so receipts and
+//! metadata can show a readable date straight from `block_timestamp` without
+//! an oracle. The conversion is Howard Hinnant's `civil_from_days`, which
+//! only needs integer division and never iterates over years.
+
+use alloc::format;
+use alloc::string::String;
+
+use alloy_primitives::U256;
+
+use crate::to_string;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Days from 0000-03-01 to 1970-01-01
+const DAYS_TO_EPOCH: u64 = 719_468;
+/// Days in a 400 year Gregorian cycle
+const DAYS_PER_ERA: u64 = 146_097;
+
+/// Calendar date and time of a Unix timestamp, in UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateParts {
+    /// Full year; timestamps past year 9999 keep counting
+    pub year: U256,
+    /// Month, 1 to 12
+    pub month: u8,
+    /// Day of the month, 1 to 31
+    pub day: u8,
+    /// Hour, 0 to 23
+    pub hour: u8,
+    /// Minute, 0 to 59
+    pub minute: u8,
+    /// Second, 0 to 59
+    pub second: u8,
+}
+
+/// Splits a Unix timestamp into its UTC calendar date and time.
+pub fn timestamp_to_date_parts(timestamp: U256) -> DateParts {
+    let seconds_per_day = U256::from(SECONDS_PER_DAY);
+    let seconds = (timestamp % seconds_per_day).to::<u64>();
+    let days = timestamp / seconds_per_day;
+
+    // Count from 0000-03-01 so the leap day falls at the end of each year
+    let shifted = days + U256::from(DAYS_TO_EPOCH);
+    let era = shifted / U256::from(DAYS_PER_ERA);
+    let day_of_era = (shifted % U256::from(DAYS_PER_ERA)).to::<u64>();
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era * U256::from(400) + U256::from(year_of_era) + U256::from(u8::from(month <= 2));
+
+    DateParts {
+        year,
+        month: month as u8,
+        day: day as u8,
+        hour: (seconds / 3600) as u8,
+        minute: (seconds / 60 % 60) as u8,
+        second: (seconds % 60) as u8,
+    }
+}
+
+/// Formats a Unix timestamp as an ISO-8601 UTC string like "2023-11-14T22:13:20Z".
+///
+/// Years are zero-padded to four digits and grow past 9999 as needed.
+pub fn timestamp_to_iso8601(timestamp: U256) -> String {
+    let parts = timestamp_to_date_parts(timestamp);
+    format!(
+        "{:0>4}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        to_string(parts.year),
+        parts.month,
+        parts.day,
+        parts.hour,
+        parts.minute,
+        parts.second
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamp_to_iso8601() {
+        let cases = [
+            (0u64, "1970-01-01T00:00:00Z"),
+            (68_256, "1970-01-01T18:57:36Z"),
+            (951_782_400, "2000-02-29T00:00:00Z"),
+            (1_700_000_000, "2023-11-14T22:13:20Z"),
+            (4_107_542_400, "2100-03-01T00:00:00Z"),
+            (253_402_300_799, "9999-12-31T23:59:59Z"),
+            (253_402_300_800, "10000-01-01T00:00:00Z"),
+        ];
+        for (timestamp, expected) in cases {
+            assert_eq!(timestamp_to_iso8601(U256::from(timestamp)), expected);
+        }
+    }
+
+    #[test]
+    fn test_timestamp_to_date_parts() {
+        let parts = timestamp_to_date_parts(U256::from(1_700_000_000u64));
+        assert_eq!(
+            parts,
+            DateParts { year: U256::from(2023), month: 11, day: 14, hour: 22, minute: 13, second: 20 }
+        );
+
+        // 2100 is not a leap year: Feb 28 is followed by Mar 1
+        let feb_28 = timestamp_to_date_parts(U256::from(4_107_542_400u64 - 86_400));
+        assert_eq!((feb_28.month, feb_28.day), (2, 28));
+    }
+
+    #[test]
+    fn test_timestamp_to_date_parts_max() {
+        // Doesn't overflow, the year just gets very large
+        let parts = timestamp_to_date_parts(U256::MAX);
+        assert!(parts.year > U256::from(u64::MAX));
+        assert!((1..=12).contains(&parts.month));
+        assert!((1..=31).contains(&parts.day));
+    }
+}
@@ -0,0 +1,90 @@
+//! Percent-encoding for URLs (RFC 3986)
+//!
+//! Everything except the unreserved characters `A-Z a-z 0-9 - . _ ~` is
+//! encoded, so the output is safe in any URL component, including query
+//! parameters of a `tokenURI` built on-chain.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::parse::hex_value;
+use crate::StringsError;
+
+/// Uppercase hex digits, as RFC 3986 recommends for percent-encoding
+const UPPER_HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Percent-encodes every byte of `s` outside the unreserved set.
+pub fn url_encode(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for &b in s.as_bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            result.push(b as char);
+        } else {
+            result.push('%');
+            result.push(UPPER_HEX_DIGITS[(b >> 4) as usize] as char);
+            result.push(UPPER_HEX_DIGITS[(b & 0xf) as usize] as char);
+        }
+    }
+    result
+}
+
+/// Decodes `%XX` escapes, in either case, back into a string.
+///
+/// `+` is left as is, since it only means a space in form encoding. Returns
+/// `InvalidCharacter` for a malformed escape and `InvalidUtf8` if the decoded
+/// bytes aren't valid UTF-8.
+pub fn url_decode(s: &str) -> Result<String, StringsError> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let digit = |offset: usize| {
+                bytes
+                    .get(i + offset)
+                    .and_then(|&c| hex_value(c))
+                    .ok_or(StringsError::InvalidCharacter { position: i + offset })
+            };
+            decoded.push((digit(1)? << 4) | digit(2)?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    String::from_utf8(decoded)
+        .map_err(|e| StringsError::InvalidUtf8 { position: e.utf8_error().valid_up_to() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_url_encode() {
+        assert_eq!(url_encode("abcXYZ019-._~"), "abcXYZ019-._~");
+        assert_eq!(url_encode("a b&c=d/e?"), "a%20b%26c%3Dd%2Fe%3F");
+        assert_eq!(url_encode("100%"), "100%25");
+        assert_eq!(url_encode("é"), "%C3%A9");
+        assert_eq!(url_encode(""), "");
+    }
+
+    #[test]
+    fn test_url_decode() {
+        assert_eq!(url_decode("a%20b%26c%3Dd").unwrap(), "a b&c=d");
+        assert_eq!(url_decode("%c3%a9").unwrap(), "é");
+        assert_eq!(url_decode("a+b").unwrap(), "a+b");
+        for s in ["", "plain", "name=Token #1&owner=0xAbC", "日本語 / 100%"] {
+            assert_eq!(url_decode(&url_encode(s)).unwrap(), s);
+        }
+    }
+
+    #[test]
+    fn test_url_decode_rejects_invalid() {
+        assert!(matches!(url_decode("abc%"), Err(StringsError::InvalidCharacter { position: 4 })));
+        assert!(matches!(url_decode("%2"), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(url_decode("x%zz"), Err(StringsError::InvalidCharacter { position: 2 })));
+        assert!(matches!(url_decode("a%FFb"), Err(StringsError::InvalidUtf8 { position: 1 })));
+    }
+}
@@ -0,0 +1,342 @@
+//! Role-based access control for Stylus smart contracts
+//!
+//! Inspired by OpenZeppelin's AccessControl.sol and
+//! AccessControlEnumerable.sol. Each role is a `bytes32` id (conventionally
+//! `keccak256("<NAME>_ROLE")`) managed by an admin role, which defaults to
+//! `DEFAULT_ADMIN_ROLE`. Members of every role are kept in an enumerable set,
+//! so off-chain tooling and the contract itself can list who holds a power.
+
+use alloc::vec::Vec;
+use alloy_primitives::{Address, B256, U256};
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageAddress, StorageB256, StorageMap, StorageU256, StorageVec},
+};
+
+/// Admin of every role whose admin hasn't been changed
+pub const DEFAULT_ADMIN_ROLE: B256 = B256::ZERO;
+
+sol! {
+    /// Emitted when `account` is granted `role` by `sender`
+    event RoleGranted(bytes32 indexed role, address indexed account, address indexed sender);
+    /// Emitted when `account` loses `role`, revoked by `sender` (or renounced when they match)
+    event RoleRevoked(bytes32 indexed role, address indexed account, address indexed sender);
+    /// Emitted when the role managing `role` changes
+    event RoleAdminChanged(bytes32 indexed role, bytes32 indexed previous_admin_role, bytes32 indexed new_admin_role);
+
+    /// Thrown when the caller is missing the role required for the operation
+    error AccessControlUnauthorizedAccount();
+    /// Thrown when an account renounces a role on behalf of someone else
+    error AccessControlBadConfirmation();
+}
+
+/// Error types for AccessControl
+#[derive(SolidityError)]
+pub enum AccessControlError {
+    UnauthorizedAccount(AccessControlUnauthorizedAccount),
+    BadConfirmation(AccessControlBadConfirmation),
+}
+
+impl From<AccessControlUnauthorizedAccount> for AccessControlError {
+    fn from(err: AccessControlUnauthorizedAccount) -> Self {
+        AccessControlError::UnauthorizedAccount(err)
+    }
+}
+
+impl From<AccessControlBadConfirmation> for AccessControlError {
+    fn from(err: AccessControlBadConfirmation) -> Self {
+        AccessControlError::BadConfirmation(err)
+    }
+}
+
+/// Storage structure for AccessControl
+#[storage]
+pub struct AccessControl {
+    /// Members of each role; revoking swaps the last member into the gap
+    members: StorageMap<B256, StorageVec<StorageAddress>>,
+    /// Index + 1 of each member in `members`, zero for non-members
+    positions: StorageMap<B256, StorageMap<Address, StorageU256>>,
+    /// Role allowed to grant and revoke each role (`DEFAULT_ADMIN_ROLE` if unset)
+    admin_roles: StorageMap<B256, StorageB256>,
+}
+
+impl AccessControl {
+    /// Check if `account` holds `role`
+    pub fn has_role(&self, role: B256, account: Address) -> bool {
+        self.positions.getter(role).get(account) != U256::ZERO
+    }
+
+    /// Check that the caller holds `role`, returning the caller
+    ///
+    /// # Errors
+    ///
+    /// Returns `AccessControlError::UnauthorizedAccount` otherwise.
+    pub fn only_role(&self, role: B256) -> Result<Address, AccessControlError> {
+        let caller = self.vm().msg_sender();
+        if !self.has_role(role, caller) {
+            return Err(AccessControlError::UnauthorizedAccount(AccessControlUnauthorizedAccount {}));
+        }
+        Ok(caller)
+    }
+
+    /// Role that manages `role`
+    pub fn get_role_admin(&self, role: B256) -> B256 {
+        self.admin_roles.get(role)
+    }
+
+    /// Make `admin_role` the manager of `role`
+    ///
+    /// Callers are responsible for access control.
+    pub fn set_role_admin(&mut self, role: B256, admin_role: B256) {
+        let previous_admin_role = self.get_role_admin(role);
+        self.admin_roles.setter(role).set(admin_role);
+        log(
+            self.vm(),
+            RoleAdminChanged {
+                role,
+                previous_admin_role,
+                new_admin_role: admin_role,
+            },
+        );
+    }
+
+    /// Give `account` `role` (holders of the role's admin only)
+    ///
+    /// Granting a role the account already holds does nothing.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AccessControlError::UnauthorizedAccount` if the caller doesn't
+    /// hold the admin role of `role`.
+    pub fn grant_role(&mut self, role: B256, account: Address) -> Result<(), AccessControlError> {
+        self.only_role(self.get_role_admin(role))?;
+        self.grant(role, account);
+        Ok(())
+    }
+
+    /// Take `role` away from `account` (holders of the role's admin only)
+    ///
+    /// # Errors
+    ///
+    /// Returns `AccessControlError::UnauthorizedAccount` if the caller doesn't
+    /// hold the admin role of `role`.
+    pub fn revoke_role(&mut self, role: B256, account: Address) -> Result<(), AccessControlError> {
+        self.only_role(self.get_role_admin(role))?;
+        self.revoke(role, account);
+        Ok(())
+    }
+
+    /// Give up one of the caller's own roles
+    ///
+    /// `confirmation` must be the caller, so a role can't be renounced by
+    /// accident from a script meant for another account.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AccessControlError::BadConfirmation` if `confirmation` isn't
+    /// the caller.
+    pub fn renounce_role(&mut self, role: B256, confirmation: Address) -> Result<(), AccessControlError> {
+        if confirmation != self.vm().msg_sender() {
+            return Err(AccessControlError::BadConfirmation(AccessControlBadConfirmation {}));
+        }
+        self.revoke(role, confirmation);
+        Ok(())
+    }
+
+    /// Add `account` to `role` without checking the caller, returning false
+    /// if it already held it
+    ///
+    /// Use in constructors to hand out the initial roles.
+    pub fn grant(&mut self, role: B256, account: Address) -> bool {
+        if self.has_role(role, account) {
+            return false;
+        }
+        let mut members = self.members.setter(role);
+        members.push(account);
+        let position = U256::from(members.len());
+        self.positions.setter(role).setter(account).set(position);
+        log(
+            self.vm(),
+            RoleGranted {
+                role,
+                account,
+                sender: self.vm().msg_sender(),
+            },
+        );
+        true
+    }
+
+    /// Remove `account` from `role` without checking the caller, returning
+    /// false if it didn't hold it
+    pub fn revoke(&mut self, role: B256, account: Address) -> bool {
+        let position = self.positions.getter(role).get(account);
+        if position == U256::ZERO {
+            return false;
+        }
+        // Swap-remove: move the last member into the freed slot
+        let index = position.saturating_to::<usize>() - 1;
+        let mut members = self.members.setter(role);
+        let last_index = members.len() - 1;
+        if index != last_index {
+            let last = members.get(last_index).unwrap_or_default();
+            if let Some(mut slot) = members.setter(index) {
+                slot.set(last);
+            }
+            self.positions.setter(role).setter(last).set(position);
+        }
+        self.members.setter(role).pop();
+        self.positions.setter(role).setter(account).set(U256::ZERO);
+        log(
+            self.vm(),
+            RoleRevoked {
+                role,
+                account,
+                sender: self.vm().msg_sender(),
+            },
+        );
+        true
+    }
+
+    /// Number of accounts holding `role`
+    pub fn member_count(&self, role: B256) -> usize {
+        self.members.getter(role).len()
+    }
+
+    /// Account at `index` in the members of `role`
+    ///
+    /// Ordering changes whenever a member is revoked, so only page through
+    /// members within a single call.
+    pub fn member(&self, role: B256, index: usize) -> Option<Address> {
+        self.members.getter(role).get(index)
+    }
+
+    /// Every account holding `role`, in no particular order
+    pub fn members(&self, role: B256) -> Vec<Address> {
+        let members = self.members.getter(role);
+        (0..members.len()).filter_map(|i| members.get(i)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::b256;
+    use stylus_sdk::testing::*;
+
+    /// `keccak256("MINTER_ROLE")`
+    const MINTER_ROLE: B256 = b256!("9f2df0fed2c77648de5860a4cc508cd0818c85b8b8a1ab4ceeef8d981c8956a6");
+
+    /// `keccak256("MINTER_ADMIN_ROLE")`
+    const MINTER_ADMIN_ROLE: B256 = b256!("70480ee89cb38eff00b7d23da25713d52ce19c6ed428691d22c58b2f615e3d67");
+
+    #[storage]
+    struct TestContract {
+        access: AccessControl,
+    }
+
+    fn admin() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn deploy(vm: &TestVM) -> TestContract {
+        let mut contract = TestContract::from(vm);
+        vm.set_sender(admin());
+        assert!(contract.access.grant(DEFAULT_ADMIN_ROLE, admin()));
+        contract
+    }
+
+    #[test]
+    fn test_grant_and_revoke() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        assert!(contract.access.grant_role(MINTER_ROLE, alice()).is_ok());
+        assert!(contract.access.grant_role(MINTER_ROLE, bob()).is_ok());
+        assert!(contract.access.grant_role(MINTER_ROLE, admin()).is_ok());
+        // Granting twice doesn't add a second entry
+        assert!(!contract.access.grant(MINTER_ROLE, alice()));
+        assert_eq!(contract.access.members(MINTER_ROLE), vec![alice(), bob(), admin()]);
+
+        // Revoking swaps the last member into the gap
+        assert!(contract.access.revoke_role(MINTER_ROLE, alice()).is_ok());
+        assert!(!contract.access.has_role(MINTER_ROLE, alice()));
+        assert_eq!(contract.access.members(MINTER_ROLE), vec![admin(), bob()]);
+        assert_eq!(contract.access.member_count(MINTER_ROLE), 2);
+        assert_eq!(contract.access.member(MINTER_ROLE, 1), Some(bob()));
+        assert_eq!(contract.access.member(MINTER_ROLE, 2), None);
+
+        // Revoking the last member and a non-member
+        assert!(contract.access.revoke_role(MINTER_ROLE, bob()).is_ok());
+        assert!(!contract.access.revoke(MINTER_ROLE, bob()));
+        assert_eq!(contract.access.members(MINTER_ROLE), vec![admin()]);
+    }
+
+    #[test]
+    fn test_only_admin_manages_roles() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(contract.access.grant_role(MINTER_ROLE, alice()).is_ok());
+
+        // Holding a role doesn't let an account hand it out
+        vm.set_sender(alice());
+        assert!(contract.access.only_role(MINTER_ROLE).is_ok());
+        assert!(matches!(
+            contract.access.only_role(DEFAULT_ADMIN_ROLE),
+            Err(AccessControlError::UnauthorizedAccount(_))
+        ));
+        assert!(matches!(
+            contract.access.grant_role(MINTER_ROLE, bob()),
+            Err(AccessControlError::UnauthorizedAccount(_))
+        ));
+        assert!(contract.access.revoke_role(MINTER_ROLE, alice()).is_err());
+        assert!(contract.access.has_role(MINTER_ROLE, alice()));
+        assert!(!contract.access.has_role(MINTER_ROLE, bob()));
+    }
+
+    #[test]
+    fn test_role_admin() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert_eq!(contract.access.get_role_admin(MINTER_ROLE), DEFAULT_ADMIN_ROLE);
+
+        contract.access.set_role_admin(MINTER_ROLE, MINTER_ADMIN_ROLE);
+        assert_eq!(contract.access.get_role_admin(MINTER_ROLE), MINTER_ADMIN_ROLE);
+        assert!(contract.access.grant_role(MINTER_ADMIN_ROLE, alice()).is_ok());
+
+        // The default admin no longer manages minters, the minter admin does
+        assert!(contract.access.grant_role(MINTER_ROLE, bob()).is_err());
+        vm.set_sender(alice());
+        assert!(contract.access.grant_role(MINTER_ROLE, bob()).is_ok());
+        assert!(contract.access.revoke_role(MINTER_ROLE, bob()).is_ok());
+        assert!(contract.access.grant_role(MINTER_ADMIN_ROLE, bob()).is_err());
+    }
+
+    #[test]
+    fn test_renounce_role() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        assert!(contract.access.grant_role(MINTER_ROLE, alice()).is_ok());
+
+        // Nobody can renounce for someone else
+        vm.set_sender(bob());
+        assert!(matches!(
+            contract.access.renounce_role(MINTER_ROLE, alice()),
+            Err(AccessControlError::BadConfirmation(_))
+        ));
+
+        vm.set_sender(alice());
+        assert!(contract.access.renounce_role(MINTER_ROLE, alice()).is_ok());
+        assert!(!contract.access.has_role(MINTER_ROLE, alice()));
+        // Renouncing a role the caller doesn't hold is a no-op
+        assert!(contract.access.renounce_role(MINTER_ROLE, alice()).is_ok());
+    }
+}
@@ -0,0 +1,137 @@
+//! Initializable module for Stylus smart contracts
+//!
+//! Guards public init functions, inspired by OpenZeppelin's Initializable.sol.
+//! Contracts that set themselves up through a callable `new()`/`initialize()`
+//! (rather than a `#[constructor]`) call `initializer()` first, so an attacker
+//! can't call it again later to take over ownership or reset parameters.
+
+use alloy_primitives::U64;
+use stylus_sdk::{alloy_sol_types::sol, prelude::*, storage::StorageU64};
+
+sol! {
+    /// Emitted when the contract is initialized or reinitialized to `version`
+    event Initialized(uint64 version);
+
+    /// Thrown when the contract is already initialized to this or a later version
+    error InvalidInitialization();
+}
+
+/// Error types for Initializable
+#[derive(SolidityError)]
+pub enum InitializableError {
+    InvalidInitialization(InvalidInitialization),
+}
+
+impl From<InvalidInitialization> for InitializableError {
+    fn from(err: InvalidInitialization) -> Self {
+        InitializableError::InvalidInitialization(err)
+    }
+}
+
+/// Storage structure for Initializable
+#[storage]
+pub struct Initializable {
+    /// Highest version initialized so far (`u64::MAX` once disabled)
+    initialized: StorageU64,
+}
+
+impl Initializable {
+    /// Check and record the first initialization (version 1)
+    ///
+    /// # Errors
+    ///
+    /// Returns `InitializableError::InvalidInitialization` if the contract is
+    /// already initialized.
+    pub fn initializer(&mut self) -> Result<(), InitializableError> {
+        self.reinitializer(1)
+    }
+
+    /// Check and record an initialization step for `version`
+    ///
+    /// Used after upgrades: each version runs once and versions must strictly
+    /// increase, so skipped intermediate versions can't be run later.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InitializableError::InvalidInitialization` if `version` is zero
+    /// or not above the current version.
+    pub fn reinitializer(&mut self, version: u64) -> Result<(), InitializableError> {
+        if version == 0 || self.initialized_version() >= version {
+            return Err(InitializableError::InvalidInitialization(InvalidInitialization {}));
+        }
+        self.set_version(version);
+        Ok(())
+    }
+
+    /// Lock the contract against any further initialization
+    ///
+    /// Call in the constructor of implementation contracts that sit behind a
+    /// proxy, so nobody can initialize the implementation itself.
+    pub fn disable_initializers(&mut self) {
+        if self.initialized_version() != u64::MAX {
+            self.set_version(u64::MAX);
+        }
+    }
+
+    /// Highest version initialized so far
+    pub fn initialized_version(&self) -> u64 {
+        self.initialized.get().to::<u64>()
+    }
+
+    /// Check if the contract has been initialized at least once
+    pub fn is_initialized(&self) -> bool {
+        self.initialized_version() > 0
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.initialized.set(U64::from(version));
+        log(self.vm(), Initialized { version });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        init: Initializable,
+    }
+
+    #[test]
+    fn test_initializer_runs_once() {
+        let mut contract = TestContract::default();
+        assert!(!contract.init.is_initialized());
+
+        assert!(contract.init.initializer().is_ok());
+        assert!(contract.init.is_initialized());
+        assert!(matches!(
+            contract.init.initializer(),
+            Err(InitializableError::InvalidInitialization(_))
+        ));
+    }
+
+    #[test]
+    fn test_reinitializer_versions() {
+        let mut contract = TestContract::default();
+        assert!(contract.init.initializer().is_ok());
+
+        assert!(contract.init.reinitializer(3).is_ok());
+        assert_eq!(contract.init.initialized_version(), 3);
+
+        // Skipped and repeated versions are rejected
+        assert!(contract.init.reinitializer(2).is_err());
+        assert!(contract.init.reinitializer(3).is_err());
+        assert!(contract.init.reinitializer(0).is_err());
+    }
+
+    #[test]
+    fn test_disable_initializers() {
+        let mut contract = TestContract::default();
+        contract.init.disable_initializers();
+
+        assert!(contract.init.initializer().is_err());
+        assert!(contract.init.reinitializer(u64::MAX).is_err());
+        assert_eq!(contract.init.initialized_version(), u64::MAX);
+    }
+}
@@ -0,0 +1,32 @@
+//! Reusable access control building blocks for Stylus smart contracts
+//!
+//! Provides composable storage components for the checks most contracts
+//! need: `Ownable`/`Ownable2Step` for a single owner, `AccessControl` for
+//! enumerable roles managed by admin roles, `Pausable` for an emergency stop
+//! and `Initializable` for guarding public init functions. The crate is
+//! `no_std` and has no entrypoint, so any contract can embed it.
+//!
+//! # Errors
+//!
+//! Each error enum wraps `sol!`-declared error structs (e.g.
+//! `OwnableError::UnauthorizedAccount(OwnableUnauthorizedAccount)`). The
+//! structs are re-exported so contracts can reuse them as variants of their
+//! own error enums, and every struct converts into its enum with `From`.
+//! Contracts returning `Vec<u8>` can use `?` directly, since every enum
+//! converts into its ABI-encoded revert data.
+
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+pub mod access_control;
+pub mod initializable;
+pub mod ownable;
+pub mod pausable;
+
+pub use access_control::{
+    AccessControl, AccessControlBadConfirmation, AccessControlError, AccessControlUnauthorizedAccount,
+    DEFAULT_ADMIN_ROLE,
+};
+pub use initializable::{Initializable, InitializableError, InvalidInitialization};
+pub use ownable::{Ownable, Ownable2Step, OwnableError, OwnableInvalidOwner, OwnableUnauthorizedAccount};
+pub use pausable::{EnforcedPause, ExpectedPause, Pausable, PausableError};
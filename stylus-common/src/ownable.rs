@@ -0,0 +1,282 @@
+//! Ownable modules for Stylus smart contracts
+//!
+//! Single-owner access control inspired by OpenZeppelin's Ownable.sol and
+//! Ownable2Step.sol. `Ownable` transfers ownership in one call; `Ownable2Step`
+//! makes the new owner accept first, so a typo can't hand the contract to an
+//! address nobody controls.
+
+use alloy_primitives::Address;
+use stylus_sdk::{alloy_sol_types::sol, prelude::*, storage::StorageAddress};
+
+sol! {
+    /// Emitted when ownership moves from `previous_owner` to `new_owner`
+    event OwnershipTransferred(address indexed previous_owner, address indexed new_owner);
+    /// Emitted when `new_owner` is nominated and must accept ownership
+    event OwnershipTransferStarted(address indexed previous_owner, address indexed new_owner);
+
+    /// Thrown when the caller is not authorized to perform the operation
+    error OwnableUnauthorizedAccount();
+    /// Thrown when the new owner is not a valid owner (e.g. `address(0)`)
+    error OwnableInvalidOwner();
+}
+
+/// Error types for Ownable and Ownable2Step
+#[derive(SolidityError)]
+pub enum OwnableError {
+    UnauthorizedAccount(OwnableUnauthorizedAccount),
+    InvalidOwner(OwnableInvalidOwner),
+}
+
+impl From<OwnableUnauthorizedAccount> for OwnableError {
+    fn from(err: OwnableUnauthorizedAccount) -> Self {
+        OwnableError::UnauthorizedAccount(err)
+    }
+}
+
+impl From<OwnableInvalidOwner> for OwnableError {
+    fn from(err: OwnableInvalidOwner) -> Self {
+        OwnableError::InvalidOwner(err)
+    }
+}
+
+/// Storage structure for Ownable
+#[storage]
+pub struct Ownable {
+    /// Current owner (`address(0)` once renounced)
+    owner: StorageAddress,
+}
+
+impl Ownable {
+    /// Set the initial owner
+    ///
+    /// This should be called in the contract's constructor.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::InvalidOwner` if `initial_owner` is `address(0)`.
+    pub fn init(&mut self, initial_owner: Address) -> Result<(), OwnableError> {
+        if initial_owner.is_zero() {
+            return Err(OwnableError::InvalidOwner(OwnableInvalidOwner {}));
+        }
+        self.set_owner(initial_owner);
+        Ok(())
+    }
+
+    /// Current owner
+    pub fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    /// Check that the caller is the owner
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` otherwise.
+    pub fn only_owner(&self) -> Result<(), OwnableError> {
+        if self.vm().msg_sender() != self.owner() {
+            return Err(OwnableError::UnauthorizedAccount(OwnableUnauthorizedAccount {}));
+        }
+        Ok(())
+    }
+
+    /// Transfer ownership to `new_owner` (owner only)
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` if the caller isn't the
+    /// owner, or `OwnableError::InvalidOwner` if `new_owner` is `address(0)`.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), OwnableError> {
+        self.only_owner()?;
+        if new_owner.is_zero() {
+            return Err(OwnableError::InvalidOwner(OwnableInvalidOwner {}));
+        }
+        self.set_owner(new_owner);
+        Ok(())
+    }
+
+    /// Give up ownership, leaving the contract without an owner (owner only)
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` if the caller isn't the owner.
+    pub fn renounce_ownership(&mut self) -> Result<(), OwnableError> {
+        self.only_owner()?;
+        self.set_owner(Address::ZERO);
+        Ok(())
+    }
+
+    fn set_owner(&mut self, new_owner: Address) {
+        let previous_owner = self.owner.get();
+        self.owner.set(new_owner);
+        log(self.vm(), OwnershipTransferred { previous_owner, new_owner });
+    }
+}
+
+/// Storage structure for Ownable2Step
+#[storage]
+pub struct Ownable2Step {
+    /// Underlying single-step ownership
+    ownable: Ownable,
+    /// Nominated owner waiting to accept (`address(0)` if none)
+    pending_owner: StorageAddress,
+}
+
+impl Ownable2Step {
+    /// Set the initial owner
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::InvalidOwner` if `initial_owner` is `address(0)`.
+    pub fn init(&mut self, initial_owner: Address) -> Result<(), OwnableError> {
+        self.ownable.init(initial_owner)
+    }
+
+    /// Current owner
+    pub fn owner(&self) -> Address {
+        self.ownable.owner()
+    }
+
+    /// Nominated owner waiting to accept
+    pub fn pending_owner(&self) -> Address {
+        self.pending_owner.get()
+    }
+
+    /// Check that the caller is the owner
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` otherwise.
+    pub fn only_owner(&self) -> Result<(), OwnableError> {
+        self.ownable.only_owner()
+    }
+
+    /// Nominate `new_owner`, who must call `accept_ownership` (owner only)
+    ///
+    /// Nominating `address(0)` cancels a pending transfer.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` if the caller isn't the owner.
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), OwnableError> {
+        self.only_owner()?;
+        self.pending_owner.set(new_owner);
+        log(
+            self.vm(),
+            OwnershipTransferStarted {
+                previous_owner: self.owner(),
+                new_owner,
+            },
+        );
+        Ok(())
+    }
+
+    /// Accept a pending transfer (nominated owner only)
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` if the caller isn't the
+    /// pending owner.
+    pub fn accept_ownership(&mut self) -> Result<(), OwnableError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.pending_owner() {
+            return Err(OwnableError::UnauthorizedAccount(OwnableUnauthorizedAccount {}));
+        }
+        self.pending_owner.set(Address::ZERO);
+        self.ownable.set_owner(caller);
+        Ok(())
+    }
+
+    /// Give up ownership and cancel any pending transfer (owner only)
+    ///
+    /// # Errors
+    ///
+    /// Returns `OwnableError::UnauthorizedAccount` if the caller isn't the owner.
+    pub fn renounce_ownership(&mut self) -> Result<(), OwnableError> {
+        self.ownable.renounce_ownership()?;
+        self.pending_owner.set(Address::ZERO);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[storage]
+    struct TestContract {
+        ownable: Ownable,
+        two_step: Ownable2Step,
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    #[test]
+    fn test_ownable() {
+        let vm = TestVM::default();
+        let mut contract = TestContract::from(&vm);
+        assert!(matches!(contract.ownable.init(Address::ZERO), Err(OwnableError::InvalidOwner(_))));
+        assert!(contract.ownable.init(alice()).is_ok());
+
+        vm.set_sender(bob());
+        assert!(matches!(contract.ownable.only_owner(), Err(OwnableError::UnauthorizedAccount(_))));
+        assert!(contract.ownable.transfer_ownership(bob()).is_err());
+
+        vm.set_sender(alice());
+        assert!(matches!(
+            contract.ownable.transfer_ownership(Address::ZERO),
+            Err(OwnableError::InvalidOwner(_))
+        ));
+        assert!(contract.ownable.transfer_ownership(bob()).is_ok());
+        assert_eq!(contract.ownable.owner(), bob());
+
+        vm.set_sender(bob());
+        assert!(contract.ownable.renounce_ownership().is_ok());
+        assert_eq!(contract.ownable.owner(), Address::ZERO);
+        assert!(contract.ownable.only_owner().is_err());
+    }
+
+    #[test]
+    fn test_two_step_transfer() {
+        let vm = TestVM::default();
+        let mut contract = TestContract::from(&vm);
+        assert!(contract.two_step.init(alice()).is_ok());
+
+        vm.set_sender(alice());
+        assert!(contract.two_step.transfer_ownership(bob()).is_ok());
+        assert_eq!(contract.two_step.owner(), alice());
+        assert_eq!(contract.two_step.pending_owner(), bob());
+
+        // Only the nominee can accept
+        assert!(matches!(
+            contract.two_step.accept_ownership(),
+            Err(OwnableError::UnauthorizedAccount(_))
+        ));
+
+        vm.set_sender(bob());
+        assert!(contract.two_step.accept_ownership().is_ok());
+        assert_eq!(contract.two_step.owner(), bob());
+        assert_eq!(contract.two_step.pending_owner(), Address::ZERO);
+    }
+
+    #[test]
+    fn test_two_step_renounce_clears_pending() {
+        let vm = TestVM::default();
+        let mut contract = TestContract::from(&vm);
+        assert!(contract.two_step.init(alice()).is_ok());
+
+        vm.set_sender(alice());
+        assert!(contract.two_step.transfer_ownership(bob()).is_ok());
+        assert!(contract.two_step.renounce_ownership().is_ok());
+        assert_eq!(contract.two_step.owner(), Address::ZERO);
+
+        vm.set_sender(bob());
+        assert!(contract.two_step.accept_ownership().is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Pausable module for Stylus smart contracts
+//!
+//! Emergency stop inspired by OpenZeppelin's Pausable.sol. Embed `Pausable`,
+//! call `when_not_paused` at the start of functions that must halt during an
+//! incident, and expose `pause`/`unpause` behind the contract's own access
+//! control (e.g. `Ownable` or a role in `AccessControl`).
+
+use stylus_sdk::{alloy_sol_types::sol, prelude::*, storage::StorageBool};
+
+sol! {
+    /// Emitted when the contract is paused by `account`
+    event Paused(address account);
+    /// Emitted when the contract is unpaused by `account`
+    event Unpaused(address account);
+
+    /// Thrown when a function that requires the contract to be unpaused is called while paused
+    error EnforcedPause();
+    /// Thrown when a function that requires the contract to be paused is called while unpaused
+    error ExpectedPause();
+}
+
+/// Error types for Pausable
+#[derive(SolidityError)]
+pub enum PausableError {
+    EnforcedPause(EnforcedPause),
+    ExpectedPause(ExpectedPause),
+}
+
+impl From<EnforcedPause> for PausableError {
+    fn from(err: EnforcedPause) -> Self {
+        PausableError::EnforcedPause(err)
+    }
+}
+
+impl From<ExpectedPause> for PausableError {
+    fn from(err: ExpectedPause) -> Self {
+        PausableError::ExpectedPause(err)
+    }
+}
+
+/// Storage structure for Pausable
+#[storage]
+pub struct Pausable {
+    /// Whether the contract is paused
+    paused: StorageBool,
+}
+
+impl Pausable {
+    /// Check if the contract is paused
+    pub fn paused(&self) -> bool {
+        self.paused.get()
+    }
+
+    /// Check that the contract is not paused
+    ///
+    /// # Errors
+    ///
+    /// Returns `PausableError::EnforcedPause` if the contract is paused.
+    pub fn when_not_paused(&self) -> Result<(), PausableError> {
+        if self.paused() {
+            return Err(PausableError::EnforcedPause(EnforcedPause {}));
+        }
+        Ok(())
+    }
+
+    /// Check that the contract is paused
+    ///
+    /// # Errors
+    ///
+    /// Returns `PausableError::ExpectedPause` if the contract is not paused.
+    pub fn when_paused(&self) -> Result<(), PausableError> {
+        if !self.paused() {
+            return Err(PausableError::ExpectedPause(ExpectedPause {}));
+        }
+        Ok(())
+    }
+
+    /// Pause the contract and emit `Paused`
+    ///
+    /// Callers are responsible for access control.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PausableError::EnforcedPause` if the contract is already paused.
+    pub fn pause(&mut self) -> Result<(), PausableError> {
+        self.when_not_paused()?;
+        self.paused.set(true);
+        log(self.vm(), Paused { account: self.vm().msg_sender() });
+        Ok(())
+    }
+
+    /// Unpause the contract and emit `Unpaused`
+    ///
+    /// Callers are responsible for access control.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PausableError::ExpectedPause` if the contract is not paused.
+    pub fn unpause(&mut self) -> Result<(), PausableError> {
+        self.when_paused()?;
+        self.paused.set(false);
+        log(self.vm(), Unpaused { account: self.vm().msg_sender() });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        pausable: Pausable,
+    }
+
+    #[test]
+    fn test_pause_cycle() {
+        let mut contract = TestContract::default();
+        assert!(!contract.pausable.paused());
+        assert!(contract.pausable.when_not_paused().is_ok());
+        assert!(matches!(contract.pausable.unpause(), Err(PausableError::ExpectedPause(_))));
+
+        assert!(contract.pausable.pause().is_ok());
+        assert!(contract.pausable.paused());
+        assert!(matches!(contract.pausable.when_not_paused(), Err(PausableError::EnforcedPause(_))));
+        assert!(matches!(contract.pausable.pause(), Err(PausableError::EnforcedPause(_))));
+
+        assert!(contract.pausable.unpause().is_ok());
+        assert!(!contract.pausable.paused());
+    }
+}
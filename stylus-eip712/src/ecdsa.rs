@@ -0,0 +1,109 @@
+//! ECDSA signature recovery through the `ecrecover` precompile
+//!
+//! Both wrappers only accept the lower-`s` form of a signature (EIP-2), so a
+//! signature can't be replayed in its flipped form to get around a hash- or
+//! signature-keyed replay check.
+
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, uint, Address, B256, U256};
+use stylus_sdk::call::RawCall;
+
+/// ecrecover precompile
+const ECRECOVER: Address = Address::with_last_byte(1);
+
+/// Half the secp256k1 order; signatures with a larger `s` are malleable
+pub const HALF_ORDER: U256 = uint!(0x7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0_U256);
+
+/// Signer of `hash` given a `v`, `r`, `s` signature
+///
+/// Returns `None` for malleable signatures, or if recovery fails.
+pub fn recover(hash: B256, v: u8, r: B256, s: B256) -> Option<Address> {
+    if (v != 27 && v != 28) || U256::from_be_bytes(s.0) > HALF_ORDER {
+        return None;
+    }
+
+    let mut input = Vec::with_capacity(128);
+    input.extend_from_slice(hash.as_slice());
+    input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+    input.extend_from_slice(r.as_slice());
+    input.extend_from_slice(s.as_slice());
+
+    // Safety: static call to a precompile, no state is modified
+    let output = unsafe { RawCall::new_static().call(ECRECOVER, &input) }.ok()?;
+    if output.len() != 32 {
+        return None;
+    }
+    let signer = Address::from_slice(&output[12..]);
+    (!signer.is_zero()).then_some(signer)
+}
+
+/// Signer of `hash` given a 65-byte `r || s || v` signature
+///
+/// `v` may be 0/1 or 27/28. Returns `None` for malformed or malleable
+/// signatures, or if recovery fails.
+pub fn recover_signature(hash: B256, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let v = match signature[64] {
+        v @ (0 | 1) => v + 27,
+        v => v,
+    };
+    recover(hash, v, B256::from_slice(&signature[..32]), B256::from_slice(&signature[32..64]))
+}
+
+/// `eth_sign`/`personal_sign` digest of a 32-byte `hash`
+pub fn to_eth_signed_message_hash(hash: B256) -> B256 {
+    let mut message = Vec::with_capacity(60);
+    message.extend_from_slice(b"\x19Ethereum Signed Message:\n32");
+    message.extend_from_slice(hash.as_slice());
+    keccak256(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::b256;
+
+    const HASH: B256 = b256!("0x1111111111111111111111111111111111111111111111111111111111111111");
+
+    fn signature(s: U256, v: u8) -> Vec<u8> {
+        let mut signature = Vec::with_capacity(65);
+        signature.extend_from_slice(&[0x22; 32]);
+        signature.extend_from_slice(&s.to_be_bytes::<32>());
+        signature.push(v);
+        signature
+    }
+
+    #[test]
+    fn test_recover_rejects_malleable_s() {
+        let s = B256::from(HALF_ORDER + U256::from(1));
+        assert_eq!(recover(HASH, 27, B256::repeat_byte(0x22), s), None);
+        assert_eq!(recover_signature(HASH, &signature(U256::MAX, 28)), None);
+    }
+
+    #[test]
+    fn test_recover_rejects_bad_v() {
+        let s = B256::from(U256::from(1));
+        for v in [0, 1, 26, 29] {
+            assert_eq!(recover(HASH, v, B256::repeat_byte(0x22), s), None);
+        }
+        assert_eq!(recover_signature(HASH, &signature(U256::from(1), 2)), None);
+    }
+
+    #[test]
+    fn test_recover_signature_rejects_bad_length() {
+        let signature = signature(U256::from(1), 27);
+        assert_eq!(recover_signature(HASH, &signature[..64]), None);
+        assert_eq!(recover_signature(HASH, &[signature.as_slice(), &[0]].concat()), None);
+        assert_eq!(recover_signature(HASH, &[]), None);
+    }
+
+    #[test]
+    fn test_eth_signed_message_hash() {
+        let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+        message.extend_from_slice(HASH.as_slice());
+        assert_eq!(to_eth_signed_message_hash(HASH), keccak256(message));
+        assert_ne!(to_eth_signed_message_hash(HASH), HASH);
+    }
+}
@@ -0,0 +1,29 @@
+//! Reusable signature building blocks for Stylus smart contracts
+//!
+//! Provides what contracts accepting off-chain signatures need: EIP-712
+//! domain and typed-data hashing in `eip712`, `ecrecover` wrappers that
+//! reject malleable signatures in `ecdsa`, and a `Nonces` storage component
+//! so each signature can be used once. The crate is `no_std` and has no
+//! entrypoint, so any contract can embed it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let domain = stylus_eip712::domain("MyToken", "1", self.vm().chain_id(), self.vm().contract_address());
+//! let hash = stylus_eip712::typed_data_hash(domain.separator(), order.eip712_hash_struct());
+//! if stylus_eip712::recover(hash, v, r, s) != Some(signer) {
+//!     return Err(..);
+//! }
+//! self.nonces.use_checked_nonce(signer, order.nonce)?;
+//! ```
+
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+pub mod ecdsa;
+pub mod eip712;
+pub mod nonces;
+
+pub use ecdsa::{recover, recover_signature, to_eth_signed_message_hash, HALF_ORDER};
+pub use eip712::{domain, struct_hash, typed_data_hash};
+pub use nonces::{InvalidAccountNonce, Nonces, NoncesError};
@@ -0,0 +1,105 @@
+//! Nonces module for Stylus smart contracts
+//!
+//! Tracks a sequential nonce per account, inspired by OpenZeppelin's
+//! Nonces.sol. Signed messages include the signer's current nonce and the
+//! contract consumes it on use, so each signature works once and the
+//! signer can invalidate everything outstanding by bumping their nonce.
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{alloy_sol_types::sol, prelude::*, storage::StorageMap, storage::StorageU256};
+
+sol! {
+    /// Thrown when a signature is for a nonce other than `account`'s current one
+    error InvalidAccountNonce(address account, uint256 currentNonce);
+}
+
+/// Error types for Nonces
+#[derive(SolidityError)]
+pub enum NoncesError {
+    InvalidAccountNonce(InvalidAccountNonce),
+}
+
+impl From<InvalidAccountNonce> for NoncesError {
+    fn from(err: InvalidAccountNonce) -> Self {
+        NoncesError::InvalidAccountNonce(err)
+    }
+}
+
+/// Storage structure for Nonces
+#[storage]
+pub struct Nonces {
+    /// Next unused nonce of each account
+    nonces: StorageMap<Address, StorageU256>,
+}
+
+impl Nonces {
+    /// Current nonce of `owner`, which the next signature must use
+    pub fn nonces(&self, owner: Address) -> U256 {
+        self.nonces.get(owner)
+    }
+
+    /// Consume `owner`'s current nonce, returning it
+    pub fn use_nonce(&mut self, owner: Address) -> U256 {
+        let nonce = self.nonces.get(owner);
+        self.nonces.setter(owner).set(nonce + U256::from(1));
+        nonce
+    }
+
+    /// Consume `owner`'s current nonce if it equals `nonce`
+    ///
+    /// # Errors
+    ///
+    /// Returns `NoncesError::InvalidAccountNonce` if `nonce` isn't the current one.
+    pub fn use_checked_nonce(&mut self, owner: Address, nonce: U256) -> Result<(), NoncesError> {
+        let current = self.nonces.get(owner);
+        if nonce != current {
+            return Err(NoncesError::InvalidAccountNonce(InvalidAccountNonce {
+                account: owner,
+                currentNonce: current,
+            }));
+        }
+        self.nonces.setter(owner).set(current + U256::from(1));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        nonces: Nonces,
+    }
+
+    #[test]
+    fn test_use_nonce_increments() {
+        let mut contract = TestContract::default();
+        let owner = Address::repeat_byte(1);
+        assert_eq!(contract.nonces.nonces(owner), U256::ZERO);
+
+        assert_eq!(contract.nonces.use_nonce(owner), U256::ZERO);
+        assert_eq!(contract.nonces.use_nonce(owner), U256::from(1));
+        assert_eq!(contract.nonces.nonces(owner), U256::from(2));
+        assert_eq!(contract.nonces.nonces(Address::repeat_byte(2)), U256::ZERO);
+    }
+
+    #[test]
+    fn test_use_checked_nonce_rejects_stale_and_future() {
+        let mut contract = TestContract::default();
+        let owner = Address::repeat_byte(1);
+        assert!(contract.nonces.use_checked_nonce(owner, U256::ZERO).is_ok());
+
+        for nonce in [U256::ZERO, U256::from(2)] {
+            match contract.nonces.use_checked_nonce(owner, nonce) {
+                Err(NoncesError::InvalidAccountNonce(e)) => {
+                    assert_eq!(e.account, owner);
+                    assert_eq!(e.currentNonce, U256::from(1));
+                }
+                Ok(()) => panic!("expected InvalidAccountNonce"),
+            }
+        }
+        assert_eq!(contract.nonces.nonces(owner), U256::from(1));
+        assert!(contract.nonces.use_checked_nonce(owner, U256::from(1)).is_ok());
+    }
+}
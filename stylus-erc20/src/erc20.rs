@@ -1,13 +1,17 @@
 
 // Imported packages
 use alloc::string::String;
-use alloy_primitives::{Address, U256};
+use alloy_primitives::{Address, B256, U256};
 use core::marker::PhantomData;
 use stylus_sdk::{
-    alloy_sol_types::sol,
+    alloy_sol_types::{sol, Eip712Domain, SolStruct},
     prelude::*,
 };
 
+use stylus_eip712::Nonces;
+
+use crate::permit::{self, Permit};
+
 pub trait Erc20Params {
     /// Immutable token name
     const NAME: &'static str;
@@ -28,6 +32,8 @@ sol_storage! {
         mapping(address => mapping(address => uint256)) allowances;
         /// The total supply of the token
         uint256 total_supply;
+        /// Next EIP-2612 permit nonce of each user
+        Nonces nonces;
         /// Used to allow [`Erc20Params`]
         PhantomData<T> phantom;
     }
@@ -42,6 +48,10 @@ sol! {
     error InsufficientBalance(address from, uint256 have, uint256 want);
     #[derive(Debug)]
     error InsufficientAllowance(address owner, address spender, uint256 have, uint256 want);
+    #[derive(Debug)]
+    error ERC2612ExpiredSignature(uint256 deadline);
+    #[derive(Debug)]
+    error ERC2612InvalidSigner(address signer, address owner);
 }
 
 /// Represents the ways methods may fail.
@@ -49,6 +59,8 @@ sol! {
 pub enum Erc20Error {
     InsufficientBalance(InsufficientBalance),
     InsufficientAllowance(InsufficientAllowance),
+    ExpiredSignature(ERC2612ExpiredSignature),
+    InvalidSigner(ERC2612InvalidSigner),
 }
 
 // These methods aren't exposed to other contracts
@@ -131,6 +143,35 @@ impl<T: Erc20Params> Erc20<T> {
 
         Ok(())
     }
+
+    /// Sets `spender`'s allowance on `owner`'s tokens to `value`
+    pub fn _approve(&mut self, owner: Address, spender: Address, value: U256) {
+        self.allowances.setter(owner).insert(spender, value);
+        log(self.vm(), Approval { owner, spender, value });
+    }
+
+    /// Decreases `spender`'s allowance on `owner`'s tokens by `value`
+    /// (invoked by transfer_from(), and usable for burn-from style methods)
+    pub fn _spend_allowance(&mut self, owner: Address, spender: Address, value: U256) -> Result<(), Erc20Error> {
+        let mut owner_allowances = self.allowances.setter(owner);
+        let mut allowance = owner_allowances.setter(spender);
+        let old_allowance = allowance.get();
+        if old_allowance < value {
+            return Err(Erc20Error::InsufficientAllowance(InsufficientAllowance {
+                owner,
+                spender,
+                have: old_allowance,
+                want: value,
+            }));
+        }
+        allowance.set(old_allowance - value);
+        Ok(())
+    }
+
+    /// EIP-712 domain of this token
+    pub fn domain(&self) -> Eip712Domain {
+        permit::domain(T::NAME, self.vm().chain_id(), self.vm().contract_address())
+    }
 }
 
 // These methods are external to other contracts
@@ -176,22 +217,8 @@ impl<T: Erc20Params> Erc20<T> {
         to: Address,
         value: U256,
     ) -> Result<bool, Erc20Error> {
-        // Check msg::sender() allowance
-        let msg_sender = self.vm().msg_sender();
-        let mut sender_allowances = self.allowances.setter(from);
-        let mut allowance = sender_allowances.setter(msg_sender);
-        let old_allowance = allowance.get();
-        if old_allowance < value {
-            return Err(Erc20Error::InsufficientAllowance(InsufficientAllowance {
-                owner: from,
-                spender: msg_sender,
-                have: old_allowance,
-                want: value,
-            }));
-        }
-
-        // Decreases allowance
-        allowance.set(old_allowance - value);
+        // Checks and decreases msg::sender() allowance
+        self._spend_allowance(from, self.vm().msg_sender(), value)?;
 
         // Calls the internal transfer function
         self._transfer(from, to, value)?;
@@ -201,13 +228,7 @@ impl<T: Erc20Params> Erc20<T> {
 
     /// Approves the spenditure of `value` tokens of msg::sender() to `spender`
     pub fn approve(&mut self, spender: Address, value: U256) -> bool {
-        let msg_sender = self.vm().msg_sender();
-        self.allowances.setter(msg_sender).insert(spender, value);
-        log(self.vm(), Approval {
-            owner: msg_sender,
-            spender,
-            value,
-        });
+        self._approve(self.vm().msg_sender(), spender, value);
         true
     }
 
@@ -215,4 +236,45 @@ impl<T: Erc20Params> Erc20<T> {
     pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
         self.allowances.getter(owner).get(spender)
     }
+
+    /// Approves the spenditure of `value` tokens of `owner` to `spender`
+    /// with `owner`'s EIP-2612 signature, valid until `deadline`
+    #[allow(clippy::too_many_arguments)]
+    pub fn permit(
+        &mut self,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        deadline: U256,
+        v: u8,
+        r: B256,
+        s: B256,
+    ) -> Result<(), Erc20Error> {
+        if U256::from(self.vm().block_timestamp()) > deadline {
+            return Err(Erc20Error::ExpiredSignature(ERC2612ExpiredSignature { deadline }));
+        }
+
+        // The signature must be over the owner's current nonce
+        let nonce = self.nonces.nonces(owner);
+        let hash = Permit { owner, spender, value, nonce, deadline }.eip712_signing_hash(&self.domain());
+        let signer = stylus_eip712::recover(hash, v, r, s).unwrap_or_default();
+        if signer != owner || owner.is_zero() {
+            return Err(Erc20Error::InvalidSigner(ERC2612InvalidSigner { signer, owner }));
+        }
+
+        self.nonces.use_nonce(owner);
+        self._approve(owner, spender, value);
+        Ok(())
+    }
+
+    /// Nonce `owner` must sign its next permit with
+    pub fn nonces(&self, owner: Address) -> U256 {
+        self.nonces.nonces(owner)
+    }
+
+    /// EIP-712 domain separator permits are signed under
+    #[selector(name = "DOMAIN_SEPARATOR")]
+    pub fn domain_separator(&self) -> B256 {
+        self.domain().separator()
+    }
 }
\ No newline at end of file
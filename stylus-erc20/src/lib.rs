@@ -1,60 +1,42 @@
+//! # Stylus ERC-20
+//!
+//! A reusable ERC-20 implementation with minting, burning, allowances and
+//! EIP-2612 permits, plus `StylusToken`, a deployable demo token.
+//!
+//! # Using the library
+//!
+//! Embed `erc20::Erc20` in a contract, configured by an `Erc20Params`
+//! implementation, and `#[inherit]` it to expose the standard methods. Other
+//! contracts should depend on the crate without default features, which
+//! leaves out the `StylusToken` demo and its entrypoint:
+//!
+//! ```toml
+//! stylus-erc20 = { path = "../stylus-erc20", default-features = false }
+//! ```
+//!
+//! # Features
+//!
+//! - `contract` (default): build the `StylusToken` demo token.
+//! - `export-abi`: print the demo token's Solidity ABI; implies `contract`.
+
 // Only run this as a WASM if the export-abi feature is not set.
 #![cfg_attr(not(any(feature = "export-abi", test)), no_main)]
 extern crate alloc;
 
 // Modules and imports
 pub mod erc20;
+pub mod permit;
+#[cfg(feature = "contract")]
+pub mod token;
 
-use alloy_primitives::{Address, U256};
-use stylus_sdk::{
-    prelude::*
-};
-use crate::erc20::{Erc20, Erc20Params, Erc20Error};
+#[cfg(feature = "export-abi")]
+use stylus_sdk::abi::export;
 
-/// Immutable definitions
-struct StylusTokenParams;
-impl Erc20Params for StylusTokenParams {
-    const NAME: &'static str = "MayToken";
-    const SYMBOL: &'static str = "MTK";
-    const DECIMALS: u8 = 18;
-}
+pub use erc20::{Erc20, Erc20Error, Erc20Params};
+#[cfg(feature = "contract")]
+pub use token::{StylusToken, StylusTokenParams};
 
-// Define the entrypoint as a Solidity storage object. The sol_storage! macro
-// will generate Rust-equivalent structs with all fields mapped to Solidity-equivalent
-// storage slots and types.
-sol_storage! {
-    #[entrypoint]
-    struct StylusToken {
-        // Allows erc20 to access StylusToken's storage and make calls
-        #[borrow]
-        Erc20<StylusTokenParams> erc20;
-    }
+#[cfg(feature = "export-abi")]
+pub fn print_from_args() {
+    export::print_from_args::<token::StylusToken>();
 }
-
-#[public]
-#[inherit(Erc20<StylusTokenParams>)]
-impl StylusToken {
-    /// Initialize contract with initial supply to deployer
-    pub fn init(&mut self, initial_supply: U256) -> Result<(), Erc20Error> {
-        self.erc20.mint(self.vm().msg_sender(), initial_supply)?;
-        Ok(())
-    }
-
-    /// Mints tokens
-    pub fn mint(&mut self, value: U256) -> Result<(), Erc20Error> {
-        self.erc20.mint(self.vm().msg_sender(), value)?;
-        Ok(())
-    }
-
-    /// Mints tokens to another address
-    pub fn mint_to(&mut self, to: Address, value: U256) -> Result<(), Erc20Error> {
-        self.erc20.mint(to, value)?;
-        Ok(())
-    }
-
-    /// Burns tokens
-    pub fn burn(&mut self, value: U256) -> Result<(), Erc20Error> {
-        self.erc20.burn(self.vm().msg_sender(), value)?;
-        Ok(())
-    }
-}
\ No newline at end of file
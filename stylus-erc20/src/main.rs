@@ -4,8 +4,7 @@
 #[no_mangle]
 pub extern "C" fn main() {}
 
-
 #[cfg(feature = "export-abi")]
 fn main() {
-    println!("ERC-20 Token COntract for Stylus")
-}
\ No newline at end of file
+    stylus_erc20::print_from_args();
+}
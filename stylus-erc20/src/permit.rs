@@ -0,0 +1,25 @@
+//! EIP-2612 permits
+//!
+//! A holder signs a `Permit` off-chain and anyone can submit it to set the
+//! holder's allowance, so spending tokens doesn't need a prior `approve`
+//! transaction. The EIP-712 domain binds permits to the token and chain, and
+//! each holder nonce can be used once.
+
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, Eip712Domain};
+
+sol! {
+    /// Holder-signed approval of `value` tokens to `spender`
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+/// EIP-712 domain of the token `name` deployed at `token`
+pub fn domain(name: &'static str, chain_id: u64, token: Address) -> Eip712Domain {
+    stylus_eip712::domain(name, "1", chain_id, token)
+}
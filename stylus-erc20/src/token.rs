@@ -0,0 +1,93 @@
+//! The `StylusToken` demo token
+//!
+//! A deployable "MayToken" (MTK) with open minting, meant as the payment token
+//! for test deployments. Only built with the `contract` feature, so crates
+//! embedding `Erc20` keep their own entrypoint.
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::prelude::*;
+
+use crate::erc20::{Erc20, Erc20Error, Erc20Params};
+
+/// Immutable definitions
+pub struct StylusTokenParams;
+impl Erc20Params for StylusTokenParams {
+    const NAME: &'static str = "MayToken";
+    const SYMBOL: &'static str = "MTK";
+    const DECIMALS: u8 = 18;
+}
+
+// Define the entrypoint as a Solidity storage object. The sol_storage! macro
+// will generate Rust-equivalent structs with all fields mapped to Solidity-equivalent
+// storage slots and types.
+sol_storage! {
+    #[entrypoint]
+    pub struct StylusToken {
+        // Allows erc20 to access StylusToken's storage and make calls
+        #[borrow]
+        Erc20<StylusTokenParams> erc20;
+    }
+}
+
+#[public]
+#[inherit(Erc20<StylusTokenParams>)]
+impl StylusToken {
+    /// Initialize contract with initial supply to deployer
+    pub fn init(&mut self, initial_supply: U256) -> Result<(), Erc20Error> {
+        self.erc20.mint(self.vm().msg_sender(), initial_supply)?;
+        Ok(())
+    }
+
+    /// Mints tokens
+    pub fn mint(&mut self, value: U256) -> Result<(), Erc20Error> {
+        self.erc20.mint(self.vm().msg_sender(), value)?;
+        Ok(())
+    }
+
+    /// Mints tokens to another address
+    pub fn mint_to(&mut self, to: Address, value: U256) -> Result<(), Erc20Error> {
+        self.erc20.mint(to, value)?;
+        Ok(())
+    }
+
+    /// Burns tokens
+    pub fn burn(&mut self, value: U256) -> Result<(), Erc20Error> {
+        self.erc20.burn(self.vm().msg_sender(), value)?;
+        Ok(())
+    }
+
+    /// Burns tokens from another address
+    /// (msg::sender() must be able to spend at least `value` tokens from `from`)
+    pub fn burn_from(&mut self, from: Address, value: U256) -> Result<(), Erc20Error> {
+        self.erc20._spend_allowance(from, self.vm().msg_sender(), value)?;
+        self.erc20.burn(from, value)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    #[test]
+    fn test_burn_from_spends_allowance() {
+        let vm = TestVM::default();
+        let mut token = StylusToken::from(&vm);
+        let (holder, spender) = (Address::from([1u8; 20]), Address::from([2u8; 20]));
+
+        vm.set_sender(holder);
+        token.init(U256::from(100)).unwrap();
+        token.erc20.approve(spender, U256::from(30));
+
+        vm.set_sender(spender);
+        assert!(matches!(
+            token.burn_from(holder, U256::from(31)),
+            Err(Erc20Error::InsufficientAllowance(_))
+        ));
+        assert!(token.burn_from(holder, U256::from(30)).is_ok());
+        assert_eq!(token.erc20.balance_of(holder), U256::from(70));
+        assert_eq!(token.erc20.total_supply(), U256::from(70));
+        assert_eq!(token.erc20.allowance(holder, spender), U256::ZERO);
+    }
+}
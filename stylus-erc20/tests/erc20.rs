@@ -1,8 +1,10 @@
 
 #[cfg(test)]
 mod test {
-    use stylus_erc20::erc20::{Erc20, Erc20Params};
-    use stylus_sdk::alloy_primitives::{Address, U256};
+    use alloy_sol_types::SolStruct;
+    use stylus_erc20::erc20::{Erc20, Erc20Error, Erc20Params};
+    use stylus_erc20::permit::Permit;
+    use stylus_sdk::alloy_primitives::{Address, B256, U256};
     use stylus_sdk::testing::*;
 
     // Test configuration for our token
@@ -269,4 +271,98 @@ mod test {
         assert_eq!(erc20.balance_of(alice()), amount);
     }
 
+    /// A signature that the mocked ecrecover attributes to `signer` for `hash`
+    fn sign(vm: &TestVM, hash: B256, signer: Address) -> (u8, B256, B256) {
+        let (v, r, s) = (27u8, B256::repeat_byte(1), B256::repeat_byte(2));
+        let mut input = hash.to_vec();
+        input.extend_from_slice(&U256::from(v).to_be_bytes::<32>());
+        input.extend_from_slice(r.as_slice());
+        input.extend_from_slice(s.as_slice());
+        let mut output = vec![0u8; 12];
+        output.extend_from_slice(signer.as_slice());
+        vm.mock_static_call(Address::with_last_byte(1), input, Ok(output));
+        (v, r, s)
+    }
+
+    /// EIP-712 hash of a permit of `value` tokens from `owner` to `spender`
+    fn permit_hash(
+        erc20: &Erc20<TestTokenParams>,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        nonce: U256,
+        deadline: U256,
+    ) -> B256 {
+        Permit { owner, spender, value, nonce, deadline }.eip712_signing_hash(&erc20.domain())
+    }
+
+    #[test]
+    fn test_permit() {
+        let vm = TestVM::default();
+        let mut erc20 = Erc20::<TestTokenParams>::from(&vm);
+        vm.set_block_timestamp(1_000);
+        let (value, deadline) = (U256::from(500u64), U256::from(2_000u64));
+        assert_eq!(erc20.nonces(alice()), U256::ZERO);
+        assert_eq!(erc20.domain_separator(), erc20.domain().separator());
+
+        // Anyone can submit the holder's signature
+        let hash = permit_hash(&erc20, alice(), bob(), value, U256::ZERO, deadline);
+        let (v, r, s) = sign(&vm, hash, alice());
+        vm.set_sender(charlie());
+        assert!(erc20.permit(alice(), bob(), value, deadline, v, r, s).is_ok());
+        assert_eq!(erc20.allowance(alice(), bob()), value);
+        assert_eq!(erc20.nonces(alice()), U256::from(1));
+
+        // The permitted spender can move the tokens
+        erc20.mint(alice(), value).unwrap();
+        vm.set_sender(bob());
+        assert!(erc20.transfer_from(alice(), bob(), value).is_ok());
+        assert_eq!(erc20.balance_of(bob()), value);
+
+        // The used nonce can't be replayed
+        assert!(matches!(
+            erc20.permit(alice(), bob(), value, deadline, v, r, s),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+        assert_eq!(erc20.allowance(alice(), bob()), U256::ZERO);
+    }
+
+    #[test]
+    fn test_permit_rejects_bad_signatures() {
+        let vm = TestVM::default();
+        let mut erc20 = Erc20::<TestTokenParams>::from(&vm);
+        vm.set_block_timestamp(1_000);
+        let value = U256::from(500u64);
+
+        // Expired permits are rejected before the signature is checked
+        let expired = U256::from(999u64);
+        let hash = permit_hash(&erc20, alice(), bob(), value, U256::ZERO, expired);
+        let (v, r, s) = sign(&vm, hash, alice());
+        assert!(matches!(
+            erc20.permit(alice(), bob(), value, expired, v, r, s),
+            Err(Erc20Error::ExpiredSignature(_))
+        ));
+
+        // Signatures by someone else, or over other terms, don't count
+        let deadline = U256::from(1_000u64);
+        let hash = permit_hash(&erc20, alice(), bob(), value, U256::ZERO, deadline);
+        let (v, r, s) = sign(&vm, hash, charlie());
+        assert!(matches!(
+            erc20.permit(alice(), bob(), value, deadline, v, r, s),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+        let (v, r, s) = sign(&vm, hash, alice());
+        assert!(matches!(
+            erc20.permit(alice(), bob(), value + U256::from(1), deadline, v, r, s),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+
+        // Malleable signatures never reach ecrecover
+        assert!(matches!(
+            erc20.permit(alice(), bob(), value, deadline, v, r, B256::repeat_byte(0xff)),
+            Err(Erc20Error::InvalidSigner(_))
+        ));
+        assert!(erc20.permit(alice(), bob(), value, deadline, v, r, s).is_ok());
+        assert_eq!(erc20.allowance(alice(), bob()), value);
+    }
 }
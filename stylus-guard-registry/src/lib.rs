@@ -0,0 +1,280 @@
+//! Shared reentrancy lock for the contracts of one protocol
+//!
+//! A per-contract guard only stops re-entry into the same contract. When a
+//! vault pays out mid-flight, an attacker can still call into the protocol's
+//! router or staking contract, which share state with the vault. The
+//! `GuardRegistry` holds one lock for every registered member: a member takes
+//! it with `enter()` before its external calls and releases it with `exit()`,
+//! and any other member that tries to enter in between reverts.
+//!
+//! Members embed `ReentrancyGuardShared` from `stylus-reentrancy-guard`, which
+//! wraps the calls to this contract.
+
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
+extern crate alloc;
+
+use alloy_primitives::Address;
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageAddress, StorageBool, StorageMap},
+};
+use stylus_reentrancy_guard::{
+    Ownable, OwnableError, OwnableInvalidOwner, OwnableUnauthorizedAccount, ReentrancyGuardReentrantCall,
+};
+
+sol! {
+    event MemberAdded(address indexed member);
+    event MemberRemoved(address indexed member);
+    event LockEntered(address indexed holder);
+    event LockExited(address indexed holder);
+    event LockForceReleased(address indexed holder);
+
+    error GuardRegistryUnauthorizedMember();
+    error GuardRegistryNotHolder();
+}
+
+/// Contract errors
+#[derive(SolidityError)]
+pub enum GuardRegistryError {
+    UnauthorizedMember(GuardRegistryUnauthorizedMember),
+    NotHolder(GuardRegistryNotHolder),
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    OwnableUnauthorizedAccount(OwnableUnauthorizedAccount),
+    OwnableInvalidOwner(OwnableInvalidOwner),
+}
+
+impl From<OwnableError> for GuardRegistryError {
+    fn from(err: OwnableError) -> Self {
+        match err {
+            OwnableError::UnauthorizedAccount(e) => GuardRegistryError::OwnableUnauthorizedAccount(e),
+            OwnableError::InvalidOwner(e) => GuardRegistryError::OwnableInvalidOwner(e),
+        }
+    }
+}
+
+/// Registry storage
+#[entrypoint]
+#[storage]
+pub struct GuardRegistry {
+    /// Admin allowed to manage members
+    ownership: Ownable,
+    /// Contracts allowed to take the lock
+    members: StorageMap<Address, StorageBool>,
+    /// Member currently holding the lock (`address(0)` when free)
+    holder: StorageAddress,
+}
+
+#[public]
+impl GuardRegistry {
+    /// Constructor - the deployer becomes the admin
+    #[constructor]
+    pub fn constructor(&mut self) -> Result<(), GuardRegistryError> {
+        self.ownership.init(self.vm().msg_sender())?;
+        Ok(())
+    }
+
+    /// Allow `member` to take the lock (owner only)
+    pub fn add_member(&mut self, member: Address) -> Result<(), GuardRegistryError> {
+        self.ownership.only_owner()?;
+        self.members.setter(member).set(true);
+        log(self.vm(), MemberAdded { member });
+        Ok(())
+    }
+
+    /// Stop `member` from taking the lock (owner only)
+    ///
+    /// A member removed while holding the lock can still release it.
+    pub fn remove_member(&mut self, member: Address) -> Result<(), GuardRegistryError> {
+        self.ownership.only_owner()?;
+        self.members.setter(member).set(false);
+        log(self.vm(), MemberRemoved { member });
+        Ok(())
+    }
+
+    /// Check if `account` may take the lock
+    pub fn is_member(&self, account: Address) -> bool {
+        self.members.get(account)
+    }
+
+    /// Take the lock for the calling member
+    ///
+    /// Reverts with `ReentrancyGuardReentrantCall()` if any member, including
+    /// the caller, already holds it.
+    pub fn enter(&mut self) -> Result<(), GuardRegistryError> {
+        let caller = self.vm().msg_sender();
+        if !self.is_member(caller) {
+            return Err(GuardRegistryError::UnauthorizedMember(GuardRegistryUnauthorizedMember {}));
+        }
+        if !self.holder.get().is_zero() {
+            return Err(GuardRegistryError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+
+        self.holder.set(caller);
+        log(self.vm(), LockEntered { holder: caller });
+        Ok(())
+    }
+
+    /// Release the lock (holder only)
+    pub fn exit(&mut self) -> Result<(), GuardRegistryError> {
+        let caller = self.vm().msg_sender();
+        if caller != self.holder.get() || caller.is_zero() {
+            return Err(GuardRegistryError::NotHolder(GuardRegistryNotHolder {}));
+        }
+
+        self.holder.set(Address::ZERO);
+        log(self.vm(), LockExited { holder: caller });
+        Ok(())
+    }
+
+    /// Clear a lock left held by a faulty member (owner only)
+    ///
+    /// Members revert when their `exit()` fails, so a stuck lock means a
+    /// member entered without exiting. Remove that member first, or it can
+    /// take the lock again.
+    pub fn force_release(&mut self) -> Result<(), GuardRegistryError> {
+        self.ownership.only_owner()?;
+        let holder = self.holder.get();
+        if holder.is_zero() {
+            return Err(GuardRegistryError::NotHolder(GuardRegistryNotHolder {}));
+        }
+
+        self.holder.set(Address::ZERO);
+        log(self.vm(), LockForceReleased { holder });
+        Ok(())
+    }
+
+    /// Check if any member holds the lock
+    pub fn is_entered(&self) -> bool {
+        !self.holder.get().is_zero()
+    }
+
+    /// Member holding the lock (`address(0)` when free)
+    pub fn holder(&self) -> Address {
+        self.holder.get()
+    }
+
+    /// Current admin
+    pub fn owner(&self) -> Address {
+        self.ownership.owner()
+    }
+
+    /// Hand the admin role to `new_owner` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), GuardRegistryError> {
+        self.ownership.transfer_ownership(new_owner)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    fn admin() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn vault() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn router() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn deploy(vm: &TestVM) -> GuardRegistry {
+        let mut registry = GuardRegistry::from(vm);
+        vm.set_sender(admin());
+        assert!(registry.constructor().is_ok());
+        assert!(registry.add_member(vault()).is_ok());
+        assert!(registry.add_member(router()).is_ok());
+        registry
+    }
+
+    #[test]
+    fn test_members_share_one_lock() {
+        let vm = TestVM::default();
+        let mut registry = deploy(&vm);
+
+        vm.set_sender(vault());
+        assert!(registry.enter().is_ok());
+        assert!(registry.is_entered());
+        assert_eq!(registry.holder(), vault());
+
+        // Mid-flight, neither another member nor the holder can enter
+        vm.set_sender(router());
+        assert!(matches!(registry.enter(), Err(GuardRegistryError::ReentrantCall(_))));
+        vm.set_sender(vault());
+        assert!(matches!(registry.enter(), Err(GuardRegistryError::ReentrantCall(_))));
+
+        assert!(registry.exit().is_ok());
+        assert!(!registry.is_entered());
+
+        vm.set_sender(router());
+        assert!(registry.enter().is_ok());
+    }
+
+    #[test]
+    fn test_caller_authentication() {
+        let vm = TestVM::default();
+        let mut registry = deploy(&vm);
+
+        // Outsiders can't take the lock
+        vm.set_sender(Address::from([3u8; 20]));
+        assert!(matches!(registry.enter(), Err(GuardRegistryError::UnauthorizedMember(_))));
+
+        // Only the holder can release it
+        vm.set_sender(vault());
+        assert!(registry.enter().is_ok());
+        vm.set_sender(router());
+        assert!(matches!(registry.exit(), Err(GuardRegistryError::NotHolder(_))));
+
+        // A removed holder can still release
+        vm.set_sender(admin());
+        assert!(registry.remove_member(vault()).is_ok());
+        vm.set_sender(vault());
+        assert!(registry.exit().is_ok());
+        assert!(matches!(registry.enter(), Err(GuardRegistryError::UnauthorizedMember(_))));
+    }
+
+    #[test]
+    fn test_force_release() {
+        let vm = TestVM::default();
+        let mut registry = deploy(&vm);
+
+        vm.set_sender(admin());
+        assert!(matches!(registry.force_release(), Err(GuardRegistryError::NotHolder(_))));
+
+        vm.set_sender(vault());
+        assert!(registry.enter().is_ok());
+        assert!(matches!(
+            registry.force_release(),
+            Err(GuardRegistryError::OwnableUnauthorizedAccount(_))
+        ));
+
+        vm.set_sender(admin());
+        assert!(registry.force_release().is_ok());
+        assert!(!registry.is_entered());
+
+        // The old holder's exit now fails, and another member can enter
+        vm.set_sender(vault());
+        assert!(matches!(registry.exit(), Err(GuardRegistryError::NotHolder(_))));
+        vm.set_sender(router());
+        assert!(registry.enter().is_ok());
+    }
+
+    #[test]
+    fn test_only_owner_manages_members() {
+        let vm = TestVM::default();
+        let mut registry = deploy(&vm);
+
+        vm.set_sender(vault());
+        assert!(matches!(
+            registry.add_member(Address::from([3u8; 20])),
+            Err(GuardRegistryError::OwnableUnauthorizedAccount(_))
+        ));
+        assert!(registry.remove_member(router()).is_err());
+        assert!(registry.is_member(router()));
+    }
+}
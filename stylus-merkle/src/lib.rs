@@ -0,0 +1,338 @@
+//! Merkle proof verification for Stylus smart contracts
+//!
+//! Checks that leaves belong to a tree committed to by its root, for
+//! allowlists, trait-criteria offers and airdrops. Pairs are hashed in
+//! sorted order, as in OpenZeppelin's `MerkleProof`, so trees built with
+//! standard tooling (e.g. `@openzeppelin/merkle-tree`) verify unchanged. The
+//! crate is `no_std` and has no storage, so any contract can use it.
+//!
+//! # Leaves
+//!
+//! A proof also verifies any inner node of the tree with a shorter path, so
+//! leaves must never be 64 bytes that could be read as a pair of nodes. Hash
+//! caller-supplied data with [`leaf_hash`], which hashes twice like
+//! `StandardMerkleTree`, rather than using it or its single hash as a leaf.
+
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, B256};
+
+/// Leaf of `data` in a `StandardMerkleTree`: `keccak256(keccak256(data))`
+///
+/// `data` is usually the ABI encoding of the leaf's values, e.g.
+/// `(account, amount).abi_encode()` for an airdrop.
+pub fn leaf_hash(data: &[u8]) -> B256 {
+    keccak256(keccak256(data))
+}
+
+/// Hash two nodes in sorted order
+pub fn hash_pair(a: B256, b: B256) -> B256 {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut input = [0u8; 64];
+    input[..32].copy_from_slice(first.as_slice());
+    input[32..].copy_from_slice(second.as_slice());
+    keccak256(input)
+}
+
+/// Root reached by walking from `leaf` up through the `proof` siblings
+pub fn process_proof(proof: &[B256], leaf: B256) -> B256 {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling))
+}
+
+/// Check that `proof` places `leaf` under `root`
+pub fn verify(proof: &[B256], root: B256, leaf: B256) -> bool {
+    process_proof(proof, leaf) == root
+}
+
+/// Root reached by combining `leaves` with the `proof` nodes as `proof_flags` directs
+///
+/// Follows OpenZeppelin's `processMultiProof`: step `i` hashes the next leaf
+/// or computed node with either another one (`proof_flags[i]` set) or the
+/// next `proof` node. Leaves must be in the order the tree tooling returns
+/// them. Returns `None` when the inputs don't form a consistent multi-proof,
+/// including for an empty `leaves`, which would otherwise prove nothing.
+pub fn process_multi_proof(proof: &[B256], proof_flags: &[bool], leaves: &[B256]) -> Option<B256> {
+    if leaves.is_empty() || leaves.len() + proof.len() != proof_flags.len() + 1 {
+        return None;
+    }
+    // A lone leaf with no steps is its own root
+    if proof_flags.is_empty() {
+        return (leaves.len() == 1 && proof.is_empty()).then(|| leaves[0]);
+    }
+
+    let mut hashes: Vec<B256> = Vec::with_capacity(proof_flags.len());
+    let (mut leaf_pos, mut hash_pos, mut proof_pos) = (0, 0, 0);
+    // Next leaf, then the nodes computed so far, in order
+    let mut next_node = |hashes: &Vec<B256>| {
+        if leaf_pos < leaves.len() {
+            leaf_pos += 1;
+            Some(leaves[leaf_pos - 1])
+        } else {
+            hash_pos += 1;
+            hashes.get(hash_pos - 1).copied()
+        }
+    };
+
+    for &flag in proof_flags {
+        let a = next_node(&hashes)?;
+        let b = if flag {
+            next_node(&hashes)?
+        } else {
+            proof_pos += 1;
+            *proof.get(proof_pos - 1)?
+        };
+        hashes.push(hash_pair(a, b));
+    }
+
+    // Every input must be used exactly once, or the proof isn't for these leaves
+    if leaf_pos != leaves.len() || proof_pos != proof.len() {
+        return None;
+    }
+    if hash_pos != hashes.len() - 1 {
+        return None;
+    }
+    hashes.last().copied()
+}
+
+/// Check that `proof` and `proof_flags` place every one of `leaves` under `root`
+pub fn multi_proof_verify(proof: &[B256], proof_flags: &[bool], root: B256, leaves: &[B256]) -> bool {
+    process_multi_proof(proof, proof_flags, leaves) == Some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Tree layout and proof generation of `@openzeppelin/merkle-tree`: the
+    // root at 0, node `i`'s children at `2i + 1` and `2i + 2`, and leaf `i`
+    // at `len - 1 - i`.
+
+    fn make_tree(leaves: &[B256]) -> Vec<B256> {
+        let len = 2 * leaves.len() - 1;
+        let mut tree = vec![B256::ZERO; len];
+        for (i, leaf) in leaves.iter().enumerate() {
+            tree[len - 1 - i] = *leaf;
+        }
+        for i in (0..len - leaves.len()).rev() {
+            tree[i] = hash_pair(tree[2 * i + 1], tree[2 * i + 2]);
+        }
+        tree
+    }
+
+    fn tree_index(tree: &[B256], leaf: usize) -> usize {
+        tree.len() - 1 - leaf
+    }
+
+    fn sibling(index: usize) -> usize {
+        if index % 2 == 1 {
+            index + 1
+        } else {
+            index - 1
+        }
+    }
+
+    fn parent(index: usize) -> usize {
+        (index - 1) / 2
+    }
+
+    fn get_proof(tree: &[B256], mut index: usize) -> Vec<B256> {
+        let mut proof = Vec::new();
+        while index > 0 {
+            proof.push(tree[sibling(index)]);
+            index = parent(index);
+        }
+        proof
+    }
+
+    /// Multi-proof of the tree `indices`, returned with the leaves in proof order
+    fn get_multi_proof(tree: &[B256], indices: &[usize]) -> (Vec<B256>, Vec<bool>, Vec<B256>) {
+        let mut stack: Vec<usize> = indices.to_vec();
+        stack.sort_unstable_by(|a, b| b.cmp(a));
+        let leaves = stack.iter().map(|&i| tree[i]).collect();
+
+        let (mut proof, mut flags) = (Vec::new(), Vec::new());
+        while !stack.is_empty() && stack[0] > 0 {
+            let j = stack.remove(0);
+            let s = sibling(j);
+            if stack.first() == Some(&s) {
+                flags.push(true);
+                stack.remove(0);
+            } else {
+                flags.push(false);
+                proof.push(tree[s]);
+            }
+            stack.push(parent(j));
+        }
+        (proof, flags, leaves)
+    }
+
+    fn leaves(count: u8) -> Vec<B256> {
+        (0..count).map(|i| leaf_hash(&[i])).collect()
+    }
+
+    #[test]
+    fn test_hash_pair_is_commutative() {
+        let (a, b) = (leaf_hash(b"a"), leaf_hash(b"b"));
+        assert_eq!(hash_pair(a, b), hash_pair(b, a));
+
+        let mut sorted = [0u8; 64];
+        let (first, second) = if a < b { (a, b) } else { (b, a) };
+        sorted[..32].copy_from_slice(first.as_slice());
+        sorted[32..].copy_from_slice(second.as_slice());
+        assert_eq!(hash_pair(a, b), keccak256(sorted));
+    }
+
+    #[test]
+    fn test_leaf_hash_hashes_twice() {
+        assert_eq!(leaf_hash(b"leaf"), keccak256(keccak256(b"leaf")));
+        assert_ne!(leaf_hash(b"leaf"), keccak256(b"leaf"));
+    }
+
+    #[test]
+    fn test_verify_every_leaf_of_every_tree_size() {
+        for count in 1..=16 {
+            let leaves = leaves(count);
+            let tree = make_tree(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = get_proof(&tree, tree_index(&tree, i));
+                assert!(verify(&proof, tree[0], *leaf), "leaf {i} of {count}");
+                assert_eq!(process_proof(&proof, *leaf), tree[0]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_single_leaf_tree_is_its_own_root() {
+        let leaf = leaf_hash(b"only");
+        assert!(verify(&[], leaf, leaf));
+        assert!(!verify(&[], leaf, leaf_hash(b"other")));
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_proofs() {
+        let leaves = leaves(8);
+        let tree = make_tree(&leaves);
+        let root = tree[0];
+        let proof = get_proof(&tree, tree_index(&tree, 3));
+
+        // Wrong leaf, including one from the same tree
+        assert!(!verify(&proof, root, leaf_hash(b"outsider")));
+        assert!(!verify(&proof, root, leaves[4]));
+
+        // Any flipped sibling
+        for i in 0..proof.len() {
+            let mut tampered = proof.clone();
+            tampered[i].0[0] ^= 1;
+            assert!(!verify(&tampered, root, leaves[3]));
+        }
+
+        // Truncated, extended and reordered proofs
+        assert!(!verify(&proof[..proof.len() - 1], root, leaves[3]));
+        assert!(!verify(&[proof.as_slice(), &[root]].concat(), root, leaves[3]));
+        let mut reordered = proof.clone();
+        reordered.swap(0, 1);
+        assert!(!verify(&reordered, root, leaves[3]));
+
+        // Wrong root
+        assert!(!verify(&proof, leaves[3], leaves[3]));
+    }
+
+    #[test]
+    fn test_inner_node_is_not_a_leaf_of_its_children() {
+        let leaves = leaves(4);
+        let tree = make_tree(&leaves);
+        let root = tree[0];
+        let (node, left, right) = (tree[1], tree[3], tree[4]);
+
+        // A shorter path does prove the inner node itself...
+        assert!(verify(&get_proof(&tree, 1), root, node));
+
+        // ...but not when it is presented as the data of a leaf, which is why
+        // leaves go through `leaf_hash`
+        let (first, second) = if left <= right { (left, right) } else { (right, left) };
+        let forged: Vec<u8> = [first.as_slice(), second.as_slice()].concat();
+        assert_eq!(keccak256(&forged), hash_pair(left, right));
+        assert!(!verify(&get_proof(&tree, 1), root, leaf_hash(&forged)));
+    }
+
+    #[test]
+    fn test_multi_proof_every_subset_of_every_tree_size() {
+        for count in 1..=8u8 {
+            let leaves = leaves(count);
+            let tree = make_tree(&leaves);
+            for subset in 1u32..(1 << count) {
+                let indices: Vec<usize> = (0..count as usize)
+                    .filter(|i| subset & (1 << i) != 0)
+                    .map(|i| tree_index(&tree, i))
+                    .collect();
+                let (proof, flags, proof_leaves) = get_multi_proof(&tree, &indices);
+                assert!(
+                    multi_proof_verify(&proof, &flags, tree[0], &proof_leaves),
+                    "subset {subset:#b} of {count}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_multi_proof_rejects_tampered_proofs() {
+        let leaves = leaves(8);
+        let tree = make_tree(&leaves);
+        let root = tree[0];
+        let indices = [tree_index(&tree, 1), tree_index(&tree, 2), tree_index(&tree, 5)];
+        let (proof, flags, proof_leaves) = get_multi_proof(&tree, &indices);
+        assert!(multi_proof_verify(&proof, &flags, root, &proof_leaves));
+
+        // A swapped-in leaf, or leaves out of order
+        let mut swapped = proof_leaves.clone();
+        swapped[0] = leaves[0];
+        assert!(!multi_proof_verify(&proof, &flags, root, &swapped));
+        let mut reordered = proof_leaves.clone();
+        reordered.swap(0, 2);
+        assert!(!multi_proof_verify(&proof, &flags, root, &reordered));
+
+        // Any flipped proof node or flag
+        for i in 0..proof.len() {
+            let mut tampered = proof.clone();
+            tampered[i].0[31] ^= 1;
+            assert!(!multi_proof_verify(&tampered, &flags, root, &proof_leaves));
+        }
+        for i in 0..flags.len() {
+            let mut tampered = flags.clone();
+            tampered[i] = !tampered[i];
+            assert!(!multi_proof_verify(&proof, &tampered, root, &proof_leaves));
+        }
+
+        // Dropped or extra inputs
+        assert!(!multi_proof_verify(&proof, &flags, root, &proof_leaves[..2]));
+        assert!(!multi_proof_verify(&proof[..proof.len() - 1], &flags, root, &proof_leaves));
+        assert!(!multi_proof_verify(&[proof.as_slice(), &[root]].concat(), &flags, root, &proof_leaves));
+    }
+
+    #[test]
+    fn test_multi_proof_rejects_malformed_inputs() {
+        let leaves = leaves(4);
+        let tree = make_tree(&leaves);
+        let root = tree[0];
+
+        // No leaves: the root alone as the proof must not verify
+        assert_eq!(process_multi_proof(&[root], &[], &[]), None);
+        assert!(!multi_proof_verify(&[], &[], root, &[]));
+
+        // Lengths that don't add up
+        assert_eq!(process_multi_proof(&[], &[true, true], &leaves), None);
+        assert_eq!(process_multi_proof(&[root, root], &[false], &leaves[..1]), None);
+        assert_eq!(process_multi_proof(&[root], &[false], &leaves[..1]), Some(hash_pair(leaves[0], root)));
+
+        // Flags that read a computed node before it exists are rejected
+        // rather than read as zero
+        assert_eq!(process_multi_proof(&[root], &[true], &leaves[..1]), None);
+        assert_eq!(process_multi_proof(&[root, root], &[true, false], &leaves[..1]), None);
+
+        // A leaf with nothing to combine is only the root of a single-leaf tree
+        assert_eq!(process_multi_proof(&[], &[], &leaves[..1]), Some(leaves[0]));
+        assert!(!multi_proof_verify(&[], &[], root, &leaves[..1]));
+    }
+}
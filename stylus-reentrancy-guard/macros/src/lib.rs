@@ -0,0 +1,87 @@
+//! Procedural macros for stylus-reentrancy-guard
+//!
+//! Provides the `#[non_reentrant]` attribute, the Stylus counterpart of
+//! Solidity's `nonReentrant` modifier.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Error, FnArg, ImplItemFn, ReturnType};
+
+/// Protects a contract method with the contract's `ReentrancyGuard`
+///
+/// The method must take `&mut self`, the contract must implement
+/// `ReentrancyGuarded`, and the method must return `Result<T, E>` where
+/// `E: From<ReentrancyError>`.
+///
+/// The original body runs inside a closure, so `return` and `?` leave the
+/// closure rather than the method and the guard is always reset afterwards.
+///
+/// ```ignore
+/// #[public]
+/// impl Vault {
+///     #[non_reentrant]
+///     pub fn withdraw(&mut self, amount: U256) -> Result<(), VaultError> {
+///         // cannot be re-entered while this call is in flight
+///         Ok(())
+///     }
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn non_reentrant(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        return Error::new(
+            proc_macro2::TokenStream::from(attr).span(),
+            "#[non_reentrant] does not take arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let method = parse_macro_input!(item as ImplItemFn);
+
+    match expand(method) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+fn expand(method: ImplItemFn) -> Result<proc_macro2::TokenStream, Error> {
+    let ImplItemFn {
+        attrs,
+        vis,
+        defaultness,
+        sig,
+        block,
+    } = method;
+
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_some() => {}
+        _ => {
+            return Err(Error::new(
+                sig.span(),
+                "#[non_reentrant] methods must take `&mut self`",
+            ))
+        }
+    }
+
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) => ty,
+        ReturnType::Default => {
+            return Err(Error::new(
+                sig.span(),
+                "#[non_reentrant] methods must return a `Result`",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #defaultness #sig {
+            ::stylus_reentrancy_guard::ReentrancyGuarded::reentrancy_guard(self).non_reentrant_before()?;
+            #[allow(clippy::redundant_closure_call)]
+            let __non_reentrant_result = (|| -> #output #block)();
+            ::stylus_reentrancy_guard::ReentrancyGuarded::reentrancy_guard(self).non_reentrant_after();
+            __non_reentrant_result
+        }
+    })
+}
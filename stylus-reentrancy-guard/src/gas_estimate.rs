@@ -0,0 +1,347 @@
+//! Gas estimate of the reentrancy guard
+//!
+//! This is a model, not a measurement. It runs the crate's `ReentrancyGuard`,
+//! with whichever status encoding is compiled in, on a `TestVM` and records
+//! the value its status slot takes on `init`, `non_reentrant_before` and
+//! `non_reentrant_after`. Those writes are then priced inside a vault deposit
+//! and withdrawal, next to the same calls without a guard.
+//!
+//! Stylus unit tests run without gas metering, so the pricing follows the
+//! EVM's storage rules, hard-coded below: EIP-2929 cold and warm access, EIP-2200 net
+//! metering and EIP-3529 refunds (capped at a fifth of the gas used), with a
+//! model of the Stylus storage cache that is flushed before the withdrawal's
+//! external call and at the end of the call. The guard's writes come from
+//! the real code; its single status read on entry and the vault's own
+//! accesses are assumed.
+//!
+//! Figures are EVM-equivalent gas for the storage and call opcodes only: ink
+//! for the WASM itself, calldata and the 21000 transaction base are left out.
+//! The crate has no transient-storage (EIP-1153) guard, so there is no
+//! estimate for one. Enable the `gas-estimate` feature to call `report()`
+//! outside the crate's tests; measure a deployed contract for real costs.
+
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use alloy_primitives::U256;
+use core::fmt::Write;
+use stylus_sdk::{prelude::*, testing::TestVM};
+
+use crate::reentrancy::ReentrancyGuard;
+
+const COLD_SLOAD: u64 = 2100;
+const WARM_ACCESS: u64 = 100;
+const SSTORE_SET: u64 = 20000;
+const SSTORE_RESET: u64 = 5000 - COLD_SLOAD;
+const SSTORE_CLEARS_REFUND: i64 = 4800;
+const COLD_ACCOUNT_ACCESS: u64 = 2600;
+const CALL_VALUE: u64 = 9000;
+const MAX_REFUND_QUOTIENT: u64 = 5;
+
+/// Slot of the guard status
+const GUARD_SLOT: u64 = 0;
+/// Slots of the vault's caller shares, total shares and total assets
+const VAULT_SLOTS: [u64; 3] = [1, 2, 3];
+
+/// Name of the compiled status encoding, used in the report
+#[cfg(not(feature = "bool-status"))]
+pub const ENCODING: &str = "storage (uint256)";
+/// Name of the compiled status encoding, used in the report
+#[cfg(feature = "bool-status")]
+pub const ENCODING: &str = "storage (bool)";
+
+/// Values the guard's status slot holds after each step of a guarded call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GuardTrace {
+    /// After `init`, i.e. before the transaction
+    pub initial: u64,
+    /// After `non_reentrant_before`
+    pub entered: u64,
+    /// After `non_reentrant_after`
+    pub exited: u64,
+}
+
+#[storage]
+struct Harness {
+    guard: ReentrancyGuard,
+}
+
+/// Run the guard through one guarded call and read back its status slot
+pub fn trace_guard() -> GuardTrace {
+    let vm = TestVM::default();
+    let mut harness = Harness::from(&vm);
+    let status = |vm: &TestVM| -> u64 {
+        U256::from_be_bytes(vm.storage_load_bytes32(U256::from(GUARD_SLOT)).0).saturating_to()
+    };
+
+    harness.guard.init();
+    let initial = status(&vm);
+    if harness.guard.non_reentrant_before().is_err() {
+        panic!("fresh guard refused to enter");
+    }
+    let entered = status(&vm);
+    harness.guard.non_reentrant_after();
+    let exited = status(&vm);
+
+    GuardTrace { initial, entered, exited }
+}
+
+/// Estimated gas of one call
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasUsage {
+    /// Gas charged before refunds
+    pub gross: u64,
+    /// Refund applied, after the cap
+    pub refund: u64,
+}
+
+impl GasUsage {
+    /// Gas paid after refunds
+    pub fn net(&self) -> u64 {
+        self.gross - self.refund
+    }
+}
+
+/// Estimated deposit and withdrawal gas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasEstimate {
+    pub deposit: GasUsage,
+    pub withdraw: GasUsage,
+}
+
+/// Storage gas accounting for a single transaction
+struct GasMeter {
+    gas: u64,
+    refund: i64,
+    warm: Vec<u64>,
+    original: BTreeMap<u64, u64>,
+    current: BTreeMap<u64, u64>,
+    /// Stylus storage cache: writes not yet flushed
+    pending: BTreeMap<u64, u64>,
+}
+
+impl GasMeter {
+    fn new(guard: Option<GuardTrace>) -> Self {
+        let mut original = BTreeMap::new();
+        original.insert(GUARD_SLOT, guard.map_or(0, |trace| trace.initial));
+        for slot in VAULT_SLOTS {
+            original.insert(slot, 100);
+        }
+        Self {
+            gas: 0,
+            refund: 0,
+            warm: Vec::new(),
+            current: original.clone(),
+            original,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// Charge the EIP-2929 access cost for `slot`, warming it
+    fn access(&mut self, slot: u64) -> u64 {
+        if self.warm.contains(&slot) {
+            WARM_ACCESS
+        } else {
+            self.warm.push(slot);
+            COLD_SLOAD
+        }
+    }
+
+    /// Read through the storage cache
+    fn load(&mut self, slot: u64) -> u64 {
+        if let Some(value) = self.pending.get(&slot) {
+            return *value;
+        }
+        let cost = self.access(slot);
+        self.gas += cost;
+        self.current[&slot]
+    }
+
+    /// Write into the storage cache
+    fn store(&mut self, slot: u64, value: u64) {
+        self.pending.insert(slot, value);
+    }
+
+    /// Write every cached slot to storage
+    fn flush(&mut self) {
+        let pending = core::mem::take(&mut self.pending);
+        for (slot, value) in pending {
+            self.sstore(slot, value);
+        }
+    }
+
+    /// SSTORE with EIP-2200 net metering and EIP-3529 refunds
+    fn sstore(&mut self, slot: u64, new: u64) {
+        if !self.warm.contains(&slot) {
+            self.warm.push(slot);
+            self.gas += COLD_SLOAD;
+        }
+        let original = self.original[&slot];
+        let current = self.current[&slot];
+
+        if new == current {
+            self.gas += WARM_ACCESS;
+        } else if original == current {
+            if original == 0 {
+                self.gas += SSTORE_SET;
+            } else {
+                self.gas += SSTORE_RESET;
+                if new == 0 {
+                    self.refund += SSTORE_CLEARS_REFUND;
+                }
+            }
+        } else {
+            self.gas += WARM_ACCESS;
+            if original != 0 {
+                if current == 0 {
+                    self.refund -= SSTORE_CLEARS_REFUND;
+                } else if new == 0 {
+                    self.refund += SSTORE_CLEARS_REFUND;
+                }
+            }
+            if new == original {
+                let restored = if original == 0 { SSTORE_SET } else { SSTORE_RESET };
+                self.refund += (restored - WARM_ACCESS) as i64;
+            }
+        }
+        self.current.insert(slot, new);
+    }
+
+    /// ETH transfer to a cold account
+    fn call_with_value(&mut self) {
+        self.gas += COLD_ACCOUNT_ACCESS + CALL_VALUE;
+    }
+
+    fn usage(&self) -> GasUsage {
+        let refund = (self.refund.max(0) as u64).min(self.gas / MAX_REFUND_QUOTIENT);
+        GasUsage { gross: self.gas, refund }
+    }
+}
+
+/// Replay the guard's entry, if any: a status read, then its write
+fn enter(guard: Option<GuardTrace>, meter: &mut GasMeter) {
+    if let Some(trace) = guard {
+        meter.load(GUARD_SLOT);
+        meter.store(GUARD_SLOT, trace.entered);
+    }
+}
+
+/// Replay the guard's exit write, if any
+fn exit(guard: Option<GuardTrace>, meter: &mut GasMeter) {
+    if let Some(trace) = guard {
+        meter.store(GUARD_SLOT, trace.exited);
+    }
+}
+
+/// Estimated gas of a deposit: update shares and totals, no external call
+///
+/// `guard` is the trace of the guard to wrap it in, `None` for no guard.
+pub fn deposit_gas(guard: Option<GuardTrace>) -> GasUsage {
+    let mut meter = GasMeter::new(guard);
+    enter(guard, &mut meter);
+    for slot in VAULT_SLOTS {
+        let value = meter.load(slot);
+        meter.store(slot, value + 1);
+    }
+    exit(guard, &mut meter);
+    meter.flush();
+    meter.usage()
+}
+
+/// Estimated gas of a withdrawal: update shares and totals, then send ETH
+///
+/// `guard` is the trace of the guard to wrap it in, `None` for no guard.
+pub fn withdraw_gas(guard: Option<GuardTrace>) -> GasUsage {
+    let mut meter = GasMeter::new(guard);
+    enter(guard, &mut meter);
+    for slot in VAULT_SLOTS {
+        let value = meter.load(slot);
+        meter.store(slot, value - 1);
+    }
+    // The storage cache is flushed before any external call
+    meter.flush();
+    meter.call_with_value();
+    exit(guard, &mut meter);
+    meter.flush();
+    meter.usage()
+}
+
+/// Estimated deposit and withdrawal gas without a guard
+pub fn estimate_unguarded() -> GasEstimate {
+    GasEstimate {
+        deposit: deposit_gas(None),
+        withdraw: withdraw_gas(None),
+    }
+}
+
+/// Estimated deposit and withdrawal gas with the compiled `ReentrancyGuard`
+pub fn estimate_guarded() -> GasEstimate {
+    let guard = Some(trace_guard());
+    GasEstimate {
+        deposit: deposit_gas(guard),
+        withdraw: withdraw_gas(guard),
+    }
+}
+
+/// Estimated gas with and without the guard, with the guard's overhead
+pub fn report() -> String {
+    let baseline = estimate_unguarded();
+    let mut out = String::new();
+    let _ = writeln!(out, "| guard | deposit | overhead | withdraw | overhead |");
+    let _ = writeln!(out, "|-------|---------|----------|----------|----------|");
+    for (name, result) in [("none", baseline), (ENCODING, estimate_guarded())] {
+        let _ = writeln!(
+            out,
+            "| {} | {} | +{} | {} | +{} |",
+            name,
+            result.deposit.net(),
+            result.deposit.net() - baseline.deposit.net(),
+            result.withdraw.net(),
+            result.withdraw.net() - baseline.withdraw.net(),
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overhead() -> (u64, u64) {
+        let baseline = estimate_unguarded();
+        let result = estimate_guarded();
+        (
+            result.deposit.net() - baseline.deposit.net(),
+            result.withdraw.net() - baseline.withdraw.net(),
+        )
+    }
+
+    #[test]
+    fn test_baseline() {
+        // Three cold read-modify-writes, plus a cold value transfer on withdraw
+        assert_eq!(deposit_gas(None).net(), 15000);
+        assert_eq!(withdraw_gas(None).net(), 26600);
+    }
+
+    #[cfg(not(feature = "bool-status"))]
+    #[test]
+    fn test_guard_budget() {
+        // 1 -> 2 -> 1: coalesced by the cache on deposit, refunded on withdraw
+        assert_eq!(trace_guard(), GuardTrace { initial: 1, entered: 2, exited: 1 });
+        assert_eq!(overhead(), (2200, 2300));
+        assert_eq!(estimate_guarded().withdraw.refund, 2800);
+    }
+
+    #[cfg(feature = "bool-status")]
+    #[test]
+    fn test_guard_budget() {
+        // 0 -> 1 costs a fresh SSTORE whose refund is cut by the cap
+        assert_eq!(trace_guard(), GuardTrace { initial: 0, entered: 1, exited: 0 });
+        assert_eq!(overhead(), (2200, 12440));
+    }
+
+    #[test]
+    fn test_report() {
+        let report = report();
+        assert!(report.contains("| none | 15000 | +0 | 26600 | +0 |"));
+        assert!(report.contains(ENCODING));
+    }
+}
@@ -0,0 +1,183 @@
+//! Keyed ReentrancyGuard for Stylus smart contracts
+//!
+//! Holds any number of independent locks identified by a `bytes32` key, so a
+//! contract can protect unrelated flows (deposits vs withdrawals, per-market
+//! operations, per-user actions) without serializing everything behind one
+//! global lock.
+
+use alloc::vec::Vec;
+use alloy_primitives::{keccak256, Address, B256};
+use stylus_sdk::{prelude::*, storage::StorageMap};
+#[cfg(feature = "bool-status")]
+use stylus_sdk::storage::StorageBool;
+#[cfg(not(feature = "bool-status"))]
+use stylus_sdk::storage::StorageU256;
+
+use crate::reentrancy::{ReentrancyError, ReentrancyGuardReadOnlyReentrantCall, ReentrancyGuardReentrantCall};
+#[cfg(not(feature = "bool-status"))]
+use crate::reentrancy::{ENTERED, NOT_ENTERED};
+
+/// Storage structure for keyed reentrancy locks
+///
+/// Unused keys read as zero and are treated as NOT_ENTERED, so no
+/// initialization is required.
+#[storage]
+pub struct ReentrancyGuardKeyed {
+    /// Reentrancy status per key (NOT_ENTERED = 1, ENTERED = 2)
+    #[cfg(not(feature = "bool-status"))]
+    statuses: StorageMap<B256, StorageU256>,
+    /// Reentrancy status per key (true while entered)
+    #[cfg(feature = "bool-status")]
+    statuses: StorageMap<B256, StorageBool>,
+}
+
+impl ReentrancyGuardKeyed {
+    /// Check if the lock for `key` is currently held
+    pub fn reentrancy_guard_entered(&self, key: B256) -> bool {
+        #[cfg(not(feature = "bool-status"))]
+        return self.statuses.get(key) == ENTERED;
+        #[cfg(feature = "bool-status")]
+        return self.statuses.get(key);
+    }
+
+    fn set_entered(&mut self, key: B256, entered: bool) {
+        #[cfg(not(feature = "bool-status"))]
+        self.statuses.setter(key).set(if entered { ENTERED } else { NOT_ENTERED });
+        #[cfg(feature = "bool-status")]
+        self.statuses.setter(key).set(entered);
+    }
+
+    /// Check that the lock for `key` is not currently held, for use in views
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReadOnlyReentrantCall` if the lock is held.
+    pub fn non_reentrant_view(&self, key: B256) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered(key) {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        Ok(())
+    }
+
+    /// Acquire the lock for `key`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if the lock is already held.
+    pub fn non_reentrant_before(&mut self, key: B256) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered(key) {
+            return Err(ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+
+        self.set_entered(key, true);
+        Ok(())
+    }
+
+    /// Release the lock for `key`
+    pub fn non_reentrant_after(&mut self, key: B256) {
+        self.set_entered(key, false);
+    }
+
+    /// Execute a closure while holding the lock for `key`
+    ///
+    /// The lock is released whether the closure succeeds or fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` (converted into `E`) if the lock
+    /// is already held, or any error returned by the closure.
+    pub fn non_reentrant<F, T, E>(&mut self, key: B256, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        self.non_reentrant_before(key)?;
+        let result = f();
+        self.non_reentrant_after(key);
+        result
+    }
+}
+
+/// Derive a lock key from a function selector
+pub fn key_from_selector(selector: [u8; 4]) -> B256 {
+    derive_key(b"selector", &selector)
+}
+
+/// Derive a lock key from a user address
+pub fn key_from_address(user: Address) -> B256 {
+    derive_key(b"address", user.as_slice())
+}
+
+/// Derive a lock key scoped to both a function selector and a user address
+pub fn key_from_selector_and_address(selector: [u8; 4], user: Address) -> B256 {
+    let mut data = [0u8; 24];
+    data[..4].copy_from_slice(&selector);
+    data[4..].copy_from_slice(user.as_slice());
+    derive_key(b"selector-address", &data)
+}
+
+/// Hash `data` under a domain tag so keys from different helpers never collide
+fn derive_key(domain: &[u8], data: &[u8]) -> B256 {
+    let mut preimage = Vec::with_capacity(domain.len() + 1 + data.len());
+    preimage.extend_from_slice(domain);
+    preimage.push(b':');
+    preimage.extend_from_slice(data);
+    keccak256(preimage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        locks: ReentrancyGuardKeyed,
+    }
+
+    #[test]
+    fn test_independent_keys() {
+        let mut contract = TestContract::default();
+        let deposits = key_from_selector([0xd0, 0xe3, 0x0d, 0xb0]);
+        let withdrawals = key_from_selector([0x2e, 0x1a, 0x7d, 0x4d]);
+
+        assert!(contract.locks.non_reentrant_before(deposits).is_ok());
+
+        // A different key is unaffected
+        assert!(!contract.locks.reentrancy_guard_entered(withdrawals));
+        assert!(contract.locks.non_reentrant_before(withdrawals).is_ok());
+
+        // The same key is locked
+        let result = contract.locks.non_reentrant_before(deposits);
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+
+        contract.locks.non_reentrant_after(deposits);
+        assert!(!contract.locks.reentrancy_guard_entered(deposits));
+        assert!(contract.locks.reentrancy_guard_entered(withdrawals));
+    }
+
+    #[test]
+    fn test_closure_releases_lock() {
+        let mut contract = TestContract::default();
+        let key = key_from_address(Address::from([7u8; 20]));
+
+        let result: Result<u32, ReentrancyError> = contract.locks.non_reentrant(key, || Ok(7));
+        assert_eq!(result.unwrap(), 7);
+        assert!(!contract.locks.reentrancy_guard_entered(key));
+
+        let result: Result<u32, ReentrancyError> =
+            contract.locks.non_reentrant(key, || Err(ReentrancyGuardReentrantCall {}.into()));
+        assert!(result.is_err());
+        assert!(!contract.locks.reentrancy_guard_entered(key));
+    }
+
+    #[test]
+    fn test_key_domains_do_not_collide() {
+        let selector = [0x12, 0x34, 0x56, 0x78];
+        let user = Address::from([0x12; 20]);
+
+        assert_ne!(key_from_selector(selector), key_from_address(user));
+        assert_ne!(key_from_selector(selector), key_from_selector_and_address(selector, user));
+        assert_ne!(key_from_address(user), key_from_selector_and_address(selector, user));
+        assert_eq!(key_from_address(user), key_from_address(user));
+    }
+}
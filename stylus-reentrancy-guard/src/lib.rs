@@ -0,0 +1,77 @@
+//! Reusable reentrancy protection for Stylus smart contracts
+//!
+//! Provides the `ReentrancyGuard` storage component, the `ReentrancyGuarded`
+//! trait, keyed locks for independent flows, a guard pinned to an ERC-7201
+//! namespaced slot, a depth-counting guard for legitimate nested entry,
+//! selector-scoped exemptions for harmless callbacks, and the
+//! `#[non_reentrant]` attribute. Alongside the guard it ships composable
+//! withdrawal `RateLimiter` and `WithdrawalCaps` modules, `ReentrancyTelemetry`
+//! for counting blocked re-entry attempts, safe ETH and ERC20 transfer
+//! helpers, and a client for a lock shared across contracts through a
+//! `GuardRegistry`. The crate is `no_std` and has no entrypoint, so any
+//! contract can embed it.
+//!
+//! `Ownable`/`Ownable2Step`, `Pausable` and `Initializable` live in
+//! `stylus-common` and are re-exported here, so existing imports keep working.
+//!
+//! # Errors
+//!
+//! Each error enum wraps `sol!`-declared error structs (e.g.
+//! `ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall)`). The structs
+//! are re-exported so contracts can reuse them as variants of their own error
+//! enums, and every struct converts into its enum with `From`.
+//!
+//! # Features
+//!
+//! - `bool-status`: store the guard status as a `bool` instead of the default
+//!   `uint256` (NOT_ENTERED = 1, ENTERED = 2) encoding.
+//! - `telemetry`: emit `ReentrancyAttemptBlocked(selector, caller)` whenever
+//!   `ReentrancyTelemetry` records an attempt.
+//! - `gas-estimate`: expose the `gas_estimate` module, which runs the guard on
+//!   a `TestVM` and prices a deposit and withdrawal with and without it from
+//!   a model of EVM storage gas (native only).
+
+#![cfg_attr(not(test), no_std)]
+extern crate alloc;
+
+// Lets `#[non_reentrant]` refer to this crate by name from inside it
+extern crate self as stylus_reentrancy_guard;
+
+#[cfg(any(test, feature = "gas-estimate"))]
+pub mod gas_estimate;
+pub mod keyed;
+pub mod namespaced;
+pub mod nested;
+pub mod policy;
+pub mod rate_limit;
+pub mod reentrancy;
+pub mod registry;
+pub mod safe_transfer;
+pub mod telemetry;
+pub mod withdrawal_cap;
+
+pub use keyed::{key_from_address, key_from_selector, key_from_selector_and_address, ReentrancyGuardKeyed};
+pub use namespaced::{ReentrancyGuardNamespaced, REENTRANCY_GUARD_NAMESPACE, REENTRANCY_GUARD_SLOT};
+pub use nested::{NestingPolicy, ReentrancyCounter};
+pub use policy::{Selector, SelectorPolicy, ON_ERC1155_BATCH_RECEIVED, ON_ERC1155_RECEIVED, ON_ERC721_RECEIVED};
+pub use rate_limit::{
+    GlobalRateLimitExceeded, RateLimitError, RateLimiter, UserRateLimitExceeded, RATE_LIMIT_DAY,
+};
+pub use reentrancy::{
+    ReentrancyError, ReentrancyGuard, ReentrancyGuardReadOnlyReentrantCall, ReentrancyGuardReentrantCall, ReentrancyGuarded,
+    ReentrancyLock, ENTERED, NOT_ENTERED,
+};
+pub use registry::{IGuardRegistry, ReentrancyGuardShared};
+pub use safe_transfer::{
+    safe_erc20_transfer, safe_erc20_transfer_from, safe_transfer_eth, GasStipend, SafeERC20FailedOperation,
+    SafeTransferError, SafeTransferEthRejected, SafeTransferEthReverted, SafeTransferInsufficientBalance,
+    TRANSFER_GAS_STIPEND,
+};
+pub use stylus_common::{
+    initializable, ownable, pausable, EnforcedPause, ExpectedPause, Initializable, InitializableError,
+    InvalidInitialization, Ownable, Ownable2Step, OwnableError, OwnableInvalidOwner, OwnableUnauthorizedAccount,
+    Pausable, PausableError,
+};
+pub use stylus_reentrancy_guard_macros::non_reentrant;
+pub use telemetry::{is_reentrant_call_revert, ReentrancyTelemetry};
+pub use withdrawal_cap::{WithdrawalCapError, WithdrawalCapExceeded, WithdrawalCaps};
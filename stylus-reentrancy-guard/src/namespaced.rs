@@ -0,0 +1,179 @@
+//! Namespaced ReentrancyGuard for Stylus smart contracts
+//!
+//! `ReentrancyGuard` lives wherever field ordering puts it, so reordering or
+//! inserting fields in an upgraded implementation silently moves the lock. This
+//! variant keeps its status at a fixed ERC-7201 slot derived from the
+//! `stylus.storage.ReentrancyGuard` namespace, so proxies, upgrades and
+//! composed components can rely on a stable location.
+
+use alloy_primitives::{B256, U256};
+use stylus_sdk::prelude::*;
+
+use crate::reentrancy::{
+    ReentrancyError, ReentrancyGuardReadOnlyReentrantCall, ReentrancyGuardReentrantCall, ENTERED, NOT_ENTERED,
+};
+
+/// ERC-7201 namespace identifier for the guard status
+pub const REENTRANCY_GUARD_NAMESPACE: &str = "stylus.storage.ReentrancyGuard";
+
+/// `keccak256(abi.encode(uint256(keccak256(REENTRANCY_GUARD_NAMESPACE)) - 1)) & ~bytes32(uint256(0xff))`
+pub const REENTRANCY_GUARD_SLOT: U256 = U256::from_be_bytes([
+    0xfd, 0x05, 0x89, 0xe5, 0x04, 0xb5, 0xba, 0x3f, 0x56, 0x2e, 0x3f, 0x29, 0x6d, 0x5b, 0x47, 0x4e,
+    0xe3, 0xfc, 0x8e, 0xdc, 0x2d, 0xaa, 0x84, 0x53, 0xa6, 0x6c, 0x07, 0xd1, 0xdf, 0xaa, 0xe8, 0x00,
+]);
+
+/// Storage structure for the namespaced guard
+///
+/// Occupies no sequential storage slots; the status is read and written at
+/// `REENTRANCY_GUARD_SLOT` directly. All instances in a contract share the
+/// same lock.
+#[storage]
+pub struct ReentrancyGuardNamespaced {}
+
+impl ReentrancyGuardNamespaced {
+    /// Storage slot holding the guard status
+    pub const fn slot() -> U256 {
+        REENTRANCY_GUARD_SLOT
+    }
+
+    fn status(&self) -> U256 {
+        U256::from_be_bytes(self.vm().storage_load_bytes32(Self::slot()).0)
+    }
+
+    fn set_entered(&mut self, entered: bool) {
+        let status = if entered { ENTERED } else { NOT_ENTERED };
+        // Safety: the slot is reserved for this guard by its namespace
+        unsafe {
+            self.vm()
+                .storage_cache_bytes32(Self::slot(), B256::from(status.to_be_bytes::<32>()));
+        }
+    }
+
+    /// Initialize the guard, setting the status to NOT_ENTERED
+    pub fn init(&mut self) {
+        self.set_entered(false);
+    }
+
+    /// Check if a non-reentrant function is currently executing
+    pub fn reentrancy_guard_entered(&self) -> bool {
+        self.status() == ENTERED
+    }
+
+    /// Check that no non-reentrant function is currently executing
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReadOnlyReentrantCall` if the guard is entered.
+    pub fn non_reentrant_view(&self) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        Ok(())
+    }
+
+    /// Acquire the lock before executing a non-reentrant function
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if a reentrant call is detected.
+    pub fn non_reentrant_before(&mut self) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+        self.set_entered(true);
+        Ok(())
+    }
+
+    /// Release the lock after executing a non-reentrant function
+    pub fn non_reentrant_after(&mut self) {
+        self.set_entered(false);
+    }
+
+    /// Execute a closure with reentrancy protection
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` (converted into `E`) if a
+    /// reentrant call is detected, or any error returned by the closure.
+    pub fn non_reentrant<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        self.non_reentrant_before()?;
+        let result = f();
+        self.non_reentrant_after();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reentrancy::ReentrancyGuard;
+    use alloy_primitives::keccak256;
+    use stylus_sdk::storage::{StorageMap, StorageU256};
+
+    #[storage]
+    struct TestContract {
+        counter: StorageU256,
+        balances: StorageMap<U256, StorageU256>,
+        legacy: ReentrancyGuard,
+        guard: ReentrancyGuardNamespaced,
+    }
+
+    #[test]
+    fn test_slot_derivation() {
+        let inner = U256::from_be_bytes(keccak256(REENTRANCY_GUARD_NAMESPACE).0) - U256::from(1);
+        let outer = U256::from_be_bytes(keccak256(inner.to_be_bytes::<32>()).0);
+        let expected = outer & !U256::from(0xff);
+
+        assert_eq!(ReentrancyGuardNamespaced::slot(), expected);
+        assert_eq!(ReentrancyGuardNamespaced::slot() & U256::from(0xff), U256::ZERO);
+    }
+
+    #[test]
+    fn test_lock_cycle() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        assert!(contract.guard.non_reentrant_before().is_ok());
+        assert!(contract.guard.reentrancy_guard_entered());
+        assert!(matches!(
+            contract.guard.non_reentrant_before(),
+            Err(ReentrancyError::ReentrantCall(_))
+        ));
+
+        contract.guard.non_reentrant_after();
+        assert!(!contract.guard.reentrancy_guard_entered());
+        assert!(contract.guard.non_reentrant_view().is_ok());
+    }
+
+    #[test]
+    fn test_sequential_fields_do_not_collide() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        contract.legacy.init();
+
+        // Writing every other component leaves the namespaced status untouched
+        contract.counter.set(ENTERED);
+        contract.balances.setter(U256::ZERO).set(ENTERED);
+        contract.balances.setter(REENTRANCY_GUARD_SLOT).set(ENTERED);
+        assert!(contract.legacy.non_reentrant_before().is_ok());
+        assert!(!contract.guard.reentrancy_guard_entered());
+
+        // And holding the namespaced lock leaves them untouched
+        contract.legacy.non_reentrant_after();
+        assert!(contract.guard.non_reentrant_before().is_ok());
+        assert!(!contract.legacy.reentrancy_guard_entered());
+        assert_eq!(contract.counter.get(), ENTERED);
+    }
+
+    #[test]
+    fn test_distinct_from_openzeppelin_namespace() {
+        let oz = U256::from_be_bytes(
+            alloy_primitives::hex!("9b779b17422d0df92223018b32b4d1fa46e071723d6817e2486d003becc55f00"),
+        );
+        assert_ne!(ReentrancyGuardNamespaced::slot(), oz);
+    }
+}
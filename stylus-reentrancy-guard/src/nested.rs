@@ -0,0 +1,166 @@
+//! Depth-counting ReentrancyGuard for Stylus smart contracts
+//!
+//! Some composed flows legitimately re-enter a protected contract through a
+//! different protected function in the same call tree, e.g. a flash-accounting
+//! callback that calls `repay` while `flash` is still executing. Instead of a
+//! binary ENTERED flag, this guard tracks how many protected frames are active
+//! and lets each function state how much nesting it tolerates.
+
+use alloy_primitives::U256;
+use stylus_sdk::{prelude::*, storage::StorageU256};
+
+use crate::reentrancy::{ReentrancyError, ReentrancyGuardReadOnlyReentrantCall, ReentrancyGuardReentrantCall};
+
+/// How many protected frames may already be active when a function is entered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NestingPolicy {
+    max_nested: u32,
+}
+
+impl NestingPolicy {
+    /// Only enterable when no other protected function is executing
+    pub const NONE: Self = Self { max_nested: 0 };
+
+    /// Enterable while up to `n` other protected functions are executing
+    pub const fn allow_nested(n: u32) -> Self {
+        Self { max_nested: n }
+    }
+
+    /// Maximum number of protected frames that may already be active
+    pub const fn max_nested(&self) -> u32 {
+        self.max_nested
+    }
+}
+
+/// Storage structure for the depth-counting guard
+#[storage]
+pub struct ReentrancyCounter {
+    /// Number of protected functions currently executing
+    depth: StorageU256,
+}
+
+impl ReentrancyCounter {
+    /// Number of protected functions currently executing
+    pub fn depth(&self) -> U256 {
+        self.depth.get()
+    }
+
+    /// Check if any protected function is currently executing
+    pub fn reentrancy_guard_entered(&self) -> bool {
+        !self.depth.get().is_zero()
+    }
+
+    /// Check that no protected function is currently executing, for use in views
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReadOnlyReentrantCall` if the depth is non-zero.
+    pub fn non_reentrant_view(&self) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        Ok(())
+    }
+
+    /// Enter a protected function under `policy`
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if more protected frames are
+    /// already active than the policy allows.
+    pub fn enter(&mut self, policy: NestingPolicy) -> Result<(), ReentrancyError> {
+        let depth = self.depth.get();
+        if depth > U256::from(policy.max_nested()) {
+            return Err(ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+
+        self.depth.set(depth + U256::from(1));
+        Ok(())
+    }
+
+    /// Leave a protected function
+    pub fn exit(&mut self) {
+        let depth = self.depth.get();
+        if !depth.is_zero() {
+            self.depth.set(depth - U256::from(1));
+        }
+    }
+
+    /// Execute a closure as a protected function under `policy`
+    ///
+    /// The depth is restored whether the closure succeeds or fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` (converted into `E`) if the
+    /// nesting limit is exceeded, or any error returned by the closure.
+    pub fn non_reentrant<F, T, E>(&mut self, policy: NestingPolicy, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        self.enter(policy)?;
+        let result = f();
+        self.exit();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Policies a flash-loan style contract might use
+    const FLASH: NestingPolicy = NestingPolicy::NONE;
+    const REPAY: NestingPolicy = NestingPolicy::allow_nested(1);
+
+    #[storage]
+    struct TestContract {
+        counter: ReentrancyCounter,
+    }
+
+    #[test]
+    fn test_none_policy_behaves_like_binary_guard() {
+        let mut contract = TestContract::default();
+
+        assert!(contract.counter.enter(NestingPolicy::NONE).is_ok());
+        let result = contract.counter.enter(NestingPolicy::NONE);
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+
+        contract.counter.exit();
+        assert!(!contract.counter.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_allowed_nesting() {
+        let mut contract = TestContract::default();
+
+        // flash -> repay is allowed
+        assert!(contract.counter.enter(FLASH).is_ok());
+        assert!(contract.counter.enter(REPAY).is_ok());
+        assert_eq!(contract.counter.depth(), U256::from(2));
+
+        // flash -> repay -> repay is not
+        let result = contract.counter.enter(REPAY);
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+
+        // Nor is re-entering flash from the callback
+        contract.counter.exit();
+        let result = contract.counter.enter(FLASH);
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+
+        contract.counter.exit();
+        assert_eq!(contract.counter.depth(), U256::ZERO);
+    }
+
+    #[test]
+    fn test_closure_restores_depth() {
+        let mut contract = TestContract::default();
+
+        let result: Result<(), ReentrancyError> =
+            contract.counter.non_reentrant(FLASH, || Err(ReentrancyGuardReentrantCall {}.into()));
+        assert!(result.is_err());
+        assert_eq!(contract.counter.depth(), U256::ZERO);
+        assert!(contract.counter.non_reentrant_view().is_ok());
+    }
+}
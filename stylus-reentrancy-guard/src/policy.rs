@@ -0,0 +1,160 @@
+//! Selector-scoped reentrancy policy for Stylus smart contracts
+//!
+//! A single global lock also rejects harmless callbacks made back into the
+//! contract while it is locked, such as `onERC721Received` during a transfer.
+//! A `SelectorPolicy` lists, at compile time, the function selectors that are
+//! exempt from the guard; every other selector stays locked.
+
+use crate::reentrancy::{ReentrancyError, ReentrancyGuard};
+
+/// Four-byte function selector
+pub type Selector = [u8; 4];
+
+/// `onERC721Received(address,address,uint256,bytes)`
+pub const ON_ERC721_RECEIVED: Selector = [0x15, 0x0b, 0x7a, 0x02];
+
+/// `onERC1155Received(address,address,uint256,uint256,bytes)`
+pub const ON_ERC1155_RECEIVED: Selector = [0xf2, 0x3a, 0x6e, 0x61];
+
+/// `onERC1155BatchReceived(address,address,uint256[],uint256[],bytes)`
+pub const ON_ERC1155_BATCH_RECEIVED: Selector = [0xbc, 0x19, 0x7c, 0x81];
+
+/// Compile-time set of selectors exempt from the reentrancy guard
+///
+/// ```ignore
+/// const POLICY: SelectorPolicy<2> = SelectorPolicy::new([ON_ERC721_RECEIVED, ON_ERC1155_RECEIVED]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SelectorPolicy<const N: usize> {
+    exempt: [Selector; N],
+}
+
+impl SelectorPolicy<0> {
+    /// Policy with no exemptions; every selector is guarded
+    pub const NONE: Self = Self { exempt: [] };
+}
+
+impl<const N: usize> SelectorPolicy<N> {
+    /// Create a policy exempting the given selectors
+    pub const fn new(exempt: [Selector; N]) -> Self {
+        Self { exempt }
+    }
+
+    /// Selectors exempt from the guard
+    pub const fn exempt_selectors(&self) -> &[Selector; N] {
+        &self.exempt
+    }
+
+    /// Check if `selector` is exempt from the guard
+    pub const fn is_exempt(&self, selector: Selector) -> bool {
+        let mut i = 0;
+        while i < N {
+            let s = self.exempt[i];
+            if s[0] == selector[0] && s[1] == selector[1] && s[2] == selector[2] && s[3] == selector[3] {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+}
+
+impl ReentrancyGuard {
+    /// Execute a closure for `selector`, applying the guard unless the policy exempts it
+    ///
+    /// Exempt selectors run without checking or taking the lock, so they can
+    /// be called back into while a guarded function is executing. Selectors
+    /// can be computed with `stylus_sdk::function_selector!`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` (converted into `E`) if a
+    /// non-exempt selector is called reentrantly, or any error returned by
+    /// the closure.
+    pub fn non_reentrant_scoped<F, T, E, const N: usize>(
+        &mut self,
+        selector: Selector,
+        policy: &SelectorPolicy<N>,
+        f: F,
+    ) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        if policy.is_exempt(selector) {
+            return f();
+        }
+
+        self.non_reentrant_before()?;
+        let result = f();
+        self.non_reentrant_after();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::keccak256;
+    use stylus_sdk::prelude::*;
+
+    const WITHDRAW: Selector = [0x2e, 0x1a, 0x7d, 0x4d];
+    const POLICY: SelectorPolicy<1> = SelectorPolicy::new([ON_ERC721_RECEIVED]);
+
+    #[storage]
+    struct TestContract {
+        guard: ReentrancyGuard,
+    }
+
+    fn selector(signature: &str) -> Selector {
+        let hash = keccak256(signature);
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    #[test]
+    fn test_receiver_selectors() {
+        assert_eq!(ON_ERC721_RECEIVED, selector("onERC721Received(address,address,uint256,bytes)"));
+        assert_eq!(ON_ERC1155_RECEIVED, selector("onERC1155Received(address,address,uint256,uint256,bytes)"));
+        assert_eq!(
+            ON_ERC1155_BATCH_RECEIVED,
+            selector("onERC1155BatchReceived(address,address,uint256[],uint256[],bytes)")
+        );
+    }
+
+    #[test]
+    fn test_is_exempt() {
+        assert!(POLICY.is_exempt(ON_ERC721_RECEIVED));
+        assert!(!POLICY.is_exempt(WITHDRAW));
+        assert!(!SelectorPolicy::NONE.is_exempt(ON_ERC721_RECEIVED));
+    }
+
+    #[test]
+    fn test_exempt_selector_runs_while_locked() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        contract.guard.non_reentrant_before().unwrap();
+
+        let result: Result<u32, ReentrancyError> =
+            contract.guard.non_reentrant_scoped(ON_ERC721_RECEIVED, &POLICY, || Ok(1));
+        assert_eq!(result.ok(), Some(1));
+        // The exempt call must not release the outer lock
+        assert!(contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_guarded_selector_blocked_while_locked() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        contract.guard.non_reentrant_before().unwrap();
+
+        let result: Result<u32, ReentrancyError> =
+            contract.guard.non_reentrant_scoped(WITHDRAW, &POLICY, || Ok(1));
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+
+        contract.guard.non_reentrant_after();
+        let result: Result<u32, ReentrancyError> =
+            contract.guard.non_reentrant_scoped(WITHDRAW, &POLICY, || Ok(1));
+        assert_eq!(result.ok(), Some(1));
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+}
@@ -0,0 +1,219 @@
+//! Withdrawal rate limiting for Stylus smart contracts
+//!
+//! Combines a token bucket per address with a global cap per UTC day. Each
+//! bucket holds up to `capacity` and refills at `refill_rate` per second; the
+//! global cap resets at every day boundary. Embed `RateLimiter` next to the
+//! guard and call `consume` on every outflow.
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageMap, StorageU256},
+};
+
+/// Seconds in a rate-limit day
+pub const RATE_LIMIT_DAY: u64 = 24 * 60 * 60;
+
+sol! {
+    /// Thrown when an outflow exceeds the account's bucket
+    error UserRateLimitExceeded();
+    /// Thrown when an outflow exceeds what is left of the global daily cap
+    error GlobalRateLimitExceeded();
+}
+
+/// Error types for RateLimiter
+#[derive(SolidityError)]
+pub enum RateLimitError {
+    UserRateLimitExceeded(UserRateLimitExceeded),
+    GlobalRateLimitExceeded(GlobalRateLimitExceeded),
+}
+
+impl From<UserRateLimitExceeded> for RateLimitError {
+    fn from(err: UserRateLimitExceeded) -> Self {
+        RateLimitError::UserRateLimitExceeded(err)
+    }
+}
+
+impl From<GlobalRateLimitExceeded> for RateLimitError {
+    fn from(err: GlobalRateLimitExceeded) -> Self {
+        RateLimitError::GlobalRateLimitExceeded(err)
+    }
+}
+
+/// Storage structure for a single account's token bucket
+#[storage]
+pub struct RateLimitBucket {
+    /// Allowance left at `updated_at`
+    tokens: StorageU256,
+    /// Timestamp of the last consumption (zero for a full, untouched bucket)
+    updated_at: StorageU256,
+}
+
+/// Storage structure for RateLimiter
+#[storage]
+pub struct RateLimiter {
+    /// Maximum per-account allowance (zero disables per-account limits)
+    capacity: StorageU256,
+    /// Per-account allowance regained per second
+    refill_rate: StorageU256,
+    /// Maximum total outflow per day (zero disables the global cap)
+    daily_cap: StorageU256,
+    /// Per-account buckets
+    buckets: StorageMap<Address, RateLimitBucket>,
+    /// Day index `daily_spent` refers to
+    day: StorageU256,
+    /// Total outflow so far on `day`
+    daily_spent: StorageU256,
+}
+
+impl RateLimiter {
+    /// Set the limits; zero disables the corresponding limit
+    pub fn configure(&mut self, capacity: U256, refill_rate: U256, daily_cap: U256) {
+        self.capacity.set(capacity);
+        self.refill_rate.set(refill_rate);
+        self.daily_cap.set(daily_cap);
+    }
+
+    /// Current limits as `(capacity, refill_rate, daily_cap)`
+    pub fn limits(&self) -> (U256, U256, U256) {
+        (self.capacity.get(), self.refill_rate.get(), self.daily_cap.get())
+    }
+
+    /// What `user` may still withdraw at `now` under their own bucket
+    pub fn user_allowance(&self, user: Address, now: U256) -> U256 {
+        let capacity = self.capacity.get();
+        if capacity.is_zero() {
+            return U256::MAX;
+        }
+
+        let bucket = self.buckets.getter(user);
+        let updated_at = bucket.updated_at.get();
+        if updated_at.is_zero() {
+            return capacity;
+        }
+
+        let elapsed = now.saturating_sub(updated_at);
+        let refilled = bucket
+            .tokens
+            .get()
+            .saturating_add(elapsed.saturating_mul(self.refill_rate.get()));
+        refilled.min(capacity)
+    }
+
+    /// What is left of the global daily cap at `now`
+    pub fn global_allowance(&self, now: U256) -> U256 {
+        let cap = self.daily_cap.get();
+        if cap.is_zero() {
+            return U256::MAX;
+        }
+        if self.day.get() != now / U256::from(RATE_LIMIT_DAY) {
+            return cap;
+        }
+        cap.saturating_sub(self.daily_spent.get())
+    }
+
+    /// What `user` may withdraw at `now` under both limits
+    pub fn remaining_allowance(&self, user: Address, now: U256) -> U256 {
+        self.user_allowance(user, now).min(self.global_allowance(now))
+    }
+
+    /// Record an outflow of `amount` by `user` at `now`
+    ///
+    /// # Errors
+    ///
+    /// Returns `RateLimitError::UserRateLimitExceeded` or
+    /// `RateLimitError::GlobalRateLimitExceeded` if `amount` is over either
+    /// allowance; nothing is recorded in that case.
+    pub fn consume(&mut self, user: Address, amount: U256, now: U256) -> Result<(), RateLimitError> {
+        let user_allowance = self.user_allowance(user, now);
+        if amount > user_allowance {
+            return Err(RateLimitError::UserRateLimitExceeded(UserRateLimitExceeded {}));
+        }
+        if amount > self.global_allowance(now) {
+            return Err(RateLimitError::GlobalRateLimitExceeded(GlobalRateLimitExceeded {}));
+        }
+
+        if !self.capacity.get().is_zero() {
+            let mut bucket = self.buckets.setter(user);
+            bucket.tokens.set(user_allowance - amount);
+            bucket.updated_at.set(now);
+        }
+
+        if !self.daily_cap.get().is_zero() {
+            let day = now / U256::from(RATE_LIMIT_DAY);
+            if self.day.get() != day {
+                self.day.set(day);
+                self.daily_spent.set(U256::ZERO);
+            }
+            self.daily_spent.set(self.daily_spent.get() + amount);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        limits: RateLimiter,
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    #[test]
+    fn test_unconfigured_is_unlimited() {
+        let mut contract = TestContract::default();
+        assert_eq!(contract.limits.remaining_allowance(alice(), U256::from(1)), U256::MAX);
+        assert!(contract.limits.consume(alice(), U256::from(1_000_000), U256::from(1)).is_ok());
+    }
+
+    #[test]
+    fn test_user_bucket_refills() {
+        let mut contract = TestContract::default();
+        contract.limits.configure(U256::from(100), U256::from(2), U256::ZERO);
+
+        let now = U256::from(1000);
+        assert!(contract.limits.consume(alice(), U256::from(80), now).is_ok());
+        assert_eq!(contract.limits.user_allowance(alice(), now), U256::from(20));
+        assert!(matches!(
+            contract.limits.consume(alice(), U256::from(21), now),
+            Err(RateLimitError::UserRateLimitExceeded(_))
+        ));
+
+        // Other accounts have their own bucket
+        assert_eq!(contract.limits.user_allowance(bob(), now), U256::from(100));
+
+        // Refills at 2 per second, up to capacity
+        assert_eq!(contract.limits.user_allowance(alice(), now + U256::from(10)), U256::from(40));
+        assert_eq!(contract.limits.user_allowance(alice(), now + U256::from(1000)), U256::from(100));
+    }
+
+    #[test]
+    fn test_global_daily_cap() {
+        let mut contract = TestContract::default();
+        contract.limits.configure(U256::ZERO, U256::ZERO, U256::from(150));
+
+        let now = U256::from(RATE_LIMIT_DAY * 10);
+        assert!(contract.limits.consume(alice(), U256::from(100), now).is_ok());
+        assert_eq!(contract.limits.remaining_allowance(bob(), now), U256::from(50));
+        assert!(matches!(
+            contract.limits.consume(bob(), U256::from(51), now),
+            Err(RateLimitError::GlobalRateLimitExceeded(_))
+        ));
+
+        // The cap resets at the next day boundary
+        let tomorrow = U256::from(RATE_LIMIT_DAY * 11);
+        assert_eq!(contract.limits.global_allowance(tomorrow), U256::from(150));
+        assert!(contract.limits.consume(bob(), U256::from(150), tomorrow).is_ok());
+    }
+}
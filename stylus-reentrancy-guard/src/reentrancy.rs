@@ -0,0 +1,444 @@
+//! ReentrancyGuard module for Stylus smart contracts
+//! 
+//! This module provides reentrancy protection inspired by OpenZeppelin's ReentrancyGuard.sol.
+//! It prevents reentrant calls to protected functions in Stylus-based smart contracts.
+
+use alloy_primitives::U256;
+use core::ops::{Deref, DerefMut};
+use stylus_sdk::{alloy_sol_types::sol, prelude::*};
+#[cfg(feature = "bool-status")]
+use stylus_sdk::storage::StorageBool;
+use stylus_sdk::storage::StorageU256;
+
+sol! {
+    /// Thrown when a reentrant call is detected
+    error ReentrancyGuardReentrantCall();
+    /// Thrown when a guarded view is read while a non-reentrant function is executing
+    error ReentrancyGuardReadOnlyReentrantCall();
+}
+
+/// Error types for ReentrancyGuard
+///
+/// Each variant wraps its `sol!` error, so contracts can reuse the structs in
+/// their own error enums and map with `From`.
+#[derive(SolidityError)]
+pub enum ReentrancyError {
+    ReentrantCall(ReentrancyGuardReentrantCall),
+    ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall),
+}
+
+impl From<ReentrancyGuardReentrantCall> for ReentrancyError {
+    fn from(err: ReentrancyGuardReentrantCall) -> Self {
+        ReentrancyError::ReentrantCall(err)
+    }
+}
+
+impl From<ReentrancyGuardReadOnlyReentrantCall> for ReentrancyError {
+    fn from(err: ReentrancyGuardReadOnlyReentrantCall) -> Self {
+        ReentrancyError::ReadOnlyReentrantCall(err)
+    }
+}
+
+/// ReentrancyGuard constants following OpenZeppelin's pattern
+pub const NOT_ENTERED: U256 = U256::from_limbs([1, 0, 0, 0]);
+pub const ENTERED: U256 = U256::from_limbs([2, 0, 0, 0]);
+
+/// Storage structure for ReentrancyGuard
+/// 
+/// This struct holds the reentrancy status and should be included
+/// in contracts that need reentrancy protection.
+#[storage]
+pub struct ReentrancyGuard {
+    /// Current reentrancy status (NOT_ENTERED = 1, ENTERED = 2)
+    #[cfg(not(feature = "bool-status"))]
+    status: StorageU256,
+    /// Current reentrancy status (true while entered)
+    #[cfg(feature = "bool-status")]
+    status: StorageBool,
+}
+
+impl ReentrancyGuard {
+    /// Initialize the ReentrancyGuard
+    /// 
+    /// Sets the initial status to NOT_ENTERED.
+    /// This should be called in the contract's constructor.
+    pub fn init(&mut self) {
+        self.set_entered(false);
+    }
+
+    /// Check if the contract is currently in a reentrant call
+    /// 
+    /// Returns true if there is a nonReentrant function in the call stack.
+    pub fn reentrancy_guard_entered(&self) -> bool {
+        #[cfg(not(feature = "bool-status"))]
+        return self.status.get() == ENTERED;
+        #[cfg(feature = "bool-status")]
+        return self.status.get();
+    }
+
+    fn set_entered(&mut self, entered: bool) {
+        #[cfg(not(feature = "bool-status"))]
+        self.status.set(if entered { ENTERED } else { NOT_ENTERED });
+        #[cfg(feature = "bool-status")]
+        self.status.set(entered);
+    }
+
+    /// Check that no non-reentrant function is currently executing
+    ///
+    /// Call this at the start of view functions whose results other protocols
+    /// rely on (balances, share prices), so they cannot observe the inconsistent
+    /// state that exists mid-call, e.g. from a callback during a withdrawal.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReadOnlyReentrantCall` if the guard is entered.
+    pub fn non_reentrant_view(&self) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        Ok(())
+    }
+
+    /// Internal function called before executing a non-reentrant function
+    /// 
+    /// Checks if the contract is already entered and throws an error if so,
+    /// otherwise sets the status to ENTERED.
+    /// 
+    /// # Errors
+    /// 
+    /// Returns `ReentrancyError::ReentrantCall` if a reentrant call is detected.
+    pub fn non_reentrant_before(&mut self) -> Result<(), ReentrancyError> {
+        // On the first call to nonReentrant, status will be NOT_ENTERED
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+        }
+
+        // Any calls to nonReentrant after this point will fail
+        self.set_entered(true);
+        Ok(())
+    }
+
+    /// Internal function called after executing a non-reentrant function
+    /// 
+    /// Resets the status back to NOT_ENTERED.
+    pub fn non_reentrant_after(&mut self) {
+        // By storing the original value once again, a refund is triggered
+        self.set_entered(false);
+    }
+
+    /// Convenience method that wraps a closure with reentrancy protection
+    /// 
+    /// This method automatically handles the before/after logic for reentrancy protection.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `f` - The closure to execute with reentrancy protection
+    /// 
+    /// # Errors
+    /// 
+    /// Returns `ReentrancyError::ReentrantCall` if a reentrant call is detected,
+    /// or any error returned by the closure.
+    pub fn non_reentrant<F, T, E>(&mut self, f: F) -> Result<T, ReentrancyError>
+    where
+        F: FnOnce() -> Result<T, E>,
+        ReentrancyError: From<E>,
+    {
+        self.non_reentrant_before()?;
+        let result = f().map_err(ReentrancyError::from);
+        self.non_reentrant_after();
+        result
+    }
+}
+
+/// Trait for contracts that use ReentrancyGuard
+/// 
+/// This trait provides a convenient interface for contracts to use reentrancy protection.
+pub trait ReentrancyGuarded {
+    /// Get a mutable reference to the ReentrancyGuard
+    fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard;
+
+    /// Execute a function with reentrancy protection
+    /// 
+    /// # Arguments
+    /// 
+    /// * `f` - The closure to execute with reentrancy protection
+    /// 
+    /// # Errors
+    /// 
+    /// Returns `ReentrancyError::ReentrantCall` if a reentrant call is detected.
+    fn with_non_reentrant<F, T>(&mut self, f: F) -> Result<T, ReentrancyError>
+    where
+        F: FnOnce(&mut Self) -> T,
+    {
+        self.reentrancy_guard().non_reentrant_before()?;
+        let result = f(self);
+        self.reentrancy_guard().non_reentrant_after();
+        Ok(result)
+    }
+
+    /// Execute a fallible function with reentrancy protection
+    ///
+    /// Unlike `with_non_reentrant`, the closure's own error type is returned
+    /// directly, so callers don't have to unwrap a nested `Result`. The guard
+    /// is reset on both the success and the error path.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The closure to execute with reentrancy protection
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` converted into `E` if a
+    /// reentrant call is detected, or any error returned by the closure.
+    fn with_non_reentrant_result<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Self) -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        self.reentrancy_guard().non_reentrant_before()?;
+        let result = f(self);
+        self.reentrancy_guard().non_reentrant_after();
+        result
+    }
+
+    /// Acquire the guard for the rest of the current scope
+    ///
+    /// The returned lock dereferences to the contract and resets the guard
+    /// when dropped, so early returns and `?` can never leave it ENTERED.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if a reentrant call is detected.
+    fn lock(&mut self) -> Result<ReentrancyLock<'_, Self>, ReentrancyError>
+    where
+        Self: Sized,
+    {
+        self.reentrancy_guard().non_reentrant_before()?;
+        Ok(ReentrancyLock { contract: self })
+    }
+}
+
+/// RAII lock over a contract's ReentrancyGuard
+///
+/// Created by `ReentrancyGuarded::lock`. While alive it gives access to the
+/// contract through `Deref`/`DerefMut`; on drop it resets the guard to
+/// NOT_ENTERED.
+pub struct ReentrancyLock<'a, C: ReentrancyGuarded> {
+    contract: &'a mut C,
+}
+
+impl<C: ReentrancyGuarded> Deref for ReentrancyLock<'_, C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        self.contract
+    }
+}
+
+impl<C: ReentrancyGuarded> DerefMut for ReentrancyLock<'_, C> {
+    fn deref_mut(&mut self) -> &mut C {
+        self.contract
+    }
+}
+
+impl<C: ReentrancyGuarded> Drop for ReentrancyLock<'_, C> {
+    fn drop(&mut self) {
+        self.contract.reentrancy_guard().non_reentrant_after();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::storage::StorageU256;
+
+    #[storage]
+    struct TestContract {
+        guard: ReentrancyGuard,
+        counter: StorageU256,
+    }
+
+    impl ReentrancyGuarded for TestContract {
+        fn reentrancy_guard(&mut self) -> &mut ReentrancyGuard {
+            &mut self.guard
+        }
+    }
+
+    impl TestContract {
+        #[crate::non_reentrant]
+        fn guarded_increment(&mut self, fail: bool) -> Result<U256, ReentrancyError> {
+            if fail {
+                return Err(ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}));
+            }
+            self.counter.set(self.counter.get() + U256::from(1));
+            Ok(self.counter.get())
+        }
+    }
+
+    #[test]
+    fn test_error_encoding() {
+        use stylus_sdk::alloy_sol_types::SolError;
+
+        let data: Vec<u8> = ReentrancyError::from(ReentrancyGuardReentrantCall {}).into();
+        assert_eq!(data, ReentrancyGuardReentrantCall::SELECTOR.to_vec());
+
+        let data: Vec<u8> = ReentrancyError::from(ReentrancyGuardReadOnlyReentrantCall {}).into();
+        assert_eq!(data, ReentrancyGuardReadOnlyReentrantCall::SELECTOR.to_vec());
+    }
+
+    #[test]
+    fn test_initial_state() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_non_reentrant_protection() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        
+        // First call should succeed
+        let result = contract.guard.non_reentrant_before();
+        assert!(result.is_ok());
+        assert!(contract.guard.reentrancy_guard_entered());
+        
+        // Second call should fail
+        let result = contract.guard.non_reentrant_before();
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+        
+        // After cleanup, should work again
+        contract.guard.non_reentrant_after();
+        assert!(!contract.guard.reentrancy_guard_entered());
+        
+        let result = contract.guard.non_reentrant_before();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_non_reentrant_view() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        assert!(contract.guard.non_reentrant_view().is_ok());
+
+        contract.guard.non_reentrant_before().unwrap();
+        let result = contract.guard.non_reentrant_view();
+        assert!(matches!(result, Err(ReentrancyError::ReadOnlyReentrantCall(_))));
+
+        contract.guard.non_reentrant_after();
+        assert!(contract.guard.non_reentrant_view().is_ok());
+    }
+
+    #[test]
+    fn test_with_trait() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        
+        let result = contract.with_non_reentrant(|c| {
+            c.counter.set(U256::from(42));
+            c.counter.get()
+        });
+        
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), U256::from(42));
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_with_non_reentrant_result() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        let result: Result<U256, ReentrancyError> = contract.with_non_reentrant_result(|c| {
+            c.counter.set(U256::from(7));
+            Ok(c.counter.get())
+        });
+        assert_eq!(result.unwrap(), U256::from(7));
+        assert!(!contract.guard.reentrancy_guard_entered());
+
+        // The closure's error is returned as-is and the guard is still reset
+        let result: Result<U256, ReentrancyError> =
+            contract.with_non_reentrant_result(|_| Err(ReentrancyGuardReadOnlyReentrantCall {}.into()));
+        assert!(matches!(result, Err(ReentrancyError::ReadOnlyReentrantCall(_))));
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_with_non_reentrant_result_blocks_reentry() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+        contract.guard.non_reentrant_before().unwrap();
+
+        let result: Result<(), ReentrancyError> = contract.with_non_reentrant_result(|c| {
+            c.counter.set(U256::from(1));
+            Ok(())
+        });
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+        assert_eq!(contract.counter.get(), U256::ZERO);
+    }
+
+    fn locked_increment(contract: &mut TestContract, fail: bool) -> Result<U256, ReentrancyError> {
+        let mut locked = contract.lock()?;
+        if fail {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        locked.counter.set(locked.counter.get() + U256::from(1));
+        Ok(locked.counter.get())
+    }
+
+    #[test]
+    fn test_lock_released_on_drop() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        {
+            let locked = contract.lock().unwrap();
+            assert!(locked.guard.reentrancy_guard_entered());
+        }
+        assert!(!contract.guard.reentrancy_guard_entered());
+
+        assert_eq!(locked_increment(&mut contract, false).unwrap(), U256::from(1));
+        assert!(!contract.guard.reentrancy_guard_entered());
+
+        // Early returns release the lock too
+        assert!(locked_increment(&mut contract, true).is_err());
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_lock_blocks_reentry() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        let mut locked = contract.lock().unwrap();
+        let result = locked.guard.non_reentrant_before();
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+    }
+
+    #[test]
+    fn test_non_reentrant_attribute() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        assert_eq!(contract.guarded_increment(false).unwrap(), U256::from(1));
+        assert!(!contract.guard.reentrancy_guard_entered());
+
+        // Early returns still reset the guard
+        assert!(contract.guarded_increment(true).is_err());
+        assert!(!contract.guard.reentrancy_guard_entered());
+    }
+
+    #[test]
+    fn test_non_reentrant_attribute_blocks_reentry() {
+        let mut contract = TestContract::default();
+        contract.guard.init();
+
+        // Simulate being inside another protected call
+        contract.guard.non_reentrant_before().unwrap();
+
+        let result = contract.guarded_increment(false);
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+        assert_eq!(contract.counter.get(), U256::ZERO);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! Cross-contract reentrancy protection for Stylus smart contracts
+//!
+//! `ReentrancyGuardShared` keeps no lock of its own: it takes the single lock
+//! of a `GuardRegistry` contract (see the `stylus-guard-registry` crate) shared
+//! by every contract of a protocol. While one member is mid-flight, entering
+//! any other member reverts, so an attacker can't reenter contract B from a
+//! callback of contract A.
+//!
+//! Every member must be registered with the registry, and a member can't call
+//! another protected member in the same transaction; that is the point.
+
+use alloy_primitives::Address;
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{call::RawCall, prelude::*, storage::StorageAddress};
+
+use crate::reentrancy::{ReentrancyError, ReentrancyGuardReadOnlyReentrantCall, ReentrancyGuardReentrantCall};
+
+sol! {
+    /// Interface of the shared `GuardRegistry` contract
+    interface IGuardRegistry {
+        function enter() external;
+        function exit() external;
+        function isEntered() external view returns (bool);
+    }
+}
+
+/// Storage structure for ReentrancyGuardShared
+#[storage]
+pub struct ReentrancyGuardShared {
+    /// GuardRegistry holding the protocol-wide lock
+    registry: StorageAddress,
+}
+
+impl ReentrancyGuardShared {
+    /// Point the guard at the protocol's registry
+    ///
+    /// This should be called in the contract's constructor. The contract must
+    /// also be added as a member of the registry.
+    pub fn init(&mut self, registry: Address) {
+        self.registry.set(registry);
+    }
+
+    /// GuardRegistry this guard uses
+    pub fn registry(&self) -> Address {
+        self.registry.get()
+    }
+
+    /// Check if any member of the protocol is mid-flight
+    ///
+    /// Reports `true` if the registry can't be queried, so guarded views fail closed.
+    pub fn reentrancy_guard_entered(&self) -> bool {
+        let calldata = IGuardRegistry::isEnteredCall {}.abi_encode();
+        // Safety: static call, no state is modified
+        match unsafe { RawCall::new_static().call(self.registry(), &calldata) } {
+            Ok(data) => IGuardRegistry::isEnteredCall::abi_decode_returns(&data, true)
+                .map(|ret| ret._0)
+                .unwrap_or(true),
+            Err(_) => true,
+        }
+    }
+
+    /// Revert if any member of the protocol is mid-flight
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReadOnlyReentrantCall` if the shared lock is held.
+    pub fn non_reentrant_view(&self) -> Result<(), ReentrancyError> {
+        if self.reentrancy_guard_entered() {
+            return Err(ReentrancyError::ReadOnlyReentrantCall(ReentrancyGuardReadOnlyReentrantCall {}));
+        }
+        Ok(())
+    }
+
+    /// Take the shared lock
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if the registry refuses: the
+    /// lock is held by any member, or this contract isn't a member.
+    pub fn non_reentrant_before(&mut self) -> Result<(), ReentrancyError> {
+        let calldata = IGuardRegistry::enterCall {}.abi_encode();
+        // Safety: the registry doesn't call back into members
+        unsafe { RawCall::new().call(self.registry(), &calldata) }
+            .map_err(|_| ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}))?;
+        Ok(())
+    }
+
+    /// Release the shared lock
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` if the registry refuses, i.e.
+    /// this contract no longer holds the lock (it was force-released and
+    /// possibly taken by another member). The caller must revert, so the
+    /// protected call doesn't complete on a lock it didn't hold throughout.
+    pub fn non_reentrant_after(&mut self) -> Result<(), ReentrancyError> {
+        let calldata = IGuardRegistry::exitCall {}.abi_encode();
+        // Safety: the registry doesn't call back into members
+        unsafe { RawCall::new().call(self.registry(), &calldata) }
+            .map_err(|_| ReentrancyError::ReentrantCall(ReentrancyGuardReentrantCall {}))?;
+        Ok(())
+    }
+
+    /// Execute a closure while holding the shared lock
+    ///
+    /// # Errors
+    ///
+    /// Returns `ReentrancyError::ReentrantCall` (converted into `E`) if the
+    /// lock can't be taken or released, or the closure's own error. The lock
+    /// is released either way.
+    pub fn non_reentrant<F, T, E>(&mut self, f: F) -> Result<T, E>
+    where
+        F: FnOnce() -> Result<T, E>,
+        E: From<ReentrancyError>,
+    {
+        self.non_reentrant_before()?;
+        let result = f();
+        self.non_reentrant_after()?;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::U256;
+    use stylus_sdk::testing::*;
+
+    #[storage]
+    struct TestContract {
+        shared: ReentrancyGuardShared,
+    }
+
+    #[test]
+    fn test_failed_exit_reverts() {
+        let vm = TestVM::default();
+        let mut contract = TestContract::from(&vm);
+        let registry = Address::from([7u8; 20]);
+        contract.shared.init(registry);
+
+        vm.mock_call(registry, IGuardRegistry::enterCall {}.abi_encode(), U256::ZERO, Ok(vec![]));
+        vm.mock_call(registry, IGuardRegistry::exitCall {}.abi_encode(), U256::ZERO, Err(vec![]));
+
+        let result: Result<(), ReentrancyError> = contract.shared.non_reentrant(|| Ok(()));
+        assert!(matches!(result, Err(ReentrancyError::ReentrantCall(_))));
+    }
+
+    #[test]
+    fn test_registry_selectors() {
+        // Must match the `#[public]` functions of GuardRegistry
+        assert_eq!(IGuardRegistry::enterCall::SELECTOR, [0xe9, 0x7d, 0xcb, 0x62]);
+        assert_eq!(IGuardRegistry::exitCall::SELECTOR, [0xe9, 0xfa, 0xd8, 0xee]);
+        assert_eq!(IGuardRegistry::isEnteredCall::SELECTOR, [0xab, 0xeb, 0xc1, 0xda]);
+    }
+}
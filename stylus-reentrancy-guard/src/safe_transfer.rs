@@ -0,0 +1,214 @@
+//! Safe ETH and ERC20 transfers for Stylus smart contracts
+//!
+//! `call::transfer_eth(..).is_err()` collapses every failure into one bit.
+//! `safe_transfer_eth` forwards a chosen gas stipend and reports why the
+//! transfer failed. `safe_erc20_transfer`/`safe_erc20_transfer_from` follow
+//! OpenZeppelin's SafeERC20: a token call succeeds if it returns `true` or, for
+//! non-standard tokens like USDT, returns no data at all.
+
+use alloc::vec::Vec;
+use alloy_primitives::{Address, U256};
+use alloy_sol_types::{sol, SolCall};
+use stylus_sdk::{call::RawCall, prelude::*, types::AddressVM};
+
+/// Gas Solidity's `transfer`/`send` forward: enough to log, not to re-enter
+pub const TRANSFER_GAS_STIPEND: u64 = 2300;
+
+sol! {
+    interface IERC20Transfer {
+        function transfer(address to, uint256 amount) external returns (bool);
+        function transferFrom(address from, address to, uint256 amount) external returns (bool);
+    }
+
+    /// Thrown when the contract holds less ETH than the transfer amount
+    error SafeTransferInsufficientBalance();
+    /// Thrown when the recipient reverted without data: no payable
+    /// `receive`/fallback, or it ran out of the forwarded gas
+    error SafeTransferEthRejected();
+    /// Thrown when the recipient reverted with its own error
+    error SafeTransferEthReverted();
+    /// Thrown when an ERC20 call reverted, returned `false`, or hit an address without code
+    error SafeERC20FailedOperation();
+}
+
+/// Error types for SafeTransfer
+#[derive(SolidityError)]
+pub enum SafeTransferError {
+    InsufficientBalance(SafeTransferInsufficientBalance),
+    EthRejected(SafeTransferEthRejected),
+    EthReverted(SafeTransferEthReverted),
+    Erc20FailedOperation(SafeERC20FailedOperation),
+}
+
+impl From<SafeTransferInsufficientBalance> for SafeTransferError {
+    fn from(err: SafeTransferInsufficientBalance) -> Self {
+        SafeTransferError::InsufficientBalance(err)
+    }
+}
+
+impl From<SafeTransferEthRejected> for SafeTransferError {
+    fn from(err: SafeTransferEthRejected) -> Self {
+        SafeTransferError::EthRejected(err)
+    }
+}
+
+impl From<SafeTransferEthReverted> for SafeTransferError {
+    fn from(err: SafeTransferEthReverted) -> Self {
+        SafeTransferError::EthReverted(err)
+    }
+}
+
+impl From<SafeERC20FailedOperation> for SafeTransferError {
+    fn from(err: SafeERC20FailedOperation) -> Self {
+        SafeTransferError::Erc20FailedOperation(err)
+    }
+}
+
+/// Gas forwarded with an ETH transfer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GasStipend {
+    /// `TRANSFER_GAS_STIPEND`, like Solidity's `transfer`
+    Transfer,
+    /// A fixed amount of gas
+    Limited(u64),
+    /// All remaining gas, for recipients that do real work on receipt
+    All,
+}
+
+/// Send `amount` wei from `host`, the calling contract, to `to`, forwarding `stipend` gas
+///
+/// The storage cache is flushed first, so the recipient sees every write
+/// made before the transfer if it calls back in.
+///
+/// # Errors
+///
+/// Returns `SafeTransferError::InsufficientBalance` if the contract can't
+/// cover `amount`, `SafeTransferError::EthRejected` if the recipient reverted
+/// without data, or `SafeTransferError::EthReverted` if it reverted with data.
+pub fn safe_transfer_eth(
+    host: &impl HostAccess,
+    to: Address,
+    amount: U256,
+    stipend: GasStipend,
+) -> Result<(), SafeTransferError> {
+    let vm = host.vm();
+    if vm.balance(vm.contract_address()) < amount {
+        return Err(SafeTransferError::InsufficientBalance(SafeTransferInsufficientBalance {}));
+    }
+
+    let call = RawCall::new_with_value(amount).flush_storage_cache();
+    let call = match stipend {
+        GasStipend::Transfer => call.gas(TRANSFER_GAS_STIPEND),
+        GasStipend::Limited(gas) => call.gas(gas),
+        GasStipend::All => call,
+    };
+
+    match unsafe { call.call(to, &[]) } {
+        Ok(_) => Ok(()),
+        Err(data) => Err(classify_eth_failure(&data)),
+    }
+}
+
+/// Call `token.transfer(to, amount)`, tolerating tokens that return no data
+///
+/// # Errors
+///
+/// Returns `SafeTransferError::Erc20FailedOperation` if the call fails.
+pub fn safe_erc20_transfer(token: Address, to: Address, amount: U256) -> Result<(), SafeTransferError> {
+    let calldata = IERC20Transfer::transferCall { to, amount }.abi_encode();
+    call_optional_return(token, &calldata)
+}
+
+/// Call `token.transferFrom(from, to, amount)`, tolerating tokens that return no data
+///
+/// # Errors
+///
+/// Returns `SafeTransferError::Erc20FailedOperation` if the call fails.
+pub fn safe_erc20_transfer_from(
+    token: Address,
+    from: Address,
+    to: Address,
+    amount: U256,
+) -> Result<(), SafeTransferError> {
+    let calldata = IERC20Transfer::transferFromCall { from, to, amount }.abi_encode();
+    call_optional_return(token, &calldata)
+}
+
+/// Call `token` with `calldata` after flushing the storage cache, checking the optional `bool` it returns
+fn call_optional_return(token: Address, calldata: &[u8]) -> Result<(), SafeTransferError> {
+    let data = unsafe { RawCall::new().flush_storage_cache().call(token, calldata) }
+        .map_err(|_| SafeTransferError::Erc20FailedOperation(SafeERC20FailedOperation {}))?;
+
+    if erc20_call_succeeded(&data, || token.has_code()) {
+        Ok(())
+    } else {
+        Err(SafeTransferError::Erc20FailedOperation(SafeERC20FailedOperation {}))
+    }
+}
+
+/// Classify the revert data of a failed ETH transfer
+fn classify_eth_failure(data: &[u8]) -> SafeTransferError {
+    if data.is_empty() {
+        SafeTransferError::EthRejected(SafeTransferEthRejected {})
+    } else {
+        SafeTransferError::EthReverted(SafeTransferEthReverted {})
+    }
+}
+
+/// Whether an ERC20 call that didn't revert succeeded
+///
+/// Empty return data only counts if `token` has code, since calls to an
+/// address without code "succeed" without doing anything.
+fn erc20_call_succeeded(data: &[u8], has_code: impl FnOnce() -> bool) -> bool {
+    if data.is_empty() {
+        return has_code();
+    }
+    data.len() == 32 && U256::from_be_slice(data) == U256::from(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded_bool(value: u8) -> Vec<u8> {
+        let mut data = vec![0u8; 32];
+        data[31] = value;
+        data
+    }
+
+    #[test]
+    fn test_erc20_return_values() {
+        assert!(erc20_call_succeeded(&encoded_bool(1), || false));
+        assert!(!erc20_call_succeeded(&encoded_bool(0), || true));
+        assert!(!erc20_call_succeeded(&encoded_bool(2), || true));
+        assert!(!erc20_call_succeeded(&[1u8], || true));
+    }
+
+    #[test]
+    fn test_erc20_no_return_data() {
+        // Non-standard tokens return nothing
+        assert!(erc20_call_succeeded(&[], || true));
+        // But an address without code is not a token
+        assert!(!erc20_call_succeeded(&[], || false));
+    }
+
+    #[test]
+    fn test_classify_eth_failure() {
+        assert!(matches!(classify_eth_failure(&[]), SafeTransferError::EthRejected(_)));
+        assert!(matches!(
+            classify_eth_failure(&[0x08, 0xc3, 0x79, 0xa0]),
+            SafeTransferError::EthReverted(_)
+        ));
+    }
+
+    #[test]
+    fn test_transfer_calldata() {
+        let to = Address::from([1u8; 20]);
+        let calldata = IERC20Transfer::transferCall { to, amount: U256::from(5) }.abi_encode();
+        assert_eq!(&calldata[..4], &[0xa9, 0x05, 0x9c, 0xbb]);
+        assert_eq!(calldata.len(), 4 + 64);
+
+        let calldata = IERC20Transfer::transferFromCall { from: to, to, amount: U256::from(5) }.abi_encode();
+        assert_eq!(&calldata[..4], &[0x23, 0xb8, 0x72, 0xdd]);
+    }
+}
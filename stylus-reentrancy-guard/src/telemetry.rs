@@ -0,0 +1,86 @@
+//! Reentrancy-attempt telemetry for Stylus smart contracts
+//!
+//! A call the guard rejects reverts, taking anything it records with it, so
+//! attempts can't be counted where the guard trips. The outer call counts
+//! them instead: when external code it handed control to fails with
+//! `ReentrancyGuardReentrantCall`, that code tried to re-enter. Embed
+//! `ReentrancyTelemetry` next to the guard, check failed calls with
+//! `is_reentrant_call_revert`, and `record` the attempt from a path that
+//! doesn't revert afterwards.
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{alloy_sol_types::SolError, prelude::*, storage::StorageU256};
+
+use crate::policy::Selector;
+use crate::reentrancy::ReentrancyGuardReentrantCall;
+
+#[cfg(feature = "telemetry")]
+stylus_sdk::alloy_sol_types::sol! {
+    /// Emitted when `caller` tried to re-enter while `selector` held the guard
+    event ReentrancyAttemptBlocked(bytes4 indexed selector, address indexed caller);
+}
+
+/// Check whether a failed call reverted with `ReentrancyGuardReentrantCall`
+pub fn is_reentrant_call_revert(revert_data: &[u8]) -> bool {
+    revert_data == ReentrancyGuardReentrantCall::SELECTOR
+}
+
+/// Storage structure for ReentrancyTelemetry
+#[storage]
+pub struct ReentrancyTelemetry {
+    /// Number of re-entry attempts recorded
+    blocked_attempts: StorageU256,
+}
+
+impl ReentrancyTelemetry {
+    /// Number of re-entry attempts recorded
+    pub fn blocked_attempts(&self) -> U256 {
+        self.blocked_attempts.get()
+    }
+
+    /// Count a re-entry attempt by `caller` while `selector` held the guard
+    ///
+    /// With the `telemetry` feature this also emits `ReentrancyAttemptBlocked`.
+    /// Only persists if the calling frame doesn't revert.
+    pub fn record(&mut self, selector: Selector, caller: Address) {
+        self.blocked_attempts
+            .set(self.blocked_attempts.get() + U256::from(1));
+
+        #[cfg(feature = "telemetry")]
+        log(self.vm(), ReentrancyAttemptBlocked {
+            selector: selector.into(),
+            caller,
+        });
+        #[cfg(not(feature = "telemetry"))]
+        let _ = (selector, caller);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        telemetry: ReentrancyTelemetry,
+    }
+
+    #[test]
+    fn test_detects_reentrant_call_revert() {
+        assert!(is_reentrant_call_revert(&[0x3e, 0xe5, 0xae, 0xb5]));
+        assert!(!is_reentrant_call_revert(&[]));
+        assert!(!is_reentrant_call_revert(&[0x3e, 0xe5, 0xae, 0xb5, 0x00]));
+        assert!(!is_reentrant_call_revert(&crate::ReentrancyGuardReadOnlyReentrantCall::SELECTOR));
+    }
+
+    #[test]
+    fn test_record_counts_attempts() {
+        let mut contract = TestContract::default();
+        assert_eq!(contract.telemetry.blocked_attempts(), U256::ZERO);
+
+        let caller = Address::from([1u8; 20]);
+        contract.telemetry.record([0x31, 0x96, 0x57, 0xe5], caller);
+        contract.telemetry.record(crate::ON_ERC721_RECEIVED, caller);
+        assert_eq!(contract.telemetry.blocked_attempts(), U256::from(2));
+    }
+}
@@ -0,0 +1,232 @@
+//! Per-account withdrawal caps for Stylus smart contracts
+//!
+//! Limits how much each account can withdraw per fixed period. Every account
+//! gets the default cap unless an admin overrides it, e.g. to lift the cap for
+//! a market maker or to freeze a compromised account. Embed `WithdrawalCaps`
+//! next to the guard and call `consume` on every outflow.
+
+use alloy_primitives::{Address, U256};
+use stylus_sdk::{
+    alloy_sol_types::sol,
+    prelude::*,
+    storage::{StorageBool, StorageMap, StorageU256},
+};
+
+sol! {
+    /// Thrown when an outflow exceeds what is left of the account's cap for the period
+    error WithdrawalCapExceeded();
+}
+
+/// Error types for WithdrawalCaps
+#[derive(SolidityError)]
+pub enum WithdrawalCapError {
+    CapExceeded(WithdrawalCapExceeded),
+}
+
+impl From<WithdrawalCapExceeded> for WithdrawalCapError {
+    fn from(err: WithdrawalCapExceeded) -> Self {
+        WithdrawalCapError::CapExceeded(err)
+    }
+}
+
+/// Storage structure for a single account's cap and usage
+#[storage]
+pub struct AccountCap {
+    /// Admin override of the default cap
+    cap: StorageU256,
+    /// Whether `cap` applies instead of the default
+    overridden: StorageBool,
+    /// Period index `spent` refers to
+    period_index: StorageU256,
+    /// Withdrawn so far in `period_index`
+    spent: StorageU256,
+}
+
+/// Storage structure for WithdrawalCaps
+#[storage]
+pub struct WithdrawalCaps {
+    /// Cap for accounts without an override (zero disables it)
+    default_cap: StorageU256,
+    /// Length of a cap period in seconds (zero makes the cap a lifetime total)
+    period: StorageU256,
+    /// Per-account overrides and usage
+    accounts: StorageMap<Address, AccountCap>,
+}
+
+impl WithdrawalCaps {
+    /// Set the default cap; zero disables it
+    pub fn set_default_cap(&mut self, cap: U256) {
+        self.default_cap.set(cap);
+    }
+
+    /// Cap for accounts without an override
+    pub fn default_cap(&self) -> U256 {
+        self.default_cap.get()
+    }
+
+    /// Set the period length in seconds
+    pub fn set_period(&mut self, period: U256) {
+        self.period.set(period);
+    }
+
+    /// Length of a cap period in seconds
+    pub fn period(&self) -> U256 {
+        self.period.get()
+    }
+
+    /// Override the default cap for `user`
+    ///
+    /// Zero blocks the account; `U256::MAX` exempts it.
+    pub fn set_user_cap(&mut self, user: Address, cap: U256) {
+        let mut account = self.accounts.setter(user);
+        account.cap.set(cap);
+        account.overridden.set(true);
+    }
+
+    /// Remove the override for `user`, falling back to the default cap
+    pub fn clear_user_cap(&mut self, user: Address) {
+        let mut account = self.accounts.setter(user);
+        account.cap.set(U256::ZERO);
+        account.overridden.set(false);
+    }
+
+    /// Check if `user` has an override
+    pub fn has_user_cap(&self, user: Address) -> bool {
+        self.accounts.getter(user).overridden.get()
+    }
+
+    /// Cap that applies to `user` (`U256::MAX` if uncapped)
+    pub fn cap_of(&self, user: Address) -> U256 {
+        let account = self.accounts.getter(user);
+        if account.overridden.get() {
+            return account.cap.get();
+        }
+        let cap = self.default_cap.get();
+        if cap.is_zero() {
+            U256::MAX
+        } else {
+            cap
+        }
+    }
+
+    /// What `user` has withdrawn in the period containing `now`
+    pub fn withdrawn(&self, user: Address, now: U256) -> U256 {
+        let account = self.accounts.getter(user);
+        if account.period_index.get() != self.period_index(now) {
+            return U256::ZERO;
+        }
+        account.spent.get()
+    }
+
+    /// What `user` may still withdraw in the period containing `now`
+    pub fn remaining(&self, user: Address, now: U256) -> U256 {
+        let cap = self.cap_of(user);
+        if cap == U256::MAX {
+            return cap;
+        }
+        cap.saturating_sub(self.withdrawn(user, now))
+    }
+
+    /// Record an outflow of `amount` by `user` at `now`
+    ///
+    /// # Errors
+    ///
+    /// Returns `WithdrawalCapError::CapExceeded` if `amount` is over what is
+    /// left of the cap; nothing is recorded in that case.
+    pub fn consume(&mut self, user: Address, amount: U256, now: U256) -> Result<(), WithdrawalCapError> {
+        if self.cap_of(user) == U256::MAX {
+            return Ok(());
+        }
+        if amount > self.remaining(user, now) {
+            return Err(WithdrawalCapError::CapExceeded(WithdrawalCapExceeded {}));
+        }
+
+        let spent = self.withdrawn(user, now) + amount;
+        let period_index = self.period_index(now);
+        let mut account = self.accounts.setter(user);
+        account.period_index.set(period_index);
+        account.spent.set(spent);
+        Ok(())
+    }
+
+    fn period_index(&self, now: U256) -> U256 {
+        let period = self.period.get();
+        if period.is_zero() {
+            return U256::ZERO;
+        }
+        now / period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[storage]
+    struct TestContract {
+        caps: WithdrawalCaps,
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    #[test]
+    fn test_uncapped_by_default() {
+        let mut contract = TestContract::default();
+        assert_eq!(contract.caps.remaining(alice(), U256::from(1)), U256::MAX);
+        assert!(contract.caps.consume(alice(), U256::from(1_000_000), U256::from(1)).is_ok());
+    }
+
+    #[test]
+    fn test_default_cap_per_period() {
+        let mut contract = TestContract::default();
+        contract.caps.set_default_cap(U256::from(100));
+        contract.caps.set_period(U256::from(DAY));
+
+        let now = U256::from(DAY * 3 + 10);
+        assert!(contract.caps.consume(alice(), U256::from(70), now).is_ok());
+        assert_eq!(contract.caps.remaining(alice(), now), U256::from(30));
+        assert!(matches!(
+            contract.caps.consume(alice(), U256::from(31), now),
+            Err(WithdrawalCapError::CapExceeded(_))
+        ));
+        assert_eq!(contract.caps.withdrawn(alice(), now), U256::from(70));
+
+        // Each account has its own cap
+        assert_eq!(contract.caps.remaining(bob(), now), U256::from(100));
+
+        // The cap resets with the next period
+        let tomorrow = U256::from(DAY * 4);
+        assert_eq!(contract.caps.remaining(alice(), tomorrow), U256::from(100));
+        assert!(contract.caps.consume(alice(), U256::from(100), tomorrow).is_ok());
+    }
+
+    #[test]
+    fn test_user_override() {
+        let mut contract = TestContract::default();
+        contract.caps.set_default_cap(U256::from(100));
+        contract.caps.set_period(U256::from(DAY));
+        let now = U256::from(DAY);
+
+        contract.caps.set_user_cap(alice(), U256::from(500));
+        contract.caps.set_user_cap(bob(), U256::ZERO);
+        assert!(contract.caps.has_user_cap(alice()));
+        assert!(contract.caps.consume(alice(), U256::from(400), now).is_ok());
+        assert!(contract.caps.consume(bob(), U256::from(1), now).is_err());
+
+        // Clearing falls back to the default, counting what was already withdrawn
+        contract.caps.clear_user_cap(alice());
+        assert_eq!(contract.caps.cap_of(alice()), U256::from(100));
+        assert_eq!(contract.caps.remaining(alice(), now), U256::ZERO);
+
+        contract.caps.set_user_cap(bob(), U256::MAX);
+        assert_eq!(contract.caps.remaining(bob(), now), U256::MAX);
+    }
+}
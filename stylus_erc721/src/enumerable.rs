@@ -0,0 +1,129 @@
+// Imported packages
+use alloy_primitives::{fixed_bytes, Address, FixedBytes, U256};
+use stylus_sdk::{alloy_sol_types::sol, prelude::*};
+
+/// ERC-721 Enumerable extension interface id
+pub const ERC721_ENUMERABLE_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("780e9d63");
+
+sol_storage! {
+    /// Erc721Enumerable indexes every token, and every token of each owner.
+    ///
+    /// It doesn't see transfers by itself: the contract calls `update` after
+    /// each mint, transfer and burn of its `Erc721`.
+    pub struct Erc721Enumerable {
+        /// Every existing token; burning swaps the last token into the gap
+        uint256[] all_tokens;
+        /// Index of each token in `all_tokens`
+        mapping(uint256 => uint256) all_tokens_index;
+        /// Tokens of each owner; transferring out swaps the last token into the gap
+        mapping(address => uint256[]) owned_tokens;
+        /// Index of each token in its owner's `owned_tokens`
+        mapping(uint256 => uint256) owned_tokens_index;
+    }
+}
+
+// Declare Solidity error types (ERC-6093)
+sol! {
+    #[derive(Debug)]
+    error ERC721OutOfBoundsIndex(address owner, uint256 index);
+}
+
+/// Represents the ways methods may fail.
+#[derive(SolidityError, Debug)]
+pub enum Erc721EnumerableError {
+    OutOfBoundsIndex(ERC721OutOfBoundsIndex),
+}
+
+// These methods aren't exposed to other contracts
+impl Erc721Enumerable {
+    /// Records that `token_id` moved from `from` to `to`, with `address(0)`
+    /// as `from` for a mint and as `to` for a burn
+    pub fn update(&mut self, from: Address, to: Address, token_id: U256) {
+        if from == to {
+            return;
+        }
+        if from.is_zero() {
+            self.add_to_all(token_id);
+        } else {
+            self.remove_from_owner(from, token_id);
+        }
+        if to.is_zero() {
+            self.remove_from_all(token_id);
+        } else {
+            self.add_to_owner(to, token_id);
+        }
+    }
+
+    fn add_to_all(&mut self, token_id: U256) {
+        let index = U256::from(self.all_tokens.len());
+        self.all_tokens.push(token_id);
+        self.all_tokens_index.setter(token_id).set(index);
+    }
+
+    fn remove_from_all(&mut self, token_id: U256) {
+        // Swap-remove: move the last token into the freed slot
+        let index = self.all_tokens_index.get(token_id);
+        let last_index = self.all_tokens.len() - 1;
+        if index != U256::from(last_index) {
+            let last = self.all_tokens.get(last_index).unwrap_or_default();
+            if let Some(mut slot) = self.all_tokens.setter(index) {
+                slot.set(last);
+            }
+            self.all_tokens_index.setter(last).set(index);
+        }
+        self.all_tokens.pop();
+        self.all_tokens_index.setter(token_id).set(U256::ZERO);
+    }
+
+    fn add_to_owner(&mut self, owner: Address, token_id: U256) {
+        let mut tokens = self.owned_tokens.setter(owner);
+        let index = U256::from(tokens.len());
+        tokens.push(token_id);
+        self.owned_tokens_index.setter(token_id).set(index);
+    }
+
+    fn remove_from_owner(&mut self, owner: Address, token_id: U256) {
+        // Swap-remove: move the owner's last token into the freed slot
+        let index = self.owned_tokens_index.get(token_id);
+        let mut tokens = self.owned_tokens.setter(owner);
+        let last_index = tokens.len() - 1;
+        if index != U256::from(last_index) {
+            let last = tokens.get(last_index).unwrap_or_default();
+            if let Some(mut slot) = tokens.setter(index) {
+                slot.set(last);
+            }
+            self.owned_tokens_index.setter(last).set(index);
+        }
+        self.owned_tokens.setter(owner).pop();
+        self.owned_tokens_index.setter(token_id).set(U256::ZERO);
+    }
+}
+
+// These methods are external to other contracts
+#[public]
+impl Erc721Enumerable {
+    /// Number of existing tokens
+    pub fn total_supply(&self) -> U256 {
+        U256::from(self.all_tokens.len())
+    }
+
+    /// Token at `index` of all existing tokens
+    ///
+    /// Ordering changes whenever a token is burned.
+    pub fn token_by_index(&self, index: U256) -> Result<U256, Erc721EnumerableError> {
+        self.all_tokens.get(index).ok_or(Erc721EnumerableError::OutOfBoundsIndex(ERC721OutOfBoundsIndex {
+            owner: Address::ZERO,
+            index,
+        }))
+    }
+
+    /// Token at `index` of the tokens owned by `owner`
+    ///
+    /// Ordering changes whenever `owner` transfers a token out.
+    pub fn token_of_owner_by_index(&self, owner: Address, index: U256) -> Result<U256, Erc721EnumerableError> {
+        self.owned_tokens
+            .getter(owner)
+            .get(index)
+            .ok_or(Erc721EnumerableError::OutOfBoundsIndex(ERC721OutOfBoundsIndex { owner, index }))
+    }
+}
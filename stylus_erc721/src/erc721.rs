@@ -0,0 +1,365 @@
+// Imported packages
+use alloc::{format, string::String, vec::Vec};
+use alloy_primitives::{fixed_bytes, Address, FixedBytes, U256};
+use core::{borrow::BorrowMut, marker::PhantomData};
+use stylus_sdk::{
+    abi::Bytes,
+    alloy_sol_types::sol,
+    call::Call,
+    prelude::*,
+    types::AddressVM,
+};
+
+/// ERC-165 interface id
+pub const ERC165_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("01ffc9a7");
+
+/// ERC-721 interface id
+pub const ERC721_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("80ac58cd");
+
+/// ERC-721 Metadata extension interface id
+pub const ERC721_METADATA_INTERFACE_ID: FixedBytes<4> = fixed_bytes!("5b5e139f");
+
+/// `onERC721Received.selector`, returned by receivers to accept a safe transfer
+pub const ERC721_RECEIVED: FixedBytes<4> = fixed_bytes!("150b7a02");
+
+pub trait Erc721Params {
+    /// Immutable collection name
+    const NAME: &'static str;
+
+    /// Immutable collection symbol
+    const SYMBOL: &'static str;
+
+    /// Prefix of every token URI, followed by the decimal token id.
+    /// Leave empty for tokens without a URI.
+    const BASE_URI: &'static str;
+}
+
+sol_storage! {
+    /// Erc721 implements all ERC-721 and ERC-721 Metadata methods.
+    pub struct Erc721<T> {
+        /// Maps tokens to their owner (`address(0)` if not minted)
+        mapping(uint256 => address) owners;
+        /// Maps users to the number of tokens they own
+        mapping(address => uint256) balances;
+        /// Maps tokens to the one account approved to move them
+        mapping(uint256 => address) token_approvals;
+        /// Maps owners to a mapping of operators allowed to move all their tokens
+        mapping(address => mapping(address => bool)) operator_approvals;
+        /// Used to allow [`Erc721Params`]
+        PhantomData<T> phantom;
+    }
+}
+
+// Receivers of safe transfers
+sol_interface! {
+    interface IERC721Receiver {
+        function onERC721Received(address operator, address from, uint256 token_id, bytes data) external returns (bytes4);
+    }
+}
+
+// Declare events and Solidity error types (ERC-6093)
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 indexed token_id);
+    event Approval(address indexed owner, address indexed approved, uint256 indexed token_id);
+    event ApprovalForAll(address indexed owner, address indexed operator, bool approved);
+
+    #[derive(Debug)]
+    error ERC721InvalidOwner(address owner);
+    #[derive(Debug)]
+    error ERC721NonexistentToken(uint256 token_id);
+    #[derive(Debug)]
+    error ERC721IncorrectOwner(address sender, uint256 token_id, address owner);
+    #[derive(Debug)]
+    error ERC721InvalidSender(address sender);
+    #[derive(Debug)]
+    error ERC721InvalidReceiver(address receiver);
+    #[derive(Debug)]
+    error ERC721InsufficientApproval(address operator, uint256 token_id);
+    #[derive(Debug)]
+    error ERC721InvalidApprover(address approver);
+    #[derive(Debug)]
+    error ERC721InvalidOperator(address operator);
+}
+
+/// Represents the ways methods may fail.
+#[derive(SolidityError, Debug)]
+pub enum Erc721Error {
+    InvalidOwner(ERC721InvalidOwner),
+    NonexistentToken(ERC721NonexistentToken),
+    IncorrectOwner(ERC721IncorrectOwner),
+    InvalidSender(ERC721InvalidSender),
+    InvalidReceiver(ERC721InvalidReceiver),
+    InsufficientApproval(ERC721InsufficientApproval),
+    InvalidApprover(ERC721InvalidApprover),
+    InvalidOperator(ERC721InvalidOperator),
+}
+
+// These methods aren't exposed to other contracts
+// Methods marked as "pub" here are usable outside of the erc721 module (i.e. they're callable from lib.rs)
+impl<T: Erc721Params> Erc721<T> {
+    /// Owner of `token_id`, failing if it hasn't been minted
+    pub fn require_owned(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        let owner = self.owners.get(token_id);
+        if owner.is_zero() {
+            return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken { token_id }));
+        }
+        Ok(owner)
+    }
+
+    /// Whether `spender` may move `owner`'s `token_id`: the owner itself, an
+    /// operator for all of the owner's tokens, or the token's approved account
+    pub fn is_authorized(&self, owner: Address, spender: Address, token_id: U256) -> bool {
+        !spender.is_zero()
+            && (owner == spender
+                || self.operator_approvals.getter(owner).get(spender)
+                || self.token_approvals.get(token_id) == spender)
+    }
+
+    /// Moves `token_id` to `to`, minting when it has no owner and burning
+    /// when `to` is `address(0)`, and returns the previous owner
+    ///
+    /// Unless `auth` is `address(0)`, it must be authorized to move the token.
+    /// Clears the token's approval. Every mint, transfer and burn goes
+    /// through here, so extensions can hook a single place.
+    pub fn _update(&mut self, to: Address, token_id: U256, auth: Address) -> Result<Address, Erc721Error> {
+        let from = self.owners.get(token_id);
+
+        if !auth.is_zero() {
+            if from.is_zero() {
+                return Err(Erc721Error::NonexistentToken(ERC721NonexistentToken { token_id }));
+            }
+            if !self.is_authorized(from, auth, token_id) {
+                return Err(Erc721Error::InsufficientApproval(ERC721InsufficientApproval {
+                    operator: auth,
+                    token_id,
+                }));
+            }
+        }
+
+        if !from.is_zero() {
+            self.token_approvals.setter(token_id).set(Address::ZERO);
+            let mut balance = self.balances.setter(from);
+            let old_balance = balance.get();
+            balance.set(old_balance - U256::from(1));
+        }
+        if !to.is_zero() {
+            let mut balance = self.balances.setter(to);
+            let old_balance = balance.get();
+            balance.set(old_balance + U256::from(1));
+        }
+        self.owners.setter(token_id).set(to);
+
+        // Emitting the transfer event
+        log(self.vm(), Transfer { from, to, token_id });
+        Ok(from)
+    }
+
+    /// Mints `token_id` to `to`
+    pub fn mint(&mut self, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver { receiver: to }));
+        }
+        if !self.owners.get(token_id).is_zero() {
+            return Err(Erc721Error::InvalidSender(ERC721InvalidSender { sender: Address::ZERO }));
+        }
+        self._update(to, token_id, Address::ZERO)?;
+        Ok(())
+    }
+
+    /// Burns `token_id`, returning its last owner
+    ///
+    /// Doesn't check the caller; wrap it with `is_authorized` (or call
+    /// `_update(Address::ZERO, token_id, caller)`) for a public burn.
+    pub fn burn(&mut self, token_id: U256) -> Result<Address, Erc721Error> {
+        self.require_owned(token_id)?;
+        self._update(Address::ZERO, token_id, Address::ZERO)
+    }
+
+    /// Moves `token_id` from `from` to `to` without checking the caller
+    pub fn _transfer(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver { receiver: to }));
+        }
+        self.check_owner(from, token_id)?;
+        self._update(to, token_id, Address::ZERO)?;
+        Ok(())
+    }
+
+    /// Calls `onERC721Received` on `to` if it is a contract, failing unless
+    /// it returns the expected selector
+    ///
+    /// Call it after the token has moved, as OpenZeppelin does, so the
+    /// receiver sees the new owner.
+    pub fn check_on_erc721_received<S: TopLevelStorage + BorrowMut<Self>>(
+        storage: &mut S,
+        operator: Address,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Vec<u8>,
+    ) -> Result<(), Erc721Error> {
+        if !to.has_code() {
+            return Ok(());
+        }
+        let receiver = IERC721Receiver::new(to);
+        match receiver.on_erc_721_received(Call::new_in(storage), operator, from, token_id, data.into()) {
+            Ok(selector) if selector == ERC721_RECEIVED => Ok(()),
+            _ => Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver { receiver: to })),
+        }
+    }
+
+    /// Mints `token_id` to `to`, checking that contract receivers accept it
+    pub fn safe_mint<S: TopLevelStorage + BorrowMut<Self>>(
+        storage: &mut S,
+        to: Address,
+        token_id: U256,
+        data: Vec<u8>,
+    ) -> Result<(), Erc721Error> {
+        storage.borrow_mut().mint(to, token_id)?;
+        let operator = storage.borrow_mut().vm().msg_sender();
+        Self::check_on_erc721_received(storage, operator, Address::ZERO, to, token_id, data)
+    }
+
+    /// Fails unless `token_id` exists and belongs to `from`
+    fn check_owner(&self, from: Address, token_id: U256) -> Result<(), Erc721Error> {
+        let owner = self.require_owned(token_id)?;
+        if owner != from {
+            return Err(Erc721Error::IncorrectOwner(ERC721IncorrectOwner {
+                sender: from,
+                token_id,
+                owner,
+            }));
+        }
+        Ok(())
+    }
+}
+
+// These methods are external to other contracts
+#[public]
+impl<T: Erc721Params> Erc721<T> {
+    /// Immutable collection name
+    pub fn name() -> String {
+        T::NAME.into()
+    }
+
+    /// Immutable collection symbol
+    pub fn symbol() -> String {
+        T::SYMBOL.into()
+    }
+
+    /// URI of `token_id`'s metadata: `BASE_URI` followed by the token id
+    #[selector(name = "tokenURI")]
+    pub fn token_uri(&self, token_id: U256) -> Result<String, Erc721Error> {
+        self.require_owned(token_id)?;
+        if T::BASE_URI.is_empty() {
+            return Ok(String::new());
+        }
+        Ok(format!("{}{}", T::BASE_URI, token_id))
+    }
+
+    /// Number of tokens owned by `owner`
+    pub fn balance_of(&self, owner: Address) -> Result<U256, Erc721Error> {
+        if owner.is_zero() {
+            return Err(Erc721Error::InvalidOwner(ERC721InvalidOwner { owner }));
+        }
+        Ok(self.balances.get(owner))
+    }
+
+    /// Owner of `token_id`
+    pub fn owner_of(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        self.require_owned(token_id)
+    }
+
+    /// Transfers `token_id` from `from` to `to`, without checking that `to` can receive it
+    /// (msg::sender() must be the owner, an approved operator, or approved for the token)
+    pub fn transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        if to.is_zero() {
+            return Err(Erc721Error::InvalidReceiver(ERC721InvalidReceiver { receiver: to }));
+        }
+        let msg_sender = self.vm().msg_sender();
+        let owner = self.require_owned(token_id)?;
+        if !self.is_authorized(owner, msg_sender, token_id) {
+            return Err(Erc721Error::InsufficientApproval(ERC721InsufficientApproval {
+                operator: msg_sender,
+                token_id,
+            }));
+        }
+        self._transfer(from, to, token_id)
+    }
+
+    /// Transfers `token_id` like `transfer_from`, then checks that a contract `to` accepts it
+    pub fn safe_transfer_from<S: TopLevelStorage + BorrowMut<Self>>(
+        storage: &mut S,
+        from: Address,
+        to: Address,
+        token_id: U256,
+    ) -> Result<(), Erc721Error> {
+        Self::safe_transfer_from_with_data(storage, from, to, token_id, Bytes(Vec::new()))
+    }
+
+    /// Transfers `token_id` like `safe_transfer_from`, passing `data` to the receiver
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data<S: TopLevelStorage + BorrowMut<Self>>(
+        storage: &mut S,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Bytes,
+    ) -> Result<(), Erc721Error> {
+        storage.borrow_mut().transfer_from(from, to, token_id)?;
+        let operator = storage.borrow_mut().vm().msg_sender();
+        Self::check_on_erc721_received(storage, operator, from, to, token_id, data.0)
+    }
+
+    /// Approves `to` to move `token_id`, replacing any previous approval
+    /// (msg::sender() must be the owner or an approved operator)
+    pub fn approve(&mut self, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        let owner = self.require_owned(token_id)?;
+        let msg_sender = self.vm().msg_sender();
+        if msg_sender != owner && !self.operator_approvals.getter(owner).get(msg_sender) {
+            return Err(Erc721Error::InvalidApprover(ERC721InvalidApprover { approver: msg_sender }));
+        }
+
+        self.token_approvals.setter(token_id).set(to);
+        log(self.vm(), Approval {
+            owner,
+            approved: to,
+            token_id,
+        });
+        Ok(())
+    }
+
+    /// Allows or forbids `operator` to move every token of msg::sender()
+    pub fn set_approval_for_all(&mut self, operator: Address, approved: bool) -> Result<(), Erc721Error> {
+        if operator.is_zero() {
+            return Err(Erc721Error::InvalidOperator(ERC721InvalidOperator { operator }));
+        }
+
+        let owner = self.vm().msg_sender();
+        self.operator_approvals.setter(owner).insert(operator, approved);
+        log(self.vm(), ApprovalForAll {
+            owner,
+            operator,
+            approved,
+        });
+        Ok(())
+    }
+
+    /// Account approved to move `token_id` (`address(0)` if none)
+    pub fn get_approved(&self, token_id: U256) -> Result<Address, Erc721Error> {
+        self.require_owned(token_id)?;
+        Ok(self.token_approvals.get(token_id))
+    }
+
+    /// Whether `operator` may move every token of `owner`
+    pub fn is_approved_for_all(&self, owner: Address, operator: Address) -> bool {
+        self.operator_approvals.getter(owner).get(operator)
+    }
+
+    /// ERC-165: whether the contract implements `interface_id`
+    pub fn supports_interface(interface_id: FixedBytes<4>) -> bool {
+        interface_id == ERC165_INTERFACE_ID
+            || interface_id == ERC721_INTERFACE_ID
+            || interface_id == ERC721_METADATA_INTERFACE_ID
+    }
+}
@@ -0,0 +1,185 @@
+// Only run this as a WASM if the export-abi feature is not set.
+#![cfg_attr(not(any(feature = "export-abi", test)), no_main)]
+extern crate alloc;
+
+// Modules and imports
+pub mod enumerable;
+pub mod erc721;
+
+use alloc::vec::Vec;
+use alloy_primitives::{Address, FixedBytes, U256};
+use stylus_common::Ownable;
+use stylus_sdk::{abi::Bytes, prelude::*};
+use crate::enumerable::{Erc721Enumerable, ERC721_ENUMERABLE_INTERFACE_ID};
+use crate::erc721::{Erc721, Erc721Error, Erc721Params};
+
+/// Immutable definitions
+pub struct StylusNftParams;
+impl Erc721Params for StylusNftParams {
+    const NAME: &'static str = "MayNFT";
+    const SYMBOL: &'static str = "MNFT";
+    const BASE_URI: &'static str = "https://nft.example.com/metadata/";
+}
+
+// Define the entrypoint as a Solidity storage object. The sol_storage! macro
+// will generate Rust-equivalent structs with all fields mapped to Solidity-equivalent
+// storage slots and types.
+sol_storage! {
+    #[entrypoint]
+    pub struct StylusNft {
+        // Allows erc721 to access StylusNft's storage and make calls
+        #[borrow]
+        Erc721<StylusNftParams> erc721;
+        #[borrow]
+        Erc721Enumerable enumerable;
+        // Only the owner can mint
+        Ownable ownership;
+        // Id of the last minted token (ids start at 1)
+        uint256 last_token_id;
+    }
+}
+
+#[public]
+#[inherit(Erc721<StylusNftParams>, Erc721Enumerable)]
+impl StylusNft {
+    /// Constructor - the deployer becomes the owner
+    #[constructor]
+    pub fn constructor(&mut self) -> Result<(), Vec<u8>> {
+        self.ownership.init(self.vm().msg_sender())?;
+        Ok(())
+    }
+
+    /// Mints the next token to `to`, returning its id (owner only)
+    pub fn mint(&mut self, to: Address) -> Result<U256, Vec<u8>> {
+        self.ownership.only_owner()?;
+        let token_id = self.last_token_id.get() + U256::from(1);
+        self.erc721.mint(to, token_id)?;
+        self.enumerable.update(Address::ZERO, to, token_id);
+        self.last_token_id.set(token_id);
+        Ok(token_id)
+    }
+
+    /// Mints the next token to `to` like `mint`, then checks that a contract `to` accepts it (owner only)
+    pub fn safe_mint(&mut self, to: Address) -> Result<U256, Vec<u8>> {
+        let token_id = self.mint(to)?;
+        let operator = self.vm().msg_sender();
+        Erc721::<StylusNftParams>::check_on_erc721_received(self, operator, Address::ZERO, to, token_id, Vec::new())?;
+        Ok(token_id)
+    }
+
+    /// Burns `token_id`
+    /// (msg::sender() must be the owner, an approved operator, or approved for the token)
+    pub fn burn(&mut self, token_id: U256) -> Result<(), Erc721Error> {
+        let msg_sender = self.vm().msg_sender();
+        let owner = self.erc721._update(Address::ZERO, token_id, msg_sender)?;
+        self.enumerable.update(owner, Address::ZERO, token_id);
+        Ok(())
+    }
+
+    /// Transfers `token_id`, keeping the enumeration in sync
+    pub fn transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self.erc721.transfer_from(from, to, token_id)?;
+        self.enumerable.update(from, to, token_id);
+        Ok(())
+    }
+
+    /// Transfers `token_id` like `transfer_from`, then checks that a contract `to` accepts it
+    pub fn safe_transfer_from(&mut self, from: Address, to: Address, token_id: U256) -> Result<(), Erc721Error> {
+        self.safe_transfer_from_with_data(from, to, token_id, Bytes(Vec::new()))
+    }
+
+    /// Transfers `token_id` like `safe_transfer_from`, passing `data` to the receiver
+    #[selector(name = "safeTransferFrom")]
+    pub fn safe_transfer_from_with_data(
+        &mut self,
+        from: Address,
+        to: Address,
+        token_id: U256,
+        data: Bytes,
+    ) -> Result<(), Erc721Error> {
+        self.transfer_from(from, to, token_id)?;
+        let operator = self.vm().msg_sender();
+        Erc721::<StylusNftParams>::check_on_erc721_received(self, operator, from, to, token_id, data.0)
+    }
+
+    /// ERC-165: whether the contract implements `interface_id`, including ERC-721 Enumerable
+    pub fn supports_interface(interface_id: FixedBytes<4>) -> bool {
+        Erc721::<StylusNftParams>::supports_interface(interface_id) || interface_id == ERC721_ENUMERABLE_INTERFACE_ID
+    }
+
+    /// Current owner
+    pub fn owner(&self) -> Address {
+        self.ownership.owner()
+    }
+
+    /// Hand minting rights to `new_owner` (owner only)
+    pub fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        self.ownership.transfer_ownership(new_owner)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stylus_sdk::testing::*;
+
+    fn owner() -> Address {
+        Address::from([9u8; 20])
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn deploy(vm: &TestVM) -> StylusNft {
+        let mut contract = StylusNft::from(vm);
+        vm.set_sender(owner());
+        assert!(contract.constructor().is_ok());
+        contract
+    }
+
+    #[test]
+    fn test_only_owner_mints() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+
+        assert_eq!(contract.mint(alice()).unwrap(), U256::from(1));
+        assert_eq!(contract.mint(alice()).unwrap(), U256::from(2));
+
+        vm.set_sender(alice());
+        assert!(contract.mint(alice()).is_err());
+        assert_eq!(contract.enumerable.total_supply(), U256::from(2));
+    }
+
+    #[test]
+    fn test_enumeration_follows_transfers_and_burns() {
+        let vm = TestVM::default();
+        let mut contract = deploy(&vm);
+        contract.mint(alice()).unwrap();
+        contract.mint(alice()).unwrap();
+
+        vm.set_sender(alice());
+        assert!(contract.transfer_from(alice(), bob(), U256::from(1)).is_ok());
+        assert_eq!(contract.erc721.owner_of(U256::from(1)).unwrap(), bob());
+        assert_eq!(contract.enumerable.token_of_owner_by_index(alice(), U256::ZERO).unwrap(), U256::from(2));
+        assert_eq!(contract.enumerable.token_of_owner_by_index(bob(), U256::ZERO).unwrap(), U256::from(1));
+
+        // Only the holder (or someone it approved) can burn
+        assert!(matches!(contract.burn(U256::from(1)), Err(Erc721Error::InsufficientApproval(_))));
+        assert!(contract.burn(U256::from(2)).is_ok());
+        assert_eq!(contract.enumerable.total_supply(), U256::from(1));
+        assert!(contract.enumerable.token_of_owner_by_index(alice(), U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_supports_enumerable_interface() {
+        assert!(StylusNft::supports_interface(ERC721_ENUMERABLE_INTERFACE_ID));
+        assert!(StylusNft::supports_interface(crate::erc721::ERC721_INTERFACE_ID));
+        assert!(!StylusNft::supports_interface(FixedBytes::from([0xff; 4])));
+    }
+}
@@ -0,0 +1,227 @@
+#[cfg(test)]
+mod test {
+    use stylus_erc721::enumerable::{Erc721Enumerable, Erc721EnumerableError};
+    use stylus_erc721::erc721::{Erc721, Erc721Error, Erc721Params};
+    use stylus_sdk::alloy_primitives::{Address, U256};
+    use stylus_sdk::testing::*;
+
+    // Test configuration for our collection
+    struct TestNftParams;
+    impl Erc721Params for TestNftParams {
+        const NAME: &'static str = "MayNFT";
+        const SYMBOL: &'static str = "MNFT";
+        const BASE_URI: &'static str = "ipfs://collection/";
+    }
+
+    struct NoUriParams;
+    impl Erc721Params for NoUriParams {
+        const NAME: &'static str = "Plain";
+        const SYMBOL: &'static str = "PLN";
+        const BASE_URI: &'static str = "";
+    }
+
+    fn alice() -> Address {
+        Address::from([1u8; 20])
+    }
+
+    fn bob() -> Address {
+        Address::from([2u8; 20])
+    }
+
+    fn charlie() -> Address {
+        Address::from([3u8; 20])
+    }
+
+    fn id(n: u64) -> U256 {
+        U256::from(n)
+    }
+
+    #[test]
+    fn test_metadata() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+        assert_eq!(Erc721::<TestNftParams>::name(), "MayNFT");
+        assert_eq!(Erc721::<TestNftParams>::symbol(), "MNFT");
+
+        assert!(matches!(erc721.token_uri(id(7)), Err(Erc721Error::NonexistentToken(_))));
+        erc721.mint(alice(), id(7)).unwrap();
+        assert_eq!(erc721.token_uri(id(7)).unwrap(), "ipfs://collection/7");
+
+        let mut plain = Erc721::<NoUriParams>::from(&vm);
+        plain.mint(alice(), id(1)).unwrap();
+        assert_eq!(plain.token_uri(id(1)).unwrap(), "");
+    }
+
+    #[test]
+    fn test_mint() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+
+        assert!(erc721.mint(alice(), id(1)).is_ok());
+        assert!(erc721.mint(alice(), id(2)).is_ok());
+        assert_eq!(erc721.owner_of(id(1)).unwrap(), alice());
+        assert_eq!(erc721.balance_of(alice()).unwrap(), id(2));
+        assert_eq!(erc721.balance_of(bob()).unwrap(), U256::ZERO);
+
+        // Ids are unique and `address(0)` can't own tokens
+        assert!(matches!(erc721.mint(bob(), id(1)), Err(Erc721Error::InvalidSender(_))));
+        assert_eq!(erc721.owner_of(id(1)).unwrap(), alice());
+        assert!(matches!(erc721.mint(Address::ZERO, id(3)), Err(Erc721Error::InvalidReceiver(_))));
+        assert!(matches!(erc721.balance_of(Address::ZERO), Err(Erc721Error::InvalidOwner(_))));
+        assert!(matches!(erc721.owner_of(id(3)), Err(Erc721Error::NonexistentToken(_))));
+    }
+
+    #[test]
+    fn test_transfer_from() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+        erc721.mint(alice(), id(1)).unwrap();
+
+        // Strangers can't move the token
+        vm.set_sender(bob());
+        assert!(matches!(
+            erc721.transfer_from(alice(), bob(), id(1)),
+            Err(Erc721Error::InsufficientApproval(_))
+        ));
+
+        vm.set_sender(alice());
+        assert!(matches!(
+            erc721.transfer_from(bob(), charlie(), id(1)),
+            Err(Erc721Error::IncorrectOwner(_))
+        ));
+        assert!(matches!(
+            erc721.transfer_from(alice(), Address::ZERO, id(1)),
+            Err(Erc721Error::InvalidReceiver(_))
+        ));
+        assert!(matches!(
+            erc721.transfer_from(alice(), bob(), id(2)),
+            Err(Erc721Error::NonexistentToken(_))
+        ));
+
+        assert!(erc721.transfer_from(alice(), bob(), id(1)).is_ok());
+        assert_eq!(erc721.owner_of(id(1)).unwrap(), bob());
+        assert_eq!(erc721.balance_of(alice()).unwrap(), U256::ZERO);
+        assert_eq!(erc721.balance_of(bob()).unwrap(), id(1));
+    }
+
+    #[test]
+    fn test_approve() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+        erc721.mint(alice(), id(1)).unwrap();
+
+        // Only the owner (or an operator) can approve
+        vm.set_sender(bob());
+        assert!(matches!(erc721.approve(bob(), id(1)), Err(Erc721Error::InvalidApprover(_))));
+
+        vm.set_sender(alice());
+        assert!(erc721.approve(bob(), id(1)).is_ok());
+        assert_eq!(erc721.get_approved(id(1)).unwrap(), bob());
+        assert!(matches!(erc721.get_approved(id(2)), Err(Erc721Error::NonexistentToken(_))));
+
+        // The approval moves the token once and is cleared by the transfer
+        vm.set_sender(bob());
+        assert!(erc721.transfer_from(alice(), charlie(), id(1)).is_ok());
+        assert_eq!(erc721.get_approved(id(1)).unwrap(), Address::ZERO);
+        assert!(erc721.transfer_from(charlie(), bob(), id(1)).is_err());
+    }
+
+    #[test]
+    fn test_approval_for_all() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+        erc721.mint(alice(), id(1)).unwrap();
+        erc721.mint(alice(), id(2)).unwrap();
+
+        vm.set_sender(alice());
+        assert!(matches!(
+            erc721.set_approval_for_all(Address::ZERO, true),
+            Err(Erc721Error::InvalidOperator(_))
+        ));
+        assert!(erc721.set_approval_for_all(bob(), true).is_ok());
+        assert!(erc721.is_approved_for_all(alice(), bob()));
+
+        // An operator can move every token and approve others for them
+        vm.set_sender(bob());
+        assert!(erc721.transfer_from(alice(), bob(), id(1)).is_ok());
+        assert!(erc721.approve(charlie(), id(2)).is_ok());
+        vm.set_sender(charlie());
+        assert!(erc721.transfer_from(alice(), charlie(), id(2)).is_ok());
+
+        // Revoked operators lose access
+        erc721.mint(alice(), id(3)).unwrap();
+        vm.set_sender(alice());
+        assert!(erc721.set_approval_for_all(bob(), false).is_ok());
+        vm.set_sender(bob());
+        assert!(erc721.transfer_from(alice(), bob(), id(3)).is_err());
+    }
+
+    #[test]
+    fn test_burn() {
+        let vm = TestVM::default();
+        let mut erc721 = Erc721::<TestNftParams>::from(&vm);
+        erc721.mint(alice(), id(1)).unwrap();
+
+        // Authorized burns go through `_update`
+        assert!(matches!(
+            erc721._update(Address::ZERO, id(1), bob()),
+            Err(Erc721Error::InsufficientApproval(_))
+        ));
+        assert_eq!(erc721._update(Address::ZERO, id(1), alice()).unwrap(), alice());
+        assert!(matches!(erc721.owner_of(id(1)), Err(Erc721Error::NonexistentToken(_))));
+        assert_eq!(erc721.balance_of(alice()).unwrap(), U256::ZERO);
+        assert!(matches!(erc721.burn(id(1)), Err(Erc721Error::NonexistentToken(_))));
+
+        // A burned id can be minted again
+        assert!(erc721.mint(bob(), id(1)).is_ok());
+        assert_eq!(erc721.burn(id(1)).unwrap(), bob());
+    }
+
+    #[test]
+    fn test_supports_interface() {
+        use stylus_erc721::erc721::{ERC165_INTERFACE_ID, ERC721_INTERFACE_ID, ERC721_METADATA_INTERFACE_ID};
+        use stylus_sdk::alloy_primitives::FixedBytes;
+
+        assert!(Erc721::<TestNftParams>::supports_interface(ERC165_INTERFACE_ID));
+        assert!(Erc721::<TestNftParams>::supports_interface(ERC721_INTERFACE_ID));
+        assert!(Erc721::<TestNftParams>::supports_interface(ERC721_METADATA_INTERFACE_ID));
+        assert!(!Erc721::<TestNftParams>::supports_interface(FixedBytes::from([0xff; 4])));
+    }
+
+    #[test]
+    fn test_enumeration() {
+        let vm = TestVM::default();
+        let mut enumerable = Erc721Enumerable::from(&vm);
+        for n in 1..=3 {
+            enumerable.update(Address::ZERO, alice(), id(n));
+        }
+        enumerable.update(Address::ZERO, bob(), id(4));
+        assert_eq!(enumerable.total_supply(), id(4));
+
+        // Transferring out swaps the owner's last token into the gap
+        enumerable.update(alice(), bob(), id(1));
+        assert_eq!(enumerable.token_of_owner_by_index(alice(), id(0)).unwrap(), id(3));
+        assert_eq!(enumerable.token_of_owner_by_index(alice(), id(1)).unwrap(), id(2));
+        assert_eq!(enumerable.token_of_owner_by_index(bob(), id(1)).unwrap(), id(1));
+
+        // Burning swaps the last token into the gap
+        enumerable.update(alice(), Address::ZERO, id(2));
+        assert_eq!(enumerable.total_supply(), id(3));
+        assert_eq!(enumerable.token_by_index(id(0)).unwrap(), id(1));
+        assert_eq!(enumerable.token_by_index(id(1)).unwrap(), id(4));
+        assert_eq!(enumerable.token_by_index(id(2)).unwrap(), id(3));
+        assert!(matches!(
+            enumerable.token_by_index(id(3)),
+            Err(Erc721EnumerableError::OutOfBoundsIndex(_))
+        ));
+        assert!(matches!(
+            enumerable.token_of_owner_by_index(alice(), id(1)),
+            Err(Erc721EnumerableError::OutOfBoundsIndex(_))
+        ));
+
+        // Self-transfers leave the indexes alone
+        enumerable.update(bob(), bob(), id(4));
+        assert_eq!(enumerable.token_of_owner_by_index(bob(), id(0)).unwrap(), id(4));
+        assert_eq!(enumerable.token_of_owner_by_index(bob(), id(1)).unwrap(), id(1));
+    }
+}